@@ -1,9 +1,13 @@
 use std::{
     io::{self, Write},
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use rucksdb::{CompressionType, DB, DBOptions, ReadOptions, Slice, WriteOptions};
+use rucksdb::{
+    BytewiseComparator, CompactionOptionsFIFO, CompactionOptionsUniversal, CompactionPri,
+    CompactionStyle, CompressionType, DB, DBOptions, ReadOptions, Slice, WriteOptions,
+};
 use tempfile::TempDir;
 
 /// Benchmark configuration
@@ -294,15 +298,34 @@ fn main() {
         } else {
             None
         },
+        filter_policy: None,
+        table_options: Default::default(),
+        prefix_extractor: None,
+        memtable_prefix_bloom_size_ratio: 0.0,
         enable_subcompaction: true,
         subcompaction_min_size: 10 * 1024 * 1024,
         parallel_compaction_threads: 4,
         merge_operator: None,
+        compaction_filter: None,
         // Background compaction settings
         enable_background_compaction: false, // Disable for benchmarking
-        compaction_check_interval_ms: 1000,
         l0_compaction_trigger: 4,
         l0_stop_writes_trigger: 12,
+        max_bytes_for_level_base: 10 * 1024 * 1024,
+        max_bytes_for_level_multiplier: 10,
+        level_compaction_dynamic_level_bytes: false,
+        target_file_size_base: 64 * 1024 * 1024,
+        target_file_size_multiplier: 1,
+        compaction_pri: CompactionPri::MinOverlappingRatio,
+        compaction_style: CompactionStyle::Level,
+        compaction_options_universal: CompactionOptionsUniversal::default(),
+        compaction_options_fifo: CompactionOptionsFIFO::default(),
+        ttl: 0,
+        periodic_compaction_seconds: 0,
+        compact_on_deletion: None,
+        create_missing_column_families: false,
+        comparator: Arc::new(BytewiseComparator),
+        ..Default::default()
     };
 
     let db = DB::open(db_path.to_str().unwrap(), options).unwrap();
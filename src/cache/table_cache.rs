@@ -3,7 +3,12 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::{cache::LRUCache, table::TableReader, util::Result};
+use crate::{
+    cache::LRUCache,
+    filter::FilterPolicy,
+    table::{BlockBasedTableOptions, TableReader},
+    util::{Comparator, Result, comparator::bytewise_comparator},
+};
 
 /// TableCache caches opened TableReader instances to avoid repeated file opens
 ///
@@ -22,6 +27,7 @@ pub struct TableCache {
     cache: Arc<Mutex<LRUCache<u64, Arc<Mutex<TableReader>>>>>,
     db_path: PathBuf,
     block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
+    filter_policy: Option<Arc<dyn FilterPolicy>>,
 }
 
 impl TableCache {
@@ -36,11 +42,27 @@ impl TableCache {
         capacity: usize,
         db_path: PathBuf,
         block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
+    ) -> Self {
+        Self::new_with_filter(capacity, db_path, block_cache, None)
+    }
+
+    /// Create a new TableCache whose readers load filter blocks
+    ///
+    /// # Arguments
+    /// * `filter_policy` - Policy used to query SSTable filters (None = filters
+    ///   are ignored)
+    #[inline]
+    pub fn new_with_filter(
+        capacity: usize,
+        db_path: PathBuf,
+        block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
+        filter_policy: Option<Arc<dyn FilterPolicy>>,
     ) -> Self {
         TableCache {
             cache: Arc::new(Mutex::new(LRUCache::new(capacity))),
             db_path,
             block_cache,
+            filter_policy,
         }
     }
 
@@ -56,6 +78,25 @@ impl TableCache {
     /// Arc<Mutex<TableReader>> for thread-safe access
     #[inline]
     pub fn get_table(&self, file_number: u64) -> Result<Arc<Mutex<TableReader>>> {
+        self.get_table_with_options(
+            file_number,
+            &BlockBasedTableOptions::default(),
+            &bytewise_comparator(),
+        )
+    }
+
+    /// Get a TableReader, opening it with the given table options and
+    /// comparator if it is not cached
+    ///
+    /// The options' filter policy falls back to the cache's policy. Options
+    /// only apply when the table is opened; a file belongs to a single
+    /// column family, so cached readers already carry that CF's options.
+    pub fn get_table_with_options(
+        &self,
+        file_number: u64,
+        options: &BlockBasedTableOptions,
+        comparator: &Arc<dyn Comparator>,
+    ) -> Result<Arc<Mutex<TableReader>>> {
         // Fast path: check cache first
         {
             let cache = self.cache.lock().unwrap();
@@ -66,7 +107,17 @@ impl TableCache {
 
         // Slow path: open table and insert into cache
         let sst_path = self.db_path.join(format!("{file_number:06}.sst"));
-        let table_reader = TableReader::open(&sst_path, file_number, self.block_cache.clone())?;
+        let mut options = options.clone();
+        if options.filter_policy.is_none() {
+            options.filter_policy = self.filter_policy.clone();
+        }
+        let mut table_reader = TableReader::open_with_options(
+            &sst_path,
+            file_number,
+            self.block_cache.clone(),
+            &options,
+        )?;
+        table_reader.set_comparator(Arc::clone(comparator));
         let table = Arc::new(Mutex::new(table_reader));
 
         // Insert into cache
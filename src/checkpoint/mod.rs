@@ -25,7 +25,7 @@ impl Checkpoint {
     /// 1. Flushing all active MemTables (ensures all data is in SSTables)
     /// 2. Listing all live SSTable files from VersionSet
     /// 3. Hard-linking SSTable files (falls back to copy if hard link fails)
-    /// 4. Copying MANIFEST and CURRENT files
+    /// 4. Copying MANIFEST, OPTIONS and CURRENT files
     ///
     /// The checkpoint directory will contain a complete, consistent copy of the
     /// database that can be opened independently.
@@ -90,7 +90,14 @@ impl Checkpoint {
                 .map_err(|e| Status::io_error(format!("Failed to copy MANIFEST: {e}")))?;
         }
 
-        // Step 5: Copy CURRENT file
+        // Step 5: Copy the latest OPTIONS file
+        if let Some((_, options_file)) = crate::options::latest_options_file(db_path)? {
+            let dst = checkpoint_dir.join(options_file.file_name().unwrap());
+            fs::copy(&options_file, &dst)
+                .map_err(|e| Status::io_error(format!("Failed to copy OPTIONS file: {e}")))?;
+        }
+
+        // Step 6: Copy CURRENT file
         let current_file = db_path.join("CURRENT");
         if current_file.exists() {
             let dst = checkpoint_dir.join("CURRENT");
@@ -1,11 +1,21 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use parking_lot::{Mutex, RwLock};
 
 use crate::{
     column_family::{ColumnFamilyHandle, ColumnFamilyOptions},
+    compaction::compaction_filter::CompactionFilter,
     memtable::MemTable,
-    version::VersionSet,
+    table::BlockBasedTableOptions,
+    ttl::TtlCompactionFilter,
+    version::{VersionSet, compaction_picker::RunningCompactions},
 };
 
 /// Internal data for a Column Family
@@ -41,8 +51,11 @@ pub struct ColumnFamilyData {
     /// Column family name
     name: String,
 
-    /// Configuration options for this CF
-    options: ColumnFamilyOptions,
+    /// Configuration options for this CF, swapped by `set_options`
+    options: RwLock<Arc<ColumnFamilyOptions>>,
+
+    /// Table options with the effective filter policy resolved
+    table_options: BlockBasedTableOptions,
 
     /// Active MemTable (receives new writes)
     mem: Arc<RwLock<MemTable>>,
@@ -58,6 +71,17 @@ pub struct ColumnFamilyData {
     /// Version set for this CF (SSTable history)
     version_set: Arc<RwLock<VersionSet>>,
 
+    /// Versions older than the newest one below this user-defined timestamp
+    /// may be dropped by compaction (0 = keep all)
+    full_history_ts_low: AtomicU64,
+
+    /// TTL in seconds of values written by `DBWithTTL` (0 = no TTL); expired
+    /// values are dropped by compaction
+    ttl_secs: AtomicU64,
+
+    /// Compactions in progress
+    running_compactions: RunningCompactions,
+
     /// Reference to this CF as a handle
     handle: ColumnFamilyHandle,
 }
@@ -67,20 +91,39 @@ impl ColumnFamilyData {
     /// Create a new column family data
     pub fn new(id: u32, name: String, options: ColumnFamilyOptions, db_path: &str) -> Self {
         let handle = ColumnFamilyHandle::new(id, name.clone());
-        let version_set = VersionSet::new(Path::new(db_path));
+        let version_set =
+            VersionSet::with_comparator(Path::new(db_path), Arc::clone(&options.comparator));
+        let mem = Self::new_memtable(&options);
+        let table_options = options.resolved_table_options();
 
         ColumnFamilyData {
             id,
             name,
-            options,
-            mem: Arc::new(RwLock::new(MemTable::new())),
+            options: RwLock::new(Arc::new(options)),
+            table_options,
+            mem: Arc::new(RwLock::new(mem)),
             imm: Arc::new(RwLock::new(None)),
             sequence: Arc::new(Mutex::new(0)),
             version_set: Arc::new(RwLock::new(version_set)),
+            full_history_ts_low: AtomicU64::new(0),
+            ttl_secs: AtomicU64::new(0),
+            running_compactions: RunningCompactions::default(),
             handle,
         }
     }
 
+    /// Create an empty MemTable configured by the CF options
+    fn new_memtable(options: &ColumnFamilyOptions) -> MemTable {
+        let mut memtable = MemTable::with_comparator(Arc::clone(&options.comparator));
+        let bloom_size = options.memtable_prefix_bloom_size();
+        if let Some(ref extractor) = options.prefix_extractor
+            && bloom_size > 0
+        {
+            memtable.set_prefix_bloom(Arc::clone(extractor), bloom_size);
+        }
+        memtable
+    }
+
     /// Get the column family ID
     pub fn id(&self) -> u32 {
         self.id
@@ -92,8 +135,73 @@ impl ColumnFamilyData {
     }
 
     /// Get the column family options
-    pub fn options(&self) -> &ColumnFamilyOptions {
-        &self.options
+    ///
+    /// Returns a snapshot: options changed later by `set_options` don't
+    /// affect an operation already holding one.
+    pub fn options(&self) -> Arc<ColumnFamilyOptions> {
+        Arc::clone(&self.options.read())
+    }
+
+    /// Replace the mutable options of this CF
+    ///
+    /// Table options are resolved once at creation and aren't affected.
+    pub(crate) fn set_options(&self, options: ColumnFamilyOptions) {
+        *self.options.write() = Arc::new(options);
+    }
+
+    /// Get the options for SSTables written and read by this CF
+    ///
+    /// Unlike `options().table_options`, the filter policy is always the
+    /// one tables are written with (see `ColumnFamilyOptions::filter_policy`).
+    pub fn table_options(&self) -> &BlockBasedTableOptions {
+        &self.table_options
+    }
+
+    /// Whether keys of this CF carry user-defined timestamps
+    pub fn has_timestamps(&self) -> bool {
+        self.options().comparator.timestamp_size() > 0
+    }
+
+    /// Timestamp below which compaction keeps only the newest version of a
+    /// key
+    pub fn full_history_ts_low(&self) -> u64 {
+        self.full_history_ts_low.load(Ordering::SeqCst)
+    }
+
+    /// Raise the full history timestamp watermark; never moves it back
+    pub(crate) fn set_full_history_ts_low(&self, timestamp: u64) {
+        self.full_history_ts_low
+            .fetch_max(timestamp, Ordering::SeqCst);
+    }
+
+    /// TTL of values written by `DBWithTTL`, if it wraps this CF
+    pub fn ttl(&self) -> Option<Duration> {
+        match self.ttl_secs.load(Ordering::SeqCst) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub(crate) fn set_ttl(&self, ttl: Duration) {
+        self.ttl_secs.store(ttl.as_secs().max(1), Ordering::SeqCst);
+    }
+
+    /// Compactions in progress in this CF
+    pub(crate) fn running_compactions(&self) -> &RunningCompactions {
+        &self.running_compactions
+    }
+
+    /// Compaction filters to run on this CF's compactions: TTL expiry
+    /// first, then the configured filter
+    pub(crate) fn compaction_filters(&self) -> Vec<Arc<dyn CompactionFilter>> {
+        let mut filters: Vec<Arc<dyn CompactionFilter>> = Vec::new();
+        if let Some(ttl) = self.ttl() {
+            filters.push(Arc::new(TtlCompactionFilter::new(ttl)));
+        }
+        if let Some(filter) = self.options().compaction_filter.clone() {
+            filters.push(filter);
+        }
+        filters
     }
 
     /// Get a handle to this column family
@@ -131,7 +239,7 @@ impl ColumnFamilyData {
     /// Check if MemTable should be flushed
     pub fn should_flush(&self) -> bool {
         let mem = self.mem.read();
-        mem.approximate_memory_usage() >= self.options.write_buffer_size
+        mem.approximate_memory_usage() >= self.options.read().write_buffer_size
     }
 
     /// Make current MemTable immutable and create new one
@@ -144,7 +252,7 @@ impl ColumnFamilyData {
         }
 
         let mut mem = self.mem.write();
-        let old_mem = std::mem::take(&mut *mem);
+        let old_mem = std::mem::replace(&mut *mem, Self::new_memtable(&self.options()));
         *imm = Some(old_mem);
         true
     }
@@ -220,4 +328,22 @@ mod tests {
         cf.clear_immutable();
         assert!(cf.make_immutable());
     }
+
+    #[test]
+    fn test_memtable_prefix_bloom_survives_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_cf");
+
+        let options = ColumnFamilyOptions {
+            prefix_extractor: Some(Arc::new(crate::util::FixedPrefixTransform::new(4))),
+            memtable_prefix_bloom_size_ratio: 0.01,
+            ..Default::default()
+        };
+        let cf =
+            ColumnFamilyData::new(1, "test_cf".to_string(), options, db_path.to_str().unwrap());
+
+        assert!(cf.mem().read().prefix_bloom().is_some());
+        assert!(cf.make_immutable());
+        assert!(cf.mem().read().prefix_bloom().is_some());
+    }
 }
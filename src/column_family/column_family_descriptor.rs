@@ -19,7 +19,8 @@ use crate::column_family::ColumnFamilyOptions;
 ///     }),
 /// ];
 ///
-/// let db = DB::open_with_column_families("mydb", DBOptions::default(), cf_descriptors)?;
+/// let db = DB::open_cf("mydb", DBOptions::default(), cf_descriptors)?;
+/// let users = db.cf_handle("users").unwrap();
 /// ```
 #[derive(Debug, Clone)]
 pub struct ColumnFamilyDescriptor {
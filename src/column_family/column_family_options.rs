@@ -1,4 +1,20 @@
-use crate::table::format::CompressionType;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compaction::compaction_filter::CompactionFilter,
+    filter::{BloomFilterFormat, BloomFilterPolicy, FilterPolicy},
+    merge::MergeOperator,
+    table::{BlockBasedTableOptions, CompactOnDeletionOptions, format::CompressionType},
+    util::{Comparator, Result, SliceTransform, Status, comparator::bytewise_comparator, now_secs},
+    version::{
+        FileMetaData, Version,
+        compaction_picker::{Compaction, CompactionPicker, CompactionPri, CompactionStyle},
+        fifo_compaction_picker::{CompactionOptionsFIFO, FifoCompactionPicker},
+        universal_compaction_picker::{CompactionOptionsUniversal, UniversalCompactionPicker},
+    },
+};
 
 /// Options for a specific Column Family
 ///
@@ -7,20 +23,38 @@ use crate::table::format::CompressionType;
 /// - Compression type (for SSTables)
 /// - Bloom filter configuration
 /// - Block cache size
+/// - Prefix extractor for prefix Bloom filters
+/// - SSTable layout (block size, restart intervals, index and filter layout)
+/// - Merge operator
+/// - Compaction filter
+/// - Compaction trigger, level sizing and output file size
+/// - Comparator (order of user keys)
 ///
 /// # Example
 ///
 /// ```ignore
-/// use rucksdb::{ColumnFamilyOptions, CompressionType};
+/// use rucksdb::{BlockBasedTableOptions, ColumnFamilyOptions, CompressionType};
 ///
 /// let options = ColumnFamilyOptions {
 ///     write_buffer_size: 8 * 1024 * 1024,  // 8MB
 ///     compression_type: CompressionType::Lz4,
 ///     filter_bits_per_key: Some(10),
 ///     block_cache_size: 2000,
+///     table_options: BlockBasedTableOptions {
+///         block_size: 16 * 1024,
+///         ..Default::default()
+///     },
+///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Options are persisted in the MANIFEST when a column family is created
+/// or opened, except for the user-supplied objects (filter policy, prefix
+/// extractor, merge operator, comparator), which have to be passed again on
+/// open. Builtin comparators are restored from the name the MANIFEST
+/// records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColumnFamilyOptions {
     /// Size of write buffer (MemTable) in bytes before flushing to disk
     /// Default: 4MB
@@ -34,9 +68,116 @@ pub struct ColumnFamilyOptions {
     /// Default: Some(10) ~1% false positive rate
     pub filter_bits_per_key: Option<usize>,
 
+    /// Filter policy for SSTables written by this CF; overrides
+    /// filter_bits_per_key (e.g. `RibbonFilterPolicy` to save space)
+    /// Default: None (Bloom filter from filter_bits_per_key)
+    #[serde(skip)]
+    pub filter_policy: Option<Arc<dyn FilterPolicy>>,
+
     /// Number of blocks to cache (block_size = 4KB by default)
     /// Default: 1000 blocks (~4MB)
     pub block_cache_size: usize,
+
+    /// Prefix extractor; when set, SSTable filters also contain key prefixes
+    /// so prefix seeks can skip files without a matching key
+    /// Default: None
+    #[serde(skip)]
+    pub prefix_extractor: Option<Arc<dyn SliceTransform>>,
+
+    /// Size of the MemTable prefix Bloom filter as a fraction of
+    /// write_buffer_size (0 = disabled, requires prefix_extractor)
+    /// Default: 0.0
+    pub memtable_prefix_bloom_size_ratio: f64,
+
+    /// Options for the SSTables of this CF
+    /// Default: BlockBasedTableOptions::default()
+    pub table_options: BlockBasedTableOptions,
+
+    /// Merge operator for this CF
+    /// Default: None
+    #[serde(skip)]
+    pub merge_operator: Option<Arc<dyn MergeOperator>>,
+
+    /// Compaction filter for this CF, called for the newest version of
+    /// every key during compaction
+    /// Default: None
+    #[serde(skip)]
+    pub compaction_filter: Option<Arc<dyn CompactionFilter>>,
+
+    /// Number of L0 files that triggers compaction; the number of sorted
+    /// runs with universal compaction
+    /// Default: 4
+    pub l0_compaction_trigger: usize,
+
+    /// Number of L0 files at which a flushing write waits for L0 to be
+    /// compacted; taken as `l0_compaction_trigger` when below it
+    /// Default: 12
+    pub l0_stop_writes_trigger: usize,
+
+    /// Target total size of level 1
+    /// Default: 10MB
+    pub max_bytes_for_level_base: u64,
+
+    /// Growth factor of the target size from one level to the next
+    /// Default: 10
+    pub max_bytes_for_level_multiplier: u64,
+
+    /// Derive level target sizes backward from the size of the last level
+    /// instead of forward from `max_bytes_for_level_base`
+    ///
+    /// Levels above the base level, the first one whose target reaches
+    /// `max_bytes_for_level_base`, stay empty and L0 compacts straight into
+    /// it. A small database lives in the last levels only.
+    /// Default: false
+    pub level_compaction_dynamic_level_bytes: bool,
+
+    /// Size at which compaction starts a new output file in level 1
+    /// Default: 64MB
+    pub target_file_size_base: u64,
+
+    /// Growth factor of the target file size from one level to the next
+    /// Default: 1 (same target in every level)
+    pub target_file_size_multiplier: u64,
+
+    /// Order in which the files of a level are picked for compaction
+    /// Default: MinOverlappingRatio
+    pub compaction_pri: CompactionPri,
+
+    /// Leveled, universal or FIFO compaction; fixed when the CF is opened
+    /// Default: Level
+    pub compaction_style: CompactionStyle,
+
+    /// Options of universal compaction
+    /// Default: size ratio 1%, merge widths 2 to unlimited, space
+    /// amplification limit 200%
+    pub compaction_options_universal: CompactionOptionsUniversal,
+
+    /// Options of FIFO compaction
+    /// Default: 1GB size budget, no merging of level 0 files
+    pub compaction_options_fifo: CompactionOptionsFIFO,
+
+    /// Age in seconds of their oldest data at which FIFO compaction drops
+    /// files, and leveled compaction compacts them into the next level
+    /// until they reach the last level (0 = never)
+    /// Default: 0
+    pub ttl: u64,
+
+    /// Seconds after being written at which leveled compaction compacts a
+    /// file into the next level, or rewrites it in the last level, so
+    /// compaction filters eventually see every key (0 = never)
+    /// Default: 0
+    pub periodic_compaction_seconds: u64,
+
+    /// Mark new files for compaction when a window of consecutive entries
+    /// holds too many deletion markers (leveled compaction)
+    /// Default: None
+    pub compact_on_deletion: Option<CompactOnDeletionOptions>,
+
+    /// Order of user keys; fixed when the CF is created, opening it with a
+    /// comparator of another name fails
+    /// Default: BytewiseComparator
+    #[serde(skip)]
+    pub comparator: Arc<dyn Comparator>,
 }
 
 impl Default for ColumnFamilyOptions {
@@ -45,7 +186,172 @@ impl Default for ColumnFamilyOptions {
             write_buffer_size: 4 * 1024 * 1024, // 4MB
             compression_type: CompressionType::Snappy,
             filter_bits_per_key: Some(10),
+            filter_policy: None,
             block_cache_size: 1000,
+            prefix_extractor: None,
+            memtable_prefix_bloom_size_ratio: 0.0,
+            table_options: BlockBasedTableOptions::default(),
+            merge_operator: None,
+            compaction_filter: None,
+            l0_compaction_trigger: 4,
+            l0_stop_writes_trigger: 12,
+            max_bytes_for_level_base: 10 * 1024 * 1024, // 10MB
+            max_bytes_for_level_multiplier: 10,
+            level_compaction_dynamic_level_bytes: false,
+            target_file_size_base: 64 * 1024 * 1024, // 64MB
+            target_file_size_multiplier: 1,
+            compaction_pri: CompactionPri::MinOverlappingRatio,
+            compaction_style: CompactionStyle::Level,
+            compaction_options_universal: CompactionOptionsUniversal::default(),
+            compaction_options_fifo: CompactionOptionsFIFO::default(),
+            ttl: 0,
+            periodic_compaction_seconds: 0,
+            compact_on_deletion: None,
+            comparator: bytewise_comparator(),
+        }
+    }
+}
+
+impl ColumnFamilyOptions {
+    /// Filter policy used when writing SSTables (None = no filter)
+    ///
+    /// In order of precedence: `table_options.filter_policy`,
+    /// `filter_policy`, or a Bloom filter from `filter_bits_per_key` in the
+    /// layout of the configured format version.
+    pub(crate) fn table_filter_policy(&self) -> Option<Arc<dyn FilterPolicy>> {
+        self.table_options
+            .filter_policy
+            .clone()
+            .or_else(|| self.filter_policy.clone())
+            .or_else(|| {
+                let format = if self.table_options.format_version == 0 {
+                    BloomFilterFormat::Legacy
+                } else {
+                    BloomFilterFormat::CacheLocal
+                };
+                self.filter_bits_per_key.map(|bits_per_key| {
+                    Arc::new(BloomFilterPolicy::with_format(bits_per_key, format))
+                        as Arc<dyn FilterPolicy>
+                })
+            })
+    }
+
+    /// Table options with the effective filter policy filled in
+    pub(crate) fn resolved_table_options(&self) -> BlockBasedTableOptions {
+        BlockBasedTableOptions {
+            filter_policy: self.table_filter_policy(),
+            ..self.table_options.clone()
+        }
+    }
+
+    /// Compaction picker scoring levels by this CF's trigger and level sizes
+    pub(crate) fn compaction_picker(&self) -> CompactionPicker {
+        CompactionPicker::with_config(
+            self.max_bytes_for_level_base,
+            self.max_bytes_for_level_multiplier,
+            self.l0_compaction_trigger,
+        )
+        .with_compaction_pri(self.compaction_pri)
+        .with_dynamic_level_bytes(self.level_compaction_dynamic_level_bytes)
+        .with_time_based_compaction(self.ttl, self.periodic_compaction_seconds)
+    }
+
+    /// Next compaction of this CF's compaction style, avoiding the
+    /// `running` ones
+    ///
+    /// `creation_time` gives the time a file's oldest data was written at
+    /// (`ttl` and `periodic_compaction_seconds`).
+    pub(crate) fn pick_compaction(
+        &self,
+        version: &Version,
+        running: &[Compaction],
+        creation_time: impl Fn(&FileMetaData) -> u64,
+    ) -> Option<Compaction> {
+        match self.compaction_style {
+            CompactionStyle::Level => {
+                let picker = self.compaction_picker();
+                picker
+                    .pick(version, running)
+                    .or_else(|| picker.pick_old_file(version, running, now_secs(), creation_time))
+            },
+            CompactionStyle::Universal => UniversalCompactionPicker::new(
+                self.compaction_options_universal.clone(),
+                self.l0_compaction_trigger,
+            )
+            .pick(version, running),
+            CompactionStyle::Fifo => FifoCompactionPicker::new(
+                self.compaction_options_fifo.clone(),
+                self.ttl,
+                self.l0_compaction_trigger,
+                self.write_buffer_size as u64,
+            )
+            .pick(version, running, now_secs(), creation_time),
+        }
+    }
+
+    /// Size at which compaction output to `level` is split into a new file
+    ///
+    /// Compactions into level 0 write a single file.
+    pub(crate) fn target_file_size(&self, level: usize) -> u64 {
+        if level == 0 {
+            return u64::MAX;
+        }
+        let exponent = level.saturating_sub(1).min(u32::MAX as usize) as u32;
+        self.target_file_size_multiplier
+            .saturating_pow(exponent)
+            .saturating_mul(self.target_file_size_base)
+    }
+
+    /// Check the options for values a column family can't run with
+    pub fn validate(&self) -> Result<()> {
+        if self.write_buffer_size == 0 {
+            return Err(Status::invalid_argument(
+                "write_buffer_size must be positive",
+            ));
+        }
+        if self.l0_compaction_trigger == 0 {
+            return Err(Status::invalid_argument(
+                "l0_compaction_trigger must be positive",
+            ));
+        }
+        if self.l0_stop_writes_trigger == 0 {
+            return Err(Status::invalid_argument(
+                "l0_stop_writes_trigger must be positive",
+            ));
+        }
+        if self.max_bytes_for_level_base == 0 || self.max_bytes_for_level_multiplier == 0 {
+            return Err(Status::invalid_argument("Level sizes must be positive"));
+        }
+        if self.target_file_size_base == 0 || self.target_file_size_multiplier == 0 {
+            return Err(Status::invalid_argument(
+                "Target file sizes must be positive",
+            ));
+        }
+        self.compaction_options_universal.validate()?;
+        if let Some(ref compact_on_deletion) = self.compact_on_deletion {
+            compact_on_deletion.validate()?;
+        }
+        self.table_options.validate()
+    }
+
+    /// Serialize the persistable options
+    pub(crate) fn encode(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserialize options written by `encode`
+    ///
+    /// Options missing from the input (e.g. written by an older release)
+    /// keep their defaults.
+    pub(crate) fn decode(data: &str) -> Result<Self> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    /// Size in bytes of the MemTable prefix Bloom filter (0 if disabled)
+    pub(crate) fn memtable_prefix_bloom_size(&self) -> usize {
+        if self.prefix_extractor.is_none() || self.memtable_prefix_bloom_size_ratio <= 0.0 {
+            return 0;
         }
+        (self.write_buffer_size as f64 * self.memtable_prefix_bloom_size_ratio) as usize
     }
 }
@@ -106,6 +106,13 @@ impl ColumnFamilySet {
             max_id = max_id.max(id);
         }
 
+        // Every CF allocates table file numbers from the first one's counter
+        if let Some(first) = cfs.get(&0).cloned() {
+            for cf in cfs.values() {
+                Self::share_file_numbers(&first, cf);
+            }
+        }
+
         Ok(ColumnFamilySet {
             column_families: RwLock::new(cfs),
             name_to_id: RwLock::new(name_map),
@@ -114,6 +121,15 @@ impl ColumnFamilySet {
         })
     }
 
+    /// Make `cf` allocate file numbers from `from`'s counter
+    fn share_file_numbers(from: &ColumnFamilyData, cf: &ColumnFamilyData) {
+        if Arc::ptr_eq(&from.version_set(), &cf.version_set()) {
+            return;
+        }
+        let counter = from.version_set().read().file_number_counter();
+        cf.version_set().write().share_file_numbers(counter);
+    }
+
     /// Get default column family
     pub fn default_cf(&self) -> Arc<ColumnFamilyData> {
         let cfs = self.column_families.read().unwrap();
@@ -126,6 +142,12 @@ impl ColumnFamilySet {
         cfs.get(&handle.id()).map(Arc::clone)
     }
 
+    /// Get column family by ID
+    pub fn get_cf_by_id(&self, id: u32) -> Option<Arc<ColumnFamilyData>> {
+        let cfs = self.column_families.read().unwrap();
+        cfs.get(&id).map(Arc::clone)
+    }
+
     /// Get column family by name
     pub fn get_cf_by_name(&self, name: &str) -> Option<Arc<ColumnFamilyData>> {
         let name_map = self.name_to_id.read().unwrap();
@@ -189,6 +211,7 @@ impl ColumnFamilySet {
             options,
             &self.db_path,
         ));
+        Self::share_file_numbers(&self.default_cf(), &cf);
 
         let handle = cf.handle().clone();
 
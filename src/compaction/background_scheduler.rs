@@ -6,17 +6,16 @@ use crate::column_family::ColumnFamilySet;
 pub struct BackgroundCompactionScheduler;
 
 impl BackgroundCompactionScheduler {
-    /// Check if compaction is needed for a column family based on L0 file count
+    /// Check if compaction is needed for a column family based on L0 file
+    /// count, or because a file above the last level is marked for
+    /// compaction
     pub fn should_compact(
         column_families: &Arc<ColumnFamilySet>,
         cf_id: u32,
         l0_trigger: usize,
     ) -> bool {
-        // Get the CF by id (0 is default CF)
-        let cf = if cf_id == 0 {
-            column_families.default_cf()
-        } else {
-            return false; // For now, only support default CF
+        let Some(cf) = column_families.get_cf_by_id(cf_id) else {
+            return false;
         };
 
         let version_set = cf.version_set();
@@ -27,6 +26,12 @@ impl BackgroundCompactionScheduler {
         // Count L0 files
         let l0_file_count = version.files[0].len();
         l0_file_count >= l0_trigger
+            || version
+                .files
+                .iter()
+                .take(version.files.len() - 1)
+                .flatten()
+                .any(|f| f.marked_for_compaction)
     }
 
     /// Check if writes should be stalled due to too many L0 files
@@ -35,11 +40,8 @@ impl BackgroundCompactionScheduler {
         cf_id: u32,
         l0_stop_trigger: usize,
     ) -> bool {
-        // Get the CF by id (0 is default CF)
-        let cf = if cf_id == 0 {
-            column_families.default_cf()
-        } else {
-            return false; // For now, only support default CF
+        let Some(cf) = column_families.get_cf_by_id(cf_id) else {
+            return false;
         };
 
         let version_set = cf.version_set();
@@ -0,0 +1,318 @@
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+use crate::{
+    memtable::memtable::{InternalKey, VALUE_TYPE_DELETION, VALUE_TYPE_VALUE},
+    util::{Comparator, Slice},
+};
+
+/// What a compaction filter does with an entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactionDecision {
+    /// Keep the entry unchanged
+    Keep,
+    /// Remove the entry
+    Remove,
+    /// Keep the entry with a new value
+    ChangeValue(Slice),
+    /// Remove the entry and every following entry with a user key below
+    /// the given one
+    ///
+    /// Skipped entries are dropped without leaving deletion markers, so
+    /// older versions of their keys in levels outside the compaction may
+    /// become visible again.
+    RemoveAndSkipUntil(Slice),
+}
+
+/// Hook to drop or rewrite entries during compaction
+///
+/// Set per column family with `ColumnFamilyOptions::compaction_filter`. It
+/// is called for the newest version of every key holding a plain value;
+/// deletions, wide-column entities and older versions kept for
+/// user-defined timestamps aren't passed to it. A removed entry becomes a
+/// deletion marker, so older versions in lower levels stay hidden, except
+/// at the bottommost level where it is dropped.
+///
+/// # Example
+///
+/// ```ignore
+/// use rucksdb::{CompactionDecision, CompactionFilter, Slice};
+///
+/// struct DropTemporary;
+///
+/// impl CompactionFilter for DropTemporary {
+///     fn name(&self) -> &str {
+///         "DropTemporary"
+///     }
+///
+///     fn filter(&self, _level: usize, key: &Slice, _value: &Slice) -> CompactionDecision {
+///         if key.data().starts_with(b"tmp:") {
+///             CompactionDecision::Remove
+///         } else {
+///             CompactionDecision::Keep
+///         }
+///     }
+/// }
+/// ```
+pub trait CompactionFilter: Send + Sync {
+    /// Returns the name of this compaction filter
+    fn name(&self) -> &str;
+
+    /// Decide what happens to an entry
+    ///
+    /// # Arguments
+    /// * `level` - Level of the compaction's input
+    /// * `key` - User key of the entry
+    /// * `existing_value` - Current value of the entry
+    fn filter(&self, level: usize, key: &Slice, existing_value: &Slice) -> CompactionDecision;
+}
+
+impl fmt::Debug for dyn CompactionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Number of entries compaction filters removed and changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionFilterStats {
+    pub removed: u64,
+    pub changed: u64,
+}
+
+impl CompactionFilterStats {
+    pub fn add(&mut self, other: CompactionFilterStats) {
+        self.removed += other.removed;
+        self.changed += other.changed;
+    }
+}
+
+/// Applies compaction filters to the entries a compaction keeps
+///
+/// Fed the output of `VersionRetention` in InternalKey order. Filters run
+/// in order until one removes the entry; changed values are passed on to
+/// the next filter.
+pub(crate) struct CompactionFilterRunner {
+    filters: Vec<Arc<dyn CompactionFilter>>,
+    level: usize,
+    bottommost: bool,
+    full_history_ts_low: u64,
+    comparator: Arc<dyn Comparator>,
+    last_user_key: Option<Slice>,
+    skip_until: Option<Slice>,
+    stats: CompactionFilterStats,
+}
+
+impl CompactionFilterRunner {
+    pub(crate) fn new(
+        filters: Vec<Arc<dyn CompactionFilter>>,
+        level: usize,
+        bottommost: bool,
+        full_history_ts_low: u64,
+        comparator: Arc<dyn Comparator>,
+    ) -> Self {
+        CompactionFilterRunner {
+            filters,
+            level,
+            bottommost,
+            full_history_ts_low,
+            comparator,
+            last_user_key: None,
+            skip_until: None,
+            stats: CompactionFilterStats::default(),
+        }
+    }
+
+    /// The entry to write in place of a kept compaction input entry
+    pub(crate) fn apply(
+        &mut self,
+        internal_key: &InternalKey,
+        key: Slice,
+        value: Slice,
+    ) -> Option<(Slice, Slice)> {
+        if self.filters.is_empty() {
+            return Some((key, value));
+        }
+        let user_key = internal_key.user_key();
+
+        if let Some(ref skip_until) = self.skip_until {
+            if self.comparator.compare(user_key.data(), skip_until.data()) == Ordering::Less {
+                self.stats.removed += 1;
+                return None;
+            }
+            self.skip_until = None;
+        }
+
+        // Only the newest version of a key is filtered
+        if self.last_user_key.as_ref() == Some(user_key) {
+            return Some((key, value));
+        }
+        self.last_user_key = Some(user_key.clone());
+        if internal_key.value_type != VALUE_TYPE_VALUE {
+            return Some((key, value));
+        }
+
+        let mut value = value;
+        let mut changed = false;
+        for filter in &self.filters {
+            match filter.filter(self.level, user_key, &value) {
+                CompactionDecision::Keep => {},
+                CompactionDecision::ChangeValue(new_value) => {
+                    value = new_value;
+                    changed = true;
+                },
+                CompactionDecision::Remove => {
+                    self.stats.removed += 1;
+                    return self.removal(internal_key);
+                },
+                CompactionDecision::RemoveAndSkipUntil(until) => {
+                    if self.comparator.compare(until.data(), user_key.data()) == Ordering::Greater {
+                        self.skip_until = Some(until);
+                    }
+                    self.stats.removed += 1;
+                    return self.removal(internal_key);
+                },
+            }
+        }
+
+        if changed {
+            self.stats.changed += 1;
+        }
+        Some((key, value))
+    }
+
+    /// Entries removed and changed so far
+    pub(crate) fn stats(&self) -> CompactionFilterStats {
+        self.stats
+    }
+
+    /// Deletion marker replacing a removed entry, or nothing at the
+    /// bottommost level
+    ///
+    /// Versions at or above `full_history_ts_low` keep their older
+    /// versions, so they are replaced by a marker at every level.
+    fn removal(&self, internal_key: &InternalKey) -> Option<(Slice, Slice)> {
+        let keeps_history = internal_key
+            .timestamp()
+            .is_some_and(|ts| ts >= self.full_history_ts_low);
+        if self.bottommost && !keeps_history {
+            return None;
+        }
+
+        let user_key = internal_key.user_key().clone();
+        let sequence = internal_key.sequence();
+        let deletion = match internal_key.timestamp() {
+            Some(ts) => InternalKey::with_timestamp(user_key, ts, sequence, VALUE_TYPE_DELETION),
+            None => InternalKey::new(user_key, sequence, VALUE_TYPE_DELETION),
+        };
+        Some((deletion.encode(), Slice::empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::comparator::bytewise_comparator;
+
+    /// Removes "drop*", uppercases "upper*", skips from "skip" to "skipz"
+    struct TestFilter;
+
+    impl CompactionFilter for TestFilter {
+        fn name(&self) -> &str {
+            "TestFilter"
+        }
+
+        fn filter(&self, _level: usize, key: &Slice, value: &Slice) -> CompactionDecision {
+            if key.data().starts_with(b"drop") {
+                CompactionDecision::Remove
+            } else if key.data().starts_with(b"upper") {
+                CompactionDecision::ChangeValue(Slice::from(value.to_string().to_uppercase()))
+            } else if key.data() == b"skip" {
+                CompactionDecision::RemoveAndSkipUntil(Slice::from("skipz"))
+            } else {
+                CompactionDecision::Keep
+            }
+        }
+    }
+
+    fn run(
+        bottommost: bool,
+        keys: &[(&str, u64, u8)],
+    ) -> (Vec<(String, u8, String)>, CompactionFilterStats) {
+        let mut runner = CompactionFilterRunner::new(
+            vec![Arc::new(TestFilter)],
+            1,
+            bottommost,
+            0,
+            bytewise_comparator(),
+        );
+        let output = keys
+            .iter()
+            .filter_map(|&(user_key, seq, value_type)| {
+                let internal_key = InternalKey::new(Slice::from(user_key), seq, value_type);
+                let value = if value_type == VALUE_TYPE_DELETION {
+                    Slice::empty()
+                } else {
+                    Slice::from("value")
+                };
+                runner.apply(&internal_key, internal_key.encode(), value)
+            })
+            .map(|(key, value)| {
+                let key = InternalKey::decode(&key).unwrap();
+                (
+                    key.user_key().to_string(),
+                    key.value_type,
+                    value.to_string(),
+                )
+            })
+            .collect();
+        (output, runner.stats())
+    }
+
+    #[test]
+    fn test_runner_decisions() {
+        let keys = [
+            ("drop1", 5, VALUE_TYPE_VALUE),
+            ("keep", 4, VALUE_TYPE_VALUE),
+            ("skip", 3, VALUE_TYPE_VALUE),
+            ("skipa", 2, VALUE_TYPE_DELETION),
+            ("skipb", 2, VALUE_TYPE_VALUE),
+            ("upper", 9, VALUE_TYPE_VALUE),
+            ("upper", 1, VALUE_TYPE_VALUE),
+        ];
+
+        let (output, stats) = run(false, &keys);
+        assert_eq!(
+            output,
+            vec![
+                ("drop1".to_string(), VALUE_TYPE_DELETION, String::new()),
+                ("keep".to_string(), VALUE_TYPE_VALUE, "value".to_string()),
+                ("skip".to_string(), VALUE_TYPE_DELETION, String::new()),
+                ("upper".to_string(), VALUE_TYPE_VALUE, "VALUE".to_string()),
+                // Older versions aren't filtered
+                ("upper".to_string(), VALUE_TYPE_VALUE, "value".to_string()),
+            ]
+        );
+        assert_eq!(
+            stats,
+            CompactionFilterStats {
+                removed: 4,
+                changed: 1
+            }
+        );
+
+        // Removed entries leave no deletion markers at the bottommost level
+        let (output, _) = run(true, &keys);
+        let user_keys: Vec<&str> = output.iter().map(|(key, _, _)| key.as_str()).collect();
+        assert_eq!(user_keys, vec!["keep", "upper", "upper"]);
+    }
+
+    #[test]
+    fn test_runner_deletions_pass_through() {
+        let (output, stats) = run(false, &[("drop1", 3, VALUE_TYPE_DELETION)]);
+        assert_eq!(
+            output,
+            vec![("drop1".to_string(), VALUE_TYPE_DELETION, String::new())]
+        );
+        assert_eq!(stats, CompactionFilterStats::default());
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    memtable::memtable::InternalKeyComparator,
+    table::{format::BlockHandle, table_reader::TableReader},
+    util::{Comparator, Result, Slice, Status},
+    version::FileMetaData,
+};
+
+/// Raw entries of non-overlapping SSTables in key order, read a data block
+/// at a time
+struct TableRun {
+    tables: Vec<Arc<Mutex<TableReader>>>,
+    table_index: usize,
+    block_handles: Vec<BlockHandle>,
+    block_index: usize,
+    entries: Vec<(Slice, Slice)>,
+    position: usize,
+}
+
+impl TableRun {
+    fn new(tables: Vec<Arc<Mutex<TableReader>>>) -> Result<Self> {
+        let mut run = TableRun {
+            tables,
+            table_index: 0,
+            block_handles: Vec::new(),
+            block_index: 0,
+            entries: Vec::new(),
+            position: 0,
+        };
+        if let Some(table) = run.tables.first() {
+            run.block_handles = lock(table)?.data_block_handles()?;
+        }
+        run.fill()?;
+        Ok(run)
+    }
+
+    fn current(&self) -> Option<&(Slice, Slice)> {
+        self.entries.get(self.position)
+    }
+
+    fn advance(&mut self) -> Result<()> {
+        self.position += 1;
+        self.fill()
+    }
+
+    /// Load blocks until the position is on an entry or the run is done
+    fn fill(&mut self) -> Result<()> {
+        while self.position >= self.entries.len() {
+            if self.block_index == self.block_handles.len() {
+                self.table_index += 1;
+                let Some(table) = self.tables.get(self.table_index) else {
+                    self.entries.clear();
+                    self.position = 0;
+                    return Ok(());
+                };
+                self.block_handles = lock(table)?.data_block_handles()?;
+                self.block_index = 0;
+                continue;
+            }
+
+            let handle = &self.block_handles[self.block_index];
+            self.entries = lock(&self.tables[self.table_index])?.block_entries(handle)?;
+            self.block_index += 1;
+            self.position = 0;
+        }
+        Ok(())
+    }
+}
+
+fn lock(table: &Mutex<TableReader>) -> Result<std::sync::MutexGuard<'_, TableReader>> {
+    table
+        .lock()
+        .map_err(|_| Status::io_error("Table reader lock poisoned"))
+}
+
+/// A run's current key in the heap, ordered so the smallest key is on top
+struct RunHead {
+    key: Slice,
+    run: usize,
+    comparator: Arc<InternalKeyComparator>,
+}
+
+impl Ord for RunHead {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap; equal keys come from the lower run first
+        self.comparator
+            .compare(other.key.data(), self.key.data())
+            .then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for RunHead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for RunHead {}
+
+impl PartialEq for RunHead {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+/// Streams the entries of a compaction's input files in InternalKey order
+///
+/// Files are grouped into runs of non-overlapping files, so a sorted level
+/// is read as one run and each overlapping L0 file as its own. Only the
+/// current data block of every run is held in memory, and a min-heap of the
+/// runs' current keys picks the next entry in O(log runs).
+pub(crate) struct CompactionInputIterator {
+    runs: Vec<TableRun>,
+    heap: BinaryHeap<RunHead>,
+    creation_time: Option<u64>,
+}
+
+impl CompactionInputIterator {
+    pub(crate) fn new(
+        files: Vec<(FileMetaData, Arc<Mutex<TableReader>>)>,
+        comparator: Arc<dyn Comparator>,
+    ) -> Result<Self> {
+        let mut creation_time = None;
+        for (_, table) in &files {
+            let time = lock(table)?.properties().creation_time;
+            if time > 0 {
+                creation_time = Some(creation_time.map_or(time, |oldest: u64| oldest.min(time)));
+            }
+        }
+        let runs = sorted_runs(files, comparator.as_ref())
+            .into_iter()
+            .map(TableRun::new)
+            .collect::<Result<Vec<_>>>()?;
+
+        let comparator = Arc::new(InternalKeyComparator::new(comparator));
+        let heap = runs
+            .iter()
+            .enumerate()
+            .filter_map(|(run, table_run)| {
+                table_run.current().map(|(key, _)| RunHead {
+                    key: key.clone(),
+                    run,
+                    comparator: Arc::clone(&comparator),
+                })
+            })
+            .collect();
+        Ok(CompactionInputIterator {
+            runs,
+            heap,
+            creation_time,
+        })
+    }
+
+    /// Oldest creation time recorded by the input tables, if any did
+    pub(crate) fn creation_time(&self) -> Option<u64> {
+        self.creation_time
+    }
+
+    /// The next (InternalKey, value) entry, or None once all inputs are read
+    pub(crate) fn next_entry(&mut self) -> Result<Option<(Slice, Slice)>> {
+        let Some(head) = self.heap.pop() else {
+            return Ok(None);
+        };
+        let run = &mut self.runs[head.run];
+        let entry = run.current().cloned();
+        run.advance()?;
+        if let Some((key, _)) = run.current() {
+            self.heap.push(RunHead {
+                key: key.clone(),
+                ..head
+            });
+        }
+        Ok(entry)
+    }
+}
+
+/// Group files into runs of non-overlapping files in key order
+fn sorted_runs(
+    mut files: Vec<(FileMetaData, Arc<Mutex<TableReader>>)>,
+    comparator: &dyn Comparator,
+) -> Vec<Vec<Arc<Mutex<TableReader>>>> {
+    files.sort_by(|a, b| comparator.compare(a.0.smallest_user_key(), b.0.smallest_user_key()));
+
+    let mut runs: Vec<(Slice, Vec<Arc<Mutex<TableReader>>>)> = Vec::new();
+    for (meta, table) in files {
+        let run = runs.iter_mut().find(|(largest, _)| {
+            comparator.compare(largest.data(), meta.smallest_user_key()) == Ordering::Less
+        });
+        let largest = Slice::from(meta.largest_user_key());
+        match run {
+            Some(run) => {
+                run.0 = largest;
+                run.1.push(table);
+            },
+            None => runs.push((largest, vec![table])),
+        }
+    }
+    runs.into_iter().map(|(_, tables)| tables).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
+        table::{format::CompressionType, table_builder::TableBuilder},
+        util::comparator::bytewise_comparator,
+    };
+
+    fn build_table(
+        dir: &TempDir,
+        number: u64,
+        entries: &[(&str, u64)],
+    ) -> (FileMetaData, Arc<Mutex<TableReader>>) {
+        let path = dir.path().join(format!("{number:06}.sst"));
+        let mut builder = TableBuilder::new(&path).unwrap();
+        for (key, seq) in entries {
+            let internal_key = InternalKey::new(Slice::from(*key), *seq, VALUE_TYPE_VALUE);
+            builder
+                .add(&internal_key.encode(), &Slice::from(format!("{key}@{seq}")))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let size = std::fs::metadata(&path).unwrap().len();
+        let meta = FileMetaData::new(
+            number,
+            size,
+            Slice::from(entries[0].0),
+            Slice::from(entries[entries.len() - 1].0),
+        );
+        let reader = TableReader::open(&path, number, None).unwrap();
+        (meta, Arc::new(Mutex::new(reader)))
+    }
+
+    #[test]
+    fn test_sorted_runs() {
+        let dir = TempDir::new().unwrap();
+        let files = vec![
+            build_table(&dir, 1, &[("c", 1), ("d", 1)]),
+            build_table(&dir, 2, &[("a", 2), ("b", 2)]),
+            build_table(&dir, 3, &[("b", 3), ("c", 3)]),
+        ];
+
+        // 1 and 2 don't overlap; 3 overlaps both
+        let runs = sorted_runs(files, &crate::util::BytewiseComparator);
+        let sizes: Vec<usize> = runs.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_merges_in_internal_key_order() {
+        let dir = TempDir::new().unwrap();
+        let many: Vec<(String, u64)> = (0..500).map(|i| (format!("k{i:04}"), 1)).collect();
+        let many: Vec<(&str, u64)> = many.iter().map(|(k, s)| (k.as_str(), *s)).collect();
+        let files = vec![
+            build_table(&dir, 1, &many),
+            build_table(&dir, 2, &[("k0001", 5), ("k0250", 5), ("k9999", 5)]),
+        ];
+
+        let mut input = CompactionInputIterator::new(files, bytewise_comparator()).unwrap();
+        let mut entries = Vec::new();
+        while let Some((key, value)) = input.next_entry().unwrap() {
+            let key = InternalKey::decode(&key).unwrap();
+            entries.push((key.user_key().to_string(), value.to_string()));
+        }
+
+        assert_eq!(entries.len(), 503);
+        assert_eq!(entries[0], ("k0000".to_string(), "k0000@1".to_string()));
+        // Newer versions come first
+        assert_eq!(entries[1], ("k0001".to_string(), "k0001@5".to_string()));
+        assert_eq!(entries[2], ("k0001".to_string(), "k0001@1".to_string()));
+        assert_eq!(entries[502], ("k9999".to_string(), "k9999@5".to_string()));
+        assert!(entries.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn test_merges_many_overlapping_runs() {
+        let dir = TempDir::new().unwrap();
+        let keys: Vec<Vec<(String, u64)>> = (0..6u64)
+            .map(|file| {
+                (0..100)
+                    .map(|i| (format!("k{:04}", i * 3 + file % 3), file + 1))
+                    .collect()
+            })
+            .collect();
+        let files = keys
+            .iter()
+            .enumerate()
+            .map(|(number, entries)| {
+                let entries: Vec<(&str, u64)> =
+                    entries.iter().map(|(k, s)| (k.as_str(), *s)).collect();
+                build_table(&dir, number as u64 + 1, &entries)
+            })
+            .collect();
+
+        let comparator = InternalKeyComparator::new(bytewise_comparator());
+        let mut input = CompactionInputIterator::new(files, bytewise_comparator()).unwrap();
+        let mut entries = Vec::new();
+        while let Some((key, _)) = input.next_entry().unwrap() {
+            entries.push(key);
+        }
+
+        assert_eq!(entries.len(), 600);
+        assert!(
+            entries
+                .windows(2)
+                .all(|pair| comparator.compare(pair[0].data(), pair[1].data()) == Ordering::Less)
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    table::{format::CompressionType, table_builder::TableBuilder},
+    util::{Result, Slice, Status},
+    version::FileMetaData,
+};
+
+/// SSTable being written
+struct OpenOutput {
+    number: u64,
+    path: PathBuf,
+    builder: TableBuilder,
+    smallest: Slice,
+    largest: Slice,
+}
+
+/// Writes the entries of a compaction to SSTables of a target size
+///
+/// A new file is started once the current one reaches `target_file_size`,
+/// but only between user keys: all versions of a key end up in the same
+/// file, so files of a level never share a key.
+pub(crate) struct CompactionOutputs<'a> {
+    db_path: &'a Path,
+    target_file_size: u64,
+    compression: CompressionType,
+    next_file_number: &'a (dyn Fn() -> u64 + Sync),
+    new_builder: &'a dyn Fn(&Path) -> Result<TableBuilder>,
+    current: Option<OpenOutput>,
+    files: Vec<FileMetaData>,
+}
+
+impl<'a> CompactionOutputs<'a> {
+    pub(crate) fn new(
+        db_path: &'a Path,
+        target_file_size: u64,
+        compression: CompressionType,
+        next_file_number: &'a (dyn Fn() -> u64 + Sync),
+        new_builder: &'a dyn Fn(&Path) -> Result<TableBuilder>,
+    ) -> Self {
+        CompactionOutputs {
+            db_path,
+            target_file_size,
+            compression,
+            next_file_number,
+            new_builder,
+            current: None,
+            files: Vec::new(),
+        }
+    }
+
+    /// Append an entry; entries must arrive in InternalKey order
+    pub(crate) fn add(&mut self, user_key: &Slice, key: &Slice, value: &Slice) -> Result<()> {
+        if let Some(ref output) = self.current
+            && output.builder.file_size() >= self.target_file_size
+            && output.largest != *user_key
+        {
+            self.finish_current()?;
+        }
+
+        let output = match self.current {
+            Some(ref mut output) => output,
+            None => {
+                let number = (self.next_file_number)();
+                let path = self.db_path.join(format!("{number:06}.sst"));
+                let builder = (self.new_builder)(&path)?;
+                self.current.insert(OpenOutput {
+                    number,
+                    path,
+                    builder,
+                    smallest: user_key.clone(),
+                    largest: user_key.clone(),
+                })
+            },
+        };
+        output.builder.add(key, value)?;
+        if output.largest != *user_key {
+            output.largest = user_key.clone();
+        }
+        Ok(())
+    }
+
+    /// Finish the last file and return the metadata of all written files
+    pub(crate) fn finish(mut self) -> Result<Vec<FileMetaData>> {
+        self.finish_current()?;
+        Ok(self.files)
+    }
+
+    /// Stop writing and delete the files written so far
+    pub(crate) fn abandon(mut self) {
+        if let Some(output) = self.current.take() {
+            drop(output.builder);
+            let _ = std::fs::remove_file(output.path);
+        }
+        for file in self.files {
+            let _ = std::fs::remove_file(self.db_path.join(format!("{:06}.sst", file.number)));
+        }
+    }
+
+    fn finish_current(&mut self) -> Result<()> {
+        let Some(mut output) = self.current.take() else {
+            return Ok(());
+        };
+        output.builder.finish(self.compression)?;
+
+        let file_size = std::fs::metadata(&output.path)
+            .map_err(|e| Status::io_error(format!("Failed to get file size: {e}")))?
+            .len();
+        let mut file = FileMetaData::new(output.number, file_size, output.smallest, output.largest);
+        file.num_entries = output.builder.num_entries();
+        file.num_deletions = output.builder.num_deletions();
+        file.marked_for_compaction = output.builder.need_compaction();
+        file.creation_time = output.builder.creation_time();
+        file.file_creation_time = output.builder.file_creation_time();
+        self.files.push(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::memtable::memtable::{InternalKey, VALUE_TYPE_VALUE};
+
+    #[test]
+    fn test_outputs_cut_at_target_size_between_user_keys() {
+        let dir = TempDir::new().unwrap();
+        let next_number = AtomicU64::new(10);
+        let next_file_number = || next_number.fetch_add(1, Ordering::SeqCst);
+        let new_builder = |path: &Path| TableBuilder::new(path);
+        let mut outputs = CompactionOutputs::new(
+            dir.path(),
+            8 * 1024,
+            CompressionType::None,
+            &next_file_number,
+            &new_builder,
+        );
+
+        let value = Slice::from(vec![b'v'; 100]);
+        for i in 0..500 {
+            let user_key = Slice::from(format!("key{i:04}"));
+            // Two versions of every key
+            for seq in [2, 1] {
+                let key = InternalKey::new(user_key.clone(), seq, VALUE_TYPE_VALUE).encode();
+                outputs.add(&user_key, &key, &value).unwrap();
+            }
+        }
+        let files = outputs.finish().unwrap();
+
+        assert!(files.len() > 5, "{} files", files.len());
+        let numbers: Vec<u64> = files.iter().map(|f| f.number).collect();
+        assert_eq!(numbers, (10..10 + files.len() as u64).collect::<Vec<_>>());
+        assert_eq!(files[0].smallest, Slice::from("key0000"));
+        assert_eq!(files.last().unwrap().largest, Slice::from("key0499"));
+        for pair in files.windows(2) {
+            assert!(pair[0].largest < pair[1].smallest);
+        }
+        for file in &files {
+            // At most one data block past the target
+            assert!(file.file_size < 8 * 1024 + 6 * 1024, "{}", file.file_size);
+        }
+    }
+
+    #[test]
+    fn test_outputs_empty() {
+        let dir = TempDir::new().unwrap();
+        let next_file_number = || 1;
+        let new_builder = |path: &Path| TableBuilder::new(path);
+        let outputs = CompactionOutputs::new(
+            dir.path(),
+            1024,
+            CompressionType::None,
+            &next_file_number,
+            &new_builder,
+        );
+        assert!(outputs.finish().unwrap().is_empty());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
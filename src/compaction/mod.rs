@@ -1,2 +1,6 @@
 pub mod background_scheduler;
+pub mod compaction_filter;
+pub(crate) mod compaction_iterator;
+pub(crate) mod compaction_outputs;
 pub mod parallel_executor;
+pub(crate) mod retention;
@@ -1,15 +1,28 @@
 use std::{
+    cmp::Ordering,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+    },
 };
 
 use rayon::prelude::*;
 
 use crate::{
-    filter::FilterPolicy,
+    compaction::{
+        compaction_filter::{CompactionFilter, CompactionFilterRunner, CompactionFilterStats},
+        compaction_iterator::CompactionInputIterator,
+        compaction_outputs::CompactionOutputs,
+        retention::VersionRetention,
+    },
     memtable::memtable::InternalKey,
-    table::{format::CompressionType, table_builder::TableBuilder, table_reader::TableReader},
-    util::{Result, Slice, Status},
+    table::{
+        format::CompressionType, table_builder::TableBuilder,
+        table_options::BlockBasedTableOptions, table_properties::CompactOnDeletionOptions,
+        table_reader::TableReader,
+    },
+    util::{Comparator, Result, SliceTransform, Status},
     version::{
         subcompaction::{Subcompaction, SubcompactionConfig, SubcompactionPlanner},
         version_edit::FileMetaData,
@@ -19,9 +32,10 @@ use crate::{
 /// Result from executing a single subcompaction
 #[derive(Debug)]
 pub struct SubcompactionResult {
-    pub file_meta: Option<FileMetaData>,
+    pub files: Vec<FileMetaData>,
     pub bytes_read: u64,
     pub bytes_written: u64,
+    pub filter_stats: CompactionFilterStats,
 }
 
 /// Configuration for parallel compaction execution
@@ -47,7 +61,15 @@ pub struct ParallelCompactionExecutor {
     config: ParallelCompactionConfig,
     db_path: PathBuf,
     compression: CompressionType,
-    filter_policy: Option<Arc<dyn FilterPolicy>>,
+    table_options: BlockBasedTableOptions,
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    comparator: Arc<dyn Comparator>,
+    full_history_ts_low: u64,
+    compaction_filters: Vec<Arc<dyn CompactionFilter>>,
+    target_file_size: u64,
+    output_level: Option<usize>,
+    compact_on_deletion: Option<CompactOnDeletionOptions>,
+    canceled: Option<Arc<AtomicBool>>,
 }
 
 impl ParallelCompactionExecutor {
@@ -55,23 +77,68 @@ impl ParallelCompactionExecutor {
         config: ParallelCompactionConfig,
         db_path: PathBuf,
         compression: CompressionType,
-        filter_policy: Option<Arc<dyn FilterPolicy>>,
+        table_options: BlockBasedTableOptions,
+        prefix_extractor: Option<Arc<dyn SliceTransform>>,
+        comparator: Arc<dyn Comparator>,
     ) -> Self {
         Self {
             config,
             db_path,
             compression,
-            filter_policy,
+            table_options,
+            prefix_extractor,
+            comparator,
+            full_history_ts_low: 0,
+            compaction_filters: Vec::new(),
+            target_file_size: u64::MAX,
+            output_level: None,
+            compact_on_deletion: None,
+            canceled: None,
         }
     }
 
+    /// Garbage-collect versions below this user-defined timestamp
+    pub fn set_full_history_ts_low(&mut self, timestamp: u64) {
+        self.full_history_ts_low = timestamp;
+    }
+
+    /// Start a new output file once the current one reaches this size
+    pub fn set_target_file_size(&mut self, target_file_size: u64) {
+        self.target_file_size = target_file_size;
+    }
+
+    /// Write the outputs to this level instead of the one after the input
+    /// level
+    pub fn set_output_level(&mut self, output_level: usize) {
+        self.output_level = Some(output_level);
+    }
+
+    /// Run these compaction filters on the newest version of every key
+    pub fn set_compaction_filters(&mut self, filters: Vec<Arc<dyn CompactionFilter>>) {
+        self.compaction_filters = filters;
+    }
+
+    /// Mark outputs with too many deletion markers for compaction
+    pub fn set_compact_on_deletion(&mut self, options: Option<CompactOnDeletionOptions>) {
+        self.compact_on_deletion = options;
+    }
+
+    /// Stop with an Incomplete status once this flag is set, deleting the
+    /// files written so far
+    pub fn set_canceled(&mut self, canceled: Option<Arc<AtomicBool>>) {
+        self.canceled = canceled;
+    }
+
     /// Execute compaction with parallel subcompactions
+    ///
+    /// Output files are cut at the target file size, so a subcompaction may
+    /// write several; `next_file_number` is called from the worker threads.
     pub fn execute_compaction(
         &self,
         level: usize,
         level_files: Vec<FileMetaData>,
         next_level_files: Vec<FileMetaData>,
-        next_file_number: &dyn Fn() -> u64,
+        next_file_number: &(dyn Fn() -> u64 + Sync),
     ) -> Result<Vec<SubcompactionResult>> {
         // If parallel execution is disabled or too few files, use sequential
         if !self.config.enable_parallel || level_files.len() + next_level_files.len() < 4 {
@@ -79,7 +146,10 @@ impl ParallelCompactionExecutor {
         }
 
         // Create subcompaction planner
-        let planner = SubcompactionPlanner::new(self.config.subcompaction_config.clone());
+        let planner = SubcompactionPlanner::with_comparator(
+            self.config.subcompaction_config.clone(),
+            Arc::clone(&self.comparator),
+        );
         let subcompactions_opt = planner.plan(&level_files, &next_level_files);
 
         // If planner returns None or only one subcompaction, execute sequentially
@@ -95,195 +165,167 @@ impl ParallelCompactionExecutor {
             },
         };
 
-        // Pre-allocate file numbers for all subcompactions
-        let file_numbers: Vec<u64> = (0..subcompactions.len())
-            .map(|_| next_file_number())
-            .collect();
-
         // Execute subcompactions in parallel using rayon
-        let results: Result<Vec<SubcompactionResult>> = subcompactions
+        let last = subcompactions.len() - 1;
+        let results: Vec<Result<SubcompactionResult>> = subcompactions
             .par_iter()
-            .zip(file_numbers.par_iter())
-            .map(|(subcompaction, &file_number)| {
-                self.execute_subcompaction(level, subcompaction, file_number)
+            .enumerate()
+            .map(|(index, subcompaction)| {
+                self.execute_subcompaction(level, subcompaction, index == last, next_file_number)
             })
             .collect();
 
-        results
+        // Outputs of the finished subcompactions are useless if one failed
+        if let Some(error) = results.iter().find_map(|result| result.as_ref().err()) {
+            let error = error.clone();
+            for result in results.into_iter().flatten() {
+                for file in result.files {
+                    let path = self.db_path.join(format!("{:06}.sst", file.number));
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+            return Err(error);
+        }
+        results.into_iter().collect()
     }
 
     /// Execute a single subcompaction
+    ///
+    /// Adjacent ranges share their boundary key, which belongs to the later
+    /// range; only the last range includes its largest key.
     fn execute_subcompaction(
         &self,
         level: usize,
         subcompaction: &Subcompaction,
-        file_number: u64,
+        is_last: bool,
+        next_file_number: &(dyn Fn() -> u64 + Sync),
     ) -> Result<SubcompactionResult> {
-        // Collect all entries from files in this subcompaction's range
-        let mut all_entries: Vec<(Slice, Slice)> = Vec::new();
-        let mut bytes_read = 0u64;
-
-        // Read from level files
-        for file in &subcompaction.level_files {
-            bytes_read += file.file_size;
-            let entries = self.read_file_in_range(file.number, &subcompaction.range)?;
-            all_entries.extend(entries);
-        }
-
-        // Read from next level files
-        for file in &subcompaction.next_level_files {
-            bytes_read += file.file_size;
-            let entries = self.read_file_in_range(file.number, &subcompaction.range)?;
-            all_entries.extend(entries);
-        }
-
-        // If no entries in this range, return empty result
-        if all_entries.is_empty() {
-            return Ok(SubcompactionResult {
-                file_meta: None,
-                bytes_read,
-                bytes_written: 0,
-            });
-        }
-
-        // Sort and merge entries
-        let merged = self.merge_entries(all_entries, level)?;
-
-        // If all entries were deleted/filtered out
-        if merged.is_empty() {
-            return Ok(SubcompactionResult {
-                file_meta: None,
-                bytes_read,
-                bytes_written: 0,
-            });
-        }
-
-        // Write output SSTable
-        let sst_path = self.db_path.join(format!("{file_number:06}.sst"));
-        let bytes_written = self.write_sstable(&sst_path, &merged)?;
-
-        // Extract user keys for file metadata
-        let smallest_internal = InternalKey::decode(&merged.first().unwrap().0)?;
-        let largest_internal = InternalKey::decode(&merged.last().unwrap().0)?;
-        let smallest = smallest_internal.user_key().clone();
-        let largest = largest_internal.user_key().clone();
-
-        let file_meta = FileMetaData::new(file_number, bytes_written, smallest, largest);
-
-        Ok(SubcompactionResult {
-            file_meta: Some(file_meta),
-            bytes_read,
-            bytes_written,
-        })
-    }
-
-    /// Read entries from a file within a specific key range
-    fn read_file_in_range(
-        &self,
-        file_number: u64,
-        range: &crate::version::subcompaction::KeyRange,
-    ) -> Result<Vec<(Slice, Slice)>> {
-        let sst_path = self.db_path.join(format!("{file_number:06}.sst"));
-        let mut reader = TableReader::open(&sst_path, file_number, None)?;
-
-        // Read all entries from the file
-        let all_entries = reader.scan_all()?;
-
-        // Filter entries by key range
-        let filtered: Vec<(Slice, Slice)> = all_entries
-            .into_iter()
-            .filter(|(key, _)| {
-                // Decode InternalKey to get user key
-                if let Ok(internal_key) = InternalKey::decode(key) {
-                    let user_key = internal_key.user_key();
-                    range.contains(user_key.data())
-                } else {
-                    // If we can't decode, include it (shouldn't happen)
-                    true
-                }
-            })
+        let files: Vec<FileMetaData> = subcompaction
+            .level_files
+            .iter()
+            .chain(&subcompaction.next_level_files)
+            .cloned()
             .collect();
 
-        Ok(filtered)
+        let range = &subcompaction.range;
+        let comparator = self.comparator.as_ref();
+        self.run_compaction(
+            level,
+            files,
+            |user_key| {
+                if comparator.compare(user_key, range.smallest.data()) == Ordering::Less {
+                    return KeyPosition::Before;
+                }
+                match comparator.compare(user_key, range.largest.data()) {
+                    Ordering::Less => KeyPosition::Inside,
+                    Ordering::Equal if is_last => KeyPosition::Inside,
+                    _ => KeyPosition::After,
+                }
+            },
+            next_file_number,
+        )
     }
 
-    /// Merge and deduplicate entries
-    fn merge_entries(
+    /// Stream the merged entries of `files` into output tables
+    ///
+    /// `position` places user keys relative to the part of the key space
+    /// being compacted; entries before it are skipped and the first entry
+    /// after it ends the compaction.
+    fn run_compaction(
         &self,
-        mut all_entries: Vec<(Slice, Slice)>,
         level: usize,
-    ) -> Result<Vec<(Slice, Slice)>> {
-        // Sort by InternalKey (user_key, sequence descending, type)
-        all_entries.sort_by(|a, b| {
-            let key_a = match InternalKey::decode(&a.0) {
-                Ok(k) => k,
-                Err(_) => return a.0.data().cmp(b.0.data()),
-            };
-            let key_b = match InternalKey::decode(&b.0) {
-                Ok(k) => k,
-                Err(_) => return a.0.data().cmp(b.0.data()),
-            };
-
-            // Compare user keys first
-            match key_a.user_key().data().cmp(key_b.user_key().data()) {
-                std::cmp::Ordering::Equal => {
-                    // Same user key: sort by sequence descending (higher first)
-                    match key_b.sequence().cmp(&key_a.sequence()) {
-                        std::cmp::Ordering::Equal => key_a.value_type.cmp(&key_b.value_type),
-                        other => other,
-                    }
-                },
-                other => other,
+        files: Vec<FileMetaData>,
+        position: impl Fn(&[u8]) -> KeyPosition,
+        next_file_number: &(dyn Fn() -> u64 + Sync),
+    ) -> Result<SubcompactionResult> {
+        let bytes_read = files.iter().map(|file| file.file_size).sum();
+        let mut inputs = Vec::with_capacity(files.len());
+        for file in files {
+            let sst_path = self.db_path.join(format!("{:06}.sst", file.number));
+            let reader = self.open_table(&sst_path, file.number)?;
+            inputs.push((file, Arc::new(Mutex::new(reader))));
+        }
+        let mut input = CompactionInputIterator::new(inputs, Arc::clone(&self.comparator))?;
+
+        let output_level = self.output_level.unwrap_or(level + 1);
+        let creation_time = input.creation_time();
+        let new_builder = |path: &Path| {
+            let mut builder = self.create_builder(path, output_level)?;
+            if let Some(creation_time) = creation_time {
+                builder.set_creation_time(creation_time);
             }
-        });
-
-        // Deduplicate: keep only the first (highest sequence) for each user key
-        let mut merged: Vec<(Slice, Slice)> = Vec::new();
-        let mut last_user_key: Option<Slice> = None;
-        let is_bottom_level = level + 1 >= 6;
-
-        for (key, value) in all_entries {
-            if let Ok(internal_key) = InternalKey::decode(&key) {
-                let user_key = internal_key.user_key().clone();
-
-                // Skip duplicates (keep first = highest sequence)
-                if let Some(ref last) = last_user_key
-                    && last == &user_key
-                {
-                    continue;
-                }
+            Ok(builder)
+        };
+        let mut outputs = CompactionOutputs::new(
+            &self.db_path,
+            self.target_file_size,
+            self.compression,
+            next_file_number,
+            &new_builder,
+        );
 
-                // Drop deletion markers at bottom level
-                if internal_key.is_deletion() && is_bottom_level {
-                    last_user_key = Some(user_key);
-                    continue;
-                }
+        // Keep the newest version of each user key (per timestamp above the
+        // full history watermark)
+        let is_bottom_level = output_level >= 6;
+        let mut retention = VersionRetention::new(is_bottom_level, self.full_history_ts_low);
+        let mut filters = CompactionFilterRunner::new(
+            self.compaction_filters.clone(),
+            level,
+            is_bottom_level,
+            self.full_history_ts_low,
+            Arc::clone(&self.comparator),
+        );
 
-                // Keep this entry
-                merged.push((key, value));
-                last_user_key = Some(user_key);
+        while let Some((key, value)) = input.next_entry()? {
+            if self
+                .canceled
+                .as_ref()
+                .is_some_and(|canceled| canceled.load(AtomicOrdering::SeqCst))
+            {
+                outputs.abandon();
+                return Err(Status::incomplete("Manual compaction canceled"));
+            }
+            let Ok(internal_key) = InternalKey::decode(&key) else {
+                continue;
+            };
+            match position(internal_key.user_key().data()) {
+                KeyPosition::Before => continue,
+                KeyPosition::Inside => {},
+                KeyPosition::After => break,
+            }
+            if retention.keep(&internal_key)
+                && let Some((key, value)) = filters.apply(&internal_key, key, value)
+            {
+                outputs.add(internal_key.user_key(), &key, &value)?;
             }
         }
 
-        Ok(merged)
+        let files = outputs.finish()?;
+        Ok(SubcompactionResult {
+            bytes_written: files.iter().map(|file| file.file_size).sum(),
+            files,
+            bytes_read,
+            filter_stats: filters.stats(),
+        })
     }
 
-    /// Write entries to SSTable
-    fn write_sstable(&self, path: &Path, entries: &[(Slice, Slice)]) -> Result<u64> {
-        let mut builder = TableBuilder::new_with_filter(path, self.filter_policy.clone())?;
-
-        for (key, value) in entries {
-            builder.add(key, value)?;
-        }
-
-        builder.finish(self.compression)?;
-
-        // Get file size
-        let file_size = std::fs::metadata(path)
-            .map_err(|e| Status::io_error(format!("Failed to get file size: {e}")))?
-            .len();
+    /// Open an input table with the compaction's table options
+    fn open_table(&self, path: &Path, file_number: u64) -> Result<TableReader> {
+        let mut reader =
+            TableReader::open_with_options(path, file_number, None, &self.table_options)?;
+        reader.set_comparator(Arc::clone(&self.comparator));
+        Ok(reader)
+    }
 
-        Ok(file_size)
+    /// Create a builder for an output table
+    fn create_builder(&self, path: &Path, output_level: usize) -> Result<TableBuilder> {
+        let mut builder = TableBuilder::new_with_options(path, None, self.table_options.clone())?;
+        builder.set_prefix_extractor(self.prefix_extractor.clone());
+        builder.set_level(output_level);
+        builder.set_compression_type(self.compression);
+        builder.set_comparator(Arc::clone(&self.comparator));
+        builder.set_compact_on_deletion(self.compact_on_deletion.clone());
+        Ok(builder)
     }
 
     /// Execute compaction sequentially (fallback)
@@ -292,64 +334,28 @@ impl ParallelCompactionExecutor {
         level: usize,
         level_files: Vec<FileMetaData>,
         next_level_files: Vec<FileMetaData>,
-        next_file_number: &dyn Fn() -> u64,
+        next_file_number: &(dyn Fn() -> u64 + Sync),
     ) -> Result<Vec<SubcompactionResult>> {
-        let mut all_entries: Vec<(Slice, Slice)> = Vec::new();
-        let mut bytes_read = 0u64;
-
-        // Read all files
-        for file in &level_files {
-            bytes_read += file.file_size;
-            let sst_path = self.db_path.join(format!("{:06}.sst", file.number));
-            let mut reader = TableReader::open(&sst_path, file.number, None)?;
-            all_entries.extend(reader.scan_all()?);
-        }
-
-        for file in &next_level_files {
-            bytes_read += file.file_size;
-            let sst_path = self.db_path.join(format!("{:06}.sst", file.number));
-            let mut reader = TableReader::open(&sst_path, file.number, None)?;
-            all_entries.extend(reader.scan_all()?);
-        }
-
-        // Merge entries
-        let merged = self.merge_entries(all_entries, level)?;
-
-        if merged.is_empty() {
-            return Ok(vec![SubcompactionResult {
-                file_meta: None,
-                bytes_read,
-                bytes_written: 0,
-            }]);
-        }
-
-        // Write output file
-        let file_number = next_file_number();
-        let sst_path = self.db_path.join(format!("{file_number:06}.sst"));
-        let bytes_written = self.write_sstable(&sst_path, &merged)?;
-
-        // Extract user keys for file metadata
-        let smallest_internal = InternalKey::decode(&merged.first().unwrap().0)?;
-        let largest_internal = InternalKey::decode(&merged.last().unwrap().0)?;
-        let smallest = smallest_internal.user_key().clone();
-        let largest = largest_internal.user_key().clone();
-
-        let file_meta = FileMetaData::new(file_number, bytes_written, smallest, largest);
-
-        Ok(vec![SubcompactionResult {
-            file_meta: Some(file_meta),
-            bytes_read,
-            bytes_written,
-        }])
+        let files = level_files.into_iter().chain(next_level_files).collect();
+        let result =
+            self.run_compaction(level, files, |_| KeyPosition::Inside, next_file_number)?;
+        Ok(vec![result])
     }
 }
 
+/// Where a user key lies relative to the key range of a compaction
+enum KeyPosition {
+    Before,
+    Inside,
+    After,
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::TempDir;
 
     use super::*;
-    use crate::memtable::memtable::VALUE_TYPE_VALUE;
+    use crate::{memtable::memtable::VALUE_TYPE_VALUE, util::Slice};
 
     fn create_test_file(
         db_path: &Path,
@@ -409,7 +415,9 @@ mod tests {
             config,
             db_path.to_path_buf(),
             CompressionType::None,
+            BlockBasedTableOptions::default(),
             None,
+            crate::util::comparator::bytewise_comparator(),
         );
 
         use std::sync::atomic::{AtomicU64, Ordering};
@@ -421,6 +429,81 @@ mod tests {
             .unwrap();
 
         assert_eq!(results.len(), 1);
-        assert!(results[0].file_meta.is_some());
+        assert_eq!(results[0].files.len(), 1);
+    }
+
+    #[test]
+    fn test_subcompactions_split_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path();
+
+        // Four overlapping files of 400 keys each, shifted by 100 keys
+        let files: Vec<FileMetaData> = (1..=4)
+            .map(|number| {
+                let keys: Vec<String> = (0..400)
+                    .map(|i| format!("key{:04}", i + 100 * (number - 1)))
+                    .collect();
+                let entries: Vec<(&str, &str, u64)> = keys
+                    .iter()
+                    .map(|key| (key.as_str(), "value_padding_padding", number))
+                    .collect();
+                create_test_file(db_path, number, &entries).unwrap()
+            })
+            .collect();
+
+        let config = ParallelCompactionConfig {
+            max_threads: 4,
+            subcompaction_config: SubcompactionConfig {
+                min_file_size: 0,
+                target_subcompactions: 4,
+                enable_parallel: true,
+            },
+            enable_parallel: true,
+        };
+        let mut executor = ParallelCompactionExecutor::new(
+            config,
+            db_path.to_path_buf(),
+            CompressionType::None,
+            BlockBasedTableOptions::default(),
+            None,
+            crate::util::comparator::bytewise_comparator(),
+        );
+        executor.set_target_file_size(2048);
+
+        use std::sync::atomic::{AtomicU64, Ordering};
+        let next_file = AtomicU64::new(100);
+        let results = executor
+            .execute_compaction(0, files[..2].to_vec(), files[2..].to_vec(), &|| {
+                next_file.fetch_add(1, Ordering::SeqCst)
+            })
+            .unwrap();
+
+        let mut outputs: Vec<FileMetaData> = results
+            .into_iter()
+            .flat_map(|result| result.files)
+            .collect();
+        assert!(outputs.len() > 4, "{} files", outputs.len());
+        outputs.sort_by(|a, b| a.smallest.data().cmp(b.smallest.data()));
+        for pair in outputs.windows(2) {
+            assert!(pair[0].largest.data() < pair[1].smallest.data());
+        }
+
+        // Every key is written exactly once, with its newest value
+        let mut written = Vec::new();
+        for file in &outputs {
+            let path = db_path.join(format!("{:06}.sst", file.number));
+            let entries = executor
+                .open_table(&path, file.number)
+                .unwrap()
+                .scan_all()
+                .unwrap();
+            for (key, _) in entries {
+                let key = InternalKey::decode(&key).unwrap();
+                let index: u64 = key.user_key().to_string()[3..].parse().unwrap();
+                assert_eq!(key.sequence(), (index / 100).min(3) + 1);
+                written.push(index);
+            }
+        }
+        assert_eq!(written, (0..700).collect::<Vec<_>>());
     }
 }
@@ -0,0 +1,126 @@
+use crate::{memtable::memtable::InternalKey, util::Slice};
+
+/// Decides which entries of a compaction's sorted input are kept
+///
+/// Entries arrive in InternalKey order, so all versions of a user key are
+/// adjacent, newest first. Without timestamps only the newest version of a
+/// key survives. With user-defined timestamps the newest version at every
+/// timestamp is kept down to `full_history_ts_low`; below it only the
+/// newest version, the one a read at the watermark sees, is kept.
+///
+/// Deletion markers that would be kept as the oldest surviving version are
+/// dropped at the bottommost level, where nothing older can remain.
+pub(crate) struct VersionRetention {
+    bottommost: bool,
+    full_history_ts_low: u64,
+    last_user_key: Option<Slice>,
+    last_timestamp: Option<u64>,
+    /// The newest version below the watermark was seen for the current key
+    history_done: bool,
+}
+
+impl VersionRetention {
+    pub(crate) fn new(bottommost: bool, full_history_ts_low: u64) -> Self {
+        VersionRetention {
+            bottommost,
+            full_history_ts_low,
+            last_user_key: None,
+            last_timestamp: None,
+            history_done: false,
+        }
+    }
+
+    /// Check whether the next entry of the input is kept
+    pub(crate) fn keep(&mut self, key: &InternalKey) -> bool {
+        let new_key = self.last_user_key.as_ref() != Some(key.user_key());
+        if new_key {
+            self.last_user_key = Some(key.user_key().clone());
+            self.last_timestamp = None;
+            self.history_done = false;
+        }
+
+        let Some(timestamp) = key.timestamp() else {
+            return new_key && !(key.is_deletion() && self.bottommost);
+        };
+
+        // Older entries at the same timestamp are shadowed
+        if self.history_done || self.last_timestamp == Some(timestamp) {
+            return false;
+        }
+        self.last_timestamp = Some(timestamp);
+        if timestamp >= self.full_history_ts_low {
+            return true;
+        }
+
+        // Newest version below the watermark; older ones are unreachable
+        self.history_done = true;
+        !(key.is_deletion() && self.bottommost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memtable::memtable::{VALUE_TYPE_DELETION, VALUE_TYPE_VALUE};
+
+    fn kept(retention: &mut VersionRetention, keys: &[InternalKey]) -> Vec<(u64, u64)> {
+        keys.iter()
+            .filter(|key| retention.keep(key))
+            .map(|key| (key.timestamp().unwrap_or(0), key.sequence()))
+            .collect()
+    }
+
+    #[test]
+    fn test_retention_without_timestamps() {
+        let keys = [
+            InternalKey::new(Slice::from("a"), 3, VALUE_TYPE_VALUE),
+            InternalKey::new(Slice::from("a"), 1, VALUE_TYPE_VALUE),
+            InternalKey::new(Slice::from("b"), 2, VALUE_TYPE_DELETION),
+        ];
+        assert_eq!(
+            kept(&mut VersionRetention::new(false, 0), &keys),
+            vec![(0, 3), (0, 2)]
+        );
+        assert_eq!(
+            kept(&mut VersionRetention::new(true, 0), &keys),
+            vec![(0, 3)]
+        );
+    }
+
+    #[test]
+    fn test_retention_full_history_ts_low() {
+        let version = |ts, seq, value_type| {
+            InternalKey::with_timestamp(Slice::from("k"), ts, seq, value_type)
+        };
+        let keys = [
+            version(40, 5, VALUE_TYPE_VALUE),
+            version(30, 4, VALUE_TYPE_VALUE),
+            version(30, 3, VALUE_TYPE_VALUE),
+            version(20, 2, VALUE_TYPE_DELETION),
+            version(10, 1, VALUE_TYPE_VALUE),
+        ];
+
+        // No watermark: every timestamp keeps its newest version
+        assert_eq!(
+            kept(&mut VersionRetention::new(false, 0), &keys),
+            vec![(40, 5), (30, 4), (20, 2), (10, 1)]
+        );
+
+        // Versions below 35 collapse into the newest one
+        assert_eq!(
+            kept(&mut VersionRetention::new(false, 35), &keys),
+            vec![(40, 5), (30, 4)]
+        );
+
+        // A deletion as the newest version below the watermark goes away at
+        // the bottommost level, along with everything older
+        assert_eq!(
+            kept(&mut VersionRetention::new(true, 25), &keys),
+            vec![(40, 5), (30, 4)]
+        );
+        assert_eq!(
+            kept(&mut VersionRetention::new(false, 25), &keys),
+            vec![(40, 5), (30, 4), (20, 2)]
+        );
+    }
+}
@@ -1,25 +1,53 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     cache::{LRUCache, TableCache},
-    column_family::{ColumnFamilyHandle, ColumnFamilySet},
+    column_family::{
+        ColumnFamilyDescriptor, ColumnFamilyHandle, ColumnFamilyOptions, ColumnFamilySet,
+        DEFAULT_COLUMN_FAMILY_NAME,
+    },
     compaction::{
         background_scheduler::BackgroundCompactionScheduler,
+        compaction_filter::{CompactionFilter, CompactionFilterRunner, CompactionFilterStats},
+        compaction_iterator::CompactionInputIterator,
+        compaction_outputs::CompactionOutputs,
         parallel_executor::{ParallelCompactionConfig, ParallelCompactionExecutor},
+        retention::VersionRetention,
+    },
+    filter::FilterPolicy,
+    memtable::memtable::{
+        InternalKey, LookupResult, VALUE_TYPE_DELETION, VALUE_TYPE_ENTITY, VALUE_TYPE_VALUE,
     },
-    filter::{BloomFilterPolicy, FilterPolicy},
-    memtable::memtable::InternalKey,
     merge::MergeOperator,
-    table::{CompressionType, TableBuilder, TableReader},
-    util::{Result, Slice, Status},
-    version::{FileMetaData, VersionEdit, subcompaction::SubcompactionConfig},
+    options::{self, ColumnFamilyOptionsRecord, OptionsFile},
+    table::{
+        BlockBasedTableOptions, CompactOnDeletionOptions, CompressionType, TableBuilder,
+        TableReader,
+    },
+    util::{
+        BytewiseComparator, Comparator, Result, Slice, SliceTransform, Status,
+        comparator::{builtin_comparator, bytewise_comparator, comparator_name_matches},
+    },
+    version::{
+        FileMetaData, NUM_LEVELS, VersionEdit,
+        compaction_picker::{Compaction, CompactionPri, CompactionStyle, files_in_range},
+        fifo_compaction_picker::CompactionOptionsFIFO,
+        subcompaction::SubcompactionConfig,
+        universal_compaction_picker::CompactionOptionsUniversal,
+    },
     wal,
+    wide_columns::WideColumns,
 };
 
 #[derive(Clone, Default)]
@@ -29,8 +57,17 @@ pub struct WriteOptions {
 
 #[derive(Clone)]
 pub struct ReadOptions {
+    /// Verify block checksums on SSTable reads, even for column families
+    /// whose table options skip verification
     pub verify_checksums: bool,
     pub fill_cache: bool,
+    /// Stop iteration once keys no longer share the seek target's prefix
+    /// (requires a prefix extractor). Lets seeks skip MemTables and SSTables
+    /// whose prefix filters exclude the target's prefix.
+    pub prefix_same_as_start: bool,
+    /// Read as of this user-defined timestamp (column families whose
+    /// comparator has timestamps only). None reads the latest versions.
+    pub timestamp: Option<u64>,
 }
 
 impl Default for ReadOptions {
@@ -38,11 +75,70 @@ impl Default for ReadOptions {
         ReadOptions {
             verify_checksums: false,
             fill_cache: true,
+            prefix_same_as_start: false,
+            timestamp: None,
         }
     }
 }
 
-#[derive(Clone)]
+/// How `compact_range_opt` treats the files already in the last level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BottommostLevelCompaction {
+    /// Leave them as they are
+    Skip,
+    /// Rewrite them if the column family has compaction filters to run
+    #[default]
+    IfHaveCompactionFilter,
+    /// Always rewrite them
+    Force,
+}
+
+/// Options of `compact_range_opt` and `compact_range_cf_opt`
+#[derive(Debug, Clone)]
+pub struct CompactRangeOptions {
+    /// Wait for the running automatic compactions, and start no new ones
+    /// until done
+    pub exclusive_manual_compaction: bool,
+    /// Move the files of the level the compaction ends in to `target_level`
+    pub change_level: bool,
+    /// Level `change_level` moves the files to; None for the highest empty
+    /// level above them that they fit in
+    pub target_level: Option<usize>,
+    /// Whether the range's files already in the last level are rewritten
+    pub bottommost_level_compaction: BottommostLevelCompaction,
+    /// Set to stop the compaction, failing it with an Incomplete status
+    ///
+    /// A step merging files stops before its next input entry and
+    /// deletes the files it has written; the steps that finished before
+    /// are kept.
+    pub canceled: Option<Arc<AtomicBool>>,
+}
+
+impl Default for CompactRangeOptions {
+    fn default() -> Self {
+        CompactRangeOptions {
+            exclusive_manual_compaction: true,
+            change_level: false,
+            target_level: None,
+            bottommost_level_compaction: BottommostLevelCompaction::default(),
+            canceled: None,
+        }
+    }
+}
+
+/// Database options
+///
+/// Written to the OPTIONS file at open, apart from the user-supplied
+/// objects (filter policy, prefix extractor, merge operator, compaction
+/// filter, comparator).
+///
+/// The column family settings here (write buffer, table, compaction and
+/// write stall triggers) seed the default CF until its options are recorded
+/// by `set_options` or `open_cf`. Later opens restore the recorded
+/// `ColumnFamilyOptions` instead, keeping only the user-supplied objects
+/// from here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DBOptions {
     pub create_if_missing: bool,
     pub error_if_exists: bool,
@@ -51,19 +147,47 @@ pub struct DBOptions {
     pub table_cache_size: usize,            // Number of table files to keep open
     pub compression_type: CompressionType,  // Compression algorithm for blocks
     pub filter_bits_per_key: Option<usize>, // Bloom filter bits per key (None = disabled)
+    #[serde(skip)]
+    pub filter_policy: Option<Arc<dyn FilterPolicy>>, // Overrides filter_bits_per_key if set
+    pub table_options: BlockBasedTableOptions, // SSTable options of the default CF
+    #[serde(skip)]
+    pub prefix_extractor: Option<Arc<dyn SliceTransform>>, // Key prefix for prefix Bloom filters
+    pub memtable_prefix_bloom_size_ratio: f64, // MemTable prefix Bloom size / write_buffer_size
     pub enable_subcompaction: bool,         // Enable parallel subcompaction
     pub subcompaction_min_size: u64,        // Minimum size to trigger subcompaction (bytes)
     pub parallel_compaction_threads: usize, /* Number of threads for parallel compaction (0 =
                                              * disable) */
+    #[serde(skip)]
     pub merge_operator: Option<Arc<dyn MergeOperator>>, // Merge operator for this database
+    #[serde(skip)]
+    pub compaction_filter: Option<Arc<dyn CompactionFilter>>, /* Compaction filter of the
+                                                               * default CF */
     // Background compaction settings
     pub enable_background_compaction: bool, // Enable automatic background compaction
-    pub compaction_check_interval_ms: u64,  // How often to check if compaction is needed
+    #[deprecated(note = "unused: compaction is checked after every flush")]
+    pub compaction_check_interval_ms: u64, // How often to check if compaction is needed
     pub l0_compaction_trigger: usize,       // Number of L0 files that triggers compaction
-    pub l0_stop_writes_trigger: usize,      // Number of L0 files that stops writes (write stall)
+    pub l0_stop_writes_trigger: usize,      // L0 files stalling writes (default CF seed)
+    pub max_bytes_for_level_base: u64,      // Target size of level 1
+    pub max_bytes_for_level_multiplier: u64, // Target size growth per level
+    pub level_compaction_dynamic_level_bytes: bool, // Size levels backward from the last one
+    pub target_file_size_base: u64,         // Compaction output file size in level 1
+    pub target_file_size_multiplier: u64,   // Output file size growth per level
+    pub compaction_pri: CompactionPri,      // Order files of a level are compacted in
+    pub compaction_style: CompactionStyle,  // Leveled, universal or FIFO compaction
+    pub compaction_options_universal: CompactionOptionsUniversal, // Universal compaction tuning
+    pub compaction_options_fifo: CompactionOptionsFIFO, // FIFO size budget and merging
+    pub ttl: u64,                           // Data age dropped or compacted down (0 = never)
+    pub periodic_compaction_seconds: u64,   // Seconds until a file is rewritten (0 = never)
+    pub compact_on_deletion: Option<CompactOnDeletionOptions>, // Mark files dense in deletions
+    pub create_missing_column_families: bool, /* open_cf creates CFs that don't
+                                             * exist yet */
+    #[serde(skip)]
+    pub comparator: Arc<dyn Comparator>, // Order of user keys in the default CF
 }
 
 impl Default for DBOptions {
+    #[allow(deprecated)]
     fn default() -> Self {
         DBOptions {
             create_if_missing: true,
@@ -74,20 +198,105 @@ impl Default for DBOptions {
             table_cache_size: 100, // Keep up to 100 table files open
             compression_type: CompressionType::Snappy, // Snappy by default
             filter_bits_per_key: Some(10), // ~1% false positive rate
+            filter_policy: None,   // Bloom filter from filter_bits_per_key
+            table_options: BlockBasedTableOptions::default(),
+            prefix_extractor: None,
+            memtable_prefix_bloom_size_ratio: 0.0,
             enable_subcompaction: true,
             subcompaction_min_size: 10 * 1024 * 1024, // 10 MB
             parallel_compaction_threads: 4,           // Use 4 threads for parallel compaction
             merge_operator: None,                     // No merge operator by default
+            compaction_filter: None,                  // No compaction filter by default
             // Background compaction defaults
             enable_background_compaction: true, // Enable by default
             compaction_check_interval_ms: 1000, // Check every 1 second
             l0_compaction_trigger: 4,           // Trigger compaction when L0 has 4+ files
             l0_stop_writes_trigger: 12,         /* Stop writes when L0 has 12+ files (safety
                                                  * threshold) */
+            max_bytes_for_level_base: 10 * 1024 * 1024, // 10MB
+            max_bytes_for_level_multiplier: 10,
+            level_compaction_dynamic_level_bytes: false,
+            target_file_size_base: 64 * 1024 * 1024, // 64MB
+            target_file_size_multiplier: 1,
+            compaction_pri: CompactionPri::MinOverlappingRatio,
+            compaction_style: CompactionStyle::Level,
+            compaction_options_universal: CompactionOptionsUniversal::default(),
+            compaction_options_fifo: CompactionOptionsFIFO::default(),
+            ttl: 0,
+            periodic_compaction_seconds: 0,
+            compact_on_deletion: None,
+            create_missing_column_families: false,
+            comparator: bytewise_comparator(),
         }
     }
 }
 
+impl DBOptions {
+    /// Check the options for values the database can't run with
+    ///
+    /// The column family settings are checked as the default CF options
+    /// they seed.
+    pub fn validate(&self) -> Result<()> {
+        #[allow(deprecated)]
+        if self.compaction_check_interval_ms == 0 {
+            return Err(Status::invalid_argument(
+                "compaction_check_interval_ms must be positive",
+            ));
+        }
+        self.default_cf_options().validate()
+    }
+
+    /// Options of the default CF when it is not opened from a descriptor
+    pub(crate) fn default_cf_options(&self) -> ColumnFamilyOptions {
+        ColumnFamilyOptions {
+            write_buffer_size: self.write_buffer_size,
+            compression_type: self.compression_type,
+            filter_bits_per_key: self.filter_bits_per_key,
+            filter_policy: self.filter_policy.clone(),
+            block_cache_size: self.block_cache_size,
+            prefix_extractor: self.prefix_extractor.clone(),
+            memtable_prefix_bloom_size_ratio: self.memtable_prefix_bloom_size_ratio,
+            table_options: self.table_options.clone(),
+            merge_operator: self.merge_operator.clone(),
+            compaction_filter: self.compaction_filter.clone(),
+            l0_compaction_trigger: self.l0_compaction_trigger,
+            l0_stop_writes_trigger: self.l0_stop_writes_trigger,
+            max_bytes_for_level_base: self.max_bytes_for_level_base,
+            max_bytes_for_level_multiplier: self.max_bytes_for_level_multiplier,
+            level_compaction_dynamic_level_bytes: self.level_compaction_dynamic_level_bytes,
+            target_file_size_base: self.target_file_size_base,
+            target_file_size_multiplier: self.target_file_size_multiplier,
+            compaction_pri: self.compaction_pri,
+            compaction_style: self.compaction_style,
+            compaction_options_universal: self.compaction_options_universal.clone(),
+            compaction_options_fifo: self.compaction_options_fifo.clone(),
+            ttl: self.ttl,
+            periodic_compaction_seconds: self.periodic_compaction_seconds,
+            compact_on_deletion: self.compact_on_deletion.clone(),
+            comparator: self.comparator.clone(),
+        }
+    }
+}
+
+/// A non-default Column Family recorded in the MANIFEST
+struct RecoveredColumnFamily {
+    name: String,
+    /// None for CFs created before options were persisted
+    options: Option<ColumnFamilyOptions>,
+    /// None for CFs created before comparators were recorded
+    comparator: Option<String>,
+}
+
+/// A decoded WAL record; `value` is empty for deletions
+struct WalRecord {
+    cf_id: u32,
+    sequence: u64,
+    key: Slice,
+    timestamp: Option<u64>,
+    value_type: u8,
+    value: Slice,
+}
+
 pub struct DB {
     /// Manages all column families
     column_families: Arc<ColumnFamilySet>,
@@ -95,8 +304,8 @@ pub struct DB {
     wal: Arc<RwLock<Option<wal::Writer>>>,
     /// Database directory path
     db_path: PathBuf,
-    /// Global database options
-    options: DBOptions,
+    /// Global database options, swapped by `set_db_options`
+    options: RwLock<DBOptions>,
     /// Block cache shared across all CFs: (file_number, block_offset) ->
     /// block_data
     block_cache: LRUCache<(u64, u64), Vec<u8>>,
@@ -104,10 +313,34 @@ pub struct DB {
     table_cache: Arc<TableCache>,
     /// Database-wide statistics
     statistics: Arc<crate::statistics::Statistics>,
+    /// Serializes option changes and writers of OPTIONS files
+    options_lock: Mutex<()>,
 }
 
 impl DB {
     pub fn open(name: &str, options: DBOptions) -> Result<Self> {
+        Self::open_internal(name, options, None)
+    }
+
+    /// Open a database with an explicit list of column families
+    ///
+    /// The list must contain the default CF and every CF already in the
+    /// database; each CF is opened with the options of its descriptor.
+    /// CFs in the list that don't exist yet are created if
+    /// `create_missing_column_families` is set, and are an error otherwise.
+    pub fn open_cf(
+        name: &str,
+        options: DBOptions,
+        descriptors: Vec<ColumnFamilyDescriptor>,
+    ) -> Result<Self> {
+        Self::open_internal(name, options, Some(descriptors))
+    }
+
+    fn open_internal(
+        name: &str,
+        options: DBOptions,
+        descriptors: Option<Vec<ColumnFamilyDescriptor>>,
+    ) -> Result<Self> {
         let db_path = Path::new(name);
 
         // Create directory if needed
@@ -119,14 +352,41 @@ impl DB {
         if options.error_if_exists && db_path.exists() {
             return Err(Status::invalid_argument("Database already exists"));
         }
-
-        // Create ColumnFamilySet with default CF using global options
-        let default_cf_options = crate::column_family::ColumnFamilyOptions {
-            write_buffer_size: options.write_buffer_size,
-            compression_type: options.compression_type,
-            filter_bits_per_key: options.filter_bits_per_key,
-            block_cache_size: options.block_cache_size,
+        options.validate()?;
+
+        // Column families already in the database
+        let existing_cfs = Self::read_column_families(db_path)?;
+
+        // Create ColumnFamilySet with default CF using global options, or
+        // the options last recorded for it (by `set_options` or `open_cf`)
+        let seed = options.default_cf_options();
+        let mut default_cf_options = match Self::read_default_cf_options(db_path)? {
+            Some(recorded) => ColumnFamilyOptions {
+                filter_policy: seed.filter_policy,
+                prefix_extractor: seed.prefix_extractor,
+                merge_operator: seed.merge_operator,
+                compaction_filter: seed.compaction_filter,
+                comparator: seed.comparator,
+                ..recorded
+            },
+            None => seed,
         };
+
+        // With explicit descriptors the default CF takes its options from
+        // its descriptor, and the rest must cover every existing CF
+        let mut descriptors = descriptors;
+        if let Some(descriptors) = descriptors.as_mut() {
+            let default_index = descriptors
+                .iter()
+                .position(|d| d.name == DEFAULT_COLUMN_FAMILY_NAME)
+                .ok_or_else(|| {
+                    Status::invalid_argument("Default column family must be specified")
+                })?;
+            default_cf_options = descriptors.remove(default_index).options;
+            Self::check_descriptors(&options, descriptors, &existing_cfs)?;
+        }
+
+        default_cf_options.validate()?;
         let cf_set = Arc::new(ColumnFamilySet::new(name, default_cf_options.clone())?);
 
         let wal_path = db_path.join("wal.log");
@@ -140,7 +400,25 @@ impl DB {
         }
 
         // Recover Column Families from MANIFEST before WAL recovery
-        Self::recover_column_families(db_path, &cf_set, &default_cf_options)?;
+        Self::recover_column_families(
+            &cf_set,
+            &existing_cfs,
+            descriptors.as_deref(),
+            &default_cf_options,
+        )?;
+
+        for (cf_id, timestamp) in Self::read_full_history_ts_low(db_path)? {
+            let cf_handle = ColumnFamilyHandle::new(cf_id, format!("cf_{}", cf_id));
+            if let Some(cf) = cf_set.get_cf(&cf_handle) {
+                cf.set_full_history_ts_low(timestamp);
+            }
+        }
+
+        // Reject options the existing data can't be read with
+        if let Some((_, path)) = options::latest_options_file(db_path)? {
+            let current = OptionsFile::new(&options, Self::options_records(&cf_set));
+            OptionsFile::load(path)?.check_compatibility(&current)?;
+        }
 
         // Recover from WAL if exists (handles all CFs)
         if wal_path.exists() {
@@ -153,7 +431,8 @@ impl DB {
         // Initialize block cache
         let block_cache = LRUCache::new(options.block_cache_size);
 
-        // Initialize table cache
+        // Initialize table cache. Readers load filters with the filter policy
+        // of the CF owning the table, which `get_table` passes on open.
         let table_cache = Arc::new(TableCache::new(
             options.table_cache_size,
             db_path.to_path_buf(),
@@ -163,15 +442,85 @@ impl DB {
         // Initialize statistics
         let statistics = Arc::new(crate::statistics::Statistics::new());
 
-        Ok(DB {
+        let db = DB {
             column_families: cf_set,
             wal: Arc::new(RwLock::new(Some(wal_writer))),
             db_path: db_path.to_path_buf(),
-            options,
+            options: RwLock::new(options),
             block_cache,
             table_cache,
             statistics,
-        })
+            options_lock: Mutex::new(()),
+        };
+
+        if let Some(descriptors) = descriptors {
+            // Record the options existing CFs were opened with, so a later
+            // open without descriptors restores them, then create the rest
+            let mut edit = VersionEdit::new();
+            edit.set_column_family_options(0, default_cf_options.encode()?);
+            let mut missing = Vec::new();
+            for descriptor in descriptors {
+                match db.column_families.get_cf_by_name(&descriptor.name) {
+                    Some(cf) => {
+                        edit.set_column_family_options(cf.id(), descriptor.options.encode()?)
+                    },
+                    None => missing.push(descriptor),
+                }
+            }
+            db.log_to_manifest(edit)?;
+            for descriptor in missing {
+                db.create_column_family(&descriptor.name, descriptor.options)?;
+            }
+        }
+
+        db.write_options_file()?;
+        Ok(db)
+    }
+
+    /// OPTIONS file records of all column families, default first
+    fn options_records(cf_set: &ColumnFamilySet) -> Vec<ColumnFamilyOptionsRecord> {
+        let mut handles = cf_set.list_column_families();
+        handles.sort_by_key(|handle| handle.id());
+        handles
+            .iter()
+            .filter_map(|handle| cf_set.get_cf(handle))
+            .map(|cf| ColumnFamilyOptionsRecord::new(cf.handle().name(), &cf.options()))
+            .collect()
+    }
+
+    /// Record the options in effect in a new OPTIONS file
+    fn write_options_file(&self) -> Result<()> {
+        let _guard = self.options_lock.lock();
+        self.save_options_file()
+    }
+
+    /// Write the OPTIONS file, with `options_lock` held
+    fn save_options_file(&self) -> Result<()> {
+        let file = OptionsFile::new(
+            &self.options.read(),
+            Self::options_records(&self.column_families),
+        );
+        options::write_options_file(&self.db_path, &file)?;
+        Ok(())
+    }
+
+    /// List the column families of the database at `name`
+    ///
+    /// Reads the MANIFEST without opening the database. The default CF
+    /// comes first, followed by the others in creation order.
+    pub fn list_column_families(name: &str) -> Result<Vec<String>> {
+        let db_path = Path::new(name);
+        if !db_path.join("MANIFEST").exists() {
+            return Err(Status::not_found(format!("No database at {name}")));
+        }
+
+        let mut names = vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()];
+        names.extend(
+            Self::read_column_families(db_path)?
+                .into_values()
+                .map(|cf| cf.name),
+        );
+        Ok(names)
     }
 
     /// Get TableReader from cache
@@ -181,27 +530,52 @@ impl DB {
     ///
     /// Without table caching, random reads are limited to ~2-3K ops/sec due to
     /// file open overhead. With caching, we achieve 50K+ ops/sec.
+    ///
+    /// Tables are opened with the table options and comparator of the CF
+    /// owning them.
     #[inline]
-    fn get_table(&self, file_number: u64) -> Result<Arc<std::sync::Mutex<TableReader>>> {
-        self.table_cache.get_table(file_number)
+    fn get_table(
+        &self,
+        cf: &crate::column_family::ColumnFamilyData,
+        file_number: u64,
+    ) -> Result<Arc<std::sync::Mutex<TableReader>>> {
+        self.table_cache.get_table_with_options(
+            file_number,
+            cf.table_options(),
+            &cf.options().comparator,
+        )
     }
 
-    /// Recover Column Families from MANIFEST
-    fn recover_column_families(
-        db_path: &Path,
-        cf_set: &Arc<crate::column_family::ColumnFamilySet>,
-        default_cf_options: &crate::column_family::ColumnFamilyOptions,
-    ) -> Result<()> {
+    /// Creation time in a table's properties (0 if unknown or unreadable)
+    fn table_creation_time(
+        &self,
+        cf: &crate::column_family::ColumnFamilyData,
+        file_number: u64,
+    ) -> u64 {
+        self.get_table(cf, file_number)
+            .ok()
+            .and_then(|table| {
+                table
+                    .lock()
+                    .ok()
+                    .map(|table| table.properties().creation_time)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Read the non-default Column Families recorded in the MANIFEST
+    ///
+    /// Returns the CFs that weren't dropped by id.
+    fn read_column_families(db_path: &Path) -> Result<BTreeMap<u32, RecoveredColumnFamily>> {
         let manifest_path = db_path.join("MANIFEST");
+        let mut cf_metadata = BTreeMap::new();
 
         // If MANIFEST doesn't exist, nothing to recover
         if !manifest_path.exists() {
-            return Ok(());
+            return Ok(cf_metadata);
         }
 
         let mut reader = wal::Reader::new(&manifest_path)?;
-        let mut cf_metadata: std::collections::HashMap<u32, String> =
-            std::collections::HashMap::new();
 
         // Read all VersionEdit records and collect CF operations
         while let Some(record) = reader.read_record()? {
@@ -209,11 +583,34 @@ impl DB {
                 continue;
             }
 
-            let edit = crate::version::VersionEdit::decode(&record)?;
+            let edit = VersionEdit::decode(&record)?;
 
             // Process CF creates
             for (cf_id, cf_name) in &edit.created_column_families {
-                cf_metadata.insert(*cf_id, cf_name.clone());
+                if *cf_id != 0 {
+                    cf_metadata.insert(
+                        *cf_id,
+                        RecoveredColumnFamily {
+                            name: cf_name.clone(),
+                            options: None,
+                            comparator: None,
+                        },
+                    );
+                }
+            }
+
+            // Process CF options (the latest record wins)
+            for (cf_id, options) in &edit.column_family_options {
+                if let Some(cf) = cf_metadata.get_mut(cf_id) {
+                    cf.options = Some(ColumnFamilyOptions::decode(options)?);
+                }
+            }
+
+            // Process CF comparators
+            for (cf_id, name) in &edit.column_family_comparators {
+                if let Some(cf) = cf_metadata.get_mut(cf_id) {
+                    cf.comparator = Some(name.clone());
+                }
             }
 
             // Process CF drops
@@ -222,15 +619,146 @@ impl DB {
             }
         }
 
-        // Recreate all non-default CFs (default CF already exists)
-        for (cf_id, cf_name) in cf_metadata {
-            if cf_id == 0 {
-                // Skip default CF (already exists)
+        Ok(cf_metadata)
+    }
+
+    /// Read the options last recorded for the default CF from the MANIFEST
+    ///
+    /// None until they are changed or the database is opened with
+    /// descriptors; the default CF is seeded from `DBOptions` then.
+    fn read_default_cf_options(db_path: &Path) -> Result<Option<ColumnFamilyOptions>> {
+        let manifest_path = db_path.join("MANIFEST");
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+
+        let mut options = None;
+        let mut reader = wal::Reader::new(&manifest_path)?;
+        while let Some(record) = reader.read_record()? {
+            if record.is_empty() {
                 continue;
             }
 
+            let edit = VersionEdit::decode(&record)?;
+            for (cf_id, recorded) in &edit.column_family_options {
+                if *cf_id == 0 {
+                    options = Some(recorded.clone());
+                }
+            }
+        }
+
+        options
+            .map(|recorded| ColumnFamilyOptions::decode(&recorded))
+            .transpose()
+    }
+
+    /// Read the latest full history watermark of each CF from the MANIFEST
+    fn read_full_history_ts_low(db_path: &Path) -> Result<BTreeMap<u32, u64>> {
+        let manifest_path = db_path.join("MANIFEST");
+        let mut watermarks = BTreeMap::new();
+        if !manifest_path.exists() {
+            return Ok(watermarks);
+        }
+
+        let mut reader = wal::Reader::new(&manifest_path)?;
+        while let Some(record) = reader.read_record()? {
+            if record.is_empty() {
+                continue;
+            }
+
+            let edit = VersionEdit::decode(&record)?;
+            for (cf_id, timestamp) in &edit.full_history_ts_low {
+                watermarks.insert(*cf_id, *timestamp);
+            }
+            for cf_id in &edit.dropped_column_families {
+                watermarks.remove(cf_id);
+            }
+        }
+
+        Ok(watermarks)
+    }
+
+    /// Check descriptors of the non-default CFs against the existing CFs
+    fn check_descriptors(
+        options: &DBOptions,
+        descriptors: &[ColumnFamilyDescriptor],
+        existing_cfs: &BTreeMap<u32, RecoveredColumnFamily>,
+    ) -> Result<()> {
+        for descriptor in descriptors {
+            descriptor.options.validate()?;
+        }
+
+        let unopened: Vec<&str> = existing_cfs
+            .values()
+            .map(|cf| cf.name.as_str())
+            .filter(|cf_name| !descriptors.iter().any(|d| d.name == *cf_name))
+            .collect();
+        if !unopened.is_empty() {
+            return Err(Status::invalid_argument(format!(
+                "Column families not opened: {}",
+                unopened.join(", ")
+            )));
+        }
+
+        if !options.create_missing_column_families {
+            let missing: Vec<&str> = descriptors
+                .iter()
+                .map(|d| d.name.as_str())
+                .filter(|name| !existing_cfs.values().any(|cf| cf.name == *name))
+                .collect();
+            if !missing.is_empty() {
+                return Err(Status::invalid_argument(format!(
+                    "Column families not found: {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recreate the existing non-default Column Families
+    ///
+    /// Each CF gets the options of its descriptor if given, else the
+    /// options persisted in the MANIFEST. Persisted options get the builtin
+    /// comparator the CF was created with back; a CF opened with a different
+    /// comparator is an error.
+    fn recover_column_families(
+        cf_set: &Arc<ColumnFamilySet>,
+        existing_cfs: &BTreeMap<u32, RecoveredColumnFamily>,
+        descriptors: Option<&[ColumnFamilyDescriptor]>,
+        default_cf_options: &ColumnFamilyOptions,
+    ) -> Result<()> {
+        for (cf_id, cf) in existing_cfs {
+            // CFs created before comparators were recorded are bytewise
+            let recorded_comparator = cf.comparator.as_deref().unwrap_or(BytewiseComparator::NAME);
+            let options = match descriptors
+                .and_then(|descriptors| descriptors.iter().find(|d| d.name == cf.name))
+            {
+                Some(descriptor) => descriptor.options.clone(),
+                None => match cf.options.clone() {
+                    Some(mut options) => {
+                        if let Some(comparator) = builtin_comparator(recorded_comparator) {
+                            options.comparator = comparator;
+                        }
+                        options
+                    },
+                    None => default_cf_options.clone(),
+                },
+            };
+
+            if !comparator_name_matches(recorded_comparator, options.comparator.as_ref()) {
+                return Err(Status::invalid_argument(format!(
+                    "Column family '{}': comparator '{}' does not match '{}' the column family \
+                     was created with",
+                    cf.name,
+                    options.comparator.name(),
+                    recorded_comparator
+                )));
+            }
+
             // Create CF with specific ID (we're recovering, don't log to MANIFEST)
-            let _ = cf_set.create_cf_with_id(cf_id, cf_name, default_cf_options.clone())?;
+            cf_set.create_cf_with_id(*cf_id, cf.name.clone(), options)?;
         }
 
         Ok(())
@@ -249,7 +777,14 @@ impl DB {
                 continue;
             }
 
-            let (cf_id, seq, key, value) = Self::decode_wal_record(&record)?;
+            let WalRecord {
+                cf_id,
+                sequence: seq,
+                key,
+                timestamp,
+                value_type,
+                value,
+            } = Self::decode_wal_record(&record)?;
 
             // Track max sequence per CF
             cf_max_seqs
@@ -263,11 +798,7 @@ impl DB {
             if let Some(cf) = cf_set.get_cf(&cf_handle) {
                 let mem = cf.mem();
                 let mem_guard = mem.write();
-                if let Some(val) = value {
-                    mem_guard.add(seq, key, val);
-                } else {
-                    mem_guard.delete(seq, key);
-                }
+                mem_guard.add_entry(seq, key, timestamp, value_type, value);
             } else {
                 // CF doesn't exist anymore - skip this record
                 // This can happen if a CF was dropped after WAL write but before recovery
@@ -292,18 +823,36 @@ impl DB {
         Ok(())
     }
 
-    /// Encode WAL record: op_type(1) + cf_id(4) + seq(8) + key_len(2) + key +
-    /// [value_len(2) + value]
+    /// Encode WAL record: op_type(1) + cf_id(4) + seq(8) + [timestamp(8)] +
+    /// key_len(2) + key + [value_len(2) + value]
     #[inline]
-    fn encode_wal_record(cf_id: u32, seq: u64, key: &Slice, value: Option<&Slice>) -> Vec<u8> {
+    fn encode_wal_record(
+        cf_id: u32,
+        seq: u64,
+        key: &Slice,
+        timestamp: Option<u64>,
+        value_type: u8,
+        value: &Slice,
+    ) -> Vec<u8> {
         // Pre-allocate buffer with exact capacity to avoid reallocations
-        // op_type(1) + cf_id(4) + seq(8) + key_len(2) + key + [value_len(2) + value]
         let key_data = key.data();
-        let capacity = 15 + key_data.len() + value.as_ref().map_or(0, |v| 2 + v.data().len());
+        let is_deletion = value_type == VALUE_TYPE_DELETION;
+        let capacity = 15
+            + timestamp.map_or(0, |_| 8)
+            + key_data.len()
+            + if is_deletion { 0 } else { 2 + value.size() };
         let mut buf = Vec::with_capacity(capacity);
 
-        // Operation type: 1=Put, 2=Delete
-        buf.push(if value.is_some() { 1 } else { 2 });
+        // Operation type: 1=Put, 2=Delete, 3=PutWithTimestamp,
+        // 4=DeleteWithTimestamp, 5=PutEntity, 6=PutEntityWithTimestamp
+        buf.push(match (value_type, timestamp.is_some()) {
+            (VALUE_TYPE_DELETION, false) => 2,
+            (VALUE_TYPE_DELETION, true) => 4,
+            (VALUE_TYPE_ENTITY, false) => 5,
+            (VALUE_TYPE_ENTITY, true) => 6,
+            (_, false) => 1,
+            (_, true) => 3,
+        });
 
         // Column Family ID
         buf.extend_from_slice(&cf_id.to_le_bytes());
@@ -311,13 +860,18 @@ impl DB {
         // Sequence number
         buf.extend_from_slice(&seq.to_le_bytes());
 
+        // User-defined timestamp
+        if let Some(ts) = timestamp {
+            buf.extend_from_slice(&ts.to_le_bytes());
+        }
+
         // Key
         buf.extend_from_slice(&(key_data.len() as u16).to_le_bytes());
         buf.extend_from_slice(key_data);
 
-        // Value (if Put)
-        if let Some(val) = value {
-            let val_data = val.data();
+        // Value (unless Delete)
+        if !is_deletion {
+            let val_data = value.data();
             buf.extend_from_slice(&(val_data.len() as u16).to_le_bytes());
             buf.extend_from_slice(val_data);
         }
@@ -326,26 +880,51 @@ impl DB {
     }
 
     /// Decode WAL record
-    fn decode_wal_record(data: &[u8]) -> Result<(u32, u64, Slice, Option<Slice>)> {
+    fn decode_wal_record(data: &[u8]) -> Result<WalRecord> {
         if data.len() < 15 {
             // op_type(1) + cf_id(4) + seq(8) + key_len(2) = 15 minimum
             return Err(Status::corruption("WAL record too short"));
         }
 
-        let op_type = data[0];
+        let (value_type, has_timestamp) = match data[0] {
+            1 => (VALUE_TYPE_VALUE, false),
+            2 => (VALUE_TYPE_DELETION, false),
+            3 => (VALUE_TYPE_VALUE, true),
+            4 => (VALUE_TYPE_DELETION, true),
+            5 => (VALUE_TYPE_ENTITY, false),
+            6 => (VALUE_TYPE_ENTITY, true),
+            op_type => {
+                return Err(Status::corruption(format!(
+                    "Unknown WAL operation type {}",
+                    op_type
+                )));
+            },
+        };
         let cf_id = u32::from_le_bytes(data[1..5].try_into().unwrap());
-        let seq = u64::from_le_bytes(data[5..13].try_into().unwrap());
+        let sequence = u64::from_le_bytes(data[5..13].try_into().unwrap());
 
-        let key_len = u16::from_le_bytes([data[13], data[14]]) as usize;
-        if data.len() < 15 + key_len {
+        let (timestamp, key_start) = if has_timestamp {
+            if data.len() < 23 {
+                return Err(Status::corruption("WAL record too short"));
+            }
+            (
+                Some(u64::from_le_bytes(data[13..21].try_into().unwrap())),
+                23,
+            )
+        } else {
+            (None, 15)
+        };
+
+        let key_len = u16::from_le_bytes([data[key_start - 2], data[key_start - 1]]) as usize;
+        if data.len() < key_start + key_len {
             return Err(Status::corruption("Invalid key length"));
         }
 
-        let key = Slice::from(&data[15..15 + key_len]);
+        let key = Slice::from(&data[key_start..key_start + key_len]);
 
-        let value = if op_type == 1 {
+        let value = if value_type != VALUE_TYPE_DELETION {
             // Put operation
-            let val_start = 15 + key_len;
+            let val_start = key_start + key_len;
             if data.len() < val_start + 2 {
                 return Err(Status::corruption("Invalid value length"));
             }
@@ -355,12 +934,19 @@ impl DB {
                 return Err(Status::corruption("Invalid value data"));
             }
 
-            Some(Slice::from(&data[val_start + 2..val_start + 2 + val_len]))
+            Slice::from(&data[val_start + 2..val_start + 2 + val_len])
         } else {
-            None
+            Slice::empty()
         };
 
-        Ok((cf_id, seq, key, value))
+        Ok(WalRecord {
+            cf_id,
+            sequence,
+            key,
+            timestamp,
+            value_type,
+            value,
+        })
     }
 
     #[inline]
@@ -375,16 +961,73 @@ impl DB {
         cf_handle: &ColumnFamilyHandle,
         key: Slice,
         value: Slice,
+    ) -> Result<()> {
+        self.write_cf(options, cf_handle, key, None, VALUE_TYPE_VALUE, value)
+    }
+
+    /// Put a key at a user-defined timestamp
+    ///
+    /// The column family's comparator must have a timestamp size.
+    pub fn put_cf_with_ts(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        timestamp: u64,
+        value: Slice,
+    ) -> Result<()> {
+        self.write_cf(
+            options,
+            cf_handle,
+            key,
+            Some(timestamp),
+            VALUE_TYPE_VALUE,
+            value,
+        )
+    }
+
+    /// Put a wide-column entity under a key
+    ///
+    /// The entity replaces any earlier value or entity of the key. `get_cf`
+    /// and iterator values return its default (anonymous) column.
+    pub fn put_entity(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        columns: &WideColumns,
+    ) -> Result<()> {
+        self.write_cf(
+            options,
+            cf_handle,
+            key,
+            None,
+            VALUE_TYPE_ENTITY,
+            columns.encode(),
+        )
+    }
+
+    /// Write an entry to the WAL and MemTable
+    fn write_cf(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        timestamp: Option<u64>,
+        value_type: u8,
+        value: Slice,
     ) -> Result<()> {
         let cf = self
             .column_families
             .get_cf(cf_handle)
             .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        Self::check_timestamp(&cf, timestamp)?;
 
         let seq = cf.next_sequence();
 
         // Write to WAL first
-        let record = Self::encode_wal_record(cf_handle.id(), seq, &key, Some(&value));
+        let record =
+            Self::encode_wal_record(cf_handle.id(), seq, &key, timestamp, value_type, &value);
         {
             let mut wal_guard = self.wal.write();
             if let Some(wal) = wal_guard.as_mut() {
@@ -401,7 +1044,11 @@ impl DB {
         let mem = cf.mem();
         let mem_guard = mem.read();
         let bytes_written = (key.size() + value.size()) as u64;
-        mem_guard.add(seq, key, value);
+        mem_guard.add_entry(seq, key, timestamp, value_type, value);
+        if value_type == VALUE_TYPE_DELETION {
+            self.statistics.record_delete();
+            return Ok(());
+        }
         self.statistics.record_write(bytes_written);
 
         // Check if we need to flush
@@ -415,6 +1062,25 @@ impl DB {
         Ok(())
     }
 
+    /// Check that a timestamp is given exactly when the CF's comparator
+    /// expects one
+    fn check_timestamp(
+        cf: &crate::column_family::ColumnFamilyData,
+        timestamp: Option<u64>,
+    ) -> Result<()> {
+        match (cf.has_timestamps(), timestamp.is_some()) {
+            (true, false) => Err(Status::invalid_argument(format!(
+                "Column family '{}' requires a timestamp",
+                cf.name()
+            ))),
+            (false, true) => Err(Status::invalid_argument(format!(
+                "Column family '{}' does not support timestamps",
+                cf.name()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     #[inline]
     pub fn get(&self, options: &ReadOptions, key: &Slice) -> Result<Option<Slice>> {
         let default_cf = self.column_families.default_cf();
@@ -423,27 +1089,62 @@ impl DB {
 
     pub fn get_cf(
         &self,
-        _options: &ReadOptions,
+        options: &ReadOptions,
         cf_handle: &ColumnFamilyHandle,
         key: &Slice,
     ) -> Result<Option<Slice>> {
+        Ok(match self.get_entry_cf(options, cf_handle, key)? {
+            LookupResult::Value(value) => Some(value),
+            // Entities without a default column read as an empty value
+            LookupResult::Entity(entity) => Some(
+                WideColumns::decode(&entity)?
+                    .default_value()
+                    .cloned()
+                    .unwrap_or_else(Slice::empty),
+            ),
+            LookupResult::NotFound | LookupResult::Deleted => None,
+        })
+    }
+
+    /// Get the wide-column entity of a key
+    ///
+    /// A plain value is returned as an entity with only the default column.
+    pub fn get_entity(
+        &self,
+        options: &ReadOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: &Slice,
+    ) -> Result<Option<WideColumns>> {
+        Ok(match self.get_entry_cf(options, cf_handle, key)? {
+            LookupResult::Value(value) => Some(WideColumns::from_value(value)),
+            LookupResult::Entity(entity) => Some(WideColumns::decode(&entity)?),
+            LookupResult::NotFound | LookupResult::Deleted => None,
+        })
+    }
+
+    /// Find the visible entry of a key in the MemTables, then the SSTables
+    fn get_entry_cf(
+        &self,
+        options: &ReadOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: &Slice,
+    ) -> Result<LookupResult> {
         let cf = self
             .column_families
             .get_cf(cf_handle)
             .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        let read_timestamp = Self::read_timestamp(&cf, options)?;
 
         // First check mutable MemTable
         {
             let mem = cf.mem();
             let mem_guard = mem.read();
-            let (found, value) = mem_guard.get(key);
-            if found {
+            let result = mem_guard.get_entry(key, read_timestamp);
+            if result != LookupResult::NotFound {
                 // Key exists in MemTable (either with value or deleted)
                 self.statistics.record_memtable_hit();
-                if let Some(ref v) = value {
-                    self.statistics.record_read(v.size() as u64);
-                }
-                return Ok(value);
+                self.record_lookup_read(&result);
+                return Ok(result);
             }
         }
 
@@ -451,14 +1152,13 @@ impl DB {
         {
             let imm = cf.imm();
             let imm_guard = imm.read();
-            if let Some(imm_table) = imm_guard.as_ref()
-                && let (true, value) = imm_table.get(key)
-            {
-                self.statistics.record_immutable_memtable_hit();
-                if let Some(ref v) = value {
-                    self.statistics.record_read(v.size() as u64);
+            if let Some(imm_table) = imm_guard.as_ref() {
+                let result = imm_table.get_entry(key, read_timestamp);
+                if result != LookupResult::NotFound {
+                    self.statistics.record_immutable_memtable_hit();
+                    self.record_lookup_read(&result);
+                    return Ok(result);
                 }
-                return Ok(value);
             }
         }
 
@@ -471,20 +1171,23 @@ impl DB {
         let current = version_set_guard.current();
         let version = current.read();
 
-        // Check level 0 first (newest files have highest numbers)
+        // Check level 0 first, newest file last: intra-L0 compaction output
+        // takes its inputs' place, so file numbers don't give the order
         let l0_files = version.get_level_files(0);
         for file in l0_files.iter().rev() {
             self.statistics.record_sstable_read();
-            let table = self.get_table(file.number)?;
+            let table = self.get_table(&cf, file.number)?;
             let mut table_guard = table.lock().unwrap();
-            let (found, value) = table_guard.get(key)?;
-            if found {
+            let result = table_guard.get_entry_with_verification(
+                key,
+                read_timestamp,
+                options.verify_checksums,
+            )?;
+            if result != LookupResult::NotFound {
                 // Key was found in this file (either with value or as deletion marker)
                 self.statistics.record_sstable_hit();
-                if let Some(ref v) = value {
-                    self.statistics.record_read(v.size() as u64);
-                }
-                return Ok(value);
+                self.record_lookup_read(&result);
+                return Ok(result);
             }
         }
 
@@ -496,23 +1199,60 @@ impl DB {
             files.sort_by(|a, b| b.number.cmp(&a.number));
             for file in files {
                 self.statistics.record_sstable_read();
-                let table = self.get_table(file.number)?;
+                let table = self.get_table(&cf, file.number)?;
                 let mut table_guard = table.lock().unwrap();
-                let (found, value) = table_guard.get(key)?;
-                if found {
+                let result = table_guard.get_entry_with_verification(
+                    key,
+                    read_timestamp,
+                    options.verify_checksums,
+                )?;
+                if result != LookupResult::NotFound {
                     // Key was found in this file (either with value or as deletion marker)
                     self.statistics.record_sstable_hit();
-                    if let Some(ref v) = value {
-                        self.statistics.record_read(v.size() as u64);
-                    }
-                    return Ok(value);
+                    self.record_lookup_read(&result);
+                    return Ok(result);
                 }
             }
         }
 
         // Not found in SSTables either - record miss
         self.statistics.record_sstable_miss();
-        Ok(None)
+        Ok(LookupResult::NotFound)
+    }
+
+    fn record_lookup_read(&self, result: &LookupResult) {
+        if let LookupResult::Value(value) | LookupResult::Entity(value) = result {
+            self.statistics.record_read(value.size() as u64);
+        }
+    }
+
+    /// Resolve the timestamp a read sees, None for CFs without timestamps
+    ///
+    /// Versions below the CF's full history watermark may have been
+    /// garbage-collected, so reading there is an error.
+    fn read_timestamp(
+        cf: &crate::column_family::ColumnFamilyData,
+        options: &ReadOptions,
+    ) -> Result<Option<u64>> {
+        if !cf.has_timestamps() {
+            return match options.timestamp {
+                Some(_) => Err(Status::invalid_argument(format!(
+                    "Column family '{}' does not support timestamps",
+                    cf.name()
+                ))),
+                None => Ok(None),
+            };
+        }
+
+        let timestamp = options.timestamp.unwrap_or(u64::MAX);
+        if timestamp < cf.full_history_ts_low() {
+            return Err(Status::invalid_argument(format!(
+                "Read timestamp {} is below the full history watermark {}",
+                timestamp,
+                cf.full_history_ts_low()
+            )));
+        }
+        Ok(Some(timestamp))
     }
 
     /// Create an iterator for scanning the database (default CF)
@@ -521,6 +1261,16 @@ impl DB {
         self.iter_cf(&default_cf.handle().clone())
     }
 
+    /// Create an iterator for scanning the database with read options
+    /// (default CF)
+    pub fn iter_with_options(
+        &self,
+        options: &ReadOptions,
+    ) -> Result<Box<dyn crate::iterator::Iterator>> {
+        let default_cf = self.column_families.default_cf();
+        self.iter_cf_with_options(options, &default_cf.handle().clone())
+    }
+
     /// Create an iterator for scanning the database
     ///
     /// Returns a MergingIterator that combines all data sources in priority
@@ -537,11 +1287,33 @@ impl DB {
     pub fn iter_cf(
         &self,
         cf_handle: &ColumnFamilyHandle,
+    ) -> Result<Box<dyn crate::iterator::Iterator>> {
+        self.iter_cf_with_options(&ReadOptions::default(), cf_handle)
+    }
+
+    /// Create an iterator for scanning a column family with read options
+    ///
+    /// With `prefix_same_as_start` and a CF prefix extractor, iteration
+    /// stops at the end of the seek target's prefix, and seeks skip
+    /// MemTables and SSTables whose prefix filters exclude that prefix.
+    /// With `timestamp` set, the iterator shows each key as of that
+    /// timestamp.
+    pub fn iter_cf_with_options(
+        &self,
+        options: &ReadOptions,
+        cf_handle: &ColumnFamilyHandle,
     ) -> Result<Box<dyn crate::iterator::Iterator>> {
         let cf = self
             .column_families
             .get_cf(cf_handle)
             .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        let read_timestamp = Self::read_timestamp(&cf, options)?;
+
+        let prefix_extractor = if options.prefix_same_as_start {
+            cf.options().prefix_extractor.clone()
+        } else {
+            None
+        };
 
         let mut iterators: Vec<Box<dyn crate::iterator::Iterator>> = Vec::new();
 
@@ -549,7 +1321,11 @@ impl DB {
         {
             let mem = cf.mem();
             let mem_guard = mem.read();
-            iterators.push(Box::new(mem_guard.iter()));
+            if prefix_extractor.is_some() {
+                iterators.push(Box::new(mem_guard.prefix_iter()));
+            } else {
+                iterators.push(Box::new(mem_guard.iter()));
+            }
         }
 
         // 2. Immutable MemTable (if exists)
@@ -557,7 +1333,11 @@ impl DB {
             let imm = cf.imm();
             let imm_guard = imm.read();
             if let Some(imm_table) = imm_guard.as_ref() {
-                iterators.push(Box::new(imm_table.iter()));
+                if prefix_extractor.is_some() {
+                    iterators.push(Box::new(imm_table.prefix_iter()));
+                } else {
+                    iterators.push(Box::new(imm_table.iter()));
+                }
             }
         }
 
@@ -567,24 +1347,44 @@ impl DB {
         let current = version_set_guard.current();
         let version = current.read();
 
+        let new_table_iter = |file: &FileMetaData| -> Result<Box<dyn crate::iterator::Iterator>> {
+            let table = self.get_table(&cf, file.number)?;
+            let mut table_iter = crate::iterator::TableIterator::new(table)?;
+            table_iter.set_verify_checksums(options.verify_checksums);
+            if let Some(ref extractor) = prefix_extractor {
+                table_iter.set_prefix_extractor(Arc::clone(extractor));
+            }
+            Ok(Box::new(table_iter))
+        };
+
         // Level 0: Add in reverse order (newest files first for priority)
         for file in version.get_level_files(0).iter().rev() {
-            let table = self.get_table(file.number)?;
-            let table_iter = crate::iterator::TableIterator::new(table)?;
-            iterators.push(Box::new(table_iter));
+            iterators.push(new_table_iter(file)?);
         }
 
         // Other levels: Add files in order (already sorted by key range)
         for level in 1..version.files.len() {
             for file in version.get_level_files(level) {
-                let table = self.get_table(file.number)?;
-                let table_iter = crate::iterator::TableIterator::new(table)?;
-                iterators.push(Box::new(table_iter));
+                iterators.push(new_table_iter(file)?);
             }
         }
 
         // Create merging iterator with proper priority order
-        Ok(Box::new(crate::iterator::MergingIterator::new(iterators)))
+        let mut merging_iter = crate::iterator::MergingIterator::with_comparator(
+            iterators,
+            Arc::clone(&cf.options().comparator),
+        );
+        if let Some(ts) = read_timestamp {
+            merging_iter.set_read_timestamp(ts);
+        }
+        let merging_iter = Box::new(merging_iter);
+        Ok(match prefix_extractor {
+            Some(extractor) => Box::new(crate::iterator::PrefixIterator::new(
+                merging_iter,
+                extractor,
+            )),
+            None => merging_iter,
+        })
     }
 
     #[inline]
@@ -599,33 +1399,35 @@ impl DB {
         cf_handle: &ColumnFamilyHandle,
         key: Slice,
     ) -> Result<()> {
-        let cf = self
-            .column_families
-            .get_cf(cf_handle)
-            .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
-
-        let seq = cf.next_sequence();
-
-        // Write to WAL first
-        let record = Self::encode_wal_record(cf_handle.id(), seq, &key, None);
-        {
-            let mut wal_guard = self.wal.write();
-            if let Some(wal) = wal_guard.as_mut() {
-                wal.add_record(&record)?;
-                self.statistics.record_wal_write(record.len() as u64);
-                if options.sync {
-                    wal.sync()?;
-                    self.statistics.record_wal_sync();
-                }
-            }
-        }
+        self.write_cf(
+            options,
+            cf_handle,
+            key,
+            None,
+            VALUE_TYPE_DELETION,
+            Slice::empty(),
+        )
+    }
 
-        // Then write to MemTable
-        let mem = cf.mem();
-        let mem_guard = mem.read();
-        mem_guard.delete(seq, key);
-        self.statistics.record_delete();
-        Ok(())
+    /// Delete a key at a user-defined timestamp
+    ///
+    /// Reads at or after `timestamp` no longer see the key; older reads
+    /// still do.
+    pub fn delete_cf_with_ts(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        timestamp: u64,
+    ) -> Result<()> {
+        self.write_cf(
+            options,
+            cf_handle,
+            key,
+            Some(timestamp),
+            VALUE_TYPE_DELETION,
+            Slice::empty(),
+        )
     }
 
     pub fn close(&self) -> Result<()> {
@@ -673,18 +1475,20 @@ impl DB {
     pub fn create_column_family(
         &self,
         name: &str,
-        options: crate::column_family::ColumnFamilyOptions,
+        options: ColumnFamilyOptions,
     ) -> Result<ColumnFamilyHandle> {
+        options.validate()?;
+        let encoded_options = options.encode()?;
+        let comparator_name = options.comparator.name().to_string();
         let handle = self.column_families.create_cf(name.to_string(), options)?;
 
-        // Log CF creation to MANIFEST (use default CF's VersionSet)
-        let default_cf = self.column_families.default_cf();
-        let version_set = default_cf.version_set();
-        let version_set_guard = version_set.read();
-
-        let mut edit = crate::version::VersionEdit::new();
+        // Log CF creation and options to MANIFEST
+        let mut edit = VersionEdit::new();
         edit.create_column_family(handle.id(), handle.name().to_string());
-        version_set_guard.log_and_apply(edit)?;
+        edit.set_column_family_options(handle.id(), encoded_options);
+        edit.set_column_family_comparator(handle.id(), comparator_name);
+        self.log_to_manifest(edit)?;
+        self.write_options_file()?;
 
         Ok(handle)
     }
@@ -694,21 +1498,154 @@ impl DB {
         let cf_id = cf_handle.id();
         self.column_families.drop_cf(cf_handle)?;
 
-        // Log CF drop to MANIFEST (use default CF's VersionSet)
+        // Log CF drop to MANIFEST
+        let mut edit = VersionEdit::new();
+        edit.drop_column_family(cf_id);
+        self.log_to_manifest(edit)?;
+        self.write_options_file()?;
+
+        Ok(())
+    }
+
+    /// Log a database-wide edit (CF create/drop/options) to the MANIFEST
+    ///
+    /// Only the default CF's VersionSet writes the MANIFEST.
+    fn log_to_manifest(&self, edit: VersionEdit) -> Result<()> {
         let default_cf = self.column_families.default_cf();
         let version_set = default_cf.version_set();
         let version_set_guard = version_set.read();
+        version_set_guard.log_and_apply(edit)
+    }
 
-        let mut edit = crate::version::VersionEdit::new();
-        edit.drop_column_family(cf_id);
-        version_set_guard.log_and_apply(edit)?;
+    /// Raise the full history watermark of a column family with timestamps
+    ///
+    /// Reads below `timestamp` are rejected from then on, and compaction
+    /// keeps only the newest version of a key older than it. The watermark
+    /// is recorded in the MANIFEST and cannot be lowered.
+    pub fn increase_full_history_ts_low(
+        &self,
+        cf_handle: &ColumnFamilyHandle,
+        timestamp: u64,
+    ) -> Result<()> {
+        let cf = self
+            .column_families
+            .get_cf(cf_handle)
+            .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        if !cf.has_timestamps() {
+            return Err(Status::invalid_argument(format!(
+                "Column family '{}' does not support timestamps",
+                cf.name()
+            )));
+        }
+        if timestamp < cf.full_history_ts_low() {
+            return Err(Status::invalid_argument(format!(
+                "Cannot decrease full_history_ts_low from {} to {}",
+                cf.full_history_ts_low(),
+                timestamp
+            )));
+        }
 
+        let mut edit = VersionEdit::new();
+        edit.set_full_history_ts_low(cf.id(), timestamp);
+        self.log_to_manifest(edit)?;
+        cf.set_full_history_ts_low(timestamp);
         Ok(())
     }
 
-    /// List all column families
-    pub fn list_column_families(&self) -> Vec<ColumnFamilyHandle> {
-        self.column_families.list_column_families()
+    /// Handles of all open column families
+    ///
+    /// Formerly the instance method `list_column_families`, a name now
+    /// taken by the static function listing the CFs of a database on disk.
+    pub fn column_family_handles(&self) -> Vec<ColumnFamilyHandle> {
+        self.column_families.list_column_families()
+    }
+
+    /// Options a column family is running with
+    pub fn get_options_cf(&self, cf_handle: &ColumnFamilyHandle) -> Result<ColumnFamilyOptions> {
+        let cf = self
+            .column_families
+            .get_cf(cf_handle)
+            .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        Ok(cf.options().as_ref().clone())
+    }
+
+    /// Change mutable options of a column family on the live database
+    ///
+    /// `changes` are `(name, value)` pairs using the field names of
+    /// `ColumnFamilyOptions` (see `options::MUTABLE_CF_OPTIONS`), e.g.
+    /// `("write_buffer_size", "8388608")` or `("compression_type", "Lz4")`.
+    /// All changes are validated before any is applied; the new options are
+    /// swapped in at once and take effect with the next write, flush or
+    /// compaction picking. They are recorded in the MANIFEST and a new
+    /// OPTIONS file.
+    pub fn set_options(
+        &self,
+        cf_handle: &ColumnFamilyHandle,
+        changes: &[(&str, &str)],
+    ) -> Result<()> {
+        let cf = self
+            .column_families
+            .get_cf(cf_handle)
+            .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+
+        let _guard = self.options_lock.lock();
+        let current = cf.options();
+        let changed =
+            options::apply_option_changes(current.as_ref(), options::MUTABLE_CF_OPTIONS, changes)?;
+        let new_options = ColumnFamilyOptions {
+            filter_policy: current.filter_policy.clone(),
+            prefix_extractor: current.prefix_extractor.clone(),
+            merge_operator: current.merge_operator.clone(),
+            compaction_filter: current.compaction_filter.clone(),
+            comparator: Arc::clone(&current.comparator),
+            ..changed
+        };
+        new_options.validate()?;
+
+        let mut edit = VersionEdit::new();
+        edit.set_column_family_options(cf.id(), new_options.encode()?);
+        self.log_to_manifest(edit)?;
+
+        cf.set_options(new_options);
+        self.save_options_file()
+    }
+
+    /// Change mutable database options on the live database
+    ///
+    /// `changes` are `(name, value)` pairs using the field names of
+    /// `DBOptions` (see `options::MUTABLE_DB_OPTIONS`), e.g.
+    /// `("parallel_compaction_threads", "8")`. All changes are validated
+    /// before any is applied, and recorded in a new OPTIONS file.
+    pub fn set_db_options(&self, changes: &[(&str, &str)]) -> Result<()> {
+        let _guard = self.options_lock.lock();
+        {
+            let mut db_options = self.options.write();
+            let changed =
+                options::apply_option_changes(&*db_options, options::MUTABLE_DB_OPTIONS, changes)?;
+            let new_options = DBOptions {
+                filter_policy: db_options.filter_policy.clone(),
+                prefix_extractor: db_options.prefix_extractor.clone(),
+                merge_operator: db_options.merge_operator.clone(),
+                compaction_filter: db_options.compaction_filter.clone(),
+                comparator: Arc::clone(&db_options.comparator),
+                ..changed
+            };
+            new_options.validate()?;
+            *db_options = new_options;
+        }
+        self.save_options_file()
+    }
+
+    /// Database options currently in effect
+    pub fn get_db_options(&self) -> DBOptions {
+        self.options.read().clone()
+    }
+
+    /// Handle of the column family with this name
+    pub fn cf_handle(&self, name: &str) -> Option<ColumnFamilyHandle> {
+        self.column_families
+            .get_cf_by_name(name)
+            .map(|cf| cf.handle().clone())
     }
 
     /// Flush MemTable to SSTable for a specific CF
@@ -738,21 +1675,30 @@ impl DB {
         let sst_path = self.db_path.join(format!("{file_num:06}.sst"));
 
         // Build SSTable with configured compression and filter
-        let mut builder = self.create_table_builder(&sst_path)?;
+        let mut builder = self.create_table_builder(&sst_path, cf, 0)?;
         for (key, value) in &entries {
             builder.add(key, value)?;
         }
-        builder.finish(self.options.compression_type)?;
+        builder.finish(cf.options().compression_type)?;
 
         // Get file size and key range
         let file_size = std::fs::metadata(&sst_path)
             .map_err(|e| Status::io_error(format!("Failed to get file size: {e}")))?
             .len();
-        let smallest = entries.first().unwrap().0.clone();
-        let largest = entries.last().unwrap().0.clone();
+        let smallest = InternalKey::decode(&entries.first().unwrap().0)?
+            .user_key()
+            .clone();
+        let largest = InternalKey::decode(&entries.last().unwrap().0)?
+            .user_key()
+            .clone();
 
         // Create FileMetaData and VersionEdit
-        let file_meta = FileMetaData::new(file_num, file_size, smallest, largest);
+        let mut file_meta = FileMetaData::new(file_num, file_size, smallest, largest);
+        file_meta.num_entries = builder.num_entries();
+        file_meta.num_deletions = builder.num_deletions();
+        file_meta.marked_for_compaction = builder.need_compaction();
+        file_meta.creation_time = builder.creation_time();
+        file_meta.file_creation_time = builder.file_creation_time();
         let mut edit = VersionEdit::new();
         edit.add_file(0, file_meta); // Always flush to Level 0
         edit.set_last_sequence(cf.current_sequence());
@@ -779,20 +1725,31 @@ impl DB {
         self.statistics.record_memtable_flush(file_size);
 
         // Check if we need to trigger compaction after flush
-        if self.options.enable_background_compaction {
+        if self.options.read().enable_background_compaction {
             self.check_and_trigger_compaction(cf)?;
         }
 
         Ok(())
     }
 
-    /// Create a TableBuilder with configured compression and filter options
-    fn create_table_builder<P: AsRef<Path>>(&self, path: P) -> Result<TableBuilder> {
-        let filter_policy = self.options.filter_bits_per_key.map(|bits_per_key| {
-            Arc::new(BloomFilterPolicy::new(bits_per_key)) as Arc<dyn FilterPolicy>
-        });
-
-        TableBuilder::new_with_filter(path, filter_policy)
+    /// Create a TableBuilder with the CF's table options
+    ///
+    /// The filter policy comes from the CF and is told the output level;
+    /// filters include key prefixes when the CF has a prefix extractor.
+    /// Data blocks use the CF's compression and keys its comparator.
+    fn create_table_builder<P: AsRef<Path>>(
+        &self,
+        path: P,
+        cf: &crate::column_family::ColumnFamilyData,
+        level: usize,
+    ) -> Result<TableBuilder> {
+        let mut builder = TableBuilder::new_with_options(path, None, cf.table_options().clone())?;
+        builder.set_prefix_extractor(cf.options().prefix_extractor.clone());
+        builder.set_level(level);
+        builder.set_compression_type(cf.options().compression_type);
+        builder.set_comparator(Arc::clone(&cf.options().comparator));
+        builder.set_compact_on_deletion(cf.options().compact_on_deletion.clone());
+        Ok(builder)
     }
 
     /// Compact a level by merging files into the next level (default CF)
@@ -802,6 +1759,13 @@ impl DB {
     }
 
     /// Compact a level for a specific CF
+    ///
+    /// All files of the level are merged with the overlapping files of the
+    /// next level (the base level for L0 with dynamic level bytes), once
+    /// the compactions running on them have finished.
+    /// Files overlapping nothing there are moved without being rewritten,
+    /// unless the CF has compaction filters to run over them. FIFO
+    /// compaction keeps all files in L0 and only runs its picker.
     pub fn compact_level_cf(&self, cf_handle: &ColumnFamilyHandle, level: usize) -> Result<()> {
         if level >= 6 {
             return Ok(()); // No compaction for last level
@@ -811,81 +1775,174 @@ impl DB {
             .column_families
             .get_cf(cf_handle)
             .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        if cf.options().compaction_style == CompactionStyle::Fifo {
+            return self.maybe_compact_cf(cf_handle);
+        }
+        self.compact_files_in_range(&cf, level, None, None, |_| true, None)
+    }
 
+    /// Compact the files of `level` holding keys in `[start, end]` that
+    /// `include` accepts into the next level, or within the last level
+    ///
+    /// Setting `canceled` stops the compaction as in `run_compaction`.
+    fn compact_files_in_range(
+        &self,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        level: usize,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        include: impl Fn(&FileMetaData) -> bool,
+        canceled: Option<&Arc<AtomicBool>>,
+    ) -> Result<()> {
         // Get files to compact
-        let (level_files, next_level_files) = {
-            let version_set = cf.version_set();
-            let version_set_guard = version_set.read();
-            let current = version_set_guard.current();
-            let version = current.read();
+        let comparator = Arc::clone(&cf.options().comparator);
+        let running = cf.running_compactions().start_when_clear(
+            || {
+                let version_set = cf.version_set();
+                let version_set_guard = version_set.read();
+                let current = version_set_guard.current();
+                let version = current.read();
 
-            let level_files: Vec<FileMetaData> = version.get_level_files(level).to_vec();
-            if level_files.is_empty() {
-                return Ok(()); // Nothing to compact
-            }
+                let level_files = files_in_range(&version, level, start, end, &include);
+                if level_files.is_empty() {
+                    return None; // Nothing to compact
+                }
 
-            // Get overlapping files in next level
-            let smallest = &level_files.iter().map(|f| &f.smallest).min().unwrap();
-            let largest = &level_files.iter().map(|f| &f.largest).max().unwrap();
-            let next_level_files = version.get_overlapping_files(level + 1, smallest, largest);
+                // Get overlapping files in next level
+                let smallest = level_files
+                    .iter()
+                    .map(|f| f.smallest_user_key())
+                    .min_by(|a, b| comparator.compare(a, b))
+                    .unwrap();
+                let largest = level_files
+                    .iter()
+                    .map(|f| f.largest_user_key())
+                    .max_by(|a, b| comparator.compare(a, b))
+                    .unwrap();
+                if level == NUM_LEVELS - 1 {
+                    return Some(
+                        Compaction::new(level, level_files, Vec::new()).with_output_level(level),
+                    );
+                }
+                let output_level = cf
+                    .options()
+                    .compaction_picker()
+                    .output_level(&version, level);
+                let next_level_files = version.get_overlapping_files(
+                    output_level,
+                    &Slice::from(smallest),
+                    &Slice::from(largest),
+                );
+
+                Some(
+                    Compaction::new(level, level_files, next_level_files)
+                        .with_output_level(output_level),
+                )
+            },
+            comparator.as_ref(),
+        );
 
-            (level_files, next_level_files)
-        };
+        match running {
+            Some(running) => {
+                let allow_trivial_move = cf.compaction_filters().is_empty();
+                self.run_compaction(cf, running.compaction(), allow_trivial_move, canceled)
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Merge the input files of a compaction into its output level
+    ///
+    /// With `allow_trivial_move`, inputs overlapping nothing in the output
+    /// level are moved there as they are. Once `canceled` is set the merge
+    /// stops with an Incomplete status, the files written so far are
+    /// deleted and the inputs are left in place.
+    fn run_compaction(
+        &self,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        compaction: &Compaction,
+        allow_trivial_move: bool,
+        canceled: Option<&Arc<AtomicBool>>,
+    ) -> Result<()> {
+        if compaction.deletion_compaction {
+            return self.delete_compaction_files(cf, compaction);
+        }
+        if allow_trivial_move && compaction.is_trivial_move(cf.options().comparator.as_ref()) {
+            return self.move_compaction_files(cf, compaction);
+        }
+        let level = compaction.level;
+        let output_level = compaction.output_level;
+        let level_files = &compaction.inputs;
+        let next_level_files = &compaction.next_level_inputs;
 
         // Start timing for compaction
         let start_time = std::time::Instant::now();
 
         // Execute compaction (parallel or sequential based on configuration)
-        let (output_files, num_subcompactions) = if self.options.parallel_compaction_threads > 0
-            && self.options.enable_subcompaction
-        {
-            // Use parallel compaction executor
-            let filter_policy = self.options.filter_bits_per_key.map(|bits_per_key| {
-                Arc::new(BloomFilterPolicy::new(bits_per_key)) as Arc<dyn FilterPolicy>
-            });
-
-            let config = ParallelCompactionConfig {
-                max_threads: self.options.parallel_compaction_threads,
-                subcompaction_config: SubcompactionConfig {
-                    min_file_size: self.options.subcompaction_min_size,
-                    target_subcompactions: self.options.parallel_compaction_threads,
+        let db_options = self.get_db_options();
+        let (output_files, num_subcompactions, filter_stats) =
+            if db_options.parallel_compaction_threads > 0 && db_options.enable_subcompaction {
+                // Use parallel compaction executor
+                let config = ParallelCompactionConfig {
+                    max_threads: db_options.parallel_compaction_threads,
+                    subcompaction_config: SubcompactionConfig {
+                        min_file_size: db_options.subcompaction_min_size,
+                        target_subcompactions: db_options.parallel_compaction_threads,
+                        enable_parallel: true,
+                    },
                     enable_parallel: true,
-                },
-                enable_parallel: true,
+                };
+
+                let mut executor = ParallelCompactionExecutor::new(
+                    config,
+                    self.db_path.clone(),
+                    cf.options().compression_type,
+                    cf.table_options().clone(),
+                    cf.options().prefix_extractor.clone(),
+                    Arc::clone(&cf.options().comparator),
+                );
+                executor.set_full_history_ts_low(cf.full_history_ts_low());
+                executor.set_compaction_filters(cf.compaction_filters());
+                executor.set_compact_on_deletion(cf.options().compact_on_deletion.clone());
+                executor.set_target_file_size(cf.options().target_file_size(output_level));
+                executor.set_output_level(output_level);
+                executor.set_canceled(canceled.cloned());
+
+                // Intermediate levels are merged like the files of `level`
+                let upper_files = level_files
+                    .iter()
+                    .chain(compaction.intermediate_inputs.iter().flat_map(|(_, f)| f))
+                    .cloned()
+                    .collect();
+                let results = executor.execute_compaction(
+                    level,
+                    upper_files,
+                    next_level_files.clone(),
+                    &|| {
+                        let version_set = cf.version_set();
+                        let version_set_guard = version_set.read();
+                        version_set_guard.new_file_number()
+                    },
+                )?;
+
+                // Collect output files and count subcompactions
+                let num_subcompactions = results.len() as u64;
+                let mut filter_stats = CompactionFilterStats::default();
+                let output_files = results
+                    .into_iter()
+                    .flat_map(|r| {
+                        filter_stats.add(r.filter_stats);
+                        r.files
+                    })
+                    .collect::<Vec<_>>();
+                (output_files, num_subcompactions, filter_stats)
+            } else {
+                // Sequential compaction (original implementation)
+                let (output_files, filter_stats) =
+                    self.execute_sequential_compaction(compaction, cf, canceled)?;
+                (output_files, 0, filter_stats) // Sequential doesn't use subcompactions
             };
 
-            let executor = ParallelCompactionExecutor::new(
-                config,
-                self.db_path.clone(),
-                self.options.compression_type,
-                filter_policy,
-            );
-
-            let results = executor.execute_compaction(
-                level,
-                level_files.clone(),
-                next_level_files.clone(),
-                &|| {
-                    let version_set = cf.version_set();
-                    let version_set_guard = version_set.read();
-                    version_set_guard.new_file_number()
-                },
-            )?;
-
-            // Collect output files and count subcompactions
-            let num_subcompactions = results.len() as u64;
-            let output_files = results
-                .into_iter()
-                .filter_map(|r| r.file_meta)
-                .collect::<Vec<_>>();
-            (output_files, num_subcompactions)
-        } else {
-            // Sequential compaction (original implementation)
-            let output_files =
-                self.execute_sequential_compaction(level, &level_files, &next_level_files, &cf)?;
-            (output_files, 0) // Sequential doesn't use subcompactions
-        };
-
         // Calculate elapsed time
         let elapsed_micros = start_time.elapsed().as_micros() as u64;
 
@@ -893,9 +1950,8 @@ impl DB {
         let file_size: u64 = output_files.iter().map(|f| f.file_size).sum();
 
         // Record compaction statistics
-        let bytes_read: u64 = level_files.iter().map(|f| f.file_size).sum::<u64>()
-            + next_level_files.iter().map(|f| f.file_size).sum::<u64>();
-        let num_input_files = (level_files.len() + next_level_files.len()) as u64;
+        let bytes_read = compaction.input_bytes();
+        let num_input_files = compaction.input_files().count() as u64;
 
         if num_subcompactions > 0 {
             // Parallel compaction
@@ -915,6 +1971,8 @@ impl DB {
                 elapsed_micros,
             );
         }
+        self.statistics
+            .record_compaction_filter(filter_stats.removed, filter_stats.changed);
 
         // Record per-level statistics
         {
@@ -929,7 +1987,13 @@ impl DB {
                 stats.record_read(level_files.iter().map(|f| f.file_size).sum());
                 stats.record_compaction();
             }
-            if let Some(stats) = level_stats.level(level + 1) {
+            for (level, files) in &compaction.intermediate_inputs {
+                if let Some(stats) = level_stats.level(*level) {
+                    stats.record_read(files.iter().map(|f| f.file_size).sum());
+                    stats.record_compaction();
+                }
+            }
+            if let Some(stats) = level_stats.level(output_level) {
                 stats.record_read(next_level_files.iter().map(|f| f.file_size).sum());
                 stats.record_write(file_size);
                 stats.record_compaction();
@@ -940,16 +2004,16 @@ impl DB {
         let mut edit = VersionEdit::new();
 
         // Delete old files (always, even if output is empty)
-        for file in &level_files {
+        for (level, file) in compaction.leveled_input_files() {
             edit.delete_file(level, file.number);
         }
-        for file in &next_level_files {
-            edit.delete_file(level + 1, file.number);
-        }
 
         // Add new output files
         for file_meta in output_files {
-            edit.add_file(level + 1, file_meta);
+            edit.add_file(output_level, file_meta);
+        }
+        if let Some(ref cursor) = compaction.compact_cursor {
+            edit.set_compact_cursor(level, cursor.clone());
         }
 
         // Apply edit
@@ -960,11 +2024,7 @@ impl DB {
         }
 
         // Delete old SSTable files
-        for file in &level_files {
-            let path = self.db_path.join(format!("{:06}.sst", file.number));
-            let _ = std::fs::remove_file(path);
-        }
-        for file in &next_level_files {
+        for file in compaction.input_files() {
             let path = self.db_path.join(format!("{:06}.sst", file.number));
             let _ = std::fs::remove_file(path);
         }
@@ -972,111 +2032,121 @@ impl DB {
         Ok(())
     }
 
-    /// Execute sequential compaction (fallback when parallel is disabled)
-    fn execute_sequential_compaction(
+    /// Move the inputs of a trivial-move compaction to its output level
+    ///
+    /// Only a VersionEdit is written: the files keep their numbers and
+    /// contents.
+    fn move_compaction_files(
         &self,
-        level: usize,
-        level_files: &[FileMetaData],
-        next_level_files: &[FileMetaData],
         cf: &Arc<crate::column_family::ColumnFamilyData>,
-    ) -> Result<Vec<FileMetaData>> {
-        let mut all_entries: Vec<(Slice, Slice)> = Vec::new();
-
-        // Read from level files
-        for file in level_files {
-            let table = self.get_table(file.number)?;
-            let mut table_guard = table.lock().unwrap();
-            let entries = self.read_all_from_table(&mut table_guard)?;
-            all_entries.extend(entries);
+        compaction: &Compaction,
+    ) -> Result<()> {
+        let level = compaction.level;
+        let mut edit = VersionEdit::new();
+        for file in &compaction.inputs {
+            edit.delete_file(level, file.number);
+            edit.add_file(compaction.output_level, file.clone());
         }
-
-        // Read from next level files
-        for file in next_level_files {
-            let table = self.get_table(file.number)?;
-            let mut table_guard = table.lock().unwrap();
-            let entries = self.read_all_from_table(&mut table_guard)?;
-            all_entries.extend(entries);
+        if let Some(ref cursor) = compaction.compact_cursor {
+            edit.set_compact_cursor(level, cursor.clone());
         }
 
-        // Sort and deduplicate
-        all_entries.sort_by(|a, b| {
-            let key_a = match InternalKey::decode(&a.0) {
-                Ok(k) => k,
-                Err(_) => return a.0.data().cmp(b.0.data()),
-            };
-            let key_b = match InternalKey::decode(&b.0) {
-                Ok(k) => k,
-                Err(_) => return a.0.data().cmp(b.0.data()),
-            };
-
-            match key_a.user_key().data().cmp(key_b.user_key().data()) {
-                std::cmp::Ordering::Equal => match key_b.sequence().cmp(&key_a.sequence()) {
-                    std::cmp::Ordering::Equal => key_a.value_type.cmp(&key_b.value_type),
-                    other => other,
-                },
-                other => other,
-            }
-        });
-
-        let mut merged: Vec<(Slice, Slice)> = Vec::new();
-        let mut last_user_key: Option<Slice> = None;
-
-        for (key, value) in all_entries {
-            if let Ok(internal_key) = InternalKey::decode(&key) {
-                let user_key = internal_key.user_key().clone();
-
-                #[allow(clippy::collapsible_if)]
-                if let Some(ref last) = last_user_key {
-                    if last == &user_key {
-                        continue;
-                    }
-                }
-
-                let is_bottom_level = level + 1 >= 6;
-                if internal_key.is_deletion() && is_bottom_level {
-                    last_user_key = Some(user_key);
-                    continue;
-                }
+        let version_set = cf.version_set();
+        let version_set_guard = version_set.read();
+        version_set_guard.log_and_apply(edit)?;
 
-                merged.push((key, value));
-                last_user_key = Some(user_key);
-            }
+        let bytes = compaction.input_bytes();
+        self.statistics.record_trivial_move(bytes);
+        let current = version_set_guard.current();
+        if let Some(stats) = current.read().level_stats().level(compaction.output_level) {
+            stats.record_trivial_move(bytes);
         }
+        Ok(())
+    }
 
-        if merged.is_empty() {
-            return Ok(Vec::new());
+    /// Drop the inputs of a deletion compaction
+    ///
+    /// The files are removed from the version and deleted without being
+    /// read.
+    fn delete_compaction_files(
+        &self,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        compaction: &Compaction,
+    ) -> Result<()> {
+        let mut edit = VersionEdit::new();
+        for (level, file) in compaction.leveled_input_files() {
+            edit.delete_file(level, file.number);
         }
-
-        // Write output file
-        let file_num = {
+        {
             let version_set = cf.version_set();
             let version_set_guard = version_set.read();
-            version_set_guard.new_file_number()
-        };
-        let sst_path = self.db_path.join(format!("{file_num:06}.sst"));
-
-        let mut builder = self.create_table_builder(&sst_path)?;
-        for (key, value) in &merged {
-            builder.add(key, value)?;
+            version_set_guard.log_and_apply(edit)?;
         }
-        builder.finish(self.options.compression_type)?;
 
-        let file_size = std::fs::metadata(&sst_path)
-            .map_err(|e| Status::io_error(format!("Failed to get file size: {e}")))?
-            .len();
+        for file in compaction.input_files() {
+            let path = self.db_path.join(format!("{:06}.sst", file.number));
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(())
+    }
 
-        let smallest_internal = InternalKey::decode(&merged.first().unwrap().0)?;
-        let largest_internal = InternalKey::decode(&merged.last().unwrap().0)?;
-        let smallest = smallest_internal.user_key().clone();
-        let largest = largest_internal.user_key().clone();
+    /// Execute sequential compaction (fallback when parallel is disabled)
+    fn execute_sequential_compaction(
+        &self,
+        compaction: &Compaction,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        canceled: Option<&Arc<AtomicBool>>,
+    ) -> Result<(Vec<FileMetaData>, CompactionFilterStats)> {
+        let options = cf.options();
+        let output_level = compaction.output_level;
+        let mut inputs = Vec::new();
+        for file in compaction.input_files() {
+            inputs.push((file.clone(), self.get_table(cf, file.number)?));
+        }
+        let mut input = CompactionInputIterator::new(inputs, Arc::clone(&options.comparator))?;
+
+        let next_file_number = || cf.version_set().read().new_file_number();
+        let creation_time = input.creation_time();
+        let new_builder = |path: &Path| {
+            let mut builder = self.create_table_builder(path, cf, output_level)?;
+            if let Some(creation_time) = creation_time {
+                builder.set_creation_time(creation_time);
+            }
+            Ok(builder)
+        };
+        let mut outputs = CompactionOutputs::new(
+            &self.db_path,
+            options.target_file_size(output_level),
+            options.compression_type,
+            &next_file_number,
+            &new_builder,
+        );
 
-        let file_meta = FileMetaData::new(file_num, file_size, smallest, largest);
-        Ok(vec![file_meta])
-    }
+        // Merge the inputs, keeping the newest versions
+        let is_bottom_level = output_level >= 6;
+        let full_history_ts_low = cf.full_history_ts_low();
+        let mut retention = VersionRetention::new(is_bottom_level, full_history_ts_low);
+        let mut filters = CompactionFilterRunner::new(
+            cf.compaction_filters(),
+            compaction.level,
+            is_bottom_level,
+            full_history_ts_low,
+            Arc::clone(&options.comparator),
+        );
+        while let Some((key, value)) = input.next_entry()? {
+            if canceled.is_some_and(|canceled| canceled.load(Ordering::SeqCst)) {
+                outputs.abandon();
+                return Err(Status::incomplete("Manual compaction canceled"));
+            }
+            if let Ok(internal_key) = InternalKey::decode(&key)
+                && retention.keep(&internal_key)
+                && let Some((key, value)) = filters.apply(&internal_key, key, value)
+            {
+                outputs.add(internal_key.user_key(), &key, &value)?;
+            }
+        }
 
-    /// Read all entries from a table
-    fn read_all_from_table(&self, table: &mut TableReader) -> Result<Vec<(Slice, Slice)>> {
-        table.scan_all()
+        Ok((outputs.finish()?, filters.stats()))
     }
 
     /// Try to compact if needed (default CF)
@@ -1086,22 +2156,28 @@ impl DB {
     }
 
     /// Try to compact if needed for a specific CF
+    ///
+    /// Runs the compactions the CF's picker chooses until no level needs
+    /// one. Compactions running in other threads are left alone: only files
+    /// and key ranges they don't touch are picked.
     pub fn maybe_compact_cf(&self, cf_handle: &ColumnFamilyHandle) -> Result<()> {
         let cf = self
             .column_families
             .get_cf(cf_handle)
             .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
 
-        let level = {
+        while let Some(running) = cf.running_compactions().start(|running| {
             let version_set = cf.version_set();
             let version_set_guard = version_set.read();
             let current = version_set_guard.current();
             let version = current.read();
-            version.pick_compaction_level()
-        };
-
-        if let Some(level) = level {
-            self.compact_level_cf(cf_handle, level)?;
+            cf.options()
+                .pick_compaction(&version, running, |file| match file.creation_time {
+                    0 => self.table_creation_time(&cf, file.number),
+                    time => time,
+                })
+        }) {
+            self.run_compaction(&cf, running.compaction(), true, None)?;
         }
 
         Ok(())
@@ -1112,18 +2188,41 @@ impl DB {
         &self,
         cf: &Arc<crate::column_family::ColumnFamilyData>,
     ) -> Result<()> {
-        // Check if compaction is needed based on L0 file count
-        let should_compact = BackgroundCompactionScheduler::should_compact(
-            &self.column_families,
-            cf.id(),
-            self.options.l0_compaction_trigger,
-        );
+        // Check if compaction is needed based on L0 file count; universal
+        // compaction also counts the lower levels as sorted runs, and FIFO
+        // compaction and leveled compaction with a TTL or periodic
+        // compaction look at the age of files, so their pickers decide alone
+        let options = cf.options();
+        let compaction_style = options.compaction_style;
+        let should_compact = compaction_style != CompactionStyle::Level
+            || options.ttl > 0
+            || options.periodic_compaction_seconds > 0
+            || BackgroundCompactionScheduler::should_compact(
+                &self.column_families,
+                cf.id(),
+                cf.options().l0_compaction_trigger,
+            );
 
         if should_compact {
             // Trigger compaction for this CF
             self.maybe_compact_cf(&cf.handle().clone())?;
         }
 
+        // Writes stall while L0 has too many files: the flushing write
+        // waits for L0 to be compacted even if another level scored higher.
+        // FIFO compaction keeps all files in L0.
+        let should_stall = compaction_style != CompactionStyle::Fifo
+            && BackgroundCompactionScheduler::should_stall_writes(
+                &self.column_families,
+                cf.id(),
+                options
+                    .l0_stop_writes_trigger
+                    .max(options.l0_compaction_trigger),
+            );
+        if should_stall {
+            self.compact_level_cf(cf.handle(), 0)?;
+        }
+
         Ok(())
     }
 
@@ -1188,27 +2287,202 @@ impl DB {
     /// Manually trigger compaction for the entire database
     /// This forces compaction across all levels in the default column family
     pub fn compact_range(&self, start: Option<&[u8]>, end: Option<&[u8]>) -> Result<()> {
-        let default_cf = self.column_families.default_cf();
-        self.compact_range_cf(&default_cf.handle().clone(), start, end)
+        self.compact_range_opt(&CompactRangeOptions::default(), start, end)
     }
 
     /// Manually trigger compaction for a key range in a specific column family
+    /// (None = unbounded), with the default `CompactRangeOptions`
     pub fn compact_range_cf(
         &self,
         cf_handle: &ColumnFamilyHandle,
-        _start: Option<&[u8]>,
-        _end: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
     ) -> Result<()> {
-        // For now, just compact all levels (simplified implementation)
-        // A full implementation would filter files by key range
-        for level in 0..7 {
-            // Try to compact each level
-            // This will skip levels that don't need compaction
-            let _ = self.compact_level_cf(cf_handle, level);
+        self.compact_range_cf_opt(&CompactRangeOptions::default(), cf_handle, start, end)
+    }
+
+    /// Compact the keys in `[start, end]` of the default CF as `options`
+    /// say
+    pub fn compact_range_opt(
+        &self,
+        options: &CompactRangeOptions,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<()> {
+        let default_cf = self.column_families.default_cf();
+        self.compact_range_cf_opt(options, &default_cf.handle().clone(), start, end)
+    }
+
+    /// Compact the keys in `[start, end]` of a column family as `options`
+    /// say
+    ///
+    /// Level by level from L0, the files holding keys in the range are
+    /// compacted into the next level, down to the last level. The range's
+    /// files that were in the last level before are then rewritten as
+    /// `bottommost_level_compaction` says, and with `change_level` the files
+    /// of the level the data ends in are moved to `target_level`. The work
+    /// runs on the calling thread; `canceled` is checked before each step
+    /// and while merging. FIFO compaction only runs its picker.
+    pub fn compact_range_cf_opt(
+        &self,
+        options: &CompactRangeOptions,
+        cf_handle: &ColumnFamilyHandle,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<()> {
+        let cf = self
+            .column_families
+            .get_cf(cf_handle)
+            .ok_or_else(|| Status::invalid_argument("Column family not found"))?;
+        if options
+            .target_level
+            .is_some_and(|target_level| target_level >= NUM_LEVELS)
+        {
+            return Err(Status::invalid_argument(format!(
+                "target_level must be below {NUM_LEVELS}"
+            )));
+        }
+        self.run_manual_compaction(&cf, options, start, end)
+    }
+
+    /// The steps of `compact_range_cf_opt`
+    fn run_manual_compaction(
+        &self,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        options: &CompactRangeOptions,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<()> {
+        let canceled = options.canceled.as_ref();
+        let check_canceled = || match canceled {
+            Some(canceled) if canceled.load(Ordering::SeqCst) => {
+                Err(Status::incomplete("Manual compaction canceled"))
+            },
+            _ => Ok(()),
+        };
+
+        // FIFO compaction only ever drops or merges L0 files
+        if cf.options().compaction_style == CompactionStyle::Fifo {
+            check_canceled()?;
+            return self.maybe_compact_cf(cf.handle());
+        }
+
+        let _exclusive = options
+            .exclusive_manual_compaction
+            .then(|| cf.running_compactions().exclusive());
+
+        // Files numbered from here on are written by this compaction
+        let first_new_file = cf.version_set().read().next_file_number();
+        for level in 0..NUM_LEVELS - 1 {
+            check_canceled()?;
+            self.compact_files_in_range(cf, level, start, end, |_| true, canceled)?;
+        }
+
+        let rewrite_last_level = match options.bottommost_level_compaction {
+            BottommostLevelCompaction::Skip => false,
+            BottommostLevelCompaction::IfHaveCompactionFilter => {
+                !cf.compaction_filters().is_empty()
+            },
+            BottommostLevelCompaction::Force => true,
+        };
+        if rewrite_last_level {
+            check_canceled()?;
+            self.compact_files_in_range(
+                cf,
+                NUM_LEVELS - 1,
+                start,
+                end,
+                |file| file.number < first_new_file,
+                canceled,
+            )?;
+        }
+
+        if options.change_level {
+            check_canceled()?;
+            self.refit_level(cf, options.target_level)?;
         }
         Ok(())
     }
 
+    /// Move the files of the last non-empty level to `target_level`, or to
+    /// the highest empty level above them that they fit in, by the level
+    /// targets automatic compaction uses
+    ///
+    /// The levels from there to `target_level` must be empty.
+    fn refit_level(
+        &self,
+        cf: &Arc<crate::column_family::ColumnFamilyData>,
+        target_level: Option<usize>,
+    ) -> Result<()> {
+        let comparator = Arc::clone(&cf.options().comparator);
+        let mut refit = None;
+        // Registered as a compaction spanning both levels, so none runs on
+        // the files or the levels between meanwhile
+        let running = cf.running_compactions().start_when_clear(
+            || {
+                let version_set = cf.version_set();
+                let version_set_guard = version_set.read();
+                let current = version_set_guard.current();
+                let version = current.read();
+
+                let from = (0..NUM_LEVELS)
+                    .rev()
+                    .find(|&level| version.num_level_files(level) > 0)?;
+                let files = version.get_level_files(from).to_vec();
+                let target = target_level.unwrap_or_else(|| {
+                    let size: u64 = files.iter().map(|f| f.file_size).sum();
+                    let (_, targets) = cf.options().compaction_picker().level_targets(&version);
+                    let mut target = from;
+                    while target > 1
+                        && version.num_level_files(target - 1) == 0
+                        && size <= targets[target - 1]
+                    {
+                        target -= 1;
+                    }
+                    target
+                });
+                if target == from {
+                    return None;
+                }
+                refit = Some((from, target));
+                Some(
+                    Compaction::new(from.min(target), files, Vec::new())
+                        .with_output_level(from.max(target)),
+                )
+            },
+            comparator.as_ref(),
+        );
+        let (Some(running), Some((from, target))) = (running, refit) else {
+            return Ok(());
+        };
+
+        let version_set = cf.version_set();
+        let version_set_guard = version_set.read();
+        let levels_between = match from < target {
+            true => from + 1..target + 1,
+            false => target..from,
+        };
+        {
+            let current = version_set_guard.current();
+            let version = current.read();
+            if levels_between
+                .into_iter()
+                .any(|level| version.num_level_files(level) > 0)
+            {
+                return Err(Status::invalid_argument(format!(
+                    "Levels between {from} and target level {target} are not empty"
+                )));
+            }
+        }
+
+        let mut edit = VersionEdit::new();
+        for file in &running.compaction().inputs {
+            edit.delete_file(from, file.number);
+            edit.add_file(target, file.clone());
+        }
+        version_set_guard.log_and_apply(edit)
+    }
+
     /// Get a database property value
     /// Supported properties:
     /// - "rocksdb.num-files-at-levelN" - number of files at level N
@@ -1349,6 +2623,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wal_record_round_trip() {
+        let cases = [
+            (None, VALUE_TYPE_VALUE, Slice::from("v")),
+            (None, VALUE_TYPE_DELETION, Slice::empty()),
+            (Some(9), VALUE_TYPE_VALUE, Slice::from("v")),
+            (Some(9), VALUE_TYPE_DELETION, Slice::empty()),
+            (
+                None,
+                VALUE_TYPE_ENTITY,
+                WideColumns::from_value(Slice::from("v")).encode(),
+            ),
+            (Some(9), VALUE_TYPE_ENTITY, Slice::from("entity")),
+        ];
+        for (timestamp, value_type, value) in cases {
+            let encoded =
+                DB::encode_wal_record(3, 42, &Slice::from("key"), timestamp, value_type, &value);
+            let record = DB::decode_wal_record(&encoded).unwrap();
+            assert_eq!(record.cf_id, 3);
+            assert_eq!(record.sequence, 42);
+            assert_eq!(record.key, Slice::from("key"));
+            assert_eq!(record.timestamp, timestamp);
+            assert_eq!(record.value_type, value_type);
+            assert_eq!(record.value, value);
+        }
+    }
+
     #[test]
     fn test_db_overwrite() {
         let temp_dir = TempDir::new().unwrap();
@@ -1536,7 +2837,7 @@ mod tests {
         assert_eq!(not_found, None);
 
         // List CFs
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         assert_eq!(cfs.len(), 3); // default, users, posts
     }
 
@@ -1686,4 +2987,142 @@ mod tests {
         assert_eq!(db.get_property("rocksdb.nonexistent"), None);
         assert_eq!(db.get_property(""), None);
     }
+
+    #[test]
+    fn test_trivial_move_level_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_db");
+        let options = DBOptions {
+            write_buffer_size: 4 * 1024,
+            enable_background_compaction: false,
+            ..Default::default()
+        };
+        let db = DB::open(db_path.to_str().unwrap(), options).unwrap();
+
+        // Ascending keys: the L0 files don't overlap
+        for i in 0..200 {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("key{i:04}")),
+                Slice::from(format!("value{i:04}_padding")),
+            )
+            .unwrap();
+        }
+        db.compact_level(0).unwrap();
+
+        let cf = db.column_families().default_cf();
+        let version_set = cf.version_set();
+        let version_set_guard = version_set.read();
+        let current = version_set_guard.current();
+        let version = current.read();
+        let moved: u64 = version.get_level_files(1).iter().map(|f| f.file_size).sum();
+        assert!(moved > 0);
+
+        let stats = version.level_stats().level(1).unwrap();
+        assert_eq!(stats.trivial_moves(), 1);
+        assert_eq!(stats.bytes_moved(), moved);
+        assert_eq!(stats.compactions(), 0);
+        assert_eq!(stats.bytes_written(), 0);
+    }
+
+    #[test]
+    fn test_intra_l0_compaction_with_concurrent_flush() {
+        use std::sync::Barrier;
+
+        use crate::compaction::compaction_filter::CompactionDecision;
+
+        // Holds the compaction in its merge until the flush has landed
+        struct PausingFilter {
+            armed: AtomicBool,
+            reached: Barrier,
+            release: Barrier,
+        }
+
+        impl CompactionFilter for PausingFilter {
+            fn name(&self) -> &str {
+                "PausingFilter"
+            }
+
+            fn filter(&self, _level: usize, _key: &Slice, _value: &Slice) -> CompactionDecision {
+                if self.armed.swap(false, Ordering::SeqCst) {
+                    self.reached.wait();
+                    self.release.wait();
+                }
+                CompactionDecision::Keep
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_db");
+        let filter = Arc::new(PausingFilter {
+            armed: AtomicBool::new(false),
+            reached: Barrier::new(2),
+            release: Barrier::new(2),
+        });
+        let options = DBOptions {
+            enable_background_compaction: false,
+            parallel_compaction_threads: 0,
+            compaction_filter: Some(filter.clone()),
+            ..Default::default()
+        };
+        let db = DB::open(db_path.to_str().unwrap(), options).unwrap();
+        let cf = db.column_families().default_cf();
+        let flush = |value: &str| {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from("k"),
+                Slice::from(value),
+            )
+            .unwrap();
+            assert!(cf.make_immutable());
+            db.flush_memtable_cf(&cf).unwrap();
+        };
+
+        for i in 1..=4 {
+            flush(&format!("v{i}"));
+        }
+        let inputs = cf
+            .version_set()
+            .read()
+            .current()
+            .read()
+            .get_level_files(0)
+            .to_vec();
+        assert_eq!(inputs.len(), 4);
+        let input_numbers: Vec<u64> = inputs.iter().map(|f| f.number).collect();
+        let compaction = Compaction::new(0, inputs, Vec::new()).with_output_level(0);
+
+        filter.armed.store(true, Ordering::SeqCst);
+        std::thread::scope(|s| {
+            let compacting = s.spawn(|| db.run_compaction(&cf, &compaction, false, None));
+            filter.reached.wait();
+            // The output's file number is taken after this, so the newer
+            // flushed file ends up with the lower number
+            flush("newest");
+            filter.release.wait();
+            compacting.join().unwrap().unwrap();
+        });
+
+        // L0 is ordered by position: the output takes its inputs' place,
+        // ahead of the file flushed while it ran
+        let l0_files = cf
+            .version_set()
+            .read()
+            .current()
+            .read()
+            .get_level_files(0)
+            .to_vec();
+        assert_eq!(l0_files.len(), 2);
+        assert!(!input_numbers.contains(&l0_files[0].number));
+        assert!(l0_files[0].number > l0_files[1].number);
+
+        let value = db.get(&ReadOptions::default(), &Slice::from("k")).unwrap();
+        assert_eq!(value, Some(Slice::from("newest")));
+
+        let mut iter = db.iter().unwrap();
+        assert!(iter.seek(&Slice::from("k")).unwrap());
+        assert_eq!(iter.key(), Slice::from("k"));
+        assert_eq!(iter.value(), Slice::from("newest"));
+        assert!(!iter.next().unwrap());
+    }
 }
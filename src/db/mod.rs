@@ -1,4 +1,6 @@
 #[allow(clippy::module_inception)]
 pub mod db;
 
-pub use db::{DB, DBOptions, ReadOptions, WriteOptions};
+pub use db::{
+    BottommostLevelCompaction, CompactRangeOptions, DB, DBOptions, ReadOptions, WriteOptions,
+};
@@ -1,10 +1,36 @@
-use super::FilterPolicy;
+use super::{
+    BLOCKED_BLOOM_FILTER_MARKER, FilterPolicy,
+    hash::{GOLDEN_RATIO_64, fast_range64, fmix64, hash64},
+};
+
+/// Size of a cache line, the block size of the cache-local format
+const CACHE_LINE_SIZE: usize = 64;
+
+/// Bits per cache line
+const CACHE_LINE_BITS: u32 = (CACHE_LINE_SIZE * 8) as u32;
+
+/// On-disk format of Bloom filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloomFilterFormat {
+    /// Original format ("leveldb.BuiltinBloomFilter2"): 32-bit multiplicative
+    /// hash, probes spread over the whole filter
+    Legacy,
+    /// Cache-local format ("rocksdb.BuiltinBloomFilter"): 64-bit hash picks a
+    /// 64-byte cache line and all probes land in it via double hashing, so a
+    /// lookup costs one cache miss
+    CacheLocal,
+}
 
 /// Bloom filter policy
 /// Uses k hash functions to reduce false positive rate
 /// Default: 10 bits per key gives ~1% false positive rate
+///
+/// New filters use `BloomFilterFormat::CacheLocal`. Filters in either format
+/// are readable by any builtin policy, so tables written before the format
+/// change keep working.
 pub struct BloomFilterPolicy {
     bits_per_key: usize,
+    format: BloomFilterFormat,
 }
 
 impl BloomFilterPolicy {
@@ -19,7 +45,68 @@ impl BloomFilterPolicy {
     /// This is a const fn for compile-time construction of bloom filter
     /// policies.
     pub const fn new(bits_per_key: usize) -> Self {
-        BloomFilterPolicy { bits_per_key }
+        Self::with_format(bits_per_key, BloomFilterFormat::CacheLocal)
+    }
+
+    /// Create a Bloom filter policy writing the given format
+    ///
+    /// `BloomFilterFormat::Legacy` produces filters identical to those of
+    /// earlier releases.
+    pub const fn with_format(bits_per_key: usize, format: BloomFilterFormat) -> Self {
+        BloomFilterPolicy {
+            bits_per_key,
+            format,
+        }
+    }
+
+    /// Get the format of filters written by this policy
+    pub fn format(&self) -> BloomFilterFormat {
+        self.format
+    }
+
+    /// Number of probes for the cache-local format
+    ///
+    /// Blocking skews the load per cache line, so the best probe count is
+    /// lower than for a standard Bloom filter at the same bits per key.
+    fn cache_local_num_probes(bits_per_key: usize) -> u8 {
+        match bits_per_key * 1000 {
+            0..=2080 => 1,
+            2081..=3580 => 2,
+            3581..=5100 => 3,
+            5101..=6640 => 4,
+            6641..=8300 => 5,
+            8301..=10070 => 6,
+            10071..=11720 => 7,
+            11721..=14001 => 8,
+            14002..=16050 => 9,
+            16051..=18300 => 10,
+            18301..=22001 => 11,
+            22002..=25501 => 12,
+            _ => 24,
+        }
+    }
+
+    /// Build a cache-local filter: whole cache lines followed by the probe
+    /// count and the format marker
+    fn create_cache_local_filter(&self, keys: &[Vec<u8>]) -> Vec<u8> {
+        let total_bits = keys.len() * self.bits_per_key.max(1);
+        let num_lines = total_bits.div_ceil(CACHE_LINE_BITS as usize).max(1);
+        let num_probes = Self::cache_local_num_probes(self.bits_per_key);
+
+        let mut filter = vec![0u8; num_lines * CACHE_LINE_SIZE + 2];
+        for key in keys {
+            let (line, a, b) = cache_local_probe_start(hash64(key, 0), num_lines);
+            let line_data = &mut filter[line * CACHE_LINE_SIZE..(line + 1) * CACHE_LINE_SIZE];
+            for i in 0..num_probes as u32 {
+                let bit = a.wrapping_add(i.wrapping_mul(b)) % CACHE_LINE_BITS;
+                line_data[(bit / 8) as usize] |= 1 << (bit % 8);
+            }
+        }
+
+        let len = filter.len();
+        filter[len - 2] = num_probes;
+        filter[len - 1] = BLOCKED_BLOOM_FILTER_MARKER;
+        filter
     }
 
     /// Calculate number of hash functions to use
@@ -32,7 +119,7 @@ impl BloomFilterPolicy {
 
     /// Bloom hash: simple hash function for bloom filters
     #[inline]
-    fn bloom_hash(data: &[u8]) -> u32 {
+    pub(crate) fn bloom_hash(data: &[u8]) -> u32 {
         let mut h = 0xbc9f1d34u32;
         for &b in data {
             h = h.wrapping_mul(0x9e3779b9).wrapping_add(b as u32);
@@ -43,7 +130,10 @@ impl BloomFilterPolicy {
 
 impl FilterPolicy for BloomFilterPolicy {
     fn name(&self) -> &str {
-        "leveldb.BuiltinBloomFilter2"
+        match self.format {
+            BloomFilterFormat::Legacy => "leveldb.BuiltinBloomFilter2",
+            BloomFilterFormat::CacheLocal => "rocksdb.BuiltinBloomFilter",
+        }
     }
 
     fn create_filter(&self, keys: &[Vec<u8>]) -> Vec<u8> {
@@ -52,6 +142,10 @@ impl FilterPolicy for BloomFilterPolicy {
             return vec![0]; // k = 0
         }
 
+        if self.format == BloomFilterFormat::CacheLocal {
+            return self.create_cache_local_filter(keys);
+        }
+
         // Calculate filter size in bits and bytes
         let mut bits = keys.len() * self.bits_per_key;
 
@@ -86,31 +180,68 @@ impl FilterPolicy for BloomFilterPolicy {
 
     #[inline]
     fn may_contain(&self, filter: &[u8], key: &[u8]) -> bool {
-        if filter.len() < 2 {
-            return false;
-        }
+        super::builtin_may_contain(filter, key)
+    }
+}
 
-        let bytes = filter.len() - 1;
-        let bits = bytes * 8;
-        let k = filter[bytes] as usize;
+/// Derive (cache line, first probe, probe step) from a key hash
+///
+/// The line comes from the full hash; the probe sequence from an
+/// independent remix, with an odd step so probes within the 512-bit line
+/// never repeat.
+#[inline]
+fn cache_local_probe_start(hash: u64, num_lines: usize) -> (usize, u32, u32) {
+    let line = fast_range64(hash, num_lines as u64) as usize;
+    let h = fmix64(hash ^ GOLDEN_RATIO_64);
+    (line, h as u32, (h >> 32) as u32 | 1)
+}
 
-        if k > 30 {
-            // Invalid k value, consider it a match to be safe
-            return true;
-        }
+/// Query a cache-local Bloom filter
+#[inline]
+pub(crate) fn cache_local_bloom_may_contain(filter: &[u8], key: &[u8]) -> bool {
+    if filter.len() < CACHE_LINE_SIZE + 2 || !(filter.len() - 2).is_multiple_of(CACHE_LINE_SIZE) {
+        // Not something we wrote, consider it a match to be safe
+        return true;
+    }
 
-        let h = Self::bloom_hash(key);
-        let delta = h.rotate_left(15); // Rotate left 15 bits
+    let num_lines = (filter.len() - 2) / CACHE_LINE_SIZE;
+    let num_probes = filter[filter.len() - 2] as u32;
+    let (line, a, b) = cache_local_probe_start(hash64(key, 0), num_lines);
+    let line_data = &filter[line * CACHE_LINE_SIZE..(line + 1) * CACHE_LINE_SIZE];
 
-        for i in 0..k {
-            let bit_pos = h.wrapping_add((i as u32).wrapping_mul(delta)) as usize % bits;
-            if (filter[bit_pos / 8] & (1 << (bit_pos % 8))) == 0 {
-                return false; // Definitely not in set
-            }
-        }
+    (0..num_probes).all(|i| {
+        let bit = a.wrapping_add(i.wrapping_mul(b)) % CACHE_LINE_BITS;
+        line_data[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+    })
+}
+
+/// Query a legacy Bloom filter
+#[inline]
+pub(crate) fn bloom_may_contain(filter: &[u8], key: &[u8]) -> bool {
+    if filter.len() < 2 {
+        return false;
+    }
+
+    let bytes = filter.len() - 1;
+    let bits = bytes * 8;
+    let k = filter[bytes] as usize;
+
+    if k > 30 {
+        // Invalid k value, consider it a match to be safe
+        return true;
+    }
 
-        true // Might be in set
+    let h = BloomFilterPolicy::bloom_hash(key);
+    let delta = h.rotate_left(15); // Rotate left 15 bits
+
+    for i in 0..k {
+        let bit_pos = h.wrapping_add((i as u32).wrapping_mul(delta)) as usize % bits;
+        if (filter[bit_pos / 8] & (1 << (bit_pos % 8))) == 0 {
+            return false; // Definitely not in set
+        }
     }
+
+    true // Might be in set
 }
 
 #[cfg(test)]
@@ -217,4 +348,64 @@ mod tests {
         let h3 = BloomFilterPolicy::bloom_hash(b"test2");
         assert_ne!(h1, h3);
     }
+
+    #[test]
+    fn test_cache_local_probes_stay_in_one_line() {
+        let policy = BloomFilterPolicy::new(10);
+        let keys: Vec<Vec<u8>> = (0..1000)
+            .map(|i| format!("key{i:04}").into_bytes())
+            .collect();
+        let filter = policy.create_filter(&keys);
+        assert_eq!(*filter.last().unwrap(), BLOCKED_BLOOM_FILTER_MARKER);
+        assert!((filter.len() - 2).is_multiple_of(CACHE_LINE_SIZE));
+
+        // A single key sets bits in exactly one cache line
+        let single = policy.create_filter(&[b"hello".to_vec()]);
+        let lines_touched = single[..single.len() - 2]
+            .chunks(CACHE_LINE_SIZE)
+            .filter(|line| line.iter().any(|&b| b != 0))
+            .count();
+        assert_eq!(lines_touched, 1);
+
+        for key in &keys {
+            assert!(policy.may_contain(&filter, key));
+        }
+    }
+
+    #[test]
+    fn test_legacy_format_still_readable() {
+        let legacy = BloomFilterPolicy::with_format(10, BloomFilterFormat::Legacy);
+        assert_eq!(legacy.name(), "leveldb.BuiltinBloomFilter2");
+
+        let keys: Vec<Vec<u8>> = (0..100)
+            .map(|i| format!("key{i:04}").into_bytes())
+            .collect();
+        let filter = legacy.create_filter(&keys);
+        assert!(*filter.last().unwrap() <= 30);
+
+        // Readable through the current default policy
+        let current = BloomFilterPolicy::new(10);
+        assert_eq!(current.name(), "rocksdb.BuiltinBloomFilter");
+        for key in &keys {
+            assert!(current.may_contain(&filter, key));
+        }
+    }
+
+    #[test]
+    fn test_cache_local_false_positive_rate() {
+        let policy = BloomFilterPolicy::new(10);
+        let keys: Vec<Vec<u8>> = (0..10_000)
+            .map(|i| format!("key{i:06}").into_bytes())
+            .collect();
+        let filter = policy.create_filter(&keys);
+
+        let false_positives = (0..100_000)
+            .filter(|i| policy.may_contain(&filter, format!("absent{i:06}").as_bytes()))
+            .count();
+        // ~1.2% expected for a blocked filter at 10 bits/key
+        assert!(
+            false_positives < 2000,
+            "False positive rate too high: {false_positives}/100000"
+        );
+    }
 }
@@ -0,0 +1,74 @@
+/// 64-bit hashing for filters
+///
+/// Filter probes are derived from a single 64-bit hash per key, so the hash
+/// has to mix well in all bit positions. This is a Murmur-style block hash
+/// with the MurmurHash3 64-bit finalizer.
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+/// Golden ratio constant, used to derive independent values from one hash
+pub(crate) const GOLDEN_RATIO_64: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Hash a key to 64 bits
+pub(crate) fn hash64(data: &[u8], seed: u64) -> u64 {
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(GOLDEN_RATIO_64);
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let k = u64::from_le_bytes(chunk.try_into().unwrap());
+        h ^= mix_block(k);
+        h = h.rotate_left(27).wrapping_mul(5).wrapping_add(0x52dc_e729);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut buf = [0u8; 8];
+        buf[..tail.len()].copy_from_slice(tail);
+        h ^= mix_block(u64::from_le_bytes(buf));
+    }
+
+    fmix64(h)
+}
+
+#[inline]
+fn mix_block(k: u64) -> u64 {
+    k.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2)
+}
+
+/// MurmurHash3 finalizer: every input bit affects every output bit
+#[inline]
+pub(crate) fn fmix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+}
+
+/// Map a hash uniformly onto [0, n) without division
+#[inline]
+pub(crate) fn fast_range64(hash: u64, n: u64) -> u64 {
+    ((hash as u128 * n as u128) >> 64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash64_deterministic_and_seeded() {
+        assert_eq!(hash64(b"key", 0), hash64(b"key", 0));
+        assert_ne!(hash64(b"key", 0), hash64(b"key", 1));
+        assert_ne!(hash64(b"key", 0), hash64(b"kez", 0));
+        // Trailing zero bytes change the length and therefore the hash
+        assert_ne!(hash64(b"key", 0), hash64(b"key\0", 0));
+    }
+
+    #[test]
+    fn test_fast_range64() {
+        assert_eq!(fast_range64(0, 10), 0);
+        assert_eq!(fast_range64(u64::MAX, 10), 9);
+        assert_eq!(fast_range64(u64::MAX / 2, 10), 4);
+    }
+}
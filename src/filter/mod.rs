@@ -1,6 +1,20 @@
+use std::fmt;
+
 pub mod bloom;
+pub(crate) mod hash;
+pub mod ribbon;
+
+pub use bloom::{BloomFilterFormat, BloomFilterPolicy};
+pub use ribbon::RibbonFilterPolicy;
 
-pub use bloom::BloomFilterPolicy;
+/// Last byte of a Ribbon filter
+///
+/// Legacy Bloom filters end with their probe count (at most 30), so the
+/// trailing byte tells the builtin formats apart.
+pub(crate) const RIBBON_FILTER_MARKER: u8 = 0xfe;
+
+/// Last byte of a cache-local Bloom filter
+pub(crate) const BLOCKED_BLOOM_FILTER_MARKER: u8 = 0xfd;
 
 /// Filter policy trait for determining if a key might exist
 pub trait FilterPolicy: Send + Sync {
@@ -11,7 +25,33 @@ pub trait FilterPolicy: Send + Sync {
     /// Keys are concatenated with their lengths
     fn create_filter(&self, keys: &[Vec<u8>]) -> Vec<u8>;
 
+    /// Create a filter for a table written to the given LSM level
+    ///
+    /// Lets a policy trade construction cost for space depending on how long
+    /// the table is expected to live. Defaults to `create_filter`.
+    fn create_filter_for_level(&self, keys: &[Vec<u8>], _level: usize) -> Vec<u8> {
+        self.create_filter(keys)
+    }
+
     /// Test if the key may exist in the filter
     /// Returns false if definitely not in filter, true if might be in filter
     fn may_contain(&self, filter: &[u8], key: &[u8]) -> bool;
 }
+
+impl fmt::Debug for dyn FilterPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Query a filter written by any builtin policy
+///
+/// Builtin policies share this reader so a table can be read regardless of
+/// which builtin policy (or level setting) produced its filter.
+pub(crate) fn builtin_may_contain(filter: &[u8], key: &[u8]) -> bool {
+    match filter.last() {
+        Some(&RIBBON_FILTER_MARKER) => ribbon::ribbon_may_contain(filter, key),
+        Some(&BLOCKED_BLOOM_FILTER_MARKER) => bloom::cache_local_bloom_may_contain(filter, key),
+        _ => bloom::bloom_may_contain(filter, key),
+    }
+}
@@ -0,0 +1,326 @@
+use super::{
+    BloomFilterPolicy, FilterPolicy, RIBBON_FILTER_MARKER,
+    hash::{GOLDEN_RATIO_64, fast_range64, fmix64, hash64},
+};
+
+/// Width of a coefficient row in bits
+const RIBBON_WIDTH: usize = 64;
+
+/// Extra slots per key, as a fraction of the key count (1/32 ≈ 3%)
+const SLOT_OVERHEAD_SHIFT: u32 = 5;
+
+/// Construction attempts (each with a new seed) before adding slots
+const SEEDS_PER_SIZE: u8 = 8;
+
+/// Trailer: num_slots (u32 LE) + seed (u8) + result bits (u8) + marker (u8)
+const TRAILER_SIZE: usize = 7;
+
+/// Ribbon filter policy
+///
+/// A Ribbon filter stores an r-bit fingerprint per key as the solution of a
+/// banded linear system over GF(2): each key maps to a 64-bit coefficient
+/// row starting at a hashed slot, and a lookup XORs the solution values
+/// selected by the row and compares the result with the key's fingerprint.
+/// Space is close to the information-theoretic minimum of r bits per key,
+/// about 30% less than a Bloom filter with the same false-positive rate, at
+/// the cost of slower construction.
+///
+/// Configured like RocksDB's `NewRibbonFilterPolicy`:
+/// - `bloom_equivalent_bits_per_key` picks the false-positive rate of a Bloom
+///   filter with that many bits per key
+/// - `bloom_before_level` keeps Bloom filters for levels below it (e.g. 1 =
+///   Bloom for L0, whose short-lived tables favour fast construction, and
+///   Ribbon for every lower level)
+pub struct RibbonFilterPolicy {
+    bloom_equivalent_bits_per_key: usize,
+    bloom_before_level: usize,
+    result_bits: u32,
+    bloom: BloomFilterPolicy,
+}
+
+impl RibbonFilterPolicy {
+    /// Create a Ribbon filter policy used at every level
+    pub fn new(bloom_equivalent_bits_per_key: usize) -> Self {
+        Self::with_bloom_before_level(bloom_equivalent_bits_per_key, 0)
+    }
+
+    /// Create a policy that writes Bloom filters for levels below
+    /// `bloom_before_level` and Ribbon filters for the others
+    pub fn with_bloom_before_level(
+        bloom_equivalent_bits_per_key: usize,
+        bloom_before_level: usize,
+    ) -> Self {
+        // A Bloom filter with b bits/key has FP rate ~0.6185^b = 2^(-0.69 b);
+        // an r-bit fingerprint has FP rate 2^-r
+        let result_bits = ((bloom_equivalent_bits_per_key as f64 * std::f64::consts::LN_2).round()
+            as u32)
+            .clamp(1, 16);
+
+        RibbonFilterPolicy {
+            bloom_equivalent_bits_per_key,
+            bloom_before_level,
+            result_bits,
+            bloom: BloomFilterPolicy::new(bloom_equivalent_bits_per_key),
+        }
+    }
+
+    /// Get the Bloom-equivalent bits per key this policy was created with
+    pub fn bloom_equivalent_bits_per_key(&self) -> usize {
+        self.bloom_equivalent_bits_per_key
+    }
+
+    /// Build a Ribbon filter, retrying with new seeds (and eventually more
+    /// slots) until the linear system is solvable
+    fn create_ribbon_filter(&self, keys: &[Vec<u8>]) -> Vec<u8> {
+        let hashes: Vec<u64> = keys.iter().map(|k| hash64(k, 0)).collect();
+
+        let mut num_starts = (hashes.len() + (hashes.len() >> SLOT_OVERHEAD_SHIFT)).max(1);
+        loop {
+            for seed in 0..SEEDS_PER_SIZE {
+                if let Some(filter) = self.try_build(&hashes, num_starts, seed) {
+                    return filter;
+                }
+            }
+            num_starts += (num_starts >> SLOT_OVERHEAD_SHIFT).max(1);
+        }
+    }
+
+    /// Attempt construction with a fixed size and seed
+    fn try_build(&self, hashes: &[u64], num_starts: usize, seed: u8) -> Option<Vec<u8>> {
+        let num_slots = num_starts + RIBBON_WIDTH - 1;
+        if num_slots > u32::MAX as usize {
+            return None;
+        }
+
+        // Banding: on-the-fly Gaussian elimination keeping the matrix in
+        // echelon form, one row per slot
+        let mut coeffs = vec![0u64; num_slots];
+        let mut results = vec![0u32; num_slots];
+        for &hash in hashes {
+            let row = RibbonRow::new(hash, seed, num_starts, self.result_bits);
+            let (mut start, mut coeff, mut result) = (row.start, row.coeff, row.result);
+            loop {
+                if coeffs[start] == 0 {
+                    coeffs[start] = coeff;
+                    results[start] = result;
+                    break;
+                }
+                coeff ^= coeffs[start];
+                result ^= results[start];
+                if coeff == 0 {
+                    // Row reduced to nothing: fine for duplicate keys,
+                    // inconsistent otherwise
+                    if result != 0 {
+                        return None;
+                    }
+                    break;
+                }
+                let shift = coeff.trailing_zeros();
+                start += shift as usize;
+                coeff >>= shift;
+            }
+        }
+
+        // Back substitution from the last slot; free variables are zero
+        let mut solution = vec![0u32; num_slots];
+        for i in (0..num_slots).rev() {
+            let coeff = coeffs[i];
+            if coeff == 0 {
+                continue;
+            }
+            let mut value = results[i];
+            let mut rest = coeff & !1;
+            while rest != 0 {
+                let j = rest.trailing_zeros() as usize;
+                value ^= solution[i + j];
+                rest &= rest - 1;
+            }
+            solution[i] = value;
+        }
+
+        // Pack the solution at result_bits per slot
+        let r = self.result_bits as usize;
+        let mut filter = vec![0u8; (num_slots * r).div_ceil(8) + TRAILER_SIZE];
+        for (i, &value) in solution.iter().enumerate() {
+            for b in 0..r {
+                if value & (1 << b) != 0 {
+                    let bit = i * r + b;
+                    filter[bit / 8] |= 1 << (bit % 8);
+                }
+            }
+        }
+
+        let trailer_start = filter.len() - TRAILER_SIZE;
+        filter[trailer_start..trailer_start + 4].copy_from_slice(&(num_slots as u32).to_le_bytes());
+        filter[trailer_start + 4] = seed;
+        filter[trailer_start + 5] = self.result_bits as u8;
+        filter[trailer_start + 6] = RIBBON_FILTER_MARKER;
+        Some(filter)
+    }
+}
+
+impl FilterPolicy for RibbonFilterPolicy {
+    fn name(&self) -> &str {
+        "rocksdb.RibbonFilter"
+    }
+
+    fn create_filter(&self, keys: &[Vec<u8>]) -> Vec<u8> {
+        if keys.is_empty() {
+            // Same empty filter as Bloom: matches nothing
+            return self.bloom.create_filter(keys);
+        }
+        self.create_ribbon_filter(keys)
+    }
+
+    fn create_filter_for_level(&self, keys: &[Vec<u8>], level: usize) -> Vec<u8> {
+        if level < self.bloom_before_level {
+            self.bloom.create_filter(keys)
+        } else {
+            self.create_filter(keys)
+        }
+    }
+
+    #[inline]
+    fn may_contain(&self, filter: &[u8], key: &[u8]) -> bool {
+        super::builtin_may_contain(filter, key)
+    }
+}
+
+/// Equation for one key: `start`, the 64-bit coefficient row (lowest bit
+/// set) and the r-bit fingerprint
+struct RibbonRow {
+    start: usize,
+    coeff: u64,
+    result: u32,
+}
+
+impl RibbonRow {
+    #[inline]
+    fn new(hash: u64, seed: u8, num_starts: usize, result_bits: u32) -> Self {
+        let h = fmix64(hash ^ (seed as u64 + 1).wrapping_mul(GOLDEN_RATIO_64));
+        let start = fast_range64(h, num_starts as u64) as usize;
+        let coeff = fmix64(h.wrapping_add(GOLDEN_RATIO_64)) | 1;
+        let result_mask = (1u64 << result_bits) - 1;
+        let result = (fmix64(h ^ GOLDEN_RATIO_64.rotate_left(32)) & result_mask) as u32;
+        RibbonRow {
+            start,
+            coeff,
+            result,
+        }
+    }
+}
+
+/// Query a Ribbon filter
+pub(crate) fn ribbon_may_contain(filter: &[u8], key: &[u8]) -> bool {
+    if filter.len() < TRAILER_SIZE {
+        return true;
+    }
+
+    let trailer_start = filter.len() - TRAILER_SIZE;
+    let num_slots =
+        u32::from_le_bytes(filter[trailer_start..trailer_start + 4].try_into().unwrap()) as usize;
+    let seed = filter[trailer_start + 4];
+    let result_bits = filter[trailer_start + 5] as u32;
+    let r = result_bits as usize;
+
+    if num_slots < RIBBON_WIDTH || !(1..=16).contains(&result_bits) {
+        // Not something we wrote, consider it a match to be safe
+        return true;
+    }
+    if (num_slots * r).div_ceil(8) != trailer_start {
+        return true;
+    }
+
+    let num_starts = num_slots - RIBBON_WIDTH + 1;
+    let row = RibbonRow::new(hash64(key, 0), seed, num_starts, result_bits);
+
+    let mut acc = 0u32;
+    let mut coeff = row.coeff;
+    while coeff != 0 {
+        let slot = row.start + coeff.trailing_zeros() as usize;
+        acc ^= read_packed(filter, slot * r, r);
+        coeff &= coeff - 1;
+    }
+    acc == row.result
+}
+
+/// Read `width` (<= 16) bits starting at bit offset `bit`
+#[inline]
+fn read_packed(data: &[u8], bit: usize, width: usize) -> u32 {
+    let byte = bit / 8;
+    let mut word = 0u32;
+    for (i, &b) in data[byte..(byte + 3).min(data.len())].iter().enumerate() {
+        word |= (b as u32) << (8 * i);
+    }
+    (word >> (bit % 8)) & ((1 << width) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(prefix: &str, range: std::ops::Range<usize>) -> Vec<Vec<u8>> {
+        range
+            .map(|i| format!("{prefix}{i:06}").into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_ribbon_filter_empty() {
+        let policy = RibbonFilterPolicy::new(10);
+        let filter = policy.create_filter(&[]);
+        assert!(!policy.may_contain(&filter, b"anything"));
+    }
+
+    #[test]
+    fn test_ribbon_filter_no_false_negatives() {
+        let policy = RibbonFilterPolicy::new(10);
+        for n in [1, 2, 10, 63, 64, 65, 1000, 10_000] {
+            let inserted = keys("key", 0..n);
+            let filter = policy.create_filter(&inserted);
+            for key in &inserted {
+                assert!(policy.may_contain(&filter, key), "n={n}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_ribbon_filter_duplicate_keys() {
+        let policy = RibbonFilterPolicy::new(10);
+        let mut inserted = keys("key", 0..100);
+        inserted.extend(keys("key", 0..100));
+        let filter = policy.create_filter(&inserted);
+        for key in &inserted {
+            assert!(policy.may_contain(&filter, key));
+        }
+    }
+
+    #[test]
+    fn test_ribbon_filter_smaller_than_bloom() {
+        let inserted = keys("key", 0..10_000);
+        let ribbon = RibbonFilterPolicy::new(10).create_filter(&inserted);
+        let bloom = BloomFilterPolicy::new(10).create_filter(&inserted);
+
+        // ~7 bits/key plus a few percent of slack vs 10 bits/key
+        let ratio = ribbon.len() as f64 / bloom.len() as f64;
+        assert!(ratio < 0.8, "ribbon/bloom size ratio {ratio:.3}");
+    }
+
+    #[test]
+    fn test_ribbon_filter_bloom_before_level() {
+        let policy = RibbonFilterPolicy::with_bloom_before_level(10, 1);
+        let inserted = keys("key", 0..100);
+
+        let l0_filter = policy.create_filter_for_level(&inserted, 0);
+        let l1_filter = policy.create_filter_for_level(&inserted, 1);
+        assert_ne!(*l0_filter.last().unwrap(), RIBBON_FILTER_MARKER);
+        assert_eq!(*l1_filter.last().unwrap(), RIBBON_FILTER_MARKER);
+
+        // Both formats are readable through either builtin policy
+        let bloom = BloomFilterPolicy::new(10);
+        for key in &inserted {
+            assert!(policy.may_contain(&l0_filter, key));
+            assert!(bloom.may_contain(&l1_filter, key));
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::{fs, path::Path};
 use crate::{
     DB, Result, Slice,
     column_family::ColumnFamilyHandle,
+    memtable::InternalKey,
+    util::now_secs,
     version::{FileMetaData, VersionEdit},
 };
 
@@ -29,6 +31,8 @@ impl Default for IngestExternalFileOptions {
 pub struct ExternalFileInfo {
     pub file_size: u64,
     pub num_entries: u64,
+    pub num_deletions: u64,
+    pub creation_time: u64,
     pub smallest_key: Slice,
     pub largest_key: Slice,
 }
@@ -67,12 +71,18 @@ pub fn validate_external_file<P: AsRef<Path>>(
     }
 
     let num_entries = entries.len() as u64;
+    let num_deletions = entries
+        .iter()
+        .filter(|(key, _)| InternalKey::decode(key).is_ok_and(|key| key.is_deletion()))
+        .count() as u64;
     let smallest_key = entries.first().unwrap().0.clone();
     let largest_key = entries.last().unwrap().0.clone();
 
     Ok(ExternalFileInfo {
         file_size,
         num_entries,
+        num_deletions,
+        creation_time: table_reader.properties().creation_time,
         smallest_key,
         largest_key,
     })
@@ -159,12 +169,21 @@ impl DB {
         let target_path = self.db_path().join(format!("{:06}.sst", file_number));
         copy_external_file(file_path, &target_path, options.move_files)?;
 
-        // 5. Create file metadata
+        // 5. Create file metadata (bounds are user keys)
         let file_meta = FileMetaData {
             number: file_number,
             file_size: file_info.file_size,
-            smallest: file_info.smallest_key,
-            largest: file_info.largest_key,
+            smallest: InternalKey::decode(&file_info.smallest_key)?
+                .user_key()
+                .clone(),
+            largest: InternalKey::decode(&file_info.largest_key)?
+                .user_key()
+                .clone(),
+            num_entries: file_info.num_entries,
+            num_deletions: file_info.num_deletions,
+            marked_for_compaction: false,
+            creation_time: file_info.creation_time,
+            file_creation_time: now_secs(),
         };
 
         // 6. Add file to LSM tree at level 0 via VersionEdit
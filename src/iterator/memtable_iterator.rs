@@ -1,10 +1,14 @@
-use std::sync::Arc;
+use std::{ops::Bound, sync::Arc};
 
-use crossbeam_skiplist::SkipMap;
+use crossbeam_skiplist::map::Entry;
 
 use crate::{
     iterator::Iterator,
-    memtable::memtable::InternalKey,
+    memtable::{
+        PrefixBloom,
+        memtable::{InternalKey, VALUE_TYPE_DELETION, VALUE_TYPE_ENTITY, VALUE_TYPE_VALUE},
+        skiplist::{SkipList, SkipListKey},
+    },
     util::{Result, Slice, Status},
 };
 
@@ -19,97 +23,93 @@ use crate::{
 ///
 /// The crossbeam_skiplist iterator is used indirectly through range queries.
 /// We maintain the current position and use range() to get the next/previous
-/// elements.
+/// elements, in the order of the MemTable's comparator.
 ///
 /// Deletion markers are exposed to allow proper merging with other iterators.
 /// The MergingIterator will filter them from final results.
 pub struct MemTableIterator {
-    map: Arc<SkipMap<Vec<u8>, Vec<u8>>>,
+    list: SkipList,
     current_key: Option<Vec<u8>>,
     current_value: Option<Vec<u8>>,
-    current_is_deletion: bool,
+    current_value_type: u8,
+    current_timestamp: Option<u64>,
     valid: bool,
+    /// Prefix filter consulted on seek (prefix iteration only)
+    prefix_bloom: Option<Arc<PrefixBloom>>,
 }
 
 impl MemTableIterator {
-    pub fn new(map: Arc<SkipMap<Vec<u8>, Vec<u8>>>) -> Self {
+    pub fn new(list: SkipList) -> Self {
         MemTableIterator {
-            map,
+            list,
             current_key: None,
             current_value: None,
-            current_is_deletion: false,
+            current_value_type: VALUE_TYPE_VALUE,
+            current_timestamp: None,
             valid: false,
+            prefix_bloom: None,
+        }
+    }
+
+    /// Skip seeks whose target prefix is excluded by the MemTable's prefix
+    /// Bloom filter
+    pub fn set_prefix_bloom(&mut self, prefix_bloom: Arc<PrefixBloom>) {
+        self.prefix_bloom = Some(prefix_bloom);
+    }
+
+    /// Position at a SkipList entry, or invalidate if there is none
+    fn set_current(&mut self, entry: Option<Entry<'_, SkipListKey, Vec<u8>>>) -> Result<bool> {
+        match entry {
+            Some(entry) => {
+                let internal_slice = Slice::from(entry.key().data());
+                let internal_key = InternalKey::decode(&internal_slice)?;
+
+                // Store entry (including deletion markers)
+                self.current_key = Some(entry.key().data().to_vec());
+                self.current_value = Some(entry.value().clone());
+                self.current_value_type = internal_key.value_type;
+                self.current_timestamp = internal_key.timestamp();
+                self.valid = true;
+                Ok(true)
+            },
+            None => {
+                self.valid = false;
+                Ok(false)
+            },
         }
     }
 
     /// Advance to next entry (including deletion markers)
     fn advance_forward(&mut self) -> Result<bool> {
-        let start_key = if let Some(ref key) = self.current_key {
-            // Find next key after current
-            let mut next_key = key.clone();
-            // Increment to get strictly greater key
-            next_key.push(0);
-            next_key
-        } else {
+        let list = self.list.clone();
+        let entry = match self.current_key {
+            // Find next key strictly after current
+            Some(ref key) => list
+                .map
+                .range((Bound::Excluded(list.key(key)), Bound::Unbounded))
+                .next(),
             // Start from beginning
-            vec![]
+            None => list.map.front(),
         };
-
-        // Get next entry from SkipMap
-        if let Some(entry) = self.map.range(start_key..).next() {
-            let internal_slice = Slice::from(entry.key().clone());
-            let internal_key = InternalKey::decode(&internal_slice)?;
-
-            // Store entry (including deletion markers)
-            self.current_key = Some(entry.key().clone());
-            self.current_value = Some(entry.value().clone());
-            self.current_is_deletion = internal_key.is_deletion();
-            self.valid = true;
-            Ok(true)
-        } else {
-            // Reached end
-            self.valid = false;
-            Ok(false)
-        }
+        self.set_current(entry)
     }
 
     /// Move to previous entry (including deletion markers)
     fn advance_backward(&mut self) -> Result<bool> {
-        if let Some(ref key) = self.current_key {
-            // Need to scan from beginning to find previous
-            // This is inefficient but crossbeam_skiplist doesn't support reverse iteration
-            let mut last_valid: Option<(Vec<u8>, Vec<u8>, bool)> = None;
-
-            for entry in self.map.iter() {
-                if entry.key() >= key {
-                    break;
-                }
-
-                let internal_slice = Slice::from(entry.key().clone());
-                let internal_key = InternalKey::decode(&internal_slice)?;
-
-                // Store all entries including deletion markers
-                last_valid = Some((
-                    entry.key().clone(),
-                    entry.value().clone(),
-                    internal_key.is_deletion(),
-                ));
-            }
-
-            if let Some((key, value, is_deletion)) = last_valid {
-                self.current_key = Some(key);
-                self.current_value = Some(value);
-                self.current_is_deletion = is_deletion;
-                self.valid = true;
-                Ok(true)
-            } else {
+        let list = self.list.clone();
+        match self.current_key {
+            Some(ref key) => {
+                let entry = list
+                    .map
+                    .range((Bound::Unbounded, Bound::Excluded(list.key(key))))
+                    .next_back();
+                self.set_current(entry)
+            },
+            None => {
+                // Not positioned, can't go backward
                 self.valid = false;
                 Ok(false)
-            }
-        } else {
-            // Not positioned, can't go backward
-            self.valid = false;
-            Ok(false)
+            },
         }
     }
 
@@ -135,92 +135,52 @@ impl Iterator for MemTableIterator {
 
     fn seek_to_last(&mut self) -> Result<bool> {
         // Find last entry (including deletion markers)
-        let mut last_entry: Option<(Vec<u8>, Vec<u8>, bool)> = None;
-
-        for entry in self.map.iter() {
-            let internal_slice = Slice::from(entry.key().clone());
-            let internal_key = InternalKey::decode(&internal_slice)?;
-
-            // Store all entries including deletion markers
-            last_entry = Some((
-                entry.key().clone(),
-                entry.value().clone(),
-                internal_key.is_deletion(),
-            ));
-        }
-
-        if let Some((key, value, is_deletion)) = last_entry {
-            self.current_key = Some(key);
-            self.current_value = Some(value);
-            self.current_is_deletion = is_deletion;
-            self.valid = true;
-            Ok(true)
-        } else {
-            self.valid = false;
-            Ok(false)
-        }
+        let list = self.list.clone();
+        self.set_current(list.map.back())
     }
 
     fn seek(&mut self, target: &Slice) -> Result<bool> {
-        // Encode target as internal key for searching
-        // We use a high sequence number to match any version
-        let target_internal = InternalKey::new(target.clone(), u64::MAX, 1);
-        let target_encoded = target_internal.encode();
-
         self.current_key = None;
         self.current_value = None;
-        self.current_is_deletion = false;
+        self.current_value_type = VALUE_TYPE_VALUE;
         self.valid = false;
 
-        // Find first key >= target (including deletion markers)
-        for entry in self.map.range(target_encoded.data().to_vec()..) {
-            let internal_slice = Slice::from(entry.key().clone());
-            let internal_key = InternalKey::decode(&internal_slice)?;
-
-            // Check if user key matches or is greater
-            if internal_key.user_key() >= target {
-                self.current_key = Some(entry.key().clone());
-                self.current_value = Some(entry.value().clone());
-                self.current_is_deletion = internal_key.is_deletion();
-                self.valid = true;
-                return Ok(true);
-            }
+        // No key with the target's prefix was ever inserted
+        if let Some(ref bloom) = self.prefix_bloom
+            && !bloom.may_contain_key(target.data())
+        {
+            return Ok(false);
         }
 
-        self.valid = false;
-        Ok(false)
+        // Find first entry with user key >= target (including deletion
+        // markers): the highest sequence sorts first within a user key
+        let target_encoded =
+            InternalKey::new(target.clone(), u64::MAX, VALUE_TYPE_DELETION).encode();
+        let list = self.list.clone();
+        let entry = list
+            .map
+            .range((
+                Bound::Included(list.key(target_encoded.data())),
+                Bound::Unbounded,
+            ))
+            .next();
+        self.set_current(entry)
     }
 
     fn seek_for_prev(&mut self, target: &Slice) -> Result<bool> {
-        // Find last key <= target (including deletion markers)
-        let mut last_entry: Option<(Vec<u8>, Vec<u8>, bool)> = None;
-
-        for entry in self.map.iter() {
-            let internal_slice = Slice::from(entry.key().clone());
-            let internal_key = InternalKey::decode(&internal_slice)?;
-
-            if internal_key.user_key() > target {
-                break;
-            }
-
-            // Store all entries including deletion markers
-            last_entry = Some((
-                entry.key().clone(),
-                entry.value().clone(),
-                internal_key.is_deletion(),
-            ));
-        }
-
-        if let Some((key, value, is_deletion)) = last_entry {
-            self.current_key = Some(key);
-            self.current_value = Some(value);
-            self.current_is_deletion = is_deletion;
-            self.valid = true;
-            Ok(true)
-        } else {
-            self.valid = false;
-            Ok(false)
-        }
+        // Find last entry with user key <= target (including deletion
+        // markers): sequence 0 with the largest type sorts last within a
+        // user key
+        let target_encoded = InternalKey::new(target.clone(), 0, u8::MAX).encode();
+        let list = self.list.clone();
+        let entry = list
+            .map
+            .range((
+                Bound::Unbounded,
+                Bound::Included(list.key(target_encoded.data())),
+            ))
+            .next_back();
+        self.set_current(entry)
     }
 
     fn next(&mut self) -> Result<bool> {
@@ -253,7 +213,15 @@ impl Iterator for MemTableIterator {
     }
 
     fn is_deletion(&self) -> bool {
-        self.current_is_deletion
+        self.current_value_type == VALUE_TYPE_DELETION
+    }
+
+    fn is_entity(&self) -> bool {
+        self.current_value_type == VALUE_TYPE_ENTITY
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        self.current_timestamp
     }
 }
 
@@ -1,8 +1,9 @@
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{cmp::Ordering, collections::BinaryHeap, sync::Arc};
 
 use crate::{
     iterator::Iterator,
-    util::{Result, Slice},
+    util::{Comparator, Result, Slice, comparator::bytewise_comparator},
+    wide_columns::WideColumns,
 };
 
 /// Merging iterator for combining multiple sorted iterators
@@ -32,16 +33,24 @@ use crate::{
 ///
 /// # Implementation Notes
 ///
+/// - Keys are ordered by the comparator given at creation (bytewise by
+///   default), which must be the one the children are sorted by
 /// - Uses BinaryHeap with reversed ordering for min-heap behavior
 /// - Each heap entry stores (key, iterator_index) for tie-breaking
 /// - Clones current key/value to avoid lifetime issues
 /// - Forward iteration (next) is O(log k) where k = number of iterators
 /// - Backward iteration (prev) is expensive - requires full re-scan
+/// - With a read timestamp, forward iteration skips versions with a newer
+///   user-defined timestamp
 pub struct MergingIterator {
     iterators: Vec<Box<dyn Iterator>>,
     heap: BinaryHeap<HeapEntry>,
+    comparator: Arc<dyn Comparator>,
+    read_timestamp: Option<u64>,
     current_key: Option<Slice>,
     current_value: Option<Slice>,
+    current_columns: Option<WideColumns>,
+    current_timestamp: Option<u64>,
     valid: bool,
 }
 
@@ -49,16 +58,15 @@ pub struct MergingIterator {
 struct HeapEntry {
     key: Slice,
     index: usize,
+    comparator: Arc<dyn Comparator>,
 }
 
 impl Ord for HeapEntry {
     fn cmp(&self, other: &Self) -> Ordering {
         // Reverse ordering for min-heap (BinaryHeap is max-heap by default)
         // When keys are equal, prioritize lower index (earlier iterator)
-        other
-            .key
-            .data()
-            .cmp(self.key.data())
+        self.comparator
+            .compare(other.key.data(), self.key.data())
             .then_with(|| other.index.cmp(&self.index))
     }
 }
@@ -84,24 +92,70 @@ impl MergingIterator {
     /// - iterators[0] has highest priority (e.g., active MemTable)
     /// - iterators[n] has lowest priority (e.g., oldest SSTable)
     pub fn new(iterators: Vec<Box<dyn Iterator>>) -> Self {
+        Self::with_comparator(iterators, bytewise_comparator())
+    }
+
+    /// Create a merging iterator over children sorted by `comparator`
+    pub fn with_comparator(
+        iterators: Vec<Box<dyn Iterator>>,
+        comparator: Arc<dyn Comparator>,
+    ) -> Self {
         MergingIterator {
             iterators,
             heap: BinaryHeap::new(),
+            comparator,
+            read_timestamp: None,
             current_key: None,
             current_value: None,
+            current_columns: None,
+            current_timestamp: None,
             valid: false,
         }
     }
 
+    /// Only expose versions written at or before `timestamp`
+    pub fn set_read_timestamp(&mut self, timestamp: u64) {
+        self.read_timestamp = Some(timestamp);
+    }
+
+    /// Expose the current entry of a child iterator
+    ///
+    /// Entities are decoded; their default column becomes the value.
+    fn set_current(&mut self, index: usize) -> Result<()> {
+        let iter = &self.iterators[index];
+        self.current_key = Some(iter.key());
+        if iter.is_entity() {
+            let columns = WideColumns::decode(&iter.value())?;
+            self.current_value = Some(
+                columns
+                    .default_value()
+                    .cloned()
+                    .unwrap_or_else(Slice::empty),
+            );
+            self.current_columns = Some(columns);
+        } else {
+            self.current_value = Some(iter.value());
+            self.current_columns = None;
+        }
+        self.current_timestamp = iter.timestamp();
+        self.valid = true;
+        Ok(())
+    }
+
+    fn heap_entry(&self, key: Slice, index: usize) -> HeapEntry {
+        HeapEntry {
+            key,
+            index,
+            comparator: Arc::clone(&self.comparator),
+        }
+    }
+
     /// Rebuild heap with current positions of all valid iterators
     fn rebuild_heap(&mut self) {
         self.heap.clear();
         for (idx, iter) in self.iterators.iter().enumerate() {
             if iter.valid() {
-                self.heap.push(HeapEntry {
-                    key: iter.key(),
-                    index: idx,
-                });
+                self.heap.push(self.heap_entry(iter.key(), idx));
             }
         }
     }
@@ -111,7 +165,23 @@ impl MergingIterator {
     fn update_current(&mut self) -> Result<bool> {
         loop {
             if let Some(entry) = self.heap.peek() {
-                let is_deletion = self.iterators[entry.index].is_deletion();
+                let idx = entry.index;
+
+                // Skip versions newer than the read timestamp
+                if let Some(read_timestamp) = self.read_timestamp
+                    && self.iterators[idx]
+                        .timestamp()
+                        .is_some_and(|ts| ts > read_timestamp)
+                {
+                    self.heap.pop();
+                    if self.iterators[idx].next()? {
+                        self.heap
+                            .push(self.heap_entry(self.iterators[idx].key(), idx));
+                    }
+                    continue;
+                }
+
+                let is_deletion = self.iterators[idx].is_deletion();
 
                 // Skip deletion markers
                 if is_deletion {
@@ -139,10 +209,7 @@ impl MergingIterator {
                             let current_key = self.iterators[idx].key();
                             if current_key.data() != deleted_key.data() {
                                 // Different user key - add back to heap
-                                self.heap.push(HeapEntry {
-                                    key: current_key,
-                                    index: idx,
-                                });
+                                self.heap.push(self.heap_entry(current_key, idx));
                                 break;
                             }
                             // Same user key - keep advancing to skip old
@@ -153,10 +220,7 @@ impl MergingIterator {
                 }
 
                 // Not a deletion - return this entry
-                let idx = entry.index;
-                self.current_key = Some(self.iterators[idx].key());
-                self.current_value = Some(self.iterators[idx].value());
-                self.valid = true;
+                self.set_current(idx)?;
                 return Ok(true);
             } else {
                 self.valid = false;
@@ -203,7 +267,9 @@ impl Iterator for MergingIterator {
                         max_idx = Some(idx);
                     },
                     Some(current_max) => {
-                        if key.data() > current_max.data() {
+                        if self.comparator.compare(key.data(), current_max.data())
+                            == Ordering::Greater
+                        {
                             max_key = Some(key);
                             max_idx = Some(idx);
                         } else if key.data() == current_max.data() && idx < max_idx.unwrap() {
@@ -216,9 +282,7 @@ impl Iterator for MergingIterator {
         }
 
         if let Some(idx) = max_idx {
-            self.current_key = Some(self.iterators[idx].key());
-            self.current_value = Some(self.iterators[idx].value());
-            self.valid = true;
+            self.set_current(idx)?;
             Ok(true)
         } else {
             self.valid = false;
@@ -258,7 +322,9 @@ impl Iterator for MergingIterator {
                         max_idx = Some(idx);
                     },
                     Some(current_max) => {
-                        if key.data() > current_max.data() {
+                        if self.comparator.compare(key.data(), current_max.data())
+                            == Ordering::Greater
+                        {
                             max_key = Some(key);
                             max_idx = Some(idx);
                         } else if key.data() == current_max.data() && idx < max_idx.unwrap() {
@@ -271,9 +337,7 @@ impl Iterator for MergingIterator {
         }
 
         if let Some(idx) = max_idx {
-            self.current_key = Some(self.iterators[idx].key());
-            self.current_value = Some(self.iterators[idx].value());
-            self.valid = true;
+            self.set_current(idx)?;
             Ok(true)
         } else {
             self.valid = false;
@@ -301,10 +365,7 @@ impl Iterator for MergingIterator {
                 let new_key = self.iterators[idx].key();
                 if new_key.data() != current_key.data() {
                     // Different user key - add back to heap
-                    self.heap.push(HeapEntry {
-                        key: new_key,
-                        index: idx,
-                    });
+                    self.heap.push(self.heap_entry(new_key, idx));
                     break;
                 }
                 // Same user key - keep advancing to skip old versions
@@ -331,10 +392,7 @@ impl Iterator for MergingIterator {
                     let new_key = self.iterators[idx].key();
                     if new_key.data() != current_key.data() {
                         // Different user key - add back to heap
-                        self.heap.push(HeapEntry {
-                            key: new_key,
-                            index: idx,
-                        });
+                        self.heap.push(self.heap_entry(new_key, idx));
                         break;
                     }
                     // Same user key - keep advancing to skip old versions
@@ -366,6 +424,16 @@ impl Iterator for MergingIterator {
     fn valid(&self) -> bool {
         self.valid
     }
+
+    fn columns(&self) -> WideColumns {
+        self.current_columns
+            .clone()
+            .unwrap_or_else(|| WideColumns::from_value(self.value()))
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        self.current_timestamp
+    }
 }
 
 #[cfg(test)]
@@ -446,4 +514,38 @@ mod tests {
         assert!(merge_iter.seek(&Slice::from("key4")).unwrap());
         assert_eq!(merge_iter.key(), Slice::from("key5"));
     }
+
+    #[test]
+    fn test_merging_iterator_read_timestamp() {
+        let mt1 = MemTable::with_comparator(Arc::new(crate::util::BytewiseComparatorWithU64Ts));
+        mt1.add_with_timestamp(3, Slice::from("a"), 30, Slice::from("a30"));
+        mt1.delete_with_timestamp(4, Slice::from("b"), 20);
+
+        let mt2 = MemTable::with_comparator(Arc::new(crate::util::BytewiseComparatorWithU64Ts));
+        mt2.add_with_timestamp(1, Slice::from("a"), 10, Slice::from("a10"));
+        mt2.add_with_timestamp(2, Slice::from("b"), 10, Slice::from("b10"));
+
+        let scan = |read_timestamp: u64| {
+            let iter1: Box<dyn Iterator> = Box::new(mt1.iter());
+            let iter2: Box<dyn Iterator> = Box::new(mt2.iter());
+            let mut merge_iter = MergingIterator::new(vec![iter1, iter2]);
+            merge_iter.set_read_timestamp(read_timestamp);
+
+            let mut entries = Vec::new();
+            let mut valid = merge_iter.seek_to_first().unwrap();
+            while valid {
+                entries.push((merge_iter.value(), merge_iter.timestamp().unwrap()));
+                valid = merge_iter.next().unwrap();
+            }
+            entries
+        };
+
+        assert_eq!(scan(5), vec![]);
+        assert_eq!(
+            scan(15),
+            vec![(Slice::from("a10"), 10), (Slice::from("b10"), 10)]
+        );
+        assert_eq!(scan(25), vec![(Slice::from("a10"), 10)]);
+        assert_eq!(scan(u64::MAX), vec![(Slice::from("a30"), 30)]);
+    }
 }
@@ -31,7 +31,10 @@
 /// - Min-heap for efficient multi-way merge (O(log N) per next())
 /// - Block-level caching in TableIterator
 /// - Seek operations use binary search where possible
-use crate::util::{Result, Slice};
+use crate::{
+    util::{Result, Slice},
+    wide_columns::WideColumns,
+};
 
 /// Iterator trait for traversing key-value pairs in sorted order
 ///
@@ -114,12 +117,40 @@ pub trait Iterator {
     fn is_deletion(&self) -> bool {
         false
     }
+
+    /// Check if current entry is a wide-column entity
+    ///
+    /// Prerequisite: valid() == true
+    /// Iterators over a single source return entities serialized from
+    /// value(); merging iterators decode them, returning the default column
+    /// from value() and all of them from columns()
+    fn is_entity(&self) -> bool {
+        false
+    }
+
+    /// Get the columns of the current entry
+    ///
+    /// Prerequisite: valid() == true
+    /// A plain value is a single default (anonymous) column
+    fn columns(&self) -> WideColumns {
+        WideColumns::from_value(self.value())
+    }
+
+    /// Get the user-defined timestamp of the current entry
+    ///
+    /// Prerequisite: valid() == true
+    /// Returns None for column families without timestamps
+    fn timestamp(&self) -> Option<u64> {
+        None
+    }
 }
 
 mod memtable_iterator;
 mod merging_iterator;
+mod prefix_iterator;
 mod table_iterator;
 
 pub use memtable_iterator::MemTableIterator;
 pub use merging_iterator::MergingIterator;
+pub use prefix_iterator::PrefixIterator;
 pub use table_iterator::TableIterator;
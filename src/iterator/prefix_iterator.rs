@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use crate::{
+    iterator::Iterator,
+    util::{Result, Slice, SliceTransform},
+    wide_columns::WideColumns,
+};
+
+/// Iterator bounded to the prefix of its seek target
+///
+/// Implements `ReadOptions::prefix_same_as_start`: after `seek()` or
+/// `seek_for_prev()`, the iterator becomes invalid as soon as the inner
+/// iterator reaches a key whose prefix differs from the target's. Since
+/// iteration never leaves the prefix, child iterators are free to skip
+/// sources whose prefix filters exclude it.
+///
+/// `seek_to_first()` and `seek_to_last()` are not bounded.
+pub struct PrefixIterator {
+    inner: Box<dyn Iterator>,
+    prefix_extractor: Arc<dyn SliceTransform>,
+    /// Prefix of the last seek target (None = unbounded)
+    prefix: Option<Vec<u8>>,
+    valid: bool,
+}
+
+impl PrefixIterator {
+    pub fn new(inner: Box<dyn Iterator>, prefix_extractor: Arc<dyn SliceTransform>) -> Self {
+        PrefixIterator {
+            inner,
+            prefix_extractor,
+            prefix: None,
+            valid: false,
+        }
+    }
+
+    /// Remember the prefix of a seek target
+    fn set_prefix(&mut self, target: &Slice) {
+        self.prefix = if self.prefix_extractor.in_domain(target.data()) {
+            Some(self.prefix_extractor.transform(target.data()).to_vec())
+        } else {
+            None
+        };
+    }
+
+    /// Recompute validity after moving the inner iterator
+    fn update_valid(&mut self) -> bool {
+        self.valid = self.inner.valid()
+            && match self.prefix {
+                Some(ref prefix) => {
+                    let key = self.inner.key();
+                    self.prefix_extractor.in_domain(key.data())
+                        && self.prefix_extractor.transform(key.data()) == prefix.as_slice()
+                },
+                None => true,
+            };
+        self.valid
+    }
+}
+
+impl Iterator for PrefixIterator {
+    fn seek_to_first(&mut self) -> Result<bool> {
+        self.prefix = None;
+        self.inner.seek_to_first()?;
+        Ok(self.update_valid())
+    }
+
+    fn seek_to_last(&mut self) -> Result<bool> {
+        self.prefix = None;
+        self.inner.seek_to_last()?;
+        Ok(self.update_valid())
+    }
+
+    fn seek(&mut self, target: &Slice) -> Result<bool> {
+        self.set_prefix(target);
+        self.inner.seek(target)?;
+        Ok(self.update_valid())
+    }
+
+    fn seek_for_prev(&mut self, target: &Slice) -> Result<bool> {
+        self.set_prefix(target);
+        self.inner.seek_for_prev(target)?;
+        Ok(self.update_valid())
+    }
+
+    fn next(&mut self) -> Result<bool> {
+        if !self.valid {
+            return Ok(false);
+        }
+        self.inner.next()?;
+        Ok(self.update_valid())
+    }
+
+    fn prev(&mut self) -> Result<bool> {
+        if !self.valid {
+            return Ok(false);
+        }
+        self.inner.prev()?;
+        Ok(self.update_valid())
+    }
+
+    fn key(&self) -> Slice {
+        self.inner.key()
+    }
+
+    fn value(&self) -> Slice {
+        self.inner.value()
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        self.inner.timestamp()
+    }
+
+    fn valid(&self) -> bool {
+        self.valid
+    }
+
+    fn is_deletion(&self) -> bool {
+        self.inner.is_deletion()
+    }
+
+    fn is_entity(&self) -> bool {
+        self.inner.is_entity()
+    }
+
+    fn columns(&self) -> WideColumns {
+        self.inner.columns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memtable::MemTable, util::FixedPrefixTransform};
+
+    #[test]
+    fn test_prefix_iterator_stops_at_prefix_end() {
+        let mem = MemTable::new();
+        mem.add(1, Slice::from("aaa1"), Slice::from("v1"));
+        mem.add(2, Slice::from("aaa2"), Slice::from("v2"));
+        mem.add(3, Slice::from("bbb1"), Slice::from("v3"));
+
+        let mut iter =
+            PrefixIterator::new(Box::new(mem.iter()), Arc::new(FixedPrefixTransform::new(3)));
+
+        assert!(iter.seek(&Slice::from("aaa")).unwrap());
+        assert_eq!(iter.key(), Slice::from("aaa1"));
+        assert!(iter.next().unwrap());
+        assert_eq!(iter.key(), Slice::from("aaa2"));
+        assert!(!iter.next().unwrap());
+        assert!(!iter.valid());
+
+        // No key with this prefix
+        assert!(!iter.seek(&Slice::from("abc")).unwrap());
+
+        // Unbounded after seek_to_first
+        assert!(iter.seek_to_first().unwrap());
+        let mut count = 1;
+        while iter.next().unwrap() {
+            count += 1;
+        }
+        assert_eq!(count, 3);
+    }
+}
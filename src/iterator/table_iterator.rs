@@ -1,9 +1,17 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
     iterator::Iterator,
-    table::{block::Block, format::BlockHandle, table_reader::TableReader},
-    util::{Result, Slice, Status},
+    memtable::memtable::{InternalKey, VALUE_TYPE_DELETION, VALUE_TYPE_ENTITY, VALUE_TYPE_VALUE},
+    table::{
+        block::Block,
+        format::{BlockHandle, IndexType},
+        table_reader::TableReader,
+    },
+    util::{Comparator, Result, Slice, SliceTransform, Status},
 };
 
 /// Iterator for SSTable
@@ -16,6 +24,7 @@ use crate::{
 /// ```text
 /// TableIterator
 ///     ├─→ Index Block (list of BlockHandles)
+///     │   └─→ Current Index Partition (partitioned tables)
 ///     └─→ Current Data Block + BlockIterator
 ///         └─→ When exhausted, load next block
 /// ```
@@ -23,159 +32,266 @@ use crate::{
 /// # Implementation Notes
 ///
 /// - Holds Arc<Mutex<TableReader>> for shared access to file I/O
-/// - Caches list of BlockHandles from index on first seek
+/// - Decodes the index on creation; for partitioned tables only the top-level
+///   index, with each partition read when iteration reaches it
+/// - Seeks pick the block from the index keys instead of scanning blocks
 /// - Owns current data block to avoid lifetime issues
 /// - Creates new BlockIterator when moving between blocks
+/// - Entries are stored as InternalKeys; `key()` returns the user key and
+///   deletion markers are exposed via `is_deletion()`
+/// - Seeks compare user keys with the reader's comparator
+/// - With a prefix extractor, `seek()` first checks the table's prefix filter
+///   and becomes invalid without reading data blocks if no key with the
+///   target's prefix exists
 pub struct TableIterator {
     reader: Arc<Mutex<TableReader>>,
-    block_handles: Vec<BlockHandle>,
+    /// Top-level index entries of a partitioned table, `None` otherwise
+    partitions: Option<Vec<(Vec<u8>, BlockHandle)>>,
+    /// Partition whose entries are in `block_handles`
+    current_partition: Option<usize>,
+    /// (last user key, handle) of the data blocks of the current partition,
+    /// or of the whole table without partitions
+    block_handles: Vec<(Vec<u8>, BlockHandle)>,
     current_block_index: Option<usize>,
     current_block: Option<Block>,
     current_block_iter_key: Option<Slice>,
     current_block_iter_value: Option<Slice>,
     current_block_iter_offset: usize,
+    current_value_type: u8,
+    current_timestamp: Option<u64>,
     valid: bool,
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    comparator: Arc<dyn Comparator>,
+    verify_checksums: bool,
 }
 
 impl TableIterator {
     pub fn new(reader: Arc<Mutex<TableReader>>) -> Result<Self> {
-        // Load all block handles from index
-        let handles = {
-            let mut reader_guard = reader.lock().unwrap();
-            Self::load_block_handles(&mut reader_guard)?
+        let (index_entries, partitioned, comparator, verify_checksums) = {
+            let reader_guard = reader.lock().unwrap();
+            (
+                reader_guard.index_entries()?,
+                reader_guard.index_type() == IndexType::TwoLevelIndexSearch,
+                Arc::clone(reader_guard.comparator()),
+                reader_guard.verify_checksums(),
+            )
+        };
+
+        let (partitions, current_partition, block_handles) = if partitioned {
+            (Some(index_entries), None, Vec::new())
+        } else {
+            (None, Some(0), index_entries)
         };
 
         Ok(TableIterator {
             reader,
-            block_handles: handles,
+            partitions,
+            current_partition,
+            block_handles,
             current_block_index: None,
             current_block: None,
             current_block_iter_key: None,
             current_block_iter_value: None,
             current_block_iter_offset: 0,
+            current_value_type: VALUE_TYPE_VALUE,
+            current_timestamp: None,
             valid: false,
+            prefix_extractor: None,
+            comparator,
+            verify_checksums,
         })
     }
 
-    /// Load all BlockHandles from the index block
-    fn load_block_handles(reader: &mut TableReader) -> Result<Vec<BlockHandle>> {
-        let mut handles = Vec::new();
-        let index_block = reader.index_block();
+    /// Consult the table's prefix filter on seek (prefix iteration only)
+    pub fn set_prefix_extractor(&mut self, prefix_extractor: Arc<dyn SliceTransform>) {
+        self.prefix_extractor = Some(prefix_extractor);
+    }
+
+    /// Verify block checksums even if the table options skip verification
+    /// (`ReadOptions::verify_checksums`)
+    pub fn set_verify_checksums(&mut self, verify_checksums: bool) {
+        self.verify_checksums |= verify_checksums;
+    }
 
-        let mut index_iter = index_block.iter();
-        if !index_iter.seek_to_first()? {
-            return Ok(handles);
+    /// Split an entry key into its user key, value type and timestamp
+    ///
+    /// Tables written outside the DB may hold plain keys, which are returned
+    /// unchanged as values.
+    fn decode_entry_key(key: Slice) -> (Slice, u8, Option<u64>) {
+        match InternalKey::decode(&key) {
+            Ok(internal_key) => (
+                internal_key.user_key().clone(),
+                internal_key.value_type,
+                internal_key.timestamp(),
+            ),
+            Err(_) => (key, VALUE_TYPE_VALUE, None),
         }
+    }
 
-        loop {
-            let handle_data = index_iter.value();
-            let handle = BlockHandle::decode(handle_data.data())
-                .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
-            handles.push(handle);
+    /// Position at an entry of the current block
+    fn set_current(&mut self, key: Slice, value: Slice, offset: usize) {
+        let (user_key, value_type, timestamp) = Self::decode_entry_key(key);
+        self.current_block_iter_key = Some(user_key);
+        self.current_block_iter_value = Some(value);
+        self.current_block_iter_offset = offset;
+        self.current_value_type = value_type;
+        self.current_timestamp = timestamp;
+        self.valid = true;
+    }
 
-            if !index_iter.next()? {
-                break;
-            }
+    /// Check the prefix filter for a seek target
+    fn prefix_may_match(&self, target: &Slice) -> Result<bool> {
+        match self.prefix_extractor {
+            Some(ref extractor) if extractor.in_domain(target.data()) => {
+                let prefix = extractor.transform(target.data());
+                self.reader.lock().unwrap().prefix_may_match(prefix)
+            },
+            _ => Ok(true),
         }
+    }
 
-        Ok(handles)
+    /// Number of index partitions (1 for tables without partitions)
+    fn num_partitions(&self) -> usize {
+        self.partitions.as_ref().map_or(1, Vec::len)
     }
 
-    /// Load a specific data block by index
+    /// Make a partition's data blocks the ones `load_block` picks from
+    ///
+    /// Reads the index partition through the block cache unless it is
+    /// already loaded.
+    fn load_partition(&mut self, partition_index: usize) -> Result<()> {
+        if self.current_partition == Some(partition_index) {
+            return Ok(());
+        }
+        let Some(ref partitions) = self.partitions else {
+            return Err(Status::invalid_argument("Partition index out of range"));
+        };
+        let Some((_, handle)) = partitions.get(partition_index) else {
+            return Err(Status::invalid_argument("Partition index out of range"));
+        };
+
+        let handle = *handle;
+        let partition_data = self.reader.lock().unwrap().read_block_for_iter(&handle)?;
+        let partition = Block::with_verification(partition_data, self.verify_checksums)?;
+        self.block_handles = TableReader::decode_index_entries(&partition)?;
+        self.current_partition = Some(partition_index);
+        self.current_block_index = None;
+        Ok(())
+    }
+
+    /// Load a specific data block of the current partition by index
     fn load_block(&mut self, block_index: usize) -> Result<()> {
         if block_index >= self.block_handles.len() {
             return Err(Status::invalid_argument("Block index out of range"));
         }
 
-        let handle = self.block_handles[block_index];
-        let block_data = {
-            let mut reader = self.reader.lock().unwrap();
-            reader.read_block_for_iter(&handle)?
-        };
-
-        let block = Block::new(block_data)?;
+        let handle = self.block_handles[block_index].1;
+        let block_data = self.reader.lock().unwrap().read_block_for_iter(&handle)?;
+        let block = Block::with_verification(block_data, self.verify_checksums)?;
         self.current_block = Some(block);
         self.current_block_index = Some(block_index);
         Ok(())
     }
 
-    /// Position the internal BlockIterator at the first entry of current block
-    fn position_at_block_start(&mut self) -> Result<bool> {
-        if let Some(ref block) = self.current_block {
-            let mut iter = block.iter();
-            if iter.seek_to_first()? {
-                self.current_block_iter_key = Some(iter.key());
-                self.current_block_iter_value = Some(iter.value());
-                self.current_block_iter_offset = 0;
-                self.valid = true;
+    /// Load the first data block at or after a partition
+    fn load_first_block_from(&mut self, partition_index: usize) -> Result<bool> {
+        for partition in partition_index..self.num_partitions() {
+            self.load_partition(partition)?;
+            if !self.block_handles.is_empty() {
+                self.load_block(0)?;
                 return Ok(true);
             }
         }
-        self.valid = false;
         Ok(false)
     }
 
-    /// Advance to next entry within current block or move to next block
-    fn advance_forward(&mut self) -> Result<bool> {
-        if let Some(ref block) = self.current_block {
-            // Try to advance within current block
-            let mut iter = block.iter();
-            // Reposition iterator to current position
-            if !iter.seek_to_first()? {
-                self.valid = false;
-                return Ok(false);
-            }
-
-            // Skip to current offset
-            for _ in 0..=self.current_block_iter_offset {
-                if !iter.next()? {
-                    // Reached end of current block, try next block
-                    if let Some(current_idx) = self.current_block_index
-                        && current_idx + 1 < self.block_handles.len()
-                    {
-                        self.load_block(current_idx + 1)?;
-                        return self.position_at_block_start();
-                    }
-                    self.valid = false;
-                    return Ok(false);
-                }
+    /// Load the last data block at or before a partition
+    fn load_last_block_from(&mut self, partition_index: usize) -> Result<bool> {
+        for partition in (0..=partition_index).rev() {
+            self.load_partition(partition)?;
+            if let Some(last_index) = self.block_handles.len().checked_sub(1) {
+                self.load_block(last_index)?;
+                return Ok(true);
             }
-
-            self.current_block_iter_key = Some(iter.key());
-            self.current_block_iter_value = Some(iter.value());
-            self.current_block_iter_offset += 1;
-            self.valid = true;
-            return Ok(true);
         }
-
-        self.valid = false;
         Ok(false)
     }
-}
 
-impl Iterator for TableIterator {
-    fn seek_to_first(&mut self) -> Result<bool> {
-        if self.block_handles.is_empty() {
-            self.valid = false;
+    /// Load the data block after the current one, crossing into the next
+    /// partition at the end of the current one
+    fn load_next_block(&mut self) -> Result<bool> {
+        let (Some(partition), Some(block_index)) =
+            (self.current_partition, self.current_block_index)
+        else {
             return Ok(false);
+        };
+        if block_index + 1 < self.block_handles.len() {
+            self.load_block(block_index + 1)?;
+            return Ok(true);
         }
+        self.load_first_block_from(partition + 1)
+    }
 
-        // Load first data block
-        self.load_block(0)?;
-        self.position_at_block_start()
+    /// Load the data block before the current one, crossing into the
+    /// previous partition at the start of the current one
+    fn load_prev_block(&mut self) -> Result<bool> {
+        let (Some(partition), Some(block_index)) =
+            (self.current_partition, self.current_block_index)
+        else {
+            return Ok(false);
+        };
+        if block_index > 0 {
+            self.load_block(block_index - 1)?;
+            return Ok(true);
+        }
+        match partition.checked_sub(1) {
+            Some(prev_partition) => self.load_last_block_from(prev_partition),
+            None => Ok(false),
+        }
     }
 
-    fn seek_to_last(&mut self) -> Result<bool> {
-        if self.block_handles.is_empty() {
-            self.valid = false;
+    /// Load the first data block whose last key is at or past the target
+    ///
+    /// Only the index partition covering the target is read.
+    fn load_block_for_target(&mut self, target: &Slice) -> Result<bool> {
+        let comparator = Arc::clone(&self.comparator);
+        let at_or_past = |(key, _): &(Vec<u8>, BlockHandle)| {
+            comparator.compare(key, target.data()) != Ordering::Less
+        };
+
+        let partition = match self.partitions {
+            Some(ref partitions) => partitions.iter().position(at_or_past),
+            None => Some(0),
+        };
+        let Some(partition) = partition else {
             return Ok(false);
+        };
+
+        self.load_partition(partition)?;
+        match self.block_handles.iter().position(at_or_past) {
+            Some(block_index) => {
+                self.load_block(block_index)?;
+                Ok(true)
+            },
+            None => Ok(false),
         }
+    }
 
-        // Load last data block
-        let last_index = self.block_handles.len() - 1;
-        self.load_block(last_index)?;
+    /// Position the internal BlockIterator at the first entry of current block
+    fn position_at_block_start(&mut self) -> Result<bool> {
+        if let Some(ref block) = self.current_block {
+            let mut iter = block.iter();
+            if iter.seek_to_first()? {
+                self.set_current(iter.key(), iter.value(), 0);
+                return Ok(true);
+            }
+        }
+        self.valid = false;
+        Ok(false)
+    }
 
-        // Position at last entry of the block
+    /// Position the internal BlockIterator at the last entry of current block
+    fn position_at_block_end(&mut self) -> Result<bool> {
         if let Some(ref block) = self.current_block {
             let mut iter = block.iter();
             if !iter.seek_to_first()? {
@@ -200,10 +316,7 @@ impl Iterator for TableIterator {
                 iter.next()?;
             }
 
-            self.current_block_iter_key = Some(iter.key());
-            self.current_block_iter_value = Some(iter.value());
-            self.current_block_iter_offset = offset;
-            self.valid = true;
+            self.set_current(iter.key(), iter.value(), offset);
             return Ok(true);
         }
 
@@ -211,73 +324,120 @@ impl Iterator for TableIterator {
         Ok(false)
     }
 
-    fn seek(&mut self, target: &Slice) -> Result<bool> {
-        // Binary search through blocks using index block keys
-        // For now, linear search (can optimize later)
-        for idx in 0..self.block_handles.len() {
-            self.load_block(idx)?;
-
-            if let Some(ref block) = self.current_block {
-                let mut iter = block.iter();
-                if !iter.seek_to_first()? {
-                    continue;
-                }
+    /// Advance to next entry within current block or move to next block
+    fn advance_forward(&mut self) -> Result<bool> {
+        if let Some(ref block) = self.current_block {
+            // Try to advance within current block
+            let mut iter = block.iter();
+            // Reposition iterator to current position
+            if !iter.seek_to_first()? {
+                self.valid = false;
+                return Ok(false);
+            }
 
-                // Check if target might be in this block
-                // The index key is >= all keys in the block
-                let first_key = iter.key();
-                if target < &first_key {
-                    // Target would be before this block, not found
+            // Skip to current offset
+            for _ in 0..=self.current_block_iter_offset {
+                if !iter.next()? {
+                    // Reached end of current block, try next block
+                    if self.load_next_block()? {
+                        return self.position_at_block_start();
+                    }
                     self.valid = false;
                     return Ok(false);
                 }
-
-                // Search within this block
-                let mut offset = 0;
-                loop {
-                    let current_key = iter.key();
-                    if &current_key >= target {
-                        self.current_block_iter_key = Some(current_key);
-                        self.current_block_iter_value = Some(iter.value());
-                        self.current_block_iter_offset = offset;
-                        self.valid = true;
-                        return Ok(true);
-                    }
-
-                    if !iter.next()? {
-                        break;
-                    }
-                    offset += 1;
-                }
             }
+
+            let offset = self.current_block_iter_offset + 1;
+            self.set_current(iter.key(), iter.value(), offset);
+            return Ok(true);
         }
 
-        // Not found in any block
         self.valid = false;
         Ok(false)
     }
+}
 
-    fn seek_for_prev(&mut self, target: &Slice) -> Result<bool> {
-        // Find last key <= target
-        let mut last_valid: Option<(Slice, Slice, usize, usize)> = None;
+impl Iterator for TableIterator {
+    fn seek_to_first(&mut self) -> Result<bool> {
+        if !self.load_first_block_from(0)? {
+            self.valid = false;
+            return Ok(false);
+        }
+        self.position_at_block_start()
+    }
 
-        for block_idx in 0..self.block_handles.len() {
-            self.load_block(block_idx)?;
+    fn seek_to_last(&mut self) -> Result<bool> {
+        let last_partition = self.num_partitions().saturating_sub(1);
+        if self.num_partitions() == 0 || !self.load_last_block_from(last_partition)? {
+            self.valid = false;
+            return Ok(false);
+        }
+        self.position_at_block_end()
+    }
+
+    fn seek(&mut self, target: &Slice) -> Result<bool> {
+        self.valid = false;
+        if !self.prefix_may_match(target)? {
+            // No key with the target's prefix in this table
+            return Ok(false);
+        }
 
+        if !self.load_block_for_target(target)? {
+            // Every key is before the target
+            return Ok(false);
+        }
+
+        loop {
             if let Some(ref block) = self.current_block {
                 let mut iter = block.iter();
-                if !iter.seek_to_first()? {
-                    continue;
+                if iter.seek_to_first()? {
+                    // Search within this block for the first user key >= target
+                    let mut offset = 0;
+                    loop {
+                        let (user_key, ..) = Self::decode_entry_key(iter.key());
+                        if self.comparator.compare(user_key.data(), target.data()) != Ordering::Less
+                        {
+                            let value = iter.value();
+                            self.set_current(iter.key(), value, offset);
+                            return Ok(true);
+                        }
+
+                        if !iter.next()? {
+                            break;
+                        }
+                        offset += 1;
+                    }
                 }
+            }
+
+            // The index key may sort past the block's entries
+            if !self.load_next_block()? {
+                return Ok(false);
+            }
+        }
+    }
 
+    fn seek_for_prev(&mut self, target: &Slice) -> Result<bool> {
+        // Find last key <= target. It is in the first block whose last key is
+        // at or past the target, or else ends the block before it.
+        self.valid = false;
+        if !self.load_block_for_target(target)? {
+            // Every key is before the target
+            return self.seek_to_last();
+        }
+
+        let mut last_valid: Option<(Slice, Slice, usize)> = None;
+        if let Some(ref block) = self.current_block {
+            let mut iter = block.iter();
+            if iter.seek_to_first()? {
                 let mut offset = 0;
                 loop {
-                    let current_key = iter.key();
-                    if &current_key <= target {
-                        last_valid = Some((current_key, iter.value(), block_idx, offset));
-                    } else {
+                    let (user_key, ..) = Self::decode_entry_key(iter.key());
+                    if self.comparator.compare(user_key.data(), target.data()) == Ordering::Greater
+                    {
                         break;
                     }
+                    last_valid = Some((iter.key(), iter.value(), offset));
 
                     if !iter.next()? {
                         break;
@@ -287,17 +447,14 @@ impl Iterator for TableIterator {
             }
         }
 
-        if let Some((key, value, block_idx, offset)) = last_valid {
-            self.load_block(block_idx)?;
-            self.current_block_iter_key = Some(key);
-            self.current_block_iter_value = Some(value);
-            self.current_block_iter_offset = offset;
-            self.valid = true;
-            Ok(true)
-        } else {
-            self.valid = false;
-            Ok(false)
+        if let Some((key, value, offset)) = last_valid {
+            self.set_current(key, value, offset);
+            return Ok(true);
         }
+        if self.load_prev_block()? {
+            return self.position_at_block_end();
+        }
+        Ok(false)
     }
 
     fn next(&mut self) -> Result<bool> {
@@ -327,6 +484,18 @@ impl Iterator for TableIterator {
     fn valid(&self) -> bool {
         self.valid
     }
+
+    fn is_deletion(&self) -> bool {
+        self.current_value_type == VALUE_TYPE_DELETION
+    }
+
+    fn is_entity(&self) -> bool {
+        self.current_value_type == VALUE_TYPE_ENTITY
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        self.current_timestamp
+    }
 }
 
 #[cfg(test)]
@@ -430,4 +599,169 @@ mod tests {
         assert!(!iter.seek_to_first().unwrap());
         assert!(!iter.valid());
     }
+
+    #[test]
+    fn test_table_iterator_partitioned_index() {
+        use crate::{
+            memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
+            table::{format::IndexType, table_options::BlockBasedTableOptions},
+        };
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            index_type: IndexType::TwoLevelIndexSearch,
+            metadata_block_size: 128,
+            ..Default::default()
+        };
+        let mut builder = TableBuilder::new_with_options(temp_file.path(), None, options).unwrap();
+        for i in 0..500u64 {
+            let key = InternalKey::new(Slice::from(format!("key{i:04}")), i + 1, VALUE_TYPE_VALUE);
+            builder
+                .add(&key.encode(), &Slice::from(format!("value{i:04}")))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+        let mut iter = TableIterator::new(Arc::new(Mutex::new(reader))).unwrap();
+
+        let mut count = 0;
+        assert!(iter.seek_to_first().unwrap());
+        loop {
+            count += 1;
+            if !iter.next().unwrap() {
+                break;
+            }
+        }
+        assert_eq!(count, 500);
+    }
+
+    #[test]
+    fn test_table_iterator_loads_partitions_lazily() {
+        use crate::{
+            cache::LRUCache,
+            memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
+            table::{format::IndexType, table_options::BlockBasedTableOptions},
+        };
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            index_type: IndexType::TwoLevelIndexSearch,
+            block_size: 256,
+            metadata_block_size: 128,
+            ..Default::default()
+        };
+        let mut builder = TableBuilder::new_with_options(temp_file.path(), None, options).unwrap();
+        for i in 0..500u64 {
+            let key = InternalKey::new(Slice::from(format!("key{i:04}")), i + 1, VALUE_TYPE_VALUE);
+            builder
+                .add(&key.encode(), &Slice::from(format!("value{i:04}")))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let block_cache = LRUCache::new(1000);
+        let reader = TableReader::open(temp_file.path(), 1, Some(block_cache.clone())).unwrap();
+        let num_partitions = reader.index_entries().unwrap().len();
+        assert!(num_partitions > 4);
+        let mut iter = TableIterator::new(Arc::new(Mutex::new(reader))).unwrap();
+        assert_eq!(block_cache.stats().entries, 0);
+
+        // A seek reads one index partition and one data block
+        assert!(iter.seek(&Slice::from("key0250")).unwrap());
+        assert_eq!(iter.key(), Slice::from("key0250"));
+        assert_eq!(block_cache.stats().entries, 2);
+
+        assert!(iter.seek_for_prev(&Slice::from("key0250a")).unwrap());
+        assert_eq!(iter.key(), Slice::from("key0250"));
+        assert_eq!(block_cache.stats().entries, 2);
+
+        // Iterating on reads the following partitions as it reaches them
+        let mut count = 1;
+        while iter.next().unwrap() {
+            count += 1;
+        }
+        assert_eq!(count, 250);
+
+        assert!(iter.seek_for_prev(&Slice::from("key0000a")).unwrap());
+        assert_eq!(iter.key(), Slice::from("key0000"));
+        assert!(!iter.seek_for_prev(&Slice::from("a")).unwrap());
+        assert!(iter.seek_to_last().unwrap());
+        assert_eq!(iter.key(), Slice::from("key0499"));
+    }
+
+    #[test]
+    fn test_table_iterator_internal_keys() {
+        use crate::memtable::memtable::{InternalKey, VALUE_TYPE_DELETION, VALUE_TYPE_VALUE};
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut builder = TableBuilder::new(temp_file.path()).unwrap();
+        for (key, seq, value_type) in [
+            ("k01", 1, VALUE_TYPE_VALUE),
+            ("k02", 2, VALUE_TYPE_DELETION),
+            ("k03", 3, VALUE_TYPE_VALUE),
+        ] {
+            let internal_key = InternalKey::new(Slice::from(key), seq, value_type);
+            builder
+                .add(&internal_key.encode(), &Slice::from("v"))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+        let mut iter = TableIterator::new(Arc::new(Mutex::new(reader))).unwrap();
+
+        // Keys are exposed as user keys, deletion markers are flagged
+        assert!(iter.seek(&Slice::from("k00")).unwrap());
+        assert_eq!(iter.key(), Slice::from("k01"));
+        assert!(!iter.is_deletion());
+        assert!(iter.next().unwrap());
+        assert_eq!(iter.key(), Slice::from("k02"));
+        assert!(iter.is_deletion());
+
+        assert!(iter.seek_for_prev(&Slice::from("k025")).unwrap());
+        assert_eq!(iter.key(), Slice::from("k02"));
+        assert!(!iter.seek(&Slice::from("k04")).unwrap());
+    }
+
+    #[test]
+    fn test_table_iterator_prefix_seek_skips_table() {
+        use crate::{
+            cache::LRUCache,
+            filter::{BloomFilterPolicy, FilterPolicy},
+            memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
+            util::FixedPrefixTransform,
+        };
+
+        let policy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+        let extractor = Arc::new(FixedPrefixTransform::new(4));
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut builder =
+            TableBuilder::new_with_filter(temp_file.path(), Some(policy.clone())).unwrap();
+        builder.set_prefix_extractor(Some(extractor.clone()));
+        for i in 0..100u64 {
+            let key = InternalKey::new(Slice::from(format!("aaaa{i:03}")), i + 1, VALUE_TYPE_VALUE);
+            builder.add(&key.encode(), &Slice::from("v")).unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let block_cache = LRUCache::new(100);
+        let reader = TableReader::open_with_filter(
+            temp_file.path(),
+            1,
+            Some(block_cache.clone()),
+            Some(policy),
+        )
+        .unwrap();
+        let mut iter = TableIterator::new(Arc::new(Mutex::new(reader))).unwrap();
+        iter.set_prefix_extractor(extractor);
+
+        // Excluded prefix: no data block is read
+        assert!(!iter.seek(&Slice::from("bbbb000")).unwrap());
+        assert!(!iter.valid());
+        assert_eq!(block_cache.stats().entries, 0);
+
+        assert!(iter.seek(&Slice::from("aaaa050")).unwrap());
+        assert_eq!(iter.key(), Slice::from("aaaa050"));
+    }
 }
@@ -15,23 +15,46 @@ pub mod import_export;
 pub mod iterator;
 pub mod memtable;
 pub mod merge;
+pub mod options;
 pub mod statistics;
 pub mod table;
 pub mod transaction;
+pub mod ttl;
 pub mod util;
 pub mod version;
 pub mod wal;
+pub mod wide_columns;
 
 pub use backup::{BackupEngine, BackupMetadata};
 pub use checkpoint::Checkpoint;
 pub use column_family::{
     ColumnFamilyDescriptor, ColumnFamilyHandle, ColumnFamilyOptions, DEFAULT_COLUMN_FAMILY_NAME,
 };
-pub use db::{DB, DBOptions, ReadOptions, WriteOptions};
-pub use filter::{BloomFilterPolicy, FilterPolicy};
+pub use compaction::compaction_filter::{
+    CompactionDecision, CompactionFilter, CompactionFilterStats,
+};
+pub use db::{
+    BottommostLevelCompaction, CompactRangeOptions, DB, DBOptions, ReadOptions, WriteOptions,
+};
+pub use filter::{BloomFilterFormat, BloomFilterPolicy, FilterPolicy, RibbonFilterPolicy};
 pub use import_export::IngestExternalFileOptions;
 pub use merge::{CounterMerge, MergeOperator, StringAppendMerge};
+pub use options::{OptionsFile, load_latest_options};
 pub use statistics::Statistics;
-pub use table::format::CompressionType;
+pub use table::{
+    BlockBasedTableOptions, CompactOnDeletionOptions, DataBlockIndexType,
+    format::{CompressionType, IndexType},
+};
 pub use transaction::{OptimisticTransaction, Snapshot, TransactionDB, WriteBatch, WriteOp};
-pub use util::{Result, Slice, Status};
+pub use ttl::DBWithTTL;
+pub use util::{
+    BytewiseComparator, BytewiseComparatorWithU64Ts, CappedPrefixTransform, Comparator,
+    FixedPrefixTransform, Result, ReverseBytewiseComparator, Slice, SliceTransform, Status,
+    U64BigEndianComparator,
+};
+pub use version::{
+    compaction_picker::{CompactionPri, CompactionStyle},
+    fifo_compaction_picker::CompactionOptionsFIFO,
+    universal_compaction_picker::CompactionOptionsUniversal,
+};
+pub use wide_columns::WideColumns;
@@ -1,19 +1,59 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicUsize, Ordering},
+use std::{
+    cmp,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use crate::{
-    memtable::skiplist::SkipList,
-    util::{Result, Slice, Status},
+    memtable::{prefix_bloom::PrefixBloom, skiplist::SkipList},
+    util::{Comparator, Result, Slice, SliceTransform, Status, comparator::bytewise_comparator},
 };
 
 pub const VALUE_TYPE_DELETION: u8 = 0;
 pub const VALUE_TYPE_VALUE: u8 = 1;
+/// The value is a serialized `WideColumns` entity
+pub const VALUE_TYPE_ENTITY: u8 = 2;
+
+/// Flag in the encoded type byte of a key followed by a timestamp
+const TIMESTAMP_FLAG: u8 = 0x80;
+
+/// Outcome of a point lookup in a MemTable or SSTable
+#[derive(Debug, Clone, PartialEq)]
+pub enum LookupResult {
+    /// No entry for the key
+    NotFound,
+    /// The visible entry is a deletion marker
+    Deleted,
+    /// The visible entry is a plain value
+    Value(Slice),
+    /// The visible entry is a serialized wide-column entity
+    Entity(Slice),
+}
+
+impl LookupResult {
+    /// Convert into the (found, value) pair of `get`
+    ///
+    /// Entities are returned in their serialized form.
+    pub fn into_pair(self) -> (bool, Option<Slice>) {
+        match self {
+            LookupResult::NotFound => (false, None),
+            LookupResult::Deleted => (true, None),
+            LookupResult::Value(value) | LookupResult::Entity(value) => (true, Some(value)),
+        }
+    }
+}
 
+/// A user key with the sequence number and type of one entry
+///
+/// Encoded as key length (u16), user key, inverted sequence (u64) and type
+/// (u8). Keys of column families with user-defined timestamps also carry a
+/// u64 timestamp, appended after the type byte, whose top bit flags it.
 #[derive(Clone)]
 pub struct InternalKey {
     user_key: Slice,
+    timestamp: Option<u64>,
     sequence: u64,
     pub value_type: u8,
 }
@@ -22,6 +62,17 @@ impl InternalKey {
     pub fn new(user_key: Slice, sequence: u64, value_type: u8) -> Self {
         InternalKey {
             user_key,
+            timestamp: None,
+            sequence,
+            value_type,
+        }
+    }
+
+    /// Create a key carrying a user-defined timestamp
+    pub fn with_timestamp(user_key: Slice, timestamp: u64, sequence: u64, value_type: u8) -> Self {
+        InternalKey {
+            user_key,
+            timestamp: Some(timestamp),
             sequence,
             value_type,
         }
@@ -29,7 +80,8 @@ impl InternalKey {
 
     pub fn encode(&self) -> Slice {
         let key_len = self.user_key.size();
-        let mut buf = Vec::with_capacity(key_len + 11); // key + 2 len_bytes + 8 bytes seq + 1 byte type
+        // 2 len_bytes + key + 8 bytes seq + 1 byte type [+ 8 bytes timestamp]
+        let mut buf = Vec::with_capacity(key_len + 19);
 
         // Encode key length as u16 (supports keys up to 64KB)
         buf.extend_from_slice(&(key_len as u16).to_be_bytes());
@@ -38,7 +90,13 @@ impl InternalKey {
         // Encode sequence in reverse order for descending sort
         let reversed_seq = u64::MAX - self.sequence;
         buf.extend_from_slice(&reversed_seq.to_be_bytes());
-        buf.push(self.value_type);
+        match self.timestamp {
+            Some(timestamp) => {
+                buf.push(self.value_type | TIMESTAMP_FLAG);
+                buf.extend_from_slice(&timestamp.to_be_bytes());
+            },
+            None => buf.push(self.value_type),
+        }
         Slice::from(buf)
     }
 
@@ -65,12 +123,24 @@ impl InternalKey {
             .map_err(|_| Status::corruption("Invalid sequence number"))?;
         let reversed_seq = u64::from_be_bytes(seq_bytes);
         let sequence = u64::MAX - reversed_seq;
-        let value_type = data.data()[seq_start + 8];
+        let type_byte = data.data()[seq_start + 8];
+
+        let timestamp = if type_byte & TIMESTAMP_FLAG != 0 {
+            let ts_bytes: [u8; 8] = data
+                .data()
+                .get(seq_start + 9..seq_start + 17)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| Status::corruption("InternalKey timestamp truncated"))?;
+            Some(u64::from_be_bytes(ts_bytes))
+        } else {
+            None
+        };
 
         Ok(InternalKey {
             user_key,
+            timestamp,
             sequence,
-            value_type,
+            value_type: type_byte & !TIMESTAMP_FLAG,
         })
     }
 
@@ -78,6 +148,11 @@ impl InternalKey {
         &self.user_key
     }
 
+    /// User-defined timestamp, if the key carries one
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
     pub fn sequence(&self) -> u64 {
         self.sequence
     }
@@ -87,74 +162,228 @@ impl InternalKey {
     }
 }
 
+/// Orders encoded InternalKeys
+///
+/// User keys are compared with the column family's comparator; entries of
+/// the same user key sort newest timestamp first, then newest (highest
+/// sequence) first, then by value type. Keys that aren't InternalKeys (e.g.
+/// in tables written outside the DB) are compared whole with the user
+/// comparator.
+#[derive(Clone)]
+pub struct InternalKeyComparator {
+    user_comparator: Arc<dyn Comparator>,
+}
+
+/// Parts of an encoded InternalKey: user key, timestamp and the (sequence,
+/// type) bytes, which sort as the entry order within a timestamp
+type KeyParts<'a> = (&'a [u8], Option<u64>, &'a [u8]);
+
+impl InternalKeyComparator {
+    pub fn new(user_comparator: Arc<dyn Comparator>) -> Self {
+        InternalKeyComparator { user_comparator }
+    }
+
+    /// Get the comparator of user keys
+    pub fn user_comparator(&self) -> &Arc<dyn Comparator> {
+        &self.user_comparator
+    }
+
+    /// Split an encoded InternalKey into its parts
+    #[inline]
+    fn split(key: &[u8]) -> Option<KeyParts<'_>> {
+        if key.len() < 11 {
+            return None;
+        }
+        let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+        let trailer = key.get(2 + len..2 + len + 9)?;
+        if trailer[8] & TIMESTAMP_FLAG == 0 {
+            return (key.len() == len + 11).then_some((&key[2..2 + len], None, trailer));
+        }
+        if key.len() != len + 19 {
+            return None;
+        }
+        let timestamp = u64::from_be_bytes(key[len + 11..].try_into().ok()?);
+        Some((&key[2..2 + len], Some(timestamp), trailer))
+    }
+
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> cmp::Ordering {
+        match (Self::split(a), Self::split(b)) {
+            (Some((user_a, ts_a, trailer_a)), Some((user_b, ts_b, trailer_b))) => self
+                .user_comparator
+                .compare(user_a, user_b)
+                .then_with(|| ts_b.cmp(&ts_a))
+                .then_with(|| trailer_a.cmp(trailer_b)),
+            _ => self.user_comparator.compare(a, b),
+        }
+    }
+}
+
+impl Default for InternalKeyComparator {
+    fn default() -> Self {
+        Self::new(bytewise_comparator())
+    }
+}
+
 pub struct MemTable {
     table: SkipList,
     approximate_memory: Arc<AtomicUsize>,
+    prefix_bloom: Option<Arc<PrefixBloom>>,
 }
 
 impl MemTable {
     pub fn new() -> Self {
+        Self::with_comparator(bytewise_comparator())
+    }
+
+    /// Create a MemTable ordering user keys with `comparator`
+    pub fn with_comparator(comparator: Arc<dyn Comparator>) -> Self {
         MemTable {
-            table: SkipList::new(),
+            table: SkipList::with_comparator(InternalKeyComparator::new(comparator)),
             approximate_memory: Arc::new(AtomicUsize::new(0)),
+            prefix_bloom: None,
         }
     }
 
-    pub fn add(&self, sequence: u64, key: Slice, value: Slice) {
-        let internal_key = InternalKey::new(key, sequence, VALUE_TYPE_VALUE);
-        let encoded_key = internal_key.encode();
+    /// Create a MemTable with a prefix Bloom filter of `bloom_size_bytes`
+    ///
+    /// The prefix of every inserted key is added to the filter, letting
+    /// point lookups and prefix seeks skip this MemTable when no key with
+    /// the prefix was written.
+    pub fn with_prefix_bloom(extractor: Arc<dyn SliceTransform>, bloom_size_bytes: usize) -> Self {
+        let mut memtable = Self::new();
+        memtable.set_prefix_bloom(extractor, bloom_size_bytes);
+        memtable
+    }
 
-        let mem_usage = encoded_key.size() + value.size();
-        self.approximate_memory
-            .fetch_add(mem_usage, Ordering::Relaxed);
+    /// Add a prefix Bloom filter of `bloom_size_bytes`
+    ///
+    /// Must be called before the first key is added.
+    pub fn set_prefix_bloom(
+        &mut self,
+        extractor: Arc<dyn SliceTransform>,
+        bloom_size_bytes: usize,
+    ) {
+        self.prefix_bloom = Some(Arc::new(PrefixBloom::new(extractor, bloom_size_bytes)));
+    }
 
-        self.table.insert(encoded_key, value);
+    /// Get the prefix Bloom filter, if configured
+    pub fn prefix_bloom(&self) -> Option<&Arc<PrefixBloom>> {
+        self.prefix_bloom.as_ref()
+    }
+
+    /// Check whether any key with this prefix may be in the MemTable
+    ///
+    /// Always true without a prefix Bloom filter.
+    pub fn prefix_may_match(&self, prefix: &[u8]) -> bool {
+        self.prefix_bloom
+            .as_ref()
+            .is_none_or(|bloom| bloom.may_contain_prefix(prefix))
+    }
+
+    pub fn add(&self, sequence: u64, key: Slice, value: Slice) {
+        self.insert(InternalKey::new(key, sequence, VALUE_TYPE_VALUE), value);
     }
 
     pub fn delete(&self, sequence: u64, key: Slice) {
-        let internal_key = InternalKey::new(key, sequence, VALUE_TYPE_DELETION);
+        self.insert(
+            InternalKey::new(key, sequence, VALUE_TYPE_DELETION),
+            Slice::empty(),
+        );
+    }
+
+    /// Add an entry of any value type, with an optional user-defined
+    /// timestamp
+    pub fn add_entry(
+        &self,
+        sequence: u64,
+        key: Slice,
+        timestamp: Option<u64>,
+        value_type: u8,
+        value: Slice,
+    ) {
+        let internal_key = match timestamp {
+            Some(ts) => InternalKey::with_timestamp(key, ts, sequence, value_type),
+            None => InternalKey::new(key, sequence, value_type),
+        };
+        self.insert(internal_key, value);
+    }
+
+    /// Add a value for a key at a user-defined timestamp
+    pub fn add_with_timestamp(&self, sequence: u64, key: Slice, timestamp: u64, value: Slice) {
+        self.insert(
+            InternalKey::with_timestamp(key, timestamp, sequence, VALUE_TYPE_VALUE),
+            value,
+        );
+    }
+
+    /// Delete a key at a user-defined timestamp
+    pub fn delete_with_timestamp(&self, sequence: u64, key: Slice, timestamp: u64) {
+        self.insert(
+            InternalKey::with_timestamp(key, timestamp, sequence, VALUE_TYPE_DELETION),
+            Slice::empty(),
+        );
+    }
+
+    fn insert(&self, internal_key: InternalKey, value: Slice) {
+        if let Some(ref bloom) = self.prefix_bloom {
+            bloom.add_key(internal_key.user_key().data());
+        }
+
         let encoded_key = internal_key.encode();
 
-        let mem_usage = encoded_key.size();
+        let mem_usage = encoded_key.size() + value.size();
         self.approximate_memory
             .fetch_add(mem_usage, Ordering::Relaxed);
 
-        self.table.insert(encoded_key, Slice::empty());
+        self.table.insert(encoded_key, value);
     }
 
     /// Get value for a key. Returns (found, value).
     /// - (true, Some(value)) => key found with value
     /// - (true, None) => key found but deleted
     /// - (false, None) => key not found in memtable
+    ///
+    /// Entities are returned serialized; `get_entry` tells them apart.
     pub fn get(&self, key: &Slice) -> (bool, Option<Slice>) {
-        let iter = self.table.iter();
+        self.get_entry(key, None).into_pair()
+    }
 
-        // Seek to the first entry with this user_key
-        // Use sequence u64::MAX (which becomes 0 after reverse) to get the smallest
-        // encoded value This ensures we start from the beginning of all entries
-        // for this user_key
-        let start_key = InternalKey::new(key.clone(), u64::MAX, VALUE_TYPE_VALUE).encode();
+    /// Get the newest value of a key written at or before `timestamp`
+    ///
+    /// Returns (found, value) like `get`.
+    pub fn get_with_timestamp(&self, key: &Slice, timestamp: u64) -> (bool, Option<Slice>) {
+        self.get_entry(key, Some(timestamp)).into_pair()
+    }
 
-        let entries = iter.range_from(&start_key);
+    /// Look up the newest entry of a key, as of `timestamp` if given
+    pub fn get_entry(&self, key: &Slice, timestamp: Option<u64>) -> LookupResult {
+        if let Some(ref bloom) = self.prefix_bloom
+            && !bloom.may_contain_key(key.data())
+        {
+            return LookupResult::NotFound;
+        }
 
-        // Find the first entry that matches the user_key
-        // Due to reversed sequence encoding, the first matching entry has the largest
-        // sequence
-        for (internal_key_data, value) in entries {
-            if let Ok(internal_key) = InternalKey::decode(&internal_key_data) {
-                if internal_key.user_key() == key {
-                    if internal_key.is_deletion() {
-                        return (true, None); // Found but deleted
+        // Seek to the first entry with this user_key
+        // Sequence u64::MAX sorts before every entry of the user key, and
+        // versions newer than the timestamp sort before the lookup key, so
+        // the entry found (if it has this user key) is the visible one
+        let start = match timestamp {
+            Some(ts) => InternalKey::with_timestamp(key.clone(), ts, u64::MAX, VALUE_TYPE_DELETION),
+            None => InternalKey::new(key.clone(), u64::MAX, VALUE_TYPE_DELETION),
+        };
+        match self.table.iter().seek(&start.encode()) {
+            Some((internal_key_data, value)) => match InternalKey::decode(&internal_key_data) {
+                Ok(internal_key) if internal_key.user_key() == key => {
+                    match internal_key.value_type {
+                        VALUE_TYPE_DELETION => LookupResult::Deleted,
+                        VALUE_TYPE_ENTITY => LookupResult::Entity(value),
+                        _ => LookupResult::Value(value),
                     }
-                    return (true, Some(value)); // Found with value
-                }
-                // If user_key doesn't match, we've gone past this key
-                if internal_key.user_key() > key {
-                    break;
-                }
-            }
+                },
+                _ => LookupResult::NotFound, // Past this key
+            },
+            None => LookupResult::NotFound,
         }
-        (false, None) // Not found
     }
 
     pub fn approximate_memory_usage(&self) -> usize {
@@ -170,22 +399,38 @@ impl MemTable {
     /// The iterator automatically skips deletion markers and returns only
     /// live entries with their user keys (internal encoding is hidden).
     pub fn iter(&self) -> crate::iterator::MemTableIterator {
-        crate::iterator::MemTableIterator::new(self.table.map.clone())
+        crate::iterator::MemTableIterator::new(self.table.clone())
+    }
+
+    /// Create an iterator whose seeks consult the prefix Bloom filter
+    ///
+    /// A seek to a target whose prefix is absent from the filter leaves the
+    /// iterator invalid without touching the SkipList. Only suitable when
+    /// the caller stops iterating at the end of the seek prefix.
+    pub fn prefix_iter(&self) -> crate::iterator::MemTableIterator {
+        let mut iter = self.iter();
+        if let Some(ref bloom) = self.prefix_bloom {
+            iter.set_prefix_bloom(Arc::clone(bloom));
+        }
+        iter
     }
 
     /// Collect all unique user keys with their latest values (for flushing to
     /// SSTable)
+    ///
+    /// With user-defined timestamps, the latest value at every timestamp is
+    /// kept.
     pub fn collect_entries(&self) -> Vec<(Slice, Slice)> {
         let mut result = Vec::new();
-        let mut last_user_key: Option<Slice> = None;
+        let mut last_user_key: Option<(Slice, Option<u64>)> = None;
 
         // Iterate through all entries in the SkipList (sorted by internal key)
         let iter = self.table.iter();
-        let all_entries = iter.range_from(&Slice::empty());
+        let all_entries = iter.all();
 
         for (internal_key_data, value) in all_entries {
             if let Ok(internal_key) = InternalKey::decode(&internal_key_data) {
-                let user_key = internal_key.user_key().clone();
+                let user_key = (internal_key.user_key().clone(), internal_key.timestamp());
 
                 // Skip if we've already seen this user_key (we want the first/latest entry due
                 // to reverse sequence)
@@ -261,6 +506,26 @@ mod tests {
         assert!(memtable.approximate_memory_usage() > 0);
     }
 
+    #[test]
+    fn test_memtable_prefix_bloom() {
+        let extractor = Arc::new(crate::util::FixedPrefixTransform::new(4));
+        let memtable = MemTable::with_prefix_bloom(extractor, 1024);
+
+        memtable.add(1, Slice::from("user1"), Slice::from("alice"));
+        memtable.delete(2, Slice::from("post1"));
+
+        assert!(memtable.prefix_may_match(b"user"));
+        assert!(memtable.prefix_may_match(b"post"));
+        assert!(!memtable.prefix_may_match(b"item"));
+
+        assert_eq!(
+            memtable.get(&Slice::from("user1")).1,
+            Some(Slice::from("alice"))
+        );
+        assert_eq!(memtable.get(&Slice::from("post1")), (true, None));
+        assert_eq!(memtable.get(&Slice::from("item1")), (false, None));
+    }
+
     #[test]
     fn test_internal_key_encode_decode() {
         let key = InternalKey::new(Slice::from("test_key"), 123, VALUE_TYPE_VALUE);
@@ -271,4 +536,122 @@ mod tests {
         assert_eq!(decoded.sequence, 123);
         assert_eq!(decoded.value_type, VALUE_TYPE_VALUE);
     }
+
+    #[test]
+    fn test_internal_key_timestamp_encode_decode() {
+        let key = InternalKey::with_timestamp(Slice::from("test_key"), 77, 123, VALUE_TYPE_VALUE);
+        let encoded = key.encode();
+        assert_eq!(encoded.size(), "test_key".len() + 19);
+        let decoded = InternalKey::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.user_key(), &Slice::from("test_key"));
+        assert_eq!(decoded.timestamp(), Some(77));
+        assert_eq!(decoded.sequence, 123);
+        assert_eq!(decoded.value_type, VALUE_TYPE_VALUE);
+
+        let truncated = Slice::from(&encoded.data()[..encoded.size() - 3]);
+        assert!(InternalKey::decode(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_memtable_timestamps() {
+        let memtable =
+            MemTable::with_comparator(Arc::new(crate::util::BytewiseComparatorWithU64Ts));
+        memtable.add_with_timestamp(1, Slice::from("k"), 10, Slice::from("v10"));
+        memtable.add_with_timestamp(2, Slice::from("k"), 30, Slice::from("v30"));
+        memtable.delete_with_timestamp(3, Slice::from("k"), 20);
+
+        // Versions sort newest timestamp first regardless of sequence
+        let timestamps: Vec<Option<u64>> = memtable
+            .collect_entries()
+            .iter()
+            .map(|(key, _)| InternalKey::decode(key).unwrap().timestamp())
+            .collect();
+        assert_eq!(timestamps, vec![Some(30), Some(20), Some(10)]);
+
+        let k = Slice::from("k");
+        assert_eq!(memtable.get_with_timestamp(&k, 5), (false, None));
+        assert_eq!(
+            memtable.get_with_timestamp(&k, 15),
+            (true, Some(Slice::from("v10")))
+        );
+        assert_eq!(memtable.get_with_timestamp(&k, 25), (true, None));
+        assert_eq!(
+            memtable.get_with_timestamp(&k, u64::MAX),
+            (true, Some(Slice::from("v30")))
+        );
+    }
+
+    #[test]
+    fn test_memtable_get_entry_types() {
+        let memtable = MemTable::new();
+        memtable.add(1, Slice::from("plain"), Slice::from("v"));
+        memtable.add_entry(
+            2,
+            Slice::from("entity"),
+            None,
+            VALUE_TYPE_ENTITY,
+            Slice::from("e"),
+        );
+        memtable.delete(3, Slice::from("plain"));
+
+        assert_eq!(
+            memtable.get_entry(&Slice::from("entity"), None),
+            LookupResult::Entity(Slice::from("e"))
+        );
+        assert_eq!(
+            memtable.get_entry(&Slice::from("plain"), None),
+            LookupResult::Deleted
+        );
+        assert_eq!(
+            memtable.get_entry(&Slice::from("missing"), None),
+            LookupResult::NotFound
+        );
+    }
+
+    #[test]
+    fn test_memtable_variable_length_keys_in_order() {
+        let memtable = MemTable::new();
+        for key in ["b", "ab", "a", "abc", "ba"] {
+            memtable.add(1, Slice::from(key), Slice::from(key));
+        }
+
+        let keys: Vec<Slice> = memtable
+            .collect_entries()
+            .iter()
+            .map(|(key, _)| InternalKey::decode(key).unwrap().user_key().clone())
+            .collect();
+        let expected: Vec<Slice> = ["a", "ab", "abc", "b", "ba"]
+            .into_iter()
+            .map(Slice::from)
+            .collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_memtable_reverse_comparator() {
+        let memtable = MemTable::with_comparator(Arc::new(crate::util::ReverseBytewiseComparator));
+        memtable.add(1, Slice::from("a"), Slice::from("old"));
+        memtable.add(2, Slice::from("c"), Slice::from("c"));
+        memtable.add(3, Slice::from("b"), Slice::from("b"));
+        memtable.add(4, Slice::from("a"), Slice::from("new"));
+
+        let entries: Vec<(Slice, u64)> = memtable
+            .collect_entries()
+            .iter()
+            .map(|(key, _)| {
+                let key = InternalKey::decode(key).unwrap();
+                (key.user_key().clone(), key.sequence)
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (Slice::from("c"), 2),
+                (Slice::from("b"), 3),
+                (Slice::from("a"), 4),
+            ]
+        );
+        assert_eq!(memtable.get(&Slice::from("a")).1, Some(Slice::from("new")));
+    }
 }
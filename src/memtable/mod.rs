@@ -1,6 +1,8 @@
 #[allow(clippy::module_inception)]
 pub mod memtable;
+pub mod prefix_bloom;
 pub mod skiplist;
 
-pub use memtable::{InternalKey, MemTable};
+pub use memtable::{InternalKey, InternalKeyComparator, LookupResult, MemTable};
+pub use prefix_bloom::PrefixBloom;
 pub use skiplist::SkipList;
@@ -0,0 +1,96 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use crate::{filter::BloomFilterPolicy, util::SliceTransform};
+
+/// Number of probes per prefix (~1% false positive rate at 10 bits/prefix)
+const NUM_PROBES: u32 = 6;
+
+/// Bloom filter over the key prefixes of a MemTable
+///
+/// Unlike SSTable filters, which are built once from a sorted key list,
+/// this filter is updated concurrently as keys are inserted. Bits live in
+/// an array of atomic words so writers holding a shared MemTable lock can
+/// add prefixes without further synchronization.
+///
+/// Sized as `write_buffer_size * memtable_prefix_bloom_size_ratio` bytes.
+pub struct PrefixBloom {
+    extractor: Arc<dyn SliceTransform>,
+    bits: Vec<AtomicU64>,
+    num_bits: u32,
+}
+
+impl PrefixBloom {
+    /// Create a prefix Bloom filter of roughly `size_bytes` bytes
+    pub fn new(extractor: Arc<dyn SliceTransform>, size_bytes: usize) -> Self {
+        let num_words = size_bytes.div_ceil(8).clamp(1, u32::MAX as usize / 64);
+        PrefixBloom {
+            extractor,
+            bits: (0..num_words).map(|_| AtomicU64::new(0)).collect(),
+            num_bits: (num_words * 64) as u32,
+        }
+    }
+
+    /// Get the prefix extractor this filter was built with
+    pub fn extractor(&self) -> &Arc<dyn SliceTransform> {
+        &self.extractor
+    }
+
+    /// Add the prefix of a user key (keys outside the domain are ignored)
+    pub fn add_key(&self, key: &[u8]) {
+        if !self.extractor.in_domain(key) {
+            return;
+        }
+
+        let h = BloomFilterPolicy::bloom_hash(self.extractor.transform(key));
+        let delta = h.rotate_left(15);
+        for i in 0..NUM_PROBES {
+            let bit_pos = h.wrapping_add(i.wrapping_mul(delta)) % self.num_bits;
+            self.bits[(bit_pos / 64) as usize].fetch_or(1 << (bit_pos % 64), Ordering::Relaxed);
+        }
+    }
+
+    /// Check whether any key with this prefix may have been added
+    pub fn may_contain_prefix(&self, prefix: &[u8]) -> bool {
+        let h = BloomFilterPolicy::bloom_hash(prefix);
+        let delta = h.rotate_left(15);
+        (0..NUM_PROBES).all(|i| {
+            let bit_pos = h.wrapping_add(i.wrapping_mul(delta)) % self.num_bits;
+            self.bits[(bit_pos / 64) as usize].load(Ordering::Relaxed) & (1 << (bit_pos % 64)) != 0
+        })
+    }
+
+    /// Check whether a user key may have been added, based on its prefix
+    ///
+    /// Keys outside the extractor's domain always may match.
+    pub fn may_contain_key(&self, key: &[u8]) -> bool {
+        !self.extractor.in_domain(key) || self.may_contain_prefix(self.extractor.transform(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::FixedPrefixTransform;
+
+    #[test]
+    fn test_prefix_bloom_basic() {
+        let bloom = PrefixBloom::new(Arc::new(FixedPrefixTransform::new(3)), 1024);
+
+        bloom.add_key(b"abc1");
+        bloom.add_key(b"abc2");
+        bloom.add_key(b"xy"); // Outside the domain, ignored
+
+        assert!(bloom.may_contain_prefix(b"abc"));
+        assert!(bloom.may_contain_key(b"abc9"));
+        assert!(bloom.may_contain_key(b"xy"));
+
+        // With 8192 bits and one prefix, false positives are very unlikely
+        let false_positives = (0..1000)
+            .filter(|i| bloom.may_contain_prefix(format!("{i:03}").as_bytes()))
+            .count();
+        assert!(false_positives < 10);
+    }
+}
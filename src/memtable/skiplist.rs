@@ -1,38 +1,85 @@
-use std::sync::Arc;
+use std::{cmp::Ordering, ops::Bound, sync::Arc};
 
-use crossbeam_skiplist::SkipMap;
+use crossbeam_skiplist::{SkipMap, map::Entry};
 
-use crate::util::Slice;
+use crate::{memtable::memtable::InternalKeyComparator, util::Slice};
 
+/// Key of a SkipList entry, ordered by the list's comparator
+#[derive(Clone)]
+pub struct SkipListKey {
+    data: Vec<u8>,
+    comparator: InternalKeyComparator,
+}
+
+impl SkipListKey {
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Ord for SkipListKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.comparator.compare(&self.data, &other.data)
+    }
+}
+
+impl PartialOrd for SkipListKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for SkipListKey {}
+
+impl PartialEq for SkipListKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+#[derive(Clone)]
 pub struct SkipList {
-    pub(crate) map: Arc<SkipMap<Vec<u8>, Vec<u8>>>,
+    pub(crate) map: Arc<SkipMap<SkipListKey, Vec<u8>>>,
+    comparator: InternalKeyComparator,
 }
 
 impl SkipList {
     pub fn new() -> Self {
+        Self::with_comparator(InternalKeyComparator::default())
+    }
+
+    /// Create a SkipList whose keys are ordered by `comparator`
+    pub fn with_comparator(comparator: InternalKeyComparator) -> Self {
         SkipList {
             map: Arc::new(SkipMap::new()),
+            comparator,
+        }
+    }
+
+    /// Wrap key bytes for a lookup in this list
+    pub(crate) fn key(&self, data: &[u8]) -> SkipListKey {
+        SkipListKey {
+            data: data.to_vec(),
+            comparator: self.comparator.clone(),
         }
     }
 
     pub fn insert(&self, key: Slice, value: Slice) {
-        self.map.insert(key.data().to_vec(), value.data().to_vec());
+        self.map.insert(self.key(key.data()), value.data().to_vec());
     }
 
     pub fn get(&self, key: &Slice) -> Option<Slice> {
         self.map
-            .get(key.data())
+            .get(&self.key(key.data()))
             .map(|entry| Slice::from(entry.value().clone()))
     }
 
     pub fn contains(&self, key: &Slice) -> bool {
-        self.map.contains_key(key.data())
+        self.map.contains_key(&self.key(key.data()))
     }
 
     pub fn iter(&self) -> SkipListIterator {
-        SkipListIterator {
-            inner: self.map.clone(),
-        }
+        SkipListIterator { list: self.clone() }
     }
 
     pub fn len(&self) -> usize {
@@ -51,42 +98,49 @@ impl Default for SkipList {
 }
 
 pub struct SkipListIterator {
-    inner: Arc<SkipMap<Vec<u8>, Vec<u8>>>,
+    list: SkipList,
 }
 
 impl SkipListIterator {
+    fn to_slices(entry: Entry<'_, SkipListKey, Vec<u8>>) -> (Slice, Slice) {
+        (
+            Slice::from(entry.key().data.clone()),
+            Slice::from(entry.value().clone()),
+        )
+    }
+
     pub fn seek_to_first(&self) -> Option<(Slice, Slice)> {
-        self.inner.front().map(|entry| {
-            (
-                Slice::from(entry.key().clone()),
-                Slice::from(entry.value().clone()),
-            )
-        })
+        self.list.map.front().map(Self::to_slices)
     }
 
+    /// First entry at or after `target`
     pub fn seek(&self, target: &Slice) -> Option<(Slice, Slice)> {
-        self.inner
-            .range(target.data().to_vec()..)
+        self.list
+            .map
+            .range((
+                Bound::Included(self.list.key(target.data())),
+                Bound::Unbounded,
+            ))
             .next()
-            .map(|entry| {
-                (
-                    Slice::from(entry.key().clone()),
-                    Slice::from(entry.value().clone()),
-                )
-            })
+            .map(Self::to_slices)
     }
 
+    /// Entries at or after `start`, in order
     pub fn range_from(&self, start: &Slice) -> Vec<(Slice, Slice)> {
-        self.inner
-            .range(start.data().to_vec()..)
-            .map(|entry| {
-                (
-                    Slice::from(entry.key().clone()),
-                    Slice::from(entry.value().clone()),
-                )
-            })
+        self.list
+            .map
+            .range((
+                Bound::Included(self.list.key(start.data())),
+                Bound::Unbounded,
+            ))
+            .map(Self::to_slices)
             .collect()
     }
+
+    /// All entries, in order
+    pub fn all(&self) -> Vec<(Slice, Slice)> {
+        self.list.map.iter().map(Self::to_slices).collect()
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::{Result, Slice};
 
 /// Trait for implementing custom merge operators
@@ -64,6 +66,12 @@ pub trait MergeOperator: Send + Sync {
     }
 }
 
+impl fmt::Debug for dyn MergeOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// Built-in merge operator for integer counters
 ///
 /// Interprets values and operands as i64 integers and performs addition.
@@ -0,0 +1,436 @@
+/// OPTIONS files
+///
+/// Every time a database is opened, or a column family is created or
+/// dropped, the options in effect are written to a new `OPTIONS-<n>` file
+/// in the database directory as JSON. The file with the highest `n` is the
+/// latest; the one before it is kept, older ones are deleted.
+///
+/// User-supplied objects (comparator, merge operator, filter policy,
+/// prefix extractor) can't be serialized, so only their names are
+/// recorded. The names are what the compatibility check at open compares:
+/// reopening with a different comparator or merge operator would make the
+/// data on disk unreadable, and is rejected.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    column_family::{ColumnFamilyDescriptor, ColumnFamilyOptions},
+    db::DBOptions,
+    util::{Result, Status, comparator::builtin_comparator},
+};
+
+/// Prefix of OPTIONS file names
+pub const OPTIONS_FILE_PREFIX: &str = "OPTIONS-";
+
+/// Version of the OPTIONS file format written by this release
+pub const OPTIONS_FILE_VERSION: u32 = 1;
+
+/// Column family options `DB::set_options` can change on a live database
+pub const MUTABLE_CF_OPTIONS: &[&str] = &[
+    "write_buffer_size",
+    "compression_type",
+    "l0_compaction_trigger",
+    "l0_stop_writes_trigger",
+    "max_bytes_for_level_base",
+    "max_bytes_for_level_multiplier",
+    "target_file_size_base",
+    "target_file_size_multiplier",
+    "compaction_pri",
+    "compaction_options_universal",
+    "compaction_options_fifo",
+    "ttl",
+    "periodic_compaction_seconds",
+    "compact_on_deletion",
+];
+
+/// Database options `DB::set_db_options` can change on a live database
+pub const MUTABLE_DB_OPTIONS: &[&str] = &[
+    "enable_background_compaction",
+    "enable_subcompaction",
+    "subcompaction_min_size",
+    "parallel_compaction_threads",
+];
+
+/// Options of one column family as recorded in an OPTIONS file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnFamilyOptionsRecord {
+    /// Column family name
+    pub name: String,
+    /// Comparator name
+    pub comparator: String,
+    /// Merge operator name (None = no merge operator)
+    #[serde(default)]
+    pub merge_operator: Option<String>,
+    /// Compaction filter name (None = no compaction filter)
+    #[serde(default)]
+    pub compaction_filter: Option<String>,
+    /// Prefix extractor name (None = no prefix extractor)
+    #[serde(default)]
+    pub prefix_extractor: Option<String>,
+    /// Name of the filter policy new tables are written with
+    #[serde(default)]
+    pub filter_policy: Option<String>,
+    /// Serializable options
+    pub options: ColumnFamilyOptions,
+}
+
+impl ColumnFamilyOptionsRecord {
+    pub fn new(name: &str, options: &ColumnFamilyOptions) -> Self {
+        ColumnFamilyOptionsRecord {
+            name: name.to_string(),
+            comparator: options.comparator.name().to_string(),
+            merge_operator: options
+                .merge_operator
+                .as_ref()
+                .map(|m| m.name().to_string()),
+            compaction_filter: options
+                .compaction_filter
+                .as_ref()
+                .map(|filter| filter.name().to_string()),
+            prefix_extractor: options
+                .prefix_extractor
+                .as_ref()
+                .map(|p| p.name().to_string()),
+            filter_policy: options
+                .table_filter_policy()
+                .map(|policy| policy.name().to_string()),
+            options: options.clone(),
+        }
+    }
+}
+
+/// Contents of an OPTIONS file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionsFile {
+    /// Version of the file format
+    pub format_version: u32,
+    /// Database options
+    pub db_options: DBOptions,
+    /// Options of every column family, default first
+    pub column_families: Vec<ColumnFamilyOptionsRecord>,
+}
+
+impl OptionsFile {
+    /// Record the given options
+    pub fn new(db_options: &DBOptions, column_families: Vec<ColumnFamilyOptionsRecord>) -> Self {
+        OptionsFile {
+            format_version: OPTIONS_FILE_VERSION,
+            db_options: db_options.clone(),
+            column_families,
+        }
+    }
+
+    /// Read an OPTIONS file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| Status::io_error(format!("Failed to read {}: {e}", path.display())))?;
+        let file: OptionsFile = serde_json::from_str(&data)?;
+        if file.format_version > OPTIONS_FILE_VERSION {
+            return Err(Status::not_supported(format!(
+                "{} has format version {} (latest supported is {OPTIONS_FILE_VERSION})",
+                path.display(),
+                file.format_version
+            )));
+        }
+        Ok(file)
+    }
+
+    /// Options recorded for a column family
+    pub fn column_family(&self, name: &str) -> Option<&ColumnFamilyOptionsRecord> {
+        self.column_families.iter().find(|cf| cf.name == name)
+    }
+
+    /// Descriptors for opening the database with the recorded options
+    ///
+    /// Builtin comparators are restored by name. Custom comparators, merge
+    /// operators, filter policies and prefix extractors have to be set on
+    /// the returned options again before opening.
+    pub fn descriptors(&self) -> Vec<ColumnFamilyDescriptor> {
+        self.column_families
+            .iter()
+            .map(|cf| {
+                let mut options = cf.options.clone();
+                if let Some(comparator) = builtin_comparator(&cf.comparator) {
+                    options.comparator = comparator;
+                }
+                ColumnFamilyDescriptor::new(cf.name.clone(), options)
+            })
+            .collect()
+    }
+
+    /// Check that a database written with these options can be opened with
+    /// `current`
+    ///
+    /// Column families must keep their comparator. A merge operator may be
+    /// added to a CF that had none, but not removed or replaced.
+    pub fn check_compatibility(&self, current: &OptionsFile) -> Result<()> {
+        for cf in &current.column_families {
+            let Some(previous) = self.column_family(&cf.name) else {
+                continue;
+            };
+
+            if cf.comparator != previous.comparator {
+                return Err(Status::invalid_argument(format!(
+                    "Column family '{}': comparator '{}' does not match '{}' the database was \
+                     opened with",
+                    cf.name, cf.comparator, previous.comparator
+                )));
+            }
+
+            if let Some(ref merge_operator) = previous.merge_operator
+                && cf.merge_operator.as_ref() != Some(merge_operator)
+            {
+                return Err(Status::invalid_argument(format!(
+                    "Column family '{}': merge operator '{}' does not match '{}' the database \
+                     was opened with",
+                    cf.name,
+                    cf.merge_operator.as_deref().unwrap_or("none"),
+                    merge_operator
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Apply `(name, value)` changes to a copy of `options`
+///
+/// Names are field names as written to the OPTIONS file and must be in
+/// `mutable`. Values are parsed as JSON, falling back to a plain string so
+/// enum values can be given unquoted (`("compression_type", "Lz4")`).
+/// Fields skipped by serialization come back as their defaults and have to
+/// be restored by the caller.
+pub(crate) fn apply_option_changes<T>(
+    options: &T,
+    mutable: &[&str],
+    changes: &[(&str, &str)],
+) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let mut value = serde_json::to_value(options)?;
+    for &(name, new_value) in changes {
+        if !mutable.contains(&name) {
+            return Err(Status::invalid_argument(format!(
+                "Option '{name}' can't be changed on a live database"
+            )));
+        }
+
+        let parsed = serde_json::from_str(new_value)
+            .unwrap_or_else(|_| serde_json::Value::String(new_value.to_string()));
+        value[name] = parsed;
+        if serde_json::from_value::<T>(value.clone()).is_err() {
+            return Err(Status::invalid_argument(format!(
+                "Invalid value '{new_value}' for option '{name}'"
+            )));
+        }
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Number and path of the latest OPTIONS file in a database directory
+pub fn latest_options_file<P: AsRef<Path>>(db_path: P) -> Result<Option<(u64, PathBuf)>> {
+    Ok(options_files(db_path.as_ref())?.pop())
+}
+
+/// OPTIONS files in a database directory, oldest first
+fn options_files(db_path: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let entries = match fs::read_dir(db_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(Status::io_error(format!(
+                "Failed to read db directory: {e}"
+            )));
+        },
+    };
+
+    let mut files: Vec<(u64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let number = name
+                .to_str()?
+                .strip_prefix(OPTIONS_FILE_PREFIX)?
+                .parse::<u64>()
+                .ok()?;
+            Some((number, entry.path()))
+        })
+        .collect();
+    files.sort_unstable_by_key(|(number, _)| *number);
+    Ok(files)
+}
+
+/// Load the options a database was last opened with
+///
+/// Returns the database options and a descriptor per column family, ready
+/// for `DB::open_cf` once user-supplied objects are set again.
+pub fn load_latest_options(path: &str) -> Result<(DBOptions, Vec<ColumnFamilyDescriptor>)> {
+    let (_, file_path) = latest_options_file(path)?
+        .ok_or_else(|| Status::not_found(format!("No OPTIONS file in {path}")))?;
+    let file = OptionsFile::load(file_path)?;
+    let descriptors = file.descriptors();
+    Ok((file.db_options, descriptors))
+}
+
+/// Write `file` as the next OPTIONS file of a database
+///
+/// The file is written under a temporary name and renamed, so a crash never
+/// leaves a partial latest OPTIONS file. Only the previous file is kept.
+pub(crate) fn write_options_file(db_path: &Path, file: &OptionsFile) -> Result<PathBuf> {
+    let existing = options_files(db_path)?;
+    let number = existing.last().map_or(1, |(number, _)| number + 1);
+
+    let path = db_path.join(format!("{OPTIONS_FILE_PREFIX}{number:06}"));
+    let tmp_path = path.with_extension("dbtmp");
+    let data = serde_json::to_string_pretty(file)?;
+    fs::write(&tmp_path, data)
+        .map_err(|e| Status::io_error(format!("Failed to write OPTIONS file: {e}")))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| Status::io_error(format!("Failed to rename OPTIONS file: {e}")))?;
+
+    // Keep the file just replaced, delete anything older
+    if existing.len() > 1 {
+        for (_, old_path) in &existing[..existing.len() - 1] {
+            let _ = fs::remove_file(old_path);
+        }
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        merge::CounterMerge,
+        table::format::CompressionType,
+        util::{BytewiseComparator, ReverseBytewiseComparator},
+    };
+
+    fn options_file(merge: bool) -> OptionsFile {
+        let cf_options = ColumnFamilyOptions {
+            compression_type: CompressionType::Lz4,
+            merge_operator: if merge {
+                Some(Arc::new(CounterMerge))
+            } else {
+                None
+            },
+            ..Default::default()
+        };
+        OptionsFile::new(
+            &DBOptions::default(),
+            vec![
+                ColumnFamilyOptionsRecord::new("default", &ColumnFamilyOptions::default()),
+                ColumnFamilyOptionsRecord::new("counters", &cf_options),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_options_file_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_options_file(temp_dir.path(), &options_file(true)).unwrap();
+        assert!(path.ends_with("OPTIONS-000001"));
+
+        let loaded = OptionsFile::load(&path).unwrap();
+        let counters = loaded.column_family("counters").unwrap();
+        assert_eq!(counters.merge_operator.as_deref(), Some("CounterMerge"));
+        assert_eq!(counters.comparator, BytewiseComparator::NAME);
+        assert_eq!(counters.options.compression_type, CompressionType::Lz4);
+        assert!(counters.options.merge_operator.is_none());
+    }
+
+    #[test]
+    fn test_descriptors_restore_builtin_comparator() {
+        let reverse = ColumnFamilyOptions {
+            comparator: Arc::new(ReverseBytewiseComparator),
+            ..Default::default()
+        };
+        let file = OptionsFile::new(
+            &DBOptions::default(),
+            vec![ColumnFamilyOptionsRecord::new("reverse", &reverse)],
+        );
+        let loaded: OptionsFile =
+            serde_json::from_str(&serde_json::to_string(&file).unwrap()).unwrap();
+
+        let descriptors = loaded.descriptors();
+        assert_eq!(
+            descriptors[0].options.comparator.name(),
+            ReverseBytewiseComparator::NAME
+        );
+    }
+
+    #[test]
+    fn test_options_files_rotated() {
+        let temp_dir = TempDir::new().unwrap();
+        for _ in 0..4 {
+            write_options_file(temp_dir.path(), &options_file(false)).unwrap();
+        }
+
+        let numbers: Vec<u64> = options_files(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|(number, _)| number)
+            .collect();
+        assert_eq!(numbers, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_options_compatibility() {
+        let with_merge = options_file(true);
+        let without_merge = options_file(false);
+
+        // Adding a merge operator is fine, removing one is not
+        assert!(without_merge.check_compatibility(&with_merge).is_ok());
+        assert!(with_merge.check_compatibility(&without_merge).is_err());
+
+        let mut other_comparator = options_file(true);
+        other_comparator.column_families[0].comparator = "reverse".to_string();
+        let err = with_merge
+            .check_compatibility(&other_comparator)
+            .unwrap_err();
+        assert!(err.message().unwrap().contains("comparator"));
+    }
+
+    #[test]
+    fn test_apply_option_changes() {
+        let options = ColumnFamilyOptions::default();
+        let changed = apply_option_changes(
+            &options,
+            MUTABLE_CF_OPTIONS,
+            &[("write_buffer_size", "65536"), ("compression_type", "Lz4")],
+        )
+        .unwrap();
+        assert_eq!(changed.write_buffer_size, 65536);
+        assert_eq!(changed.compression_type, CompressionType::Lz4);
+
+        // Immutable, unknown and ill-typed options are rejected
+        for change in [
+            ("table_options", "{}"),
+            ("no_such_option", "1"),
+            ("write_buffer_size", "big"),
+            ("compression_type", "Zstd"),
+        ] {
+            assert!(apply_option_changes(&options, MUTABLE_CF_OPTIONS, &[change]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_newer_options_file_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut file = options_file(false);
+        file.format_version = OPTIONS_FILE_VERSION + 1;
+        let path = write_options_file(temp_dir.path(), &file).unwrap();
+        assert!(OptionsFile::load(path).is_err());
+    }
+}
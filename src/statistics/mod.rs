@@ -44,6 +44,10 @@ pub struct Statistics {
     pub num_sequential_compactions: AtomicU64,
     pub num_subcompactions: AtomicU64,
     pub compaction_time_micros: AtomicU64,
+    pub compaction_filter_removed: AtomicU64,
+    pub compaction_filter_changed: AtomicU64,
+    pub num_trivial_moves: AtomicU64,
+    pub trivial_move_bytes: AtomicU64,
 
     // Bloom filter stats
     pub bloom_filter_useful: AtomicU64,
@@ -186,6 +190,20 @@ impl Statistics {
             .fetch_add(time_micros, Ordering::Relaxed);
     }
 
+    #[inline]
+    pub fn record_compaction_filter(&self, removed: u64, changed: u64) {
+        self.compaction_filter_removed
+            .fetch_add(removed, Ordering::Relaxed);
+        self.compaction_filter_changed
+            .fetch_add(changed, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record_trivial_move(&self, bytes: u64) {
+        self.num_trivial_moves.fetch_add(1, Ordering::Relaxed);
+        self.trivial_move_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
     // Bloom filter tracking
     #[inline]
     pub fn record_bloom_filter_check(&self, useful: bool) {
@@ -260,6 +278,22 @@ impl Statistics {
         }
     }
 
+    pub fn compaction_filter_removed(&self) -> u64 {
+        self.compaction_filter_removed.load(Ordering::Relaxed)
+    }
+
+    pub fn compaction_filter_changed(&self) -> u64 {
+        self.compaction_filter_changed.load(Ordering::Relaxed)
+    }
+
+    pub fn num_trivial_moves(&self) -> u64 {
+        self.num_trivial_moves.load(Ordering::Relaxed)
+    }
+
+    pub fn trivial_move_bytes(&self) -> u64 {
+        self.trivial_move_bytes.load(Ordering::Relaxed)
+    }
+
     pub fn parallel_compaction_ratio(&self) -> f64 {
         let parallel = self.num_parallel_compactions.load(Ordering::Relaxed) as f64;
         let total = self.num_compactions.load(Ordering::Relaxed) as f64;
@@ -295,6 +329,10 @@ impl Statistics {
         self.num_sequential_compactions.store(0, Ordering::Relaxed);
         self.num_subcompactions.store(0, Ordering::Relaxed);
         self.compaction_time_micros.store(0, Ordering::Relaxed);
+        self.compaction_filter_removed.store(0, Ordering::Relaxed);
+        self.compaction_filter_changed.store(0, Ordering::Relaxed);
+        self.num_trivial_moves.store(0, Ordering::Relaxed);
+        self.trivial_move_bytes.store(0, Ordering::Relaxed);
         self.bloom_filter_useful.store(0, Ordering::Relaxed);
         self.bloom_filter_checked.store(0, Ordering::Relaxed);
         self.num_errors.store(0, Ordering::Relaxed);
@@ -344,6 +382,10 @@ impl Statistics {
             - Bytes written: {} ({:.2} MB)\n\
             - Files:         {}\n\
             - R/W ratio:     {:.2}\n\
+            - Filter removed: {}\n\
+            - Filter changed: {}\n\
+            - Trivial moves: {}\n\
+            - Bytes moved:   {} ({:.2} MB)\n\
             \n\
             Bloom Filter:\n\
             - Checked:       {}\n\
@@ -388,6 +430,11 @@ impl Statistics {
             self.compaction_bytes_written.load(Ordering::Relaxed) as f64 / 1024.0 / 1024.0,
             self.num_files_compacted.load(Ordering::Relaxed),
             self.compaction_read_write_ratio(),
+            self.compaction_filter_removed(),
+            self.compaction_filter_changed(),
+            self.num_trivial_moves(),
+            self.trivial_move_bytes(),
+            self.trivial_move_bytes() as f64 / 1024.0 / 1024.0,
             self.bloom_filter_checked.load(Ordering::Relaxed),
             self.bloom_filter_useful.load(Ordering::Relaxed),
             self.bloom_filter_effectiveness() * 100.0,
@@ -469,6 +516,12 @@ mod tests {
         assert_eq!(stats.compaction_bytes_written.load(Ordering::Relaxed), 2400);
         assert_eq!(stats.num_files_compacted.load(Ordering::Relaxed), 15);
         assert_eq!(stats.compaction_read_write_ratio(), 1.25);
+
+        // Trivial moves are counted apart from compactions
+        stats.record_trivial_move(4096);
+        assert_eq!(stats.num_trivial_moves(), 1);
+        assert_eq!(stats.trivial_move_bytes(), 4096);
+        assert_eq!(stats.num_compactions.load(Ordering::Relaxed), 2);
     }
 
     #[test]
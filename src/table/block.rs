@@ -1,5 +1,8 @@
 use crate::{
-    table::format::{CompressionType, calculate_checksum, decode_varint},
+    table::{
+        data_block_hash_index::{self, HASH_INDEX_FLAG, HashIndexLookup},
+        format::{CompressionType, calculate_checksum, decode_varint},
+    },
     util::{Result, Slice, Status},
 };
 
@@ -9,18 +12,28 @@ use crate::{
 /// - Prefix-compressed keys
 /// - Restart points for efficient seeking
 /// - Checksum verification
+/// - An optional hash index for point lookups
 #[derive(Debug)]
 pub struct Block {
     data: Vec<u8>,
     restart_offset: usize,
     num_restarts: u32,
+    /// Offset and length of the hash index buckets, if present
+    hash_index: Option<(usize, usize)>,
 }
 
 impl Block {
     /// Create a block from raw data
     /// Data format: [compressed_data...][compression:1][checksum:4]
-    /// After decompression: [entries...][restarts...][num_restarts:4]
+    /// After decompression: [entries...][restarts...][hash
+    /// index][num_restarts:4]
     pub fn new(data: Vec<u8>) -> Result<Self> {
+        Self::with_verification(data, true)
+    }
+
+    /// Create a block from raw data, optionally skipping checksum
+    /// verification
+    pub fn with_verification(data: Vec<u8>, verify_checksum: bool) -> Result<Self> {
         if data.len() < 5 {
             // Minimum: compression(1) + checksum(4)
             return Err(Status::corruption("Block too small"));
@@ -31,7 +44,11 @@ impl Block {
         // Extract and verify checksum
         let stored_checksum =
             u32::from_le_bytes([data[len - 4], data[len - 3], data[len - 2], data[len - 1]]);
-        let actual_checksum = calculate_checksum(&data[..len - 5]); // Exclude compression and checksum
+        let actual_checksum = if verify_checksum {
+            calculate_checksum(&data[..len - 5]) // Exclude compression and checksum
+        } else {
+            stored_checksum
+        };
         if stored_checksum != actual_checksum {
             return Err(Status::corruption(format!(
                 "Block checksum mismatch: expected {actual_checksum}, got {stored_checksum}"
@@ -56,19 +73,40 @@ impl Block {
 
         // Extract number of restarts (last 4 bytes of uncompressed data)
         let unc_len = uncompressed.len();
-        let num_restarts = u32::from_le_bytes([
+        let packed_restarts = u32::from_le_bytes([
             uncompressed[unc_len - 4],
             uncompressed[unc_len - 3],
             uncompressed[unc_len - 2],
             uncompressed[unc_len - 1],
         ]);
+        let num_restarts = packed_restarts & !HASH_INDEX_FLAG;
+
+        // The hash index sits between the restart array and the count
+        let mut restarts_end = unc_len - 4;
+        let mut hash_index = None;
+        if packed_restarts & HASH_INDEX_FLAG != 0 {
+            if restarts_end < 2 {
+                return Err(Status::corruption("Block hash index truncated"));
+            }
+            let num_buckets = u16::from_le_bytes([
+                uncompressed[restarts_end - 2],
+                uncompressed[restarts_end - 1],
+            ]) as usize;
+            restarts_end = restarts_end
+                .checked_sub(2 + num_buckets)
+                .ok_or_else(|| Status::corruption("Block hash index truncated"))?;
+            hash_index = Some((restarts_end, num_buckets));
+        }
 
-        let restart_offset = unc_len - 4 - (num_restarts as usize * 4);
+        let restart_offset = restarts_end
+            .checked_sub(num_restarts as usize * 4)
+            .ok_or_else(|| Status::corruption("Restart array extends beyond block"))?;
 
         Ok(Block {
             data: uncompressed,
             restart_offset,
             num_restarts,
+            hash_index,
         })
     }
 
@@ -77,8 +115,25 @@ impl Block {
         self.num_restarts
     }
 
+    /// Whether the block carries a hash index
+    pub fn has_hash_index(&self) -> bool {
+        self.hash_index.is_some()
+    }
+
+    /// Look a user key up in the block's hash index
+    ///
+    /// Blocks without a hash index report a collision, meaning the caller
+    /// has to search the whole block.
+    pub fn hash_lookup(&self, user_key: &[u8]) -> HashIndexLookup {
+        match self.hash_index {
+            Some((offset, num_buckets)) => {
+                data_block_hash_index::lookup(&self.data[offset..offset + num_buckets], user_key)
+            },
+            None => HashIndexLookup::Collision,
+        }
+    }
+
     /// Get restart point offset by index
-    #[allow(dead_code)]
     fn get_restart_point(&self, index: u32) -> Option<u32> {
         if index >= self.num_restarts {
             return None;
@@ -175,6 +230,19 @@ impl<'a> BlockIterator<'a> {
         self.next()
     }
 
+    /// Seek to the first entry of a restart interval
+    pub fn seek_to_restart_point(&mut self, index: u32) -> Result<bool> {
+        let offset = self
+            .block
+            .get_restart_point(index)
+            .ok_or_else(|| Status::corruption("Restart index out of range"))?;
+        // Keys at restart points share nothing with their predecessor
+        self.current_offset = offset as usize;
+        self.current_key.clear();
+        self.restart_index = index;
+        self.next()
+    }
+
     /// Move to the next entry
     ///
     /// # Error Handling
@@ -338,4 +406,58 @@ mod tests {
                 .contains("checksum mismatch")
         );
     }
+
+    #[test]
+    fn test_block_hash_index() {
+        let mut builder = BlockBuilder::with_hash_index(4, 0.75);
+        for i in 0..40 {
+            builder.add(
+                &Slice::from(format!("key{i:03}")),
+                &Slice::from(format!("value{i:03}")),
+            );
+        }
+        let block = Block::new(builder.finish()).unwrap();
+        assert!(block.has_hash_index());
+        assert_eq!(block.num_restarts(), 10);
+
+        // Full iteration is unaffected by the index
+        let mut iter = block.iter();
+        assert!(iter.seek_to_first().unwrap());
+        let mut count = 1;
+        while iter.next().unwrap() {
+            count += 1;
+        }
+        assert_eq!(count, 40);
+
+        for i in 0..40 {
+            let key = format!("key{i:03}");
+            match block.hash_lookup(key.as_bytes()) {
+                HashIndexLookup::Restart(index) => {
+                    assert_eq!(index, i / 4);
+                    let mut iter = block.iter();
+                    assert!(iter.seek_to_restart_point(index).unwrap());
+                    assert_eq!(iter.key(), Slice::from(format!("key{:03}", index * 4)));
+                },
+                HashIndexLookup::Collision => {},
+                HashIndexLookup::NotFound => panic!("{key} not found"),
+            }
+        }
+
+        // Blocks written without the index fall back to a full search
+        let plain = Block::new(build_test_block(&[("key1", "value1")])).unwrap();
+        assert!(!plain.has_hash_index());
+        assert_eq!(plain.hash_lookup(b"key1"), HashIndexLookup::Collision);
+    }
+
+    #[test]
+    fn test_block_skip_checksum_verification() {
+        let mut data = build_test_block(&[("key", "value")]);
+        let len = data.len();
+        data[len - 1] ^= 0xFF;
+
+        let block = Block::with_verification(data, false).unwrap();
+        let mut iter = block.iter();
+        assert!(iter.seek_to_first().unwrap());
+        assert_eq!(iter.key(), Slice::from("key"));
+    }
 }
@@ -1,5 +1,8 @@
 use crate::{
-    table::format::{CompressionType, DEFAULT_RESTART_INTERVAL, calculate_checksum, encode_varint},
+    table::{
+        data_block_hash_index::{DataBlockHashIndexBuilder, HASH_INDEX_FLAG, extract_user_key},
+        format::{CompressionType, DEFAULT_RESTART_INTERVAL, calculate_checksum, encode_varint},
+    },
     util::Slice,
 };
 
@@ -8,6 +11,7 @@ use crate::{
 /// Builds a block with key prefix compression:
 /// - Stores restart points every N entries
 /// - Uses prefix compression for keys between restart points
+/// - Optionally appends a hash index mapping user keys to restart points
 pub struct BlockBuilder {
     /// Block data buffer
     buffer: Vec<u8>,
@@ -21,6 +25,8 @@ pub struct BlockBuilder {
     last_key: Vec<u8>,
     /// Whether the block is finished
     finished: bool,
+    /// Hash index over user keys (data blocks only)
+    hash_index: Option<DataBlockHashIndexBuilder>,
 }
 
 impl BlockBuilder {
@@ -32,11 +38,23 @@ impl BlockBuilder {
             restart_interval,
             last_key: Vec::new(),
             finished: false,
+            hash_index: None,
         };
         builder.restarts.push(0); // First restart point at offset 0
         builder
     }
 
+    /// Create a builder that also writes a data block hash index
+    ///
+    /// `util_ratio` is the target ratio of keys to hash buckets. Blocks with
+    /// more restart points than a bucket can address are written without
+    /// the index.
+    pub fn with_hash_index(restart_interval: usize, util_ratio: f64) -> Self {
+        let mut builder = Self::new(restart_interval);
+        builder.hash_index = Some(DataBlockHashIndexBuilder::new(util_ratio));
+        builder
+    }
+
     /// Add a key-value pair to the block
     /// Keys must be added in sorted order
     pub fn add(&mut self, key: &Slice, value: &Slice) {
@@ -71,6 +89,10 @@ impl BlockBuilder {
         self.buffer.extend_from_slice(&key.data()[shared..]);
         self.buffer.extend_from_slice(value.data());
 
+        if let Some(ref mut hash_index) = self.hash_index {
+            hash_index.add(extract_user_key(key.data()), self.restarts.len() - 1);
+        }
+
         // Update last key
         self.last_key.clear();
         self.last_key.extend_from_slice(key.data());
@@ -88,7 +110,8 @@ impl BlockBuilder {
     /// Returns the complete block data including:
     /// - Compressed/uncompressed block data
     /// - Restart array
-    /// - Num restarts (4 bytes)
+    /// - Hash index (optional)
+    /// - Num restarts (4 bytes, top bit set if there is a hash index)
     /// - Compression type (1 byte)
     /// - CRC32 checksum (4 bytes)
     pub fn finish_with_compression(&mut self, compression: CompressionType) -> Vec<u8> {
@@ -105,8 +128,15 @@ impl BlockBuilder {
             uncompressed.extend_from_slice(&restart.to_le_bytes());
         }
 
-        // Append number of restarts
-        uncompressed.extend_from_slice(&(self.restarts.len() as u32).to_le_bytes());
+        // Append hash index and number of restarts
+        let mut num_restarts = self.restarts.len() as u32;
+        if let Some(ref hash_index) = self.hash_index
+            && hash_index.valid()
+        {
+            hash_index.finish(&mut uncompressed);
+            num_restarts |= HASH_INDEX_FLAG;
+        }
+        uncompressed.extend_from_slice(&num_restarts.to_le_bytes());
 
         // Compress the block if needed
         let (final_data, final_compression) = if compression != CompressionType::None {
@@ -140,6 +170,7 @@ impl BlockBuilder {
     pub fn current_size_estimate(&self) -> usize {
         self.buffer.len()
             + self.restarts.len() * 4  // Restart array
+            + self.hash_index.as_ref().map_or(0, |h| h.estimate_size())
             + 4  // Num restarts
             + 1  // Compression type
             + 4 // Checksum
@@ -158,6 +189,9 @@ impl BlockBuilder {
         self.counter = 0;
         self.last_key.clear();
         self.finished = false;
+        if let Some(ref mut hash_index) = self.hash_index {
+            hash_index.reset();
+        }
     }
 }
 
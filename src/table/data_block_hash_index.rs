@@ -0,0 +1,201 @@
+/// Data block hash index
+///
+/// An optional hash table appended to a data block, mapping user keys to
+/// the restart interval holding them. A point lookup hashes the user key,
+/// reads one bucket and scans a single restart interval instead of the
+/// whole block.
+///
+/// Layout (between the restart array and the packed restart count):
+/// - Buckets (1 byte each): restart index, `NO_ENTRY` or `COLLISION`
+/// - Num Buckets (2 bytes)
+///
+/// The top bit of the block's restart count flags the index, so blocks
+/// written without it decode exactly as before.
+use crate::filter::hash::hash64;
+
+/// Seed of the bucket hash
+const HASH_SEED: u64 = 0x6461_7461_626c_6b68;
+
+/// Bucket holds no key
+pub const NO_ENTRY: u8 = 255;
+
+/// Bucket holds keys from different restart intervals
+pub const COLLISION: u8 = 254;
+
+/// Largest restart index a bucket can store
+pub const MAX_RESTART_SUPPORTED: usize = 253;
+
+/// Flag in the packed restart count marking a block with a hash index
+pub const HASH_INDEX_FLAG: u32 = 1 << 31;
+
+/// Default ratio of keys to buckets
+pub const DEFAULT_UTIL_RATIO: f64 = 0.75;
+
+/// Result of looking a user key up in a data block hash index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashIndexLookup {
+    /// The key is not in the block
+    NotFound,
+    /// The bucket is shared by several restart intervals; search the block
+    Collision,
+    /// The key, if present, starts in this restart interval
+    Restart(u32),
+}
+
+/// Builder collecting (user key hash, restart index) pairs for one block
+#[derive(Debug, Clone)]
+pub struct DataBlockHashIndexBuilder {
+    util_ratio: f64,
+    entries: Vec<(u64, u8)>,
+    valid: bool,
+}
+
+impl DataBlockHashIndexBuilder {
+    pub fn new(util_ratio: f64) -> Self {
+        DataBlockHashIndexBuilder {
+            util_ratio: if util_ratio > 0.0 {
+                util_ratio
+            } else {
+                DEFAULT_UTIL_RATIO
+            },
+            entries: Vec::new(),
+            valid: true,
+        }
+    }
+
+    /// Record a user key starting in the given restart interval
+    pub fn add(&mut self, user_key: &[u8], restart_index: usize) {
+        if restart_index > MAX_RESTART_SUPPORTED {
+            // Too many restarts to address from a bucket, skip the index
+            self.valid = false;
+            return;
+        }
+        self.entries
+            .push((hash64(user_key, HASH_SEED), restart_index as u8));
+    }
+
+    /// Whether the index can be written for the keys added so far
+    pub fn valid(&self) -> bool {
+        self.valid && !self.entries.is_empty()
+    }
+
+    fn num_buckets(&self) -> usize {
+        ((self.entries.len() as f64 / self.util_ratio) as usize).clamp(1, u16::MAX as usize)
+    }
+
+    /// Estimated size of the encoded index
+    pub fn estimate_size(&self) -> usize {
+        if self.valid() {
+            self.num_buckets() + 2
+        } else {
+            0
+        }
+    }
+
+    /// Append buckets and bucket count to `buf`
+    pub fn finish(&self, buf: &mut Vec<u8>) {
+        let num_buckets = self.num_buckets();
+        let mut buckets = vec![NO_ENTRY; num_buckets];
+        for &(hash, restart_index) in &self.entries {
+            let bucket = &mut buckets[(hash % num_buckets as u64) as usize];
+            if *bucket == NO_ENTRY {
+                *bucket = restart_index;
+            } else if *bucket != restart_index {
+                *bucket = COLLISION;
+            }
+        }
+        buf.extend_from_slice(&buckets);
+        buf.extend_from_slice(&(num_buckets as u16).to_le_bytes());
+    }
+
+    pub fn reset(&mut self) {
+        self.entries.clear();
+        self.valid = true;
+    }
+}
+
+/// User key of an encoded InternalKey, or the key itself if it isn't one
+#[inline]
+pub fn extract_user_key(key: &[u8]) -> &[u8] {
+    if key.len() >= 11 {
+        let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+        if key.len() >= len + 11 {
+            return &key[2..2 + len];
+        }
+    }
+    key
+}
+
+/// Look a user key up in the encoded buckets of a hash index
+pub fn lookup(buckets: &[u8], user_key: &[u8]) -> HashIndexLookup {
+    if buckets.is_empty() {
+        return HashIndexLookup::Collision;
+    }
+    let bucket = buckets[(hash64(user_key, HASH_SEED) % buckets.len() as u64) as usize];
+    match bucket {
+        NO_ENTRY => HashIndexLookup::NotFound,
+        COLLISION => HashIndexLookup::Collision,
+        restart_index => HashIndexLookup::Restart(restart_index as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(builder: &DataBlockHashIndexBuilder) -> Vec<u8> {
+        let mut buf = Vec::new();
+        builder.finish(&mut buf);
+        let num_buckets = u16::from_le_bytes([buf[buf.len() - 2], buf[buf.len() - 1]]) as usize;
+        assert_eq!(buf.len(), num_buckets + 2);
+        buf.truncate(num_buckets);
+        buf
+    }
+
+    #[test]
+    fn test_hash_index_lookup() {
+        let mut builder = DataBlockHashIndexBuilder::new(DEFAULT_UTIL_RATIO);
+        for i in 0..64 {
+            builder.add(format!("key{i:03}").as_bytes(), i / 16);
+        }
+        assert!(builder.valid());
+        let buckets = encode(&builder);
+
+        for i in 0..64 {
+            match lookup(&buckets, format!("key{i:03}").as_bytes()) {
+                HashIndexLookup::Restart(r) => assert_eq!(r as usize, i / 16),
+                HashIndexLookup::Collision => {},
+                HashIndexLookup::NotFound => panic!("key{i:03} not found"),
+            }
+        }
+
+        // Most absent keys land in empty buckets
+        let not_found = (0..1000)
+            .filter(|i| {
+                lookup(&buckets, format!("absent{i}").as_bytes()) == HashIndexLookup::NotFound
+            })
+            .count();
+        assert!(not_found > 100);
+    }
+
+    #[test]
+    fn test_hash_index_same_key_same_restart() {
+        let mut builder = DataBlockHashIndexBuilder::new(1.0);
+        builder.add(b"key", 3);
+        builder.add(b"key", 3);
+        let buckets = encode(&builder);
+        assert_eq!(lookup(&buckets, b"key"), HashIndexLookup::Restart(3));
+    }
+
+    #[test]
+    fn test_hash_index_too_many_restarts() {
+        let mut builder = DataBlockHashIndexBuilder::new(DEFAULT_UTIL_RATIO);
+        builder.add(b"a", 0);
+        builder.add(b"b", MAX_RESTART_SUPPORTED + 1);
+        assert!(!builder.valid());
+        builder.reset();
+        assert!(!builder.valid());
+        builder.add(b"a", 0);
+        assert!(builder.valid());
+    }
+}
@@ -15,6 +15,7 @@
 /// - Meta Block 1 (optional)
 /// - Meta Index (points to meta blocks)
 /// - Index Block (points to data blocks)
+/// - Properties Block (optional, see `TableProperties`)
 /// - Footer (48 bytes)
 ///
 /// Data Block format:
@@ -38,9 +39,23 @@
 /// Footer format (48 bytes):
 /// - Meta Index Block Handle (offset: 8 bytes, size: 8 bytes)
 /// - Index Block Handle (offset: 8 bytes, size: 8 bytes)
-/// - Padding (24 bytes, reserved for future use)
+/// - Index Type (1 byte, see `IndexType`)
+/// - Partitioned Filter Flag (1 byte)
+/// - Prefix Filtering Flag (1 byte, filters also hold key prefixes)
+/// - Format Version (1 byte, see `LATEST_FORMAT_VERSION`)
+/// - Prefix-Only Filter Flag (1 byte, filters hold no whole keys)
+/// - Properties Block Size (3 bytes, 0 = no properties block; the block ends
+///   where the footer starts)
 /// - Magic Number (8 bytes: 0x88e3f3fb2af1ecd7)
+///
+/// Partitioned tables (`IndexType::TwoLevelIndexSearch`):
+/// - The index handle points to a top-level index whose entries map the last
+///   key of each index partition to the partition's handle
+/// - With partitioned filters, the meta index handle points to a filter index
+///   block mapping the last key of each partition to its filter partition
+/// - Partitions are read through the block cache on demand
 use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
 
 /// Block size for SSTable (default 4KB)
 pub const DEFAULT_BLOCK_SIZE: usize = 4 * 1024;
@@ -48,6 +63,17 @@ pub const DEFAULT_BLOCK_SIZE: usize = 4 * 1024;
 /// Restart interval (number of entries between restart points)
 pub const DEFAULT_RESTART_INTERVAL: usize = 16;
 
+/// Newest table format version this build reads and writes
+///
+/// - 0: original format, Bloom filters built from `filter_bits_per_key` use the
+///   legacy layout
+/// - 1: Bloom filters built from `filter_bits_per_key` use the cache-local
+///   layout
+///
+/// Tables record the version they were written with; older releases left
+/// the byte zeroed, so they read as version 0.
+pub const LATEST_FORMAT_VERSION: u32 = 1;
+
 /// Footer size (48 bytes)
 pub const FOOTER_SIZE: usize = 48;
 
@@ -55,7 +81,7 @@ pub const FOOTER_SIZE: usize = 48;
 pub const MAGIC_NUMBER: u64 = 0x88e3f3fb2af1ecd7;
 
 /// Compression type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum CompressionType {
     None = 0,
@@ -74,6 +100,27 @@ impl CompressionType {
     }
 }
 
+/// Index layout of an SSTable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum IndexType {
+    /// A single index block pointing to every data block
+    #[default]
+    BinarySearch = 0,
+    /// A top-level index pointing to index partitions
+    TwoLevelIndexSearch = 1,
+}
+
+impl IndexType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(IndexType::BinarySearch),
+            1 => Some(IndexType::TwoLevelIndexSearch),
+            _ => None,
+        }
+    }
+}
+
 /// Block handle (offset and size of a block)
 #[derive(Debug, Clone, Copy)]
 pub struct BlockHandle {
@@ -114,6 +161,16 @@ impl BlockHandle {
 pub struct Footer {
     pub meta_index_handle: BlockHandle,
     pub index_handle: BlockHandle,
+    pub index_type: IndexType,
+    pub partitioned_filter: bool,
+    /// Filters also contain the key prefixes produced by the prefix extractor
+    pub prefix_filtering: bool,
+    /// Filters contain whole user keys (false = prefixes only)
+    pub whole_key_filtering: bool,
+    /// Table format version the file was written with
+    pub format_version: u32,
+    /// Size of the properties block right before the footer (0 = none)
+    pub properties_size: u32,
 }
 
 impl Footer {
@@ -121,6 +178,12 @@ impl Footer {
         Footer {
             meta_index_handle,
             index_handle,
+            index_type: IndexType::BinarySearch,
+            partitioned_filter: false,
+            prefix_filtering: false,
+            whole_key_filtering: true,
+            format_version: LATEST_FORMAT_VERSION,
+            properties_size: 0,
         }
     }
 
@@ -135,8 +198,17 @@ impl Footer {
         let index_encoded = self.index_handle.encode();
         buf[16..32].copy_from_slice(&index_encoded);
 
-        // Padding (24 bytes) - reserved for future use
-        // Already zeroed
+        // Index and filter layout flags (3 bytes)
+        buf[32] = self.index_type as u8;
+        buf[33] = self.partitioned_filter as u8;
+        buf[34] = self.prefix_filtering as u8;
+
+        // Format version and filter contents (2 bytes)
+        buf[35] = self.format_version as u8;
+        buf[36] = !self.whole_key_filtering as u8;
+
+        // Properties block size (3 bytes)
+        buf[37..40].copy_from_slice(&self.properties_size.to_le_bytes()[..3]);
 
         // Magic number (8 bytes)
         buf[40..48].copy_from_slice(&MAGIC_NUMBER.to_le_bytes());
@@ -153,10 +225,22 @@ impl Footer {
 
         let meta_index_handle = BlockHandle::decode(&data[0..16])?;
         let index_handle = BlockHandle::decode(&data[16..32])?;
+        let index_type = IndexType::from_u8(data[32])?;
+        let partitioned_filter = data[33] != 0;
+        let prefix_filtering = data[34] != 0;
+        let format_version = data[35] as u32;
+        let whole_key_filtering = data[36] == 0;
+        let properties_size = u32::from_le_bytes([data[37], data[38], data[39], 0]);
 
         Some(Footer {
             meta_index_handle,
             index_handle,
+            index_type,
+            partitioned_filter,
+            prefix_filtering,
+            whole_key_filtering,
+            format_version,
+            properties_size,
         })
     }
 }
@@ -234,6 +318,40 @@ mod tests {
         assert_eq!(decoded.meta_index_handle.size, 200);
         assert_eq!(decoded.index_handle.offset, 300);
         assert_eq!(decoded.index_handle.size, 400);
+        assert_eq!(decoded.index_type, IndexType::BinarySearch);
+        assert!(!decoded.partitioned_filter);
+        assert!(!decoded.prefix_filtering);
+        assert!(decoded.whole_key_filtering);
+        assert_eq!(decoded.format_version, LATEST_FORMAT_VERSION);
+    }
+
+    #[test]
+    fn test_footer_legacy_padding() {
+        // Footers written before format versions had zeroed padding
+        let mut data = Footer::new(BlockHandle::new(1, 2), BlockHandle::new(3, 4)).encode();
+        data[35] = 0;
+        data[36] = 0;
+        let decoded = Footer::decode(&data).unwrap();
+        assert_eq!(decoded.format_version, 0);
+        assert!(decoded.whole_key_filtering);
+        assert_eq!(decoded.properties_size, 0);
+    }
+
+    #[test]
+    fn test_footer_partitioned_flags() {
+        let mut footer = Footer::new(BlockHandle::new(1, 2), BlockHandle::new(3, 4));
+        footer.index_type = IndexType::TwoLevelIndexSearch;
+        footer.partitioned_filter = true;
+        footer.prefix_filtering = true;
+        footer.whole_key_filtering = false;
+        footer.properties_size = 0x123456;
+
+        let decoded = Footer::decode(&footer.encode()).unwrap();
+        assert_eq!(decoded.index_type, IndexType::TwoLevelIndexSearch);
+        assert!(decoded.partitioned_filter);
+        assert!(decoded.prefix_filtering);
+        assert!(!decoded.whole_key_filtering);
+        assert_eq!(decoded.properties_size, 0x123456);
     }
 
     #[test]
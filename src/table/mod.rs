@@ -1,11 +1,16 @@
 pub mod block;
 pub mod block_builder;
+pub mod data_block_hash_index;
 pub mod format;
 pub mod table_builder;
+pub mod table_options;
+pub mod table_properties;
 pub mod table_reader;
 
 pub use block::Block;
 pub use block_builder::BlockBuilder;
-pub use format::{BlockHandle, CompressionType, DEFAULT_BLOCK_SIZE, Footer};
+pub use format::{BlockHandle, CompressionType, DEFAULT_BLOCK_SIZE, Footer, IndexType};
 pub use table_builder::TableBuilder;
+pub use table_options::{BlockBasedTableOptions, DataBlockIndexType};
+pub use table_properties::{CompactOnDeletionOptions, TableProperties};
 pub use table_reader::TableReader;
@@ -1,16 +1,29 @@
-use std::{fs::File, io::Write, path::Path, sync::Arc};
+use std::{cmp::Ordering, fs::File, io::Write, path::Path, sync::Arc};
 
 use crate::{
     filter::FilterPolicy,
-    memtable::memtable::InternalKey,
+    memtable::memtable::{InternalKey, InternalKeyComparator},
     table::{
         block_builder::BlockBuilder,
-        format::{BlockHandle, CompressionType, DEFAULT_BLOCK_SIZE, Footer},
+        format::{BlockHandle, CompressionType, Footer, IndexType},
+        table_options::{BlockBasedTableOptions, DataBlockIndexType},
+        table_properties::{CompactOnDeletionOptions, DeletionWindow, TableProperties},
     },
-    util::{Result, Slice, Status},
+    util::{Comparator, Result, Slice, SliceTransform, Status, now_secs},
 };
 
 /// Table builder for creating SSTable files
+///
+/// With `IndexType::TwoLevelIndexSearch`, the index is cut into partitions of
+/// roughly `metadata_block_size` bytes. Finished partitions are buffered and
+/// written after the data blocks, followed by a top-level index pointing to
+/// them. With `partition_filters`, a filter partition is cut at the same
+/// boundaries so each index partition has a matching filter partition.
+///
+/// With a prefix extractor, the filter also contains the prefix of every
+/// key (once per run of keys sharing it), so readers can answer prefix
+/// queries from the same filter. Without `whole_key_filtering` it contains
+/// only the prefixes.
 pub struct TableBuilder {
     file: File,
     offset: u64,
@@ -18,12 +31,26 @@ pub struct TableBuilder {
     index_block: BlockBuilder,
     last_key: Vec<u8>,
     num_entries: u64,
-    block_size: usize,
     pending_index_entry: bool,
     pending_handle: BlockHandle,
     filter_policy: Option<Arc<dyn FilterPolicy>>,
     filter_keys: Vec<Vec<u8>>, // Keys to build filter from
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    last_prefix: Option<Vec<u8>>, // Last prefix added to filter_keys
+    level: Option<usize>,         // LSM level the table is written to
     compression_type: CompressionType,
+    options: BlockBasedTableOptions,
+    /// Finished index partitions: (last key, partition block data)
+    index_partitions: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Finished filter partitions: (last key, filter data)
+    filter_partitions: Vec<(Vec<u8>, Vec<u8>)>,
+    comparator: InternalKeyComparator,
+    /// Creation time recorded in the table properties
+    creation_time: u64,
+    file_creation_time: u64,
+    num_deletions: u64,
+    /// Deletion markers among the last entries (compact on deletion)
+    deletion_window: Option<DeletionWindow>,
 }
 
 impl TableBuilder {
@@ -37,29 +64,219 @@ impl TableBuilder {
         path: P,
         filter_policy: Option<Arc<dyn FilterPolicy>>,
     ) -> Result<Self> {
+        Self::new_with_options(path, filter_policy, BlockBasedTableOptions::default())
+    }
+
+    /// Create a new table builder with filter policy and table options
+    ///
+    /// `filter_policy` takes precedence over `options.filter_policy`.
+    pub fn new_with_options<P: AsRef<Path>>(
+        path: P,
+        filter_policy: Option<Arc<dyn FilterPolicy>>,
+        options: BlockBasedTableOptions,
+    ) -> Result<Self> {
+        options.validate()?;
         let file = File::create(path)
             .map_err(|e| Status::io_error(format!("Failed to create table file: {e}")))?;
 
+        let data_block = match options.data_block_index_type {
+            DataBlockIndexType::BinarySearch => BlockBuilder::new(options.block_restart_interval),
+            DataBlockIndexType::BinaryAndHash => BlockBuilder::with_hash_index(
+                options.block_restart_interval,
+                options.data_block_hash_table_util_ratio,
+            ),
+        };
+        let filter_policy = filter_policy.or_else(|| options.filter_policy.clone());
+
         Ok(TableBuilder {
             file,
             offset: 0,
-            data_block: BlockBuilder::default(),
-            index_block: BlockBuilder::default(),
+            data_block,
+            index_block: BlockBuilder::new(options.index_block_restart_interval),
             last_key: Vec::new(),
             num_entries: 0,
-            block_size: DEFAULT_BLOCK_SIZE,
             pending_index_entry: false,
             pending_handle: BlockHandle::new(0, 0),
             filter_policy,
             filter_keys: Vec::new(),
-            compression_type: CompressionType::None, // Default, will be set in finish()
+            prefix_extractor: None,
+            last_prefix: None,
+            level: None,
+            compression_type: CompressionType::None,
+            options,
+            index_partitions: Vec::new(),
+            filter_partitions: Vec::new(),
+            comparator: InternalKeyComparator::default(),
+            creation_time: now_secs(),
+            file_creation_time: now_secs(),
+            num_deletions: 0,
+            deletion_window: None,
         })
     }
 
+    /// Mark the table for compaction when a window of consecutive entries
+    /// holds too many deletion markers
+    ///
+    /// Must be called before the first key is added.
+    pub fn set_compact_on_deletion(&mut self, options: Option<CompactOnDeletionOptions>) {
+        self.deletion_window = options.map(DeletionWindow::new);
+    }
+
+    /// Record this creation time instead of the current time
+    ///
+    /// Compactions pass the oldest creation time of their inputs, so the
+    /// output ages with the data it holds.
+    pub fn set_creation_time(&mut self, creation_time: u64) {
+        self.creation_time = creation_time;
+    }
+
+    /// Seconds since the epoch the table's oldest data was written at
+    pub fn creation_time(&self) -> u64 {
+        self.creation_time
+    }
+
+    /// Seconds since the epoch the table was written at
+    pub fn file_creation_time(&self) -> u64 {
+        self.file_creation_time
+    }
+
+    /// Add key prefixes from this extractor to the filter
+    ///
+    /// Must be called before the first key is added.
+    pub fn set_prefix_extractor(&mut self, prefix_extractor: Option<Arc<dyn SliceTransform>>) {
+        self.prefix_extractor = prefix_extractor;
+    }
+
+    /// Set the LSM level the table is written to
+    ///
+    /// Passed to `FilterPolicy::create_filter_for_level` so a policy can
+    /// choose its filter format by level.
+    pub fn set_level(&mut self, level: usize) {
+        self.level = Some(level);
+    }
+
+    /// Order user keys with this comparator (bytewise by default)
+    ///
+    /// Keys must be added in the comparator's order. Must be called before
+    /// the first key is added.
+    pub fn set_comparator(&mut self, comparator: Arc<dyn Comparator>) {
+        self.comparator = InternalKeyComparator::new(comparator);
+    }
+
+    /// Compress data blocks with this algorithm
+    ///
+    /// Must be called before the first key is added; `finish` only affects
+    /// the blocks still pending when it is called.
+    pub fn set_compression_type(&mut self, compression: CompressionType) {
+        self.compression_type = compression;
+    }
+
+    /// Build a filter from the pending filter keys
+    fn create_filter(&self, policy: &Arc<dyn FilterPolicy>) -> Vec<u8> {
+        match self.level {
+            Some(level) => policy.create_filter_for_level(&self.filter_keys, level),
+            None => policy.create_filter(&self.filter_keys),
+        }
+    }
+
+    /// Whether the filter holds key prefixes
+    fn is_prefix_filtering(&self) -> bool {
+        self.filter_policy.is_some() && self.prefix_extractor.is_some()
+    }
+
+    /// Whether the filter holds whole user keys (always, unless prefixes
+    /// replace them)
+    fn is_whole_key_filtering(&self) -> bool {
+        self.options.whole_key_filtering || self.prefix_extractor.is_none()
+    }
+
+    /// Add a user key (and its prefix) to the pending filter keys
+    fn add_filter_key(&mut self, user_key: &[u8]) {
+        if self.is_whole_key_filtering() {
+            self.filter_keys.push(user_key.to_vec());
+        }
+
+        if let Some(ref extractor) = self.prefix_extractor
+            && extractor.in_domain(user_key)
+        {
+            let prefix = extractor.transform(user_key);
+            if self.last_prefix.as_deref() != Some(prefix) {
+                self.filter_keys.push(prefix.to_vec());
+                self.last_prefix = Some(prefix.to_vec());
+            }
+        }
+    }
+
+    /// Whether the index is split into partitions
+    fn is_partitioned_index(&self) -> bool {
+        self.options.index_type == IndexType::TwoLevelIndexSearch
+    }
+
+    /// Whether the filter is split into partitions (requires a partitioned
+    /// index)
+    fn is_partitioned_filter(&self) -> bool {
+        self.is_partitioned_index()
+            && self.options.partition_filters
+            && self.filter_policy.is_some()
+    }
+
+    /// Add an entry for the pending data block to the index, cutting a new
+    /// index partition if the current one is large enough
+    fn add_index_entry(&mut self, separator: Vec<u8>) {
+        let handle_encoded = self.pending_handle.encode();
+        self.index_block.add(
+            &Slice::from(separator.clone()),
+            &Slice::from(handle_encoded),
+        );
+        self.pending_index_entry = false;
+
+        if self.is_partitioned_index()
+            && self.index_block.current_size_estimate() >= self.options.metadata_block_size
+        {
+            self.cut_partition(separator);
+        }
+    }
+
+    /// Finish the current index partition (and its filter partition)
+    ///
+    /// `last_key` is the index key of the partition's last data block, used
+    /// as the partition's key in the top-level index.
+    fn cut_partition(&mut self, last_key: Vec<u8>) {
+        if self.index_block.is_empty() {
+            return;
+        }
+
+        let partition = self.index_block.finish();
+        self.index_block.reset();
+        self.index_partitions.push((last_key.clone(), partition));
+
+        if self.is_partitioned_filter()
+            && let Some(ref policy) = self.filter_policy
+        {
+            let filter_data = self.create_filter(policy);
+            self.filter_keys.clear();
+            // A prefix spanning partitions must be present in each of them
+            self.last_prefix = None;
+            self.filter_partitions.push((last_key, filter_data));
+        }
+    }
+
+    /// Write a raw block at the current offset and return its handle
+    fn write_raw_block(&mut self, data: &[u8], what: &str) -> Result<BlockHandle> {
+        let handle = BlockHandle::new(self.offset, data.len() as u64);
+        self.file
+            .write_all(data)
+            .map_err(|e| Status::io_error(format!("Failed to write {what}: {e}")))?;
+        self.offset += data.len() as u64;
+        Ok(handle)
+    }
+
     /// Add a key-value pair to the table
     /// Keys must be added in sorted order
     pub fn add(&mut self, key: &Slice, value: &Slice) -> Result<()> {
-        if !self.last_key.is_empty() && key.data() <= self.last_key.as_slice() {
+        if !self.last_key.is_empty()
+            && self.comparator.compare(key.data(), &self.last_key) != Ordering::Greater
+        {
             return Err(Status::invalid_argument(
                 "Keys must be added in sorted order",
             ));
@@ -68,10 +285,7 @@ impl TableBuilder {
         // If there's a pending index entry, add it now
         if self.pending_index_entry {
             let separator = self.find_shortest_separator(&self.last_key, key.data());
-            let handle_encoded = self.pending_handle.encode();
-            self.index_block
-                .add(&Slice::from(separator), &Slice::from(handle_encoded));
-            self.pending_index_entry = false;
+            self.add_index_entry(separator);
         }
 
         // Collect key for filter if filter policy is set
@@ -80,8 +294,7 @@ impl TableBuilder {
             match InternalKey::decode(key) {
                 Ok(internal_key) => {
                     // Use user key for bloom filter (not InternalKey)
-                    self.filter_keys
-                        .push(internal_key.user_key().data().to_vec());
+                    self.add_filter_key(internal_key.user_key().data());
                 },
                 Err(_) => {
                     // If decode fails, skip filter entry (shouldn't happen in
@@ -90,13 +303,19 @@ impl TableBuilder {
             }
         }
 
+        let is_deletion = InternalKey::decode(key).is_ok_and(|key| key.is_deletion());
+        self.num_deletions += u64::from(is_deletion);
+        if let Some(ref mut window) = self.deletion_window {
+            window.add(is_deletion);
+        }
+
         self.last_key.clear();
         self.last_key.extend_from_slice(key.data());
         self.num_entries += 1;
         self.data_block.add(key, value);
 
         // Flush block if it's large enough
-        if self.data_block.current_size_estimate() >= self.block_size {
+        if self.data_block.current_size_estimate() >= self.options.block_size {
             self.flush_data_block()?;
         }
 
@@ -140,39 +359,66 @@ impl TableBuilder {
         // Add final index entry if needed
         if self.pending_index_entry {
             let separator = self.find_short_successor(&self.last_key);
-            let handle_encoded = self.pending_handle.encode();
-            self.index_block
-                .add(&Slice::from(separator), &Slice::from(handle_encoded));
-            self.pending_index_entry = false;
+            self.add_index_entry(separator);
         }
 
-        // Write filter block if filter policy is set
-        let filter_block_handle = if let Some(ref policy) = self.filter_policy {
-            let filter_data = policy.create_filter(&self.filter_keys);
-            let handle = BlockHandle::new(self.offset, filter_data.len() as u64);
-            self.file
-                .write_all(&filter_data)
-                .map_err(|e| Status::io_error(format!("Failed to write filter block: {e}")))?;
-            self.offset += filter_data.len() as u64;
-            handle
+        if self.is_partitioned_index() {
+            let last_key = self.find_short_successor(&self.last_key);
+            self.cut_partition(last_key);
+        }
+
+        // Write filter block(s) if filter policy is set
+        let meta_index_handle = if self.is_partitioned_filter() {
+            // Filter partitions followed by the filter index block
+            let partitions = std::mem::take(&mut self.filter_partitions);
+            let mut filter_index = BlockBuilder::new(self.options.index_block_restart_interval);
+            for (last_key, filter_data) in partitions {
+                let handle = self.write_raw_block(&filter_data, "filter partition")?;
+                filter_index.add(&Slice::from(last_key), &Slice::from(handle.encode()));
+            }
+            let filter_index_data = filter_index.finish();
+            self.write_raw_block(&filter_index_data, "filter index block")?
+        } else if let Some(ref policy) = self.filter_policy {
+            let filter_data = self.create_filter(policy);
+            self.write_raw_block(&filter_data, "filter block")?
         } else {
             // No filter, use empty handle
             BlockHandle::new(0, 0)
         };
 
-        // Use filter_block_handle as meta_index_handle in footer
-        let meta_index_handle = filter_block_handle;
+        // Write index block(s)
+        let index_handle = if self.is_partitioned_index() {
+            // Index partitions followed by the top-level index
+            let partitions = std::mem::take(&mut self.index_partitions);
+            let mut top_level_index = BlockBuilder::new(self.options.index_block_restart_interval);
+            for (last_key, partition) in partitions {
+                let handle = self.write_raw_block(&partition, "index partition")?;
+                top_level_index.add(&Slice::from(last_key), &Slice::from(handle.encode()));
+            }
+            let top_level_data = top_level_index.finish();
+            self.write_raw_block(&top_level_data, "top-level index block")?
+        } else {
+            let index_block_data = self.index_block.finish();
+            self.write_raw_block(&index_block_data, "index block")?
+        };
 
-        // Write index block
-        let index_block_data = self.index_block.finish();
-        let index_handle = BlockHandle::new(self.offset, index_block_data.len() as u64);
-        self.file
-            .write_all(&index_block_data)
-            .map_err(|e| Status::io_error(format!("Failed to write index block: {e}")))?;
-        self.offset += index_block_data.len() as u64;
+        // Write properties block
+        let properties = TableProperties {
+            num_entries: self.num_entries,
+            num_deletions: self.num_deletions,
+            creation_time: self.creation_time,
+            file_creation_time: self.file_creation_time,
+        };
+        let properties_handle = self.write_raw_block(&properties.encode(), "properties block")?;
 
         // Write footer
-        let footer = Footer::new(meta_index_handle, index_handle);
+        let mut footer = Footer::new(meta_index_handle, index_handle);
+        footer.properties_size = properties_handle.size as u32;
+        footer.index_type = self.options.index_type;
+        footer.partitioned_filter = self.is_partitioned_filter();
+        footer.prefix_filtering = self.is_prefix_filtering();
+        footer.whole_key_filtering = self.is_whole_key_filtering();
+        footer.format_version = self.options.format_version;
         let footer_data = footer.encode();
         self.file
             .write_all(&footer_data)
@@ -205,6 +451,19 @@ impl TableBuilder {
         self.num_entries
     }
 
+    /// Get number of deletion markers added
+    pub fn num_deletions(&self) -> u64 {
+        self.num_deletions
+    }
+
+    /// Whether the table should be compacted soon (see
+    /// `set_compact_on_deletion`)
+    pub fn need_compaction(&self) -> bool {
+        self.deletion_window
+            .as_ref()
+            .is_some_and(|window| window.triggered())
+    }
+
     /// Get current file size
     pub fn file_size(&self) -> u64 {
         self.offset
@@ -284,4 +543,52 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_table_builder_block_size_and_restart_interval() {
+        use crate::table::TableReader;
+
+        let count_blocks = |block_size: usize, block_restart_interval: usize| {
+            let temp_file = NamedTempFile::new().unwrap();
+            let options = BlockBasedTableOptions {
+                block_size,
+                block_restart_interval,
+                ..Default::default()
+            };
+            let mut builder =
+                TableBuilder::new_with_options(temp_file.path(), None, options).unwrap();
+            for i in 0..500 {
+                let key = InternalKey::new(
+                    Slice::from(format!("key{i:04}")),
+                    i + 1,
+                    crate::memtable::memtable::VALUE_TYPE_VALUE,
+                )
+                .encode();
+                builder.add(&key, &Slice::from("value")).unwrap();
+            }
+            builder.finish(CompressionType::None).unwrap();
+
+            let mut reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+            let handles = reader.data_block_handles().unwrap();
+            let block =
+                crate::table::Block::new(reader.read_block_for_iter(&handles[0]).unwrap()).unwrap();
+            (handles.len(), block.num_restarts())
+        };
+
+        let (small_blocks, _) = count_blocks(256, 16);
+        let (large_blocks, restarts_16) = count_blocks(16 * 1024, 16);
+        let (_, restarts_4) = count_blocks(16 * 1024, 4);
+        assert!(small_blocks > large_blocks);
+        assert!(restarts_4 > restarts_16);
+    }
+
+    #[test]
+    fn test_table_builder_rejects_invalid_options() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            block_restart_interval: 0,
+            ..Default::default()
+        };
+        assert!(TableBuilder::new_with_options(temp_file.path(), None, options).is_err());
+    }
 }
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filter::FilterPolicy,
+    table::{
+        data_block_hash_index::DEFAULT_UTIL_RATIO,
+        format::{DEFAULT_BLOCK_SIZE, DEFAULT_RESTART_INTERVAL, IndexType, LATEST_FORMAT_VERSION},
+    },
+    util::{Result, Status},
+};
+
+/// Default target size for index and filter partitions (4KB)
+pub const DEFAULT_METADATA_BLOCK_SIZE: usize = 4 * 1024;
+
+/// Search structure of data blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DataBlockIndexType {
+    /// Restart points only
+    #[default]
+    BinarySearch,
+    /// Restart points plus a hash index over user keys, letting point
+    /// lookups jump to the restart interval holding the key
+    BinaryAndHash,
+}
+
+/// Options controlling the layout of block-based SSTables
+///
+/// Layout options only affect how new tables are written. Readers detect
+/// the layout of each file from its footer, so tables written with
+/// different options can coexist in the same database. `verify_checksums`
+/// and `filter_policy` also apply when tables are read.
+///
+/// # Example
+///
+/// ```ignore
+/// use rucksdb::{BlockBasedTableOptions, IndexType};
+///
+/// // Two-level index and partitioned filters for large bottom-level files
+/// let table_options = BlockBasedTableOptions {
+///     block_size: 16 * 1024,
+///     index_type: IndexType::TwoLevelIndexSearch,
+///     partition_filters: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockBasedTableOptions {
+    /// Target uncompressed size of a data block
+    /// Default: 4KB
+    pub block_size: usize,
+
+    /// Number of keys between restart points in data blocks
+    /// Default: 16
+    pub block_restart_interval: usize,
+
+    /// Number of keys between restart points in index blocks
+    /// Default: 16
+    pub index_block_restart_interval: usize,
+
+    /// Filter policy for new tables, also used to query filters on read
+    /// Overrides the column family's `filter_policy` and
+    /// `filter_bits_per_key`
+    /// Default: None
+    #[serde(skip)]
+    pub filter_policy: Option<Arc<dyn FilterPolicy>>,
+
+    /// Add whole user keys to filters
+    /// With a prefix extractor, setting this to false builds filters from
+    /// key prefixes only: smaller, but point lookups can no longer use them
+    /// Default: true
+    pub whole_key_filtering: bool,
+
+    /// Verify block checksums when reading tables
+    /// Default: true
+    pub verify_checksums: bool,
+
+    /// Table format version to write (0 ..= `LATEST_FORMAT_VERSION`)
+    /// Recorded in the footer; readers reject tables newer than they
+    /// support. Version 0 also selects the legacy Bloom filter layout when
+    /// the filter comes from `filter_bits_per_key`. Other table features are
+    /// written the same at every version.
+    /// Default: `LATEST_FORMAT_VERSION`
+    pub format_version: u32,
+
+    /// Index layout
+    /// Default: BinarySearch (one monolithic index block)
+    pub index_type: IndexType,
+
+    /// Split the filter into partitions aligned with the index partitions
+    /// Only takes effect with `IndexType::TwoLevelIndexSearch`
+    /// Default: false
+    pub partition_filters: bool,
+
+    /// Target size of an index partition before a new one is started
+    /// Default: 4KB
+    pub metadata_block_size: usize,
+
+    /// Search structure written into data blocks
+    /// Blocks without a hash index are searched as before, so this can be
+    /// changed freely on an existing database
+    /// Default: BinarySearch
+    pub data_block_index_type: DataBlockIndexType,
+
+    /// Target ratio of keys to buckets in the data block hash index
+    /// Lower values mean fewer collisions but more space
+    /// Default: 0.75
+    pub data_block_hash_table_util_ratio: f64,
+}
+
+impl Default for BlockBasedTableOptions {
+    fn default() -> Self {
+        BlockBasedTableOptions {
+            block_size: DEFAULT_BLOCK_SIZE,
+            block_restart_interval: DEFAULT_RESTART_INTERVAL,
+            index_block_restart_interval: DEFAULT_RESTART_INTERVAL,
+            filter_policy: None,
+            whole_key_filtering: true,
+            verify_checksums: true,
+            format_version: LATEST_FORMAT_VERSION,
+            index_type: IndexType::BinarySearch,
+            partition_filters: false,
+            metadata_block_size: DEFAULT_METADATA_BLOCK_SIZE,
+            data_block_index_type: DataBlockIndexType::BinarySearch,
+            data_block_hash_table_util_ratio: DEFAULT_UTIL_RATIO,
+        }
+    }
+}
+
+impl BlockBasedTableOptions {
+    /// Check the options for values no table can be written with
+    pub fn validate(&self) -> Result<()> {
+        if self.block_size == 0 {
+            return Err(Status::invalid_argument("block_size must be positive"));
+        }
+        if self.block_restart_interval == 0 || self.index_block_restart_interval == 0 {
+            return Err(Status::invalid_argument(
+                "Restart intervals must be positive",
+            ));
+        }
+        if self.format_version > LATEST_FORMAT_VERSION {
+            return Err(Status::invalid_argument(format!(
+                "Unsupported format_version {} (latest is {LATEST_FORMAT_VERSION})",
+                self.format_version
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_options_validate() {
+        assert!(BlockBasedTableOptions::default().validate().is_ok());
+
+        let options = BlockBasedTableOptions {
+            block_size: 0,
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+
+        let options = BlockBasedTableOptions {
+            index_block_restart_interval: 0,
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+
+        let options = BlockBasedTableOptions {
+            format_version: LATEST_FORMAT_VERSION + 1,
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
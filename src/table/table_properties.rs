@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    table::{
+        block::Block,
+        block_builder::BlockBuilder,
+        format::{decode_varint, encode_varint},
+    },
+    util::{Result, Slice, Status},
+};
+
+const CREATION_TIME: &[u8] = b"rucksdb.creation.time";
+const NUM_DELETIONS: &[u8] = b"rucksdb.deleted.keys";
+const FILE_CREATION_TIME: &[u8] = b"rucksdb.file.creation.time";
+const NUM_ENTRIES: &[u8] = b"rucksdb.num.entries";
+
+/// Properties recorded in the table's properties block
+///
+/// Tables written before the block existed read as the default, with
+/// every property unknown (zero).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableProperties {
+    /// Number of entries in the table
+    pub num_entries: u64,
+    /// Number of deletion markers among the entries
+    pub num_deletions: u64,
+    /// Seconds since the epoch the table's oldest data was written at,
+    /// 0 if unknown
+    ///
+    /// Compaction outputs carry the oldest creation time of their inputs.
+    pub creation_time: u64,
+    /// Seconds since the epoch the table itself was written at, 0 if
+    /// unknown
+    pub file_creation_time: u64,
+}
+
+impl TableProperties {
+    /// Encode as a block of name-value pairs sorted by name
+    pub fn encode(&self) -> Vec<u8> {
+        let mut block = BlockBuilder::new(1);
+        block.add(
+            &Slice::from(CREATION_TIME),
+            &Slice::from(encode_varint(self.creation_time)),
+        );
+        block.add(
+            &Slice::from(NUM_DELETIONS),
+            &Slice::from(encode_varint(self.num_deletions)),
+        );
+        block.add(
+            &Slice::from(FILE_CREATION_TIME),
+            &Slice::from(encode_varint(self.file_creation_time)),
+        );
+        block.add(
+            &Slice::from(NUM_ENTRIES),
+            &Slice::from(encode_varint(self.num_entries)),
+        );
+        block.finish()
+    }
+
+    /// Decode a properties block, skipping names this build doesn't know
+    pub fn decode(data: Vec<u8>, verify_checksums: bool) -> Result<Self> {
+        let block = Block::with_verification(data, verify_checksums)?;
+        let mut properties = TableProperties::default();
+        let mut iter = block.iter();
+        let mut valid = iter.seek_to_first()?;
+        while valid {
+            let value = decode_varint(iter.value().data()).map(|(value, _)| value);
+            match (iter.key().data(), value) {
+                (CREATION_TIME, Some(value)) => properties.creation_time = value,
+                (NUM_DELETIONS, Some(value)) => properties.num_deletions = value,
+                (FILE_CREATION_TIME, Some(value)) => properties.file_creation_time = value,
+                (NUM_ENTRIES, Some(value)) => properties.num_entries = value,
+                _ => {},
+            }
+            valid = iter.next()?;
+        }
+        Ok(properties)
+    }
+}
+
+/// Marks a table for compaction when any `sliding_window_size`
+/// consecutive entries of it hold at least `deletion_trigger` deletion
+/// markers
+///
+/// Compacting such tables early keeps scans from stepping over long runs
+/// of deleted keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactOnDeletionOptions {
+    /// Number of consecutive entries looked at
+    pub sliding_window_size: usize,
+    /// Deletion markers in the window that mark the table
+    pub deletion_trigger: usize,
+}
+
+impl CompactOnDeletionOptions {
+    /// Check the options for values no window can satisfy
+    pub fn validate(&self) -> Result<()> {
+        if self.deletion_trigger == 0 || self.deletion_trigger > self.sliding_window_size {
+            return Err(Status::invalid_argument(
+                "deletion_trigger must be between 1 and sliding_window_size",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Deletion markers among the last entries added to a table
+pub(crate) struct DeletionWindow {
+    options: CompactOnDeletionOptions,
+    window: VecDeque<bool>,
+    deletions: usize,
+    triggered: bool,
+}
+
+impl DeletionWindow {
+    pub(crate) fn new(options: CompactOnDeletionOptions) -> Self {
+        DeletionWindow {
+            window: VecDeque::with_capacity(options.sliding_window_size),
+            options,
+            deletions: 0,
+            triggered: false,
+        }
+    }
+
+    /// Record the next entry of the table
+    pub(crate) fn add(&mut self, is_deletion: bool) {
+        if self.triggered {
+            return;
+        }
+        if self.window.len() == self.options.sliding_window_size
+            && self.window.pop_front() == Some(true)
+        {
+            self.deletions -= 1;
+        }
+        self.window.push_back(is_deletion);
+        self.deletions += usize::from(is_deletion);
+        self.triggered = self.deletions >= self.options.deletion_trigger;
+    }
+
+    /// Whether some window reached the trigger
+    pub(crate) fn triggered(&self) -> bool {
+        self.triggered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_properties_encode_decode() {
+        let properties = TableProperties {
+            num_entries: 1000,
+            num_deletions: 10,
+            creation_time: 1_700_000_000,
+            file_creation_time: 1_700_000_500,
+        };
+        let decoded = TableProperties::decode(properties.encode(), true).unwrap();
+        assert_eq!(decoded, properties);
+    }
+
+    #[test]
+    fn test_properties_unknown_names() {
+        let mut block = BlockBuilder::new(1);
+        block.add(&Slice::from("rucksdb.a"), &Slice::from(encode_varint(7)));
+        block.add(&Slice::from(NUM_ENTRIES), &Slice::from(encode_varint(42)));
+        let decoded = TableProperties::decode(block.finish(), true).unwrap();
+        assert_eq!(decoded.num_entries, 42);
+        assert_eq!(decoded.creation_time, 0);
+    }
+
+    #[test]
+    fn test_deletion_window() {
+        let options = CompactOnDeletionOptions {
+            sliding_window_size: 4,
+            deletion_trigger: 3,
+        };
+        // 2 deletions in every 4 entries never trigger
+        let mut window = DeletionWindow::new(options.clone());
+        for index in 0..100 {
+            window.add(index % 2 == 0);
+        }
+        assert!(!window.triggered());
+
+        // 3 of the last 4 do
+        window.add(true);
+        window.add(true);
+        assert!(window.triggered());
+
+        assert!(options.validate().is_ok());
+        let options = CompactOnDeletionOptions {
+            sliding_window_size: 2,
+            deletion_trigger: 3,
+        };
+        assert!(options.validate().is_err());
+    }
+}
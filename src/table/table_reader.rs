@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     fs::File,
     io::{Read, Seek, SeekFrom},
     path::Path,
@@ -8,24 +9,43 @@ use std::{
 use crate::{
     cache::LRUCache,
     filter::FilterPolicy,
-    memtable::memtable::InternalKey,
+    memtable::memtable::{InternalKey, LookupResult, VALUE_TYPE_DELETION, VALUE_TYPE_ENTITY},
     table::{
         block::Block,
-        format::{BlockHandle, FOOTER_SIZE, Footer},
+        data_block_hash_index::HashIndexLookup,
+        format::{BlockHandle, FOOTER_SIZE, Footer, IndexType, LATEST_FORMAT_VERSION},
+        table_options::BlockBasedTableOptions,
+        table_properties::TableProperties,
     },
-    util::{Result, Slice, Status},
+    util::{Comparator, Result, Slice, Status, comparator::bytewise_comparator},
 };
 
 /// Table reader for reading SSTable files
+///
+/// The index and filter layout is taken from the footer. For partitioned
+/// tables only the top-level index (and filter index) is held in memory;
+/// index and filter partitions are loaded through the block cache when a
+/// lookup needs them.
+///
+/// Tables with a newer format version than this build supports are
+/// rejected at open.
+///
+/// Lookups assume the table is sorted by the reader's comparator
+/// (bytewise unless `set_comparator` is called).
 pub struct TableReader {
     file: File,
     file_number: u64,
     file_size: u64,
+    /// Index block, or the top-level index for partitioned tables
     index_block: Block,
-    _footer: Footer,
+    footer: Footer,
     block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
     filter_policy: Option<Arc<dyn FilterPolicy>>,
     filter_data: Option<Vec<u8>>, // Filter block data
+    filter_index: Option<Block>,  // Filter index block (partitioned filters)
+    verify_checksums: bool,
+    comparator: Arc<dyn Comparator>,
+    properties: TableProperties,
 }
 
 impl TableReader {
@@ -45,6 +65,25 @@ impl TableReader {
         block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
         filter_policy: Option<Arc<dyn FilterPolicy>>,
     ) -> Result<Self> {
+        let options = BlockBasedTableOptions {
+            filter_policy,
+            ..Default::default()
+        };
+        Self::open_with_options(path, file_number, block_cache, &options)
+    }
+
+    /// Open an SSTable file for reading with table options
+    ///
+    /// Uses `options.filter_policy` to query filters and
+    /// `options.verify_checksums` for every block read.
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        file_number: u64,
+        block_cache: Option<LRUCache<(u64, u64), Vec<u8>>>,
+        options: &BlockBasedTableOptions,
+    ) -> Result<Self> {
+        let filter_policy = options.filter_policy.clone();
+        let verify_checksums = options.verify_checksums;
         let mut file = File::open(path)
             .map_err(|e| Status::io_error(format!("Failed to open table file: {e}")))?;
 
@@ -67,32 +106,87 @@ impl TableReader {
 
         let footer =
             Footer::decode(&footer_data).ok_or_else(|| Status::corruption("Invalid footer"))?;
+        if footer.format_version > LATEST_FORMAT_VERSION {
+            return Err(Status::not_supported(format!(
+                "Table format version {} is newer than supported version {LATEST_FORMAT_VERSION}",
+                footer.format_version
+            )));
+        }
 
-        // Read filter block if present
-        let filter_data = if filter_policy.is_some() && footer.meta_index_handle.size > 0 {
+        // Read filter block (or filter index for partitioned filters) if present
+        let mut filter_data = None;
+        let mut filter_index = None;
+        if filter_policy.is_some() && footer.meta_index_handle.size > 0 {
             let filter_block_data =
                 Self::read_block_uncached(&mut file, &footer.meta_index_handle)?;
-            Some(filter_block_data)
-        } else {
-            None
-        };
+            if footer.partitioned_filter {
+                filter_index = Some(Block::with_verification(
+                    filter_block_data,
+                    verify_checksums,
+                )?);
+            } else {
+                filter_data = Some(filter_block_data);
+            }
+        }
 
         // Read index block (not cached, as it's small and accessed once)
         let index_block_data = Self::read_block_uncached(&mut file, &footer.index_handle)?;
-        let index_block = Block::new(index_block_data)?;
+        let index_block = Block::with_verification(index_block_data, verify_checksums)?;
+
+        // Read properties block if present
+        let properties = if footer.properties_size > 0 {
+            let size = u64::from(footer.properties_size);
+            let offset = (file_size - FOOTER_SIZE as u64)
+                .checked_sub(size)
+                .ok_or_else(|| Status::corruption("Properties block beyond file start"))?;
+            let data = Self::read_block_uncached(&mut file, &BlockHandle::new(offset, size))?;
+            TableProperties::decode(data, verify_checksums)?
+        } else {
+            TableProperties::default()
+        };
 
         Ok(TableReader {
             file,
             file_number,
             file_size,
             index_block,
-            _footer: footer,
+            footer,
             block_cache,
             filter_policy,
             filter_data,
+            filter_index,
+            verify_checksums,
+            comparator: bytewise_comparator(),
+            properties,
         })
     }
 
+    /// Properties recorded when the table was written
+    pub fn properties(&self) -> &TableProperties {
+        &self.properties
+    }
+
+    /// Whether block checksums are verified on read
+    pub fn verify_checksums(&self) -> bool {
+        self.verify_checksums
+    }
+
+    /// Set the comparator the table's user keys are sorted by
+    pub fn set_comparator(&mut self, comparator: Arc<dyn Comparator>) {
+        self.comparator = comparator;
+    }
+
+    /// Get the comparator the table's user keys are sorted by
+    pub fn comparator(&self) -> &Arc<dyn Comparator> {
+        &self.comparator
+    }
+
+    /// Read and parse a block (with caching support)
+    fn load_block(&mut self, handle: &BlockHandle) -> Result<Block> {
+        let data = self.read_block(handle)?;
+        Block::with_verification(data, self.verify_checksums)
+    }
+
     /// Read a block from file without caching (for index blocks)
     fn read_block_uncached(file: &mut File, handle: &BlockHandle) -> Result<Vec<u8>> {
         file.seek(SeekFrom::Start(handle.offset))
@@ -139,7 +233,10 @@ impl TableReader {
     /// 2. **Index block scan**: Linear search through index entries
     ///    - Could use binary search with restart points for large tables
     ///    - Current implementation prioritizes simplicity for education
-    /// 3. **Data block search**: Linear scan within the data block
+    ///    - Partitioned tables search the top-level index first, then the index
+    ///      partition it points to
+    /// 3. **Data block search**: Linear scan within the data block, starting at
+    ///    the restart interval given by the block's hash index if it has one
     ///
     /// # Performance Notes
     /// - Linear scan is acceptable for small/medium index blocks (<1000
@@ -148,18 +245,279 @@ impl TableReader {
     ///   better
     /// - Block cache reduces repeated block reads
     pub fn get(&mut self, key: &Slice) -> Result<(bool, Option<Slice>)> {
+        Ok(self.get_entry(key, None)?.into_pair())
+    }
+
+    /// Get the newest version of a key written at or before `timestamp`
+    ///
+    /// Returns (found, value) like `get`. Skipped newer versions may push
+    /// the visible one into a following data block, which is searched too.
+    pub fn get_with_timestamp(
+        &mut self,
+        key: &Slice,
+        timestamp: u64,
+    ) -> Result<(bool, Option<Slice>)> {
+        Ok(self.get_entry(key, Some(timestamp))?.into_pair())
+    }
+
+    /// Look up the newest entry of a key, as of `timestamp` if given
+    ///
+    /// Unlike `get`, tells wide-column entities apart from plain values.
+    pub fn get_entry(&mut self, key: &Slice, timestamp: Option<u64>) -> Result<LookupResult> {
         // Check filter first to avoid unnecessary disk I/O
-        if let (Some(policy), Some(filter_data)) = (&self.filter_policy, &self.filter_data)
-            && !policy.may_contain(filter_data, key.data())
-        {
+        if !self.filter_may_contain(key)? {
             // Filter says key definitely doesn't exist
-            return Ok((false, None));
+            return Ok(LookupResult::NotFound);
         }
         // Filter says key might exist, continue with search
 
-        // Search index block for the data block containing the key
-        let mut iter = self.index_block.iter();
-        iter.seek_to_first()?;
+        // Search index for the data block containing the key
+        let handle = match self.footer.index_type {
+            IndexType::BinarySearch => {
+                Self::find_block_handle(&self.index_block, key, self.comparator.as_ref())?
+            },
+            IndexType::TwoLevelIndexSearch => {
+                match Self::find_block_handle(&self.index_block, key, self.comparator.as_ref())? {
+                    Some(partition_handle) => {
+                        let partition = self.load_block(&partition_handle)?;
+                        Self::find_block_handle(&partition, key, self.comparator.as_ref())?
+                    },
+                    None => None,
+                }
+            },
+        };
+
+        let Some(handle) = handle else {
+            return Ok(LookupResult::NotFound);
+        };
+
+        // Read data block (with caching) and search in it
+        let data_block = self.load_block(&handle)?;
+        if let Some(result) = self.search_data_block(&data_block, key, timestamp)? {
+            return Ok(result);
+        }
+        if timestamp.is_none() {
+            return Ok(LookupResult::NotFound);
+        }
+
+        // The block ended among versions newer than the timestamp. Older
+        // versions follow in the next blocks, but only while the blocks
+        // before them end with the key.
+        let handles = self.key_block_handles(key)?;
+        for handle in handles.iter().skip(1) {
+            let data_block = self.load_block(handle)?;
+            if let Some(result) = self.search_data_block(&data_block, key, timestamp)? {
+                return Ok(result);
+            }
+        }
+        Ok(LookupResult::NotFound)
+    }
+
+    /// Handles of the data blocks that may hold versions of a user key
+    ///
+    /// Starts at the block a lookup of the key searches and follows the
+    /// index while the entries end with the key. Only the index partitions
+    /// of this run are read.
+    fn key_block_handles(&mut self, key: &Slice) -> Result<Vec<BlockHandle>> {
+        let comparator = Arc::clone(&self.comparator);
+        let (handles, _) = Self::key_index_run(&self.index_block, key, comparator.as_ref())?;
+        if self.footer.index_type == IndexType::BinarySearch {
+            return Ok(handles);
+        }
+
+        let mut data_handles = Vec::new();
+        for partition_handle in handles {
+            let partition = self.load_block(&partition_handle)?;
+            let (partition_handles, continues) =
+                Self::key_index_run(&partition, key, comparator.as_ref())?;
+            data_handles.extend(partition_handles);
+            if !continues {
+                break;
+            }
+        }
+        Ok(data_handles)
+    }
+
+    /// Collect index entries from the first one >= the user key through the
+    /// first one past it
+    ///
+    /// Also returns whether the run may continue in the next index block,
+    /// i.e. the last entry ends with the key.
+    fn key_index_run(
+        index: &Block,
+        key: &Slice,
+        comparator: &dyn Comparator,
+    ) -> Result<(Vec<BlockHandle>, bool)> {
+        let mut handles = Vec::new();
+        let mut iter = index.iter();
+        if !iter.seek_to_first()? {
+            return Ok((handles, false));
+        }
+
+        loop {
+            let index_key_data = iter.key();
+            let ordering = match InternalKey::decode(&index_key_data) {
+                Ok(internal_key) => comparator.compare(internal_key.user_key().data(), key.data()),
+                Err(_) => comparator.compare(index_key_data.data(), key.data()),
+            };
+
+            if ordering != Ordering::Less {
+                let handle = BlockHandle::decode(iter.value().data())
+                    .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
+                handles.push(handle);
+                if ordering == Ordering::Greater {
+                    return Ok((handles, false));
+                }
+            }
+
+            if !iter.next()? {
+                break;
+            }
+        }
+
+        let continues = !handles.is_empty();
+        Ok((handles, continues))
+    }
+
+    /// Look up a key, verifying block checksums even if the table options
+    /// skip verification
+    ///
+    /// Serves `ReadOptions::verify_checksums`; with `verify_checksums` false
+    /// this is `get_entry`.
+    pub fn get_entry_with_verification(
+        &mut self,
+        key: &Slice,
+        timestamp: Option<u64>,
+        verify_checksums: bool,
+    ) -> Result<LookupResult> {
+        let table_setting = self.verify_checksums;
+        self.verify_checksums |= verify_checksums;
+        let result = self.get_entry(key, timestamp);
+        self.verify_checksums = table_setting;
+        result
+    }
+
+    /// Check the (possibly partitioned) filter for a user key
+    ///
+    /// Returns true if there is no filter, the filter holds only prefixes,
+    /// or the filter says the key may exist.
+    fn filter_may_contain(&mut self, key: &Slice) -> Result<bool> {
+        if !self.footer.whole_key_filtering {
+            return Ok(true);
+        }
+        let Some(policy) = self.filter_policy.clone() else {
+            return Ok(true);
+        };
+
+        if let Some(ref filter_data) = self.filter_data {
+            return Ok(policy.may_contain(filter_data, key.data()));
+        }
+
+        let partition_handle = match self.filter_index {
+            Some(ref filter_index) => {
+                Self::find_block_handle(filter_index, key, self.comparator.as_ref())?
+            },
+            None => return Ok(true),
+        };
+
+        match partition_handle {
+            Some(handle) => {
+                let filter_partition = self.read_block(&handle)?;
+                Ok(policy.may_contain(&filter_partition, key.data()))
+            },
+            // Key is past the last partition, so it can't be in this table
+            None => Ok(false),
+        }
+    }
+
+    /// Check whether any key with this prefix may exist in the table
+    ///
+    /// Returns true if the table's filter holds no prefixes (written without
+    /// a prefix extractor) or no filter policy is configured.
+    ///
+    /// With partitioned filters, keys sharing the prefix may span several
+    /// partitions. Partitions are checked from the first one that can hold
+    /// the prefix until one ends past the prefix.
+    pub fn prefix_may_match(&mut self, prefix: &[u8]) -> Result<bool> {
+        if !self.footer.prefix_filtering {
+            return Ok(true);
+        }
+        let Some(policy) = self.filter_policy.clone() else {
+            return Ok(true);
+        };
+
+        if let Some(ref filter_data) = self.filter_data {
+            return Ok(policy.may_contain(filter_data, prefix));
+        }
+
+        let partitions = match self.filter_index {
+            Some(ref filter_index) => Self::decode_index_entries(filter_index)?,
+            None => return Ok(true),
+        };
+
+        for (last_user_key, handle) in partitions {
+            // Keys with the prefix are contiguous, so a partition ending
+            // before the prefix without reaching it holds none of them
+            if !last_user_key.starts_with(prefix)
+                && self.comparator.compare(&last_user_key, prefix) == Ordering::Less
+            {
+                continue;
+            }
+
+            let filter_partition = self.read_block(&handle)?;
+            if policy.may_contain(&filter_partition, prefix) {
+                return Ok(true);
+            }
+
+            // Later partitions start after this one's last key, which is
+            // already past the prefix
+            if !last_user_key.starts_with(prefix) {
+                break;
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Decode all (user key, handle) entries of an index block
+    pub fn decode_index_entries(index: &Block) -> Result<Vec<(Vec<u8>, BlockHandle)>> {
+        let mut entries = Vec::new();
+        let mut iter = index.iter();
+        if !iter.seek_to_first()? {
+            return Ok(entries);
+        }
+
+        loop {
+            let index_key_data = iter.key();
+            let user_key = match InternalKey::decode(&index_key_data) {
+                Ok(internal_key) => internal_key.user_key().data().to_vec(),
+                Err(_) => index_key_data.data().to_vec(),
+            };
+            let handle = BlockHandle::decode(iter.value().data())
+                .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
+            entries.push((user_key, handle));
+
+            if !iter.next()? {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Find the first index entry whose key is >= the user key
+    ///
+    /// Index entries map the last InternalKey of a block (or partition) to
+    /// its handle. Index blocks and top-level indexes share this format.
+    fn find_block_handle(
+        index: &Block,
+        key: &Slice,
+        comparator: &dyn Comparator,
+    ) -> Result<Option<BlockHandle>> {
+        let mut iter = index.iter();
+        if !iter.seek_to_first()? {
+            return Ok(None);
+        }
 
         loop {
             let index_key_data = iter.key();
@@ -168,11 +526,11 @@ impl TableReader {
             let should_search = match InternalKey::decode(&index_key_data) {
                 Ok(internal_key) => {
                     // Compare user keys: if index_user_key >= user_key, this block might contain it
-                    internal_key.user_key().data() >= key.data()
+                    comparator.compare(internal_key.user_key().data(), key.data()) != Ordering::Less
                 },
                 Err(_e) => {
                     // If decode fails, fall back to raw comparison (shouldn't happen)
-                    index_key_data.data() >= key.data()
+                    comparator.compare(index_key_data.data(), key.data()) != Ordering::Less
                 },
             };
 
@@ -181,13 +539,7 @@ impl TableReader {
                 let handle_data = iter.value();
                 let handle = BlockHandle::decode(handle_data.data())
                     .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
-
-                // Read data block (with caching)
-                let block_data = self.read_block(&handle)?;
-                let data_block = Block::new(block_data)?;
-
-                // Search in data block
-                return self.search_data_block(&data_block, key);
+                return Ok(Some(handle));
             }
 
             if !iter.next()? {
@@ -195,19 +547,37 @@ impl TableReader {
             }
         }
 
-        Ok((false, None))
+        Ok(None)
     }
 
     /// Search for key in a data block
     /// Keys are stored as InternalKeys (encoded with sequence and type)
     ///
+    /// With a timestamp, versions newer than it are skipped.
+    ///
     /// # Returns
-    /// - `(true, Some(value))` - key found with value
-    /// - `(true, None)` - key found but deleted (deletion marker)
-    /// - `(false, None)` - key not found in this block
-    fn search_data_block(&self, block: &Block, user_key: &Slice) -> Result<(bool, Option<Slice>)> {
+    /// - `Some(result)` - the key's visible entry, or `NotFound` once past the
+    ///   key
+    /// - `None` - the block ended before passing the key
+    ///
+    /// Blocks with a hash index answer absent keys without a scan and start
+    /// the scan at the key's restart interval otherwise. Bucket collisions
+    /// and blocks without an index fall back to a scan from the start.
+    fn search_data_block(
+        &self,
+        block: &Block,
+        user_key: &Slice,
+        timestamp: Option<u64>,
+    ) -> Result<Option<LookupResult>> {
         let mut iter = block.iter();
-        iter.seek_to_first()?;
+        let positioned = match block.hash_lookup(user_key.data()) {
+            HashIndexLookup::NotFound => return Ok(Some(LookupResult::NotFound)),
+            HashIndexLookup::Restart(index) => iter.seek_to_restart_point(index)?,
+            HashIndexLookup::Collision => iter.seek_to_first()?,
+        };
+        if !positioned {
+            return Ok(None);
+        }
 
         loop {
             let internal_key_data = iter.key();
@@ -217,17 +587,24 @@ impl TableReader {
                 Ok(internal_key) => {
                     let current_user_key = internal_key.user_key();
 
-                    if current_user_key == user_key {
-                        // Found matching key - check if it's a deletion marker
-                        if internal_key.is_deletion() {
-                            return Ok((true, None)); // Key is deleted
-                        }
-                        return Ok((true, Some(iter.value()))); // Key exists with value
+                    let newer = timestamp.is_some_and(|read_ts| {
+                        internal_key.timestamp().is_some_and(|ts| ts > read_ts)
+                    });
+                    if current_user_key == user_key && !newer {
+                        return Ok(Some(match internal_key.value_type {
+                            VALUE_TYPE_DELETION => LookupResult::Deleted,
+                            VALUE_TYPE_ENTITY => LookupResult::Entity(iter.value()),
+                            _ => LookupResult::Value(iter.value()),
+                        }));
                     }
 
-                    if current_user_key.data() > user_key.data() {
+                    if self
+                        .comparator
+                        .compare(current_user_key.data(), user_key.data())
+                        == Ordering::Greater
+                    {
                         // Passed the key, not found
-                        return Ok((false, None));
+                        return Ok(Some(LookupResult::NotFound));
                     }
                 },
                 Err(_) => {
@@ -240,7 +617,7 @@ impl TableReader {
             }
         }
 
-        Ok((false, None))
+        Ok(None)
     }
 
     /// Get file size
@@ -248,59 +625,104 @@ impl TableReader {
         self.file_size
     }
 
-    /// Get reference to index block (for iterator)
+    /// Get reference to index block
+    ///
+    /// For partitioned tables this is the top-level index, whose values are
+    /// handles of index partitions. Use `data_block_handles()` to list data
+    /// blocks regardless of layout.
     pub fn index_block(&self) -> &Block {
         &self.index_block
     }
 
+    /// Get the index layout of this table
+    pub fn index_type(&self) -> IndexType {
+        self.footer.index_type
+    }
+
+    /// Decode the (user key, handle) entries of the index block
+    ///
+    /// For partitioned tables the handles point at index partitions, which
+    /// `decode_index_entries()` decodes once read.
+    pub fn index_entries(&self) -> Result<Vec<(Vec<u8>, BlockHandle)>> {
+        Self::decode_index_entries(&self.index_block)
+    }
+
+    /// Collect the handles of all data blocks in key order
+    ///
+    /// For partitioned tables, index partitions are read through the block
+    /// cache.
+    pub fn data_block_handles(&mut self) -> Result<Vec<BlockHandle>> {
+        let top_level = Self::decode_index_handles(&self.index_block)?;
+        if self.footer.index_type == IndexType::BinarySearch {
+            return Ok(top_level);
+        }
+
+        let mut handles = Vec::new();
+        for partition_handle in top_level {
+            let partition = self.load_block(&partition_handle)?;
+            handles.extend(Self::decode_index_handles(&partition)?);
+        }
+        Ok(handles)
+    }
+
+    /// Decode all block handles stored in an index block
+    fn decode_index_handles(index: &Block) -> Result<Vec<BlockHandle>> {
+        let mut handles = Vec::new();
+        let mut index_iter = index.iter();
+        if !index_iter.seek_to_first()? {
+            return Ok(handles);
+        }
+
+        loop {
+            let handle_data = index_iter.value();
+            let handle = BlockHandle::decode(handle_data.data())
+                .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
+            handles.push(handle);
+
+            if !index_iter.next()? {
+                break;
+            }
+        }
+
+        Ok(handles)
+    }
+
     /// Read a data block by handle (for iterator)
     pub fn read_block_for_iter(&mut self, handle: &BlockHandle) -> Result<Vec<u8>> {
         self.read_block(handle)
     }
 
-    /// Scan all entries in the table (for compaction)
+    /// Scan all entries in the table
     pub fn scan_all(&mut self) -> Result<Vec<(Slice, Slice)>> {
         let mut all_entries = Vec::new();
 
         // First, collect all block handles from the index
-        let mut handles = Vec::new();
-        {
-            let mut index_iter = self.index_block.iter();
-            if !index_iter.seek_to_first()? {
-                return Ok(all_entries);
-            }
-
-            loop {
-                let handle_data = index_iter.value();
-                let handle = BlockHandle::decode(handle_data.data())
-                    .ok_or_else(|| Status::corruption("Invalid block handle in index"))?;
-                handles.push(handle);
-
-                if !index_iter.next()? {
-                    break;
-                }
-            }
-        }
+        let handles = self.data_block_handles()?;
 
         // Now read data blocks using the collected handles
         for handle in handles {
-            let block_data = self.read_block(&handle)?;
-            let data_block = Block::new(block_data)?;
-
-            // Read all entries from data block
-            let mut data_iter = data_block.iter();
-            if data_iter.seek_to_first()? {
-                loop {
-                    all_entries.push((data_iter.key(), data_iter.value()));
-                    if !data_iter.next()? {
-                        break;
-                    }
-                }
-            }
+            all_entries.extend(self.block_entries(&handle)?);
         }
 
         Ok(all_entries)
     }
+
+    /// Read all entries of one data block (for compaction)
+    pub fn block_entries(&mut self, handle: &BlockHandle) -> Result<Vec<(Slice, Slice)>> {
+        let data_block = self.load_block(handle)?;
+
+        let mut entries = Vec::new();
+        let mut data_iter = data_block.iter();
+        if data_iter.seek_to_first()? {
+            loop {
+                entries.push((data_iter.key(), data_iter.value()));
+                if !data_iter.next()? {
+                    break;
+                }
+            }
+        }
+        Ok(entries)
+    }
 }
 
 #[cfg(test)]
@@ -336,6 +758,29 @@ mod tests {
         assert!(reader.is_ok());
     }
 
+    #[test]
+    fn test_table_reader_properties() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut builder = TableBuilder::new(temp_file.path()).unwrap();
+        builder.set_creation_time(1234);
+        for (seq, key) in ["a", "b", "c"].into_iter().enumerate() {
+            let internal_key = InternalKey::new(
+                Slice::from(key),
+                seq as u64 + 1,
+                crate::memtable::memtable::VALUE_TYPE_VALUE,
+            )
+            .encode();
+            builder.add(&internal_key, &Slice::from(key)).unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let mut reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+        assert_eq!(reader.properties().num_entries, 3);
+        assert_eq!(reader.properties().creation_time, 1234);
+        let (found, _) = reader.get(&Slice::from("b")).unwrap();
+        assert!(found);
+    }
+
     #[test]
     fn test_table_reader_get_single() {
         let temp_file = build_test_table(&[("key1", "value1")]);
@@ -403,4 +848,350 @@ mod tests {
             assert_eq!(result, Some(Slice::from(value.as_str())));
         }
     }
+
+    fn build_partitioned_table(
+        num_keys: usize,
+        filter_policy: Option<Arc<dyn FilterPolicy>>,
+    ) -> NamedTempFile {
+        use crate::table::{format::IndexType, table_options::BlockBasedTableOptions};
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            index_type: IndexType::TwoLevelIndexSearch,
+            partition_filters: true,
+            // Tiny partitions so that even a small table has several of them
+            metadata_block_size: 128,
+            ..Default::default()
+        };
+        let mut builder =
+            TableBuilder::new_with_options(temp_file.path(), filter_policy, options).unwrap();
+
+        for i in 0..num_keys {
+            let internal_key = InternalKey::new(
+                Slice::from(format!("key{i:05}")),
+                i as u64 + 1,
+                crate::memtable::memtable::VALUE_TYPE_VALUE,
+            )
+            .encode();
+            builder
+                .add(&internal_key, &Slice::from(format!("value{i:05}")))
+                .unwrap();
+        }
+
+        builder.finish(CompressionType::None).unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_table_reader_partitioned_index() {
+        let temp_file = build_partitioned_table(2000, None);
+        let mut reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+        assert_eq!(reader.index_type(), IndexType::TwoLevelIndexSearch);
+
+        // Top-level index points to partitions, not data blocks
+        let data_blocks = reader.data_block_handles().unwrap().len();
+        let mut top_level = reader.index_block().iter();
+        assert!(top_level.seek_to_first().unwrap());
+        let mut num_partitions = 1;
+        while top_level.next().unwrap() {
+            num_partitions += 1;
+        }
+        assert!(num_partitions > 1);
+        assert!(data_blocks > num_partitions);
+
+        for i in (0..2000).step_by(7) {
+            let (found, value) = reader.get(&Slice::from(format!("key{i:05}"))).unwrap();
+            assert!(found);
+            assert_eq!(value, Some(Slice::from(format!("value{i:05}"))));
+        }
+
+        let (found, _) = reader.get(&Slice::from("key99999")).unwrap();
+        assert!(!found);
+        assert_eq!(reader.scan_all().unwrap().len(), 2000);
+    }
+
+    #[test]
+    fn test_table_reader_timestamp_versions_across_blocks() {
+        use crate::{
+            memtable::memtable::VALUE_TYPE_VALUE,
+            table::{format::IndexType, table_options::BlockBasedTableOptions},
+        };
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            index_type: IndexType::TwoLevelIndexSearch,
+            block_size: 256,
+            metadata_block_size: 128,
+            ..Default::default()
+        };
+        let mut builder = TableBuilder::new_with_options(temp_file.path(), None, options).unwrap();
+
+        // Versions of one key span several blocks and partitions, newest
+        // first, followed by many other keys
+        let mut sequence = 1;
+        for ts in (1..=100u64).rev() {
+            let key =
+                InternalKey::with_timestamp(Slice::from("hot"), ts, sequence, VALUE_TYPE_VALUE);
+            builder
+                .add(&key.encode(), &Slice::from(format!("value{ts:03}")))
+                .unwrap();
+            sequence += 1;
+        }
+        for i in 0..2000 {
+            let key = InternalKey::with_timestamp(
+                Slice::from(format!("z{i:04}")),
+                1,
+                sequence,
+                VALUE_TYPE_VALUE,
+            );
+            builder.add(&key.encode(), &Slice::from("v")).unwrap();
+            sequence += 1;
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let block_cache = LRUCache::new(10_000);
+        let mut reader = TableReader::open(temp_file.path(), 1, Some(block_cache.clone())).unwrap();
+        let num_partitions = reader.index_entries().unwrap().len();
+
+        let result = reader.get_entry(&Slice::from("hot"), Some(1)).unwrap();
+        assert_eq!(result, LookupResult::Value(Slice::from("value001")));
+        let result = reader.get_entry(&Slice::from("hot"), Some(0)).unwrap();
+        assert_eq!(result, LookupResult::NotFound);
+
+        // Only the partitions and blocks holding the key were read
+        let entries = block_cache.stats().entries;
+        assert!(
+            entries * 4 < num_partitions,
+            "{entries} blocks cached, {num_partitions} partitions"
+        );
+    }
+
+    #[test]
+    fn test_table_reader_partitioned_filter() {
+        use crate::filter::BloomFilterPolicy;
+
+        let policy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+        let temp_file = build_partitioned_table(2000, Some(policy.clone()));
+        let block_cache = LRUCache::new(1000);
+        let mut reader = TableReader::open_with_filter(
+            temp_file.path(),
+            1,
+            Some(block_cache.clone()),
+            Some(policy),
+        )
+        .unwrap();
+        assert!(reader.filter_data.is_none());
+        assert!(reader.filter_index.is_some());
+
+        for i in 0..2000 {
+            let (found, _) = reader.get(&Slice::from(format!("key{i:05}"))).unwrap();
+            assert!(found, "key{i:05} should be found");
+        }
+
+        // Partitions were loaded through the block cache
+        assert!(block_cache.stats().entries > 0);
+
+        // Absent keys inside the key range are mostly rejected by the filter
+        let mut false_positives = 0;
+        for i in 0..2000 {
+            if reader
+                .filter_may_contain(&Slice::from(format!("key{i:05}x")))
+                .unwrap()
+            {
+                false_positives += 1;
+            }
+        }
+        assert!(false_positives < 100, "false positives: {false_positives}");
+    }
+
+    #[test]
+    fn test_table_reader_prefix_may_match() {
+        use crate::{
+            filter::BloomFilterPolicy,
+            table::{format::IndexType, table_options::BlockBasedTableOptions},
+            util::FixedPrefixTransform,
+        };
+
+        let policy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+        for index_type in [IndexType::BinarySearch, IndexType::TwoLevelIndexSearch] {
+            let temp_file = NamedTempFile::new().unwrap();
+            let options = BlockBasedTableOptions {
+                index_type,
+                partition_filters: true,
+                metadata_block_size: 128,
+                ..Default::default()
+            };
+            let mut builder =
+                TableBuilder::new_with_options(temp_file.path(), Some(policy.clone()), options)
+                    .unwrap();
+            // "key000" .. "key019", a hundred keys per prefix
+            builder.set_prefix_extractor(Some(Arc::new(FixedPrefixTransform::new(6))));
+            for i in 0..2000 {
+                let internal_key = InternalKey::new(
+                    Slice::from(format!("key{i:05}")),
+                    i as u64 + 1,
+                    crate::memtable::memtable::VALUE_TYPE_VALUE,
+                )
+                .encode();
+                builder.add(&internal_key, &Slice::from("value")).unwrap();
+            }
+            builder.finish(CompressionType::None).unwrap();
+
+            let mut reader =
+                TableReader::open_with_filter(temp_file.path(), 1, None, Some(policy.clone()))
+                    .unwrap();
+            for i in 0..20 {
+                let prefix = format!("key{i:03}");
+                assert!(
+                    reader.prefix_may_match(prefix.as_bytes()).unwrap(),
+                    "{index_type:?} {prefix}"
+                );
+            }
+
+            let false_positives = (0..200)
+                .filter(|i| {
+                    reader
+                        .prefix_may_match(format!("kez{i:03}").as_bytes())
+                        .unwrap()
+                })
+                .count();
+            assert!(false_positives < 20, "false positives: {false_positives}");
+        }
+
+        // Tables written without a prefix extractor can't rule prefixes out
+        let temp_file = build_partitioned_table(100, Some(policy.clone()));
+        let mut reader =
+            TableReader::open_with_filter(temp_file.path(), 1, None, Some(policy)).unwrap();
+        assert!(reader.prefix_may_match(b"zzz").unwrap());
+    }
+
+    #[test]
+    fn test_table_reader_data_block_hash_index() {
+        use crate::table::table_options::{BlockBasedTableOptions, DataBlockIndexType};
+
+        for index_type in [
+            DataBlockIndexType::BinarySearch,
+            DataBlockIndexType::BinaryAndHash,
+        ] {
+            let temp_file = NamedTempFile::new().unwrap();
+            let options = BlockBasedTableOptions {
+                data_block_index_type: index_type,
+                ..Default::default()
+            };
+            let mut builder =
+                TableBuilder::new_with_options(temp_file.path(), None, options).unwrap();
+            for i in 0..1000 {
+                let internal_key = InternalKey::new(
+                    Slice::from(format!("key{i:05}")),
+                    i as u64 + 1,
+                    crate::memtable::memtable::VALUE_TYPE_VALUE,
+                )
+                .encode();
+                builder
+                    .add(&internal_key, &Slice::from(format!("value{i:05}")))
+                    .unwrap();
+            }
+            builder.finish(CompressionType::None).unwrap();
+
+            let mut reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+            let handles = reader.data_block_handles().unwrap();
+            let first_block = Block::new(reader.read_block(&handles[0]).unwrap()).unwrap();
+            assert_eq!(
+                first_block.has_hash_index(),
+                index_type == DataBlockIndexType::BinaryAndHash
+            );
+
+            for i in 0..1000 {
+                let (found, value) = reader.get(&Slice::from(format!("key{i:05}"))).unwrap();
+                assert!(found, "{index_type:?} key{i:05}");
+                assert_eq!(value, Some(Slice::from(format!("value{i:05}"))));
+                let (found, _) = reader.get(&Slice::from(format!("key{i:05}x"))).unwrap();
+                assert!(!found);
+            }
+            assert_eq!(reader.scan_all().unwrap().len(), 1000);
+        }
+    }
+
+    #[test]
+    fn test_table_reader_rejects_newer_format_version() {
+        use crate::table::format::LATEST_FORMAT_VERSION;
+
+        let temp_file = build_test_table(&[("key1", "value1")]);
+        let mut data = std::fs::read(temp_file.path()).unwrap();
+        let footer_start = data.len() - FOOTER_SIZE;
+        data[footer_start + 35] = LATEST_FORMAT_VERSION as u8 + 1;
+        std::fs::write(temp_file.path(), &data).unwrap();
+
+        let err = match TableReader::open(temp_file.path(), 1, None) {
+            Ok(_) => panic!("newer format version should be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.message().unwrap().contains("format version"));
+    }
+
+    #[test]
+    fn test_table_reader_checksum_verification_option() {
+        let temp_file = build_test_table(&[("key1", "value1")]);
+
+        // Corrupt the stored checksum of the first data block
+        let mut data = std::fs::read(temp_file.path()).unwrap();
+        let handle = TableReader::open(temp_file.path(), 1, None)
+            .unwrap()
+            .data_block_handles()
+            .unwrap()[0];
+        let checksum_pos = (handle.offset + handle.size - 1) as usize;
+        data[checksum_pos] ^= 0xFF;
+        std::fs::write(temp_file.path(), &data).unwrap();
+
+        let mut reader = TableReader::open(temp_file.path(), 1, None).unwrap();
+        assert!(reader.get(&Slice::from("key1")).is_err());
+
+        let options = BlockBasedTableOptions {
+            verify_checksums: false,
+            ..Default::default()
+        };
+        let mut reader =
+            TableReader::open_with_options(temp_file.path(), 1, None, &options).unwrap();
+        assert!(!reader.verify_checksums());
+        let (found, value) = reader.get(&Slice::from("key1")).unwrap();
+        assert!(found);
+        assert_eq!(value, Some(Slice::from("value1")));
+    }
+
+    #[test]
+    fn test_table_reader_prefix_only_filter() {
+        use crate::{filter::BloomFilterPolicy, util::FixedPrefixTransform};
+
+        let policy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = BlockBasedTableOptions {
+            whole_key_filtering: false,
+            ..Default::default()
+        };
+        let mut builder =
+            TableBuilder::new_with_options(temp_file.path(), Some(policy.clone()), options)
+                .unwrap();
+        builder.set_prefix_extractor(Some(Arc::new(FixedPrefixTransform::new(3))));
+        for i in 0..100 {
+            let internal_key = InternalKey::new(
+                Slice::from(format!("key{i:03}")),
+                i as u64 + 1,
+                crate::memtable::memtable::VALUE_TYPE_VALUE,
+            )
+            .encode();
+            builder.add(&internal_key, &Slice::from("value")).unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+
+        let mut reader =
+            TableReader::open_with_filter(temp_file.path(), 1, None, Some(policy)).unwrap();
+        assert!(!reader.footer.whole_key_filtering);
+        // Whole keys aren't in the filter, so point lookups skip it
+        assert!(reader.filter_may_contain(&Slice::from("nope")).unwrap());
+        for i in 0..100 {
+            assert!(reader.get(&Slice::from(format!("key{i:03}"))).unwrap().0);
+        }
+        assert!(reader.prefix_may_match(b"key").unwrap());
+        assert!(!reader.prefix_may_match(b"zzz").unwrap());
+    }
 }
@@ -0,0 +1,341 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    column_family::{ColumnFamilyHandle, ColumnFamilyOptions},
+    compaction::compaction_filter::{CompactionDecision, CompactionFilter},
+    db::{DB, ReadOptions, WriteOptions},
+    iterator::Iterator,
+    util::{Result, Slice, Status, now_secs},
+};
+
+/// Bytes appended to every value: write time (u64) and TTL override (u32)
+const TTL_SUFFIX_LEN: usize = 12;
+
+/// Database wrapper whose values expire after a TTL
+///
+/// Every value is stored with its write time (seconds since the epoch) and
+/// an optional per-write TTL appended:
+///
+/// ```text
+/// value | write_time (u64 LE) | ttl_secs (u32 LE, 0 = the database TTL)
+/// ```
+///
+/// Expired values are hidden from `get` and iterators right away and
+/// dropped by compaction later. All column families written through the
+/// wrapper must only be written through it, since plain values don't carry
+/// the suffix. The TTL isn't persisted; wrap the database again after
+/// reopening it.
+pub struct DBWithTTL {
+    db: Arc<DB>,
+    ttl: Duration,
+}
+
+impl DBWithTTL {
+    /// Wrap a database, expiring values of all its column families after
+    /// `ttl` (rounded down to whole seconds, at least one)
+    pub fn open(db: Arc<DB>, ttl: Duration) -> Self {
+        for cf_handle in db.column_family_handles() {
+            if let Some(cf) = db.column_families().get_cf(&cf_handle) {
+                cf.set_ttl(ttl);
+            }
+        }
+        DBWithTTL { db, ttl }
+    }
+
+    /// Get reference to underlying database
+    #[inline]
+    pub fn db(&self) -> &Arc<DB> {
+        &self.db
+    }
+
+    /// Default TTL of values
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Create a column family whose values expire after the database TTL
+    pub fn create_column_family(
+        &self,
+        name: &str,
+        options: ColumnFamilyOptions,
+    ) -> Result<ColumnFamilyHandle> {
+        let handle = self.db.create_column_family(name, options)?;
+        if let Some(cf) = self.db.column_families().get_cf(&handle) {
+            cf.set_ttl(self.ttl);
+        }
+        Ok(handle)
+    }
+
+    pub fn put(&self, options: &WriteOptions, key: Slice, value: Slice) -> Result<()> {
+        self.put_cf(options, &self.db.default_cf(), key, value)
+    }
+
+    pub fn put_cf(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        value: Slice,
+    ) -> Result<()> {
+        let value = append_write_time(&value, now_secs(), 0);
+        self.db.put_cf(options, cf_handle, key, value)
+    }
+
+    /// Put a value expiring after `ttl` instead of the database TTL
+    pub fn put_with_ttl(
+        &self,
+        options: &WriteOptions,
+        key: Slice,
+        value: Slice,
+        ttl: Duration,
+    ) -> Result<()> {
+        self.put_cf_with_ttl(options, &self.db.default_cf(), key, value, ttl)
+    }
+
+    /// Put a value into a column family expiring after `ttl` instead of the
+    /// database TTL
+    pub fn put_cf_with_ttl(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+        value: Slice,
+        ttl: Duration,
+    ) -> Result<()> {
+        let ttl_secs = u32::try_from(ttl.as_secs())
+            .map_err(|_| Status::invalid_argument("TTL too long"))?
+            .max(1);
+        let value = append_write_time(&value, now_secs(), ttl_secs);
+        self.db.put_cf(options, cf_handle, key, value)
+    }
+
+    pub fn get(&self, options: &ReadOptions, key: &Slice) -> Result<Option<Slice>> {
+        self.get_cf(options, &self.db.default_cf(), key)
+    }
+
+    /// Get a value unless it has expired
+    pub fn get_cf(
+        &self,
+        options: &ReadOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: &Slice,
+    ) -> Result<Option<Slice>> {
+        let Some(value) = self.db.get_cf(options, cf_handle, key)? else {
+            return Ok(None);
+        };
+        let ttl_secs = self.ttl.as_secs().max(1);
+        if is_expired(value.data(), ttl_secs, now_secs()) {
+            return Ok(None);
+        }
+        Ok(Some(strip_write_time(&value)?))
+    }
+
+    pub fn delete(&self, options: &WriteOptions, key: Slice) -> Result<()> {
+        self.db.delete(options, key)
+    }
+
+    pub fn delete_cf(
+        &self,
+        options: &WriteOptions,
+        cf_handle: &ColumnFamilyHandle,
+        key: Slice,
+    ) -> Result<()> {
+        self.db.delete_cf(options, cf_handle, key)
+    }
+
+    /// Iterate over the unexpired entries of the default column family
+    pub fn iter(&self) -> Result<Box<dyn Iterator>> {
+        self.iter_cf(&self.db.default_cf())
+    }
+
+    /// Iterate over the unexpired entries of a column family
+    pub fn iter_cf(&self, cf_handle: &ColumnFamilyHandle) -> Result<Box<dyn Iterator>> {
+        Ok(Box::new(TtlIterator {
+            inner: self.db.iter_cf(cf_handle)?,
+            ttl_secs: self.ttl.as_secs().max(1),
+            now: now_secs(),
+        }))
+    }
+}
+
+/// Append the write time and TTL override (0 = database TTL) to a value
+fn append_write_time(value: &Slice, write_time: u64, ttl_secs: u32) -> Slice {
+    let mut buf = Vec::with_capacity(value.size() + TTL_SUFFIX_LEN);
+    buf.extend_from_slice(value.data());
+    buf.extend_from_slice(&write_time.to_le_bytes());
+    buf.extend_from_slice(&ttl_secs.to_le_bytes());
+    Slice::from(buf)
+}
+
+/// Remove the write time suffix from a stored value
+fn strip_write_time(value: &Slice) -> Result<Slice> {
+    let len = value
+        .size()
+        .checked_sub(TTL_SUFFIX_LEN)
+        .ok_or_else(|| Status::corruption("Value too short for a TTL suffix"))?;
+    Ok(Slice::from(&value.data()[..len]))
+}
+
+/// Check whether a stored value has outlived its TTL at `now`
+///
+/// Values too short to carry a suffix never expire.
+pub(crate) fn is_expired(value: &[u8], default_ttl_secs: u64, now: u64) -> bool {
+    let Some(suffix) = value
+        .len()
+        .checked_sub(TTL_SUFFIX_LEN)
+        .map(|len| &value[len..])
+    else {
+        return false;
+    };
+    let write_time = u64::from_le_bytes(suffix[..8].try_into().unwrap());
+    let ttl_secs = match u32::from_le_bytes(suffix[8..].try_into().unwrap()) {
+        0 => default_ttl_secs,
+        ttl_secs => u64::from(ttl_secs),
+    };
+    write_time.saturating_add(ttl_secs) <= now
+}
+
+/// Compaction filter removing values written by `DBWithTTL` once expired
+///
+/// Expiry is judged as of the filter's creation, once per compaction.
+pub(crate) struct TtlCompactionFilter {
+    ttl_secs: u64,
+    now: u64,
+}
+
+impl TtlCompactionFilter {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        TtlCompactionFilter {
+            ttl_secs: ttl.as_secs().max(1),
+            now: now_secs(),
+        }
+    }
+}
+
+impl CompactionFilter for TtlCompactionFilter {
+    fn name(&self) -> &str {
+        "TtlCompactionFilter"
+    }
+
+    fn filter(&self, _level: usize, _key: &Slice, existing_value: &Slice) -> CompactionDecision {
+        if is_expired(existing_value.data(), self.ttl_secs, self.now) {
+            CompactionDecision::Remove
+        } else {
+            CompactionDecision::Keep
+        }
+    }
+}
+
+/// Iterator skipping expired entries and stripping write times from values
+///
+/// Expiry is judged as of the iterator's creation.
+struct TtlIterator {
+    inner: Box<dyn Iterator>,
+    ttl_secs: u64,
+    now: u64,
+}
+
+impl TtlIterator {
+    fn current_expired(&self) -> bool {
+        self.inner.valid() && is_expired(self.inner.value().data(), self.ttl_secs, self.now)
+    }
+
+    fn skip_forward(&mut self, mut valid: bool) -> Result<bool> {
+        while valid && self.current_expired() {
+            valid = self.inner.next()?;
+        }
+        Ok(valid)
+    }
+
+    fn skip_backward(&mut self, mut valid: bool) -> Result<bool> {
+        while valid && self.current_expired() {
+            valid = self.inner.prev()?;
+        }
+        Ok(valid)
+    }
+}
+
+impl Iterator for TtlIterator {
+    fn seek_to_first(&mut self) -> Result<bool> {
+        let valid = self.inner.seek_to_first()?;
+        self.skip_forward(valid)
+    }
+
+    fn seek_to_last(&mut self) -> Result<bool> {
+        let valid = self.inner.seek_to_last()?;
+        self.skip_backward(valid)
+    }
+
+    fn seek(&mut self, target: &Slice) -> Result<bool> {
+        let valid = self.inner.seek(target)?;
+        self.skip_forward(valid)
+    }
+
+    fn seek_for_prev(&mut self, target: &Slice) -> Result<bool> {
+        let valid = self.inner.seek_for_prev(target)?;
+        self.skip_backward(valid)
+    }
+
+    fn next(&mut self) -> Result<bool> {
+        let valid = self.inner.next()?;
+        self.skip_forward(valid)
+    }
+
+    fn prev(&mut self) -> Result<bool> {
+        let valid = self.inner.prev()?;
+        self.skip_backward(valid)
+    }
+
+    fn key(&self) -> Slice {
+        self.inner.key()
+    }
+
+    fn value(&self) -> Slice {
+        let value = self.inner.value();
+        strip_write_time(&value).unwrap_or(value)
+    }
+
+    fn valid(&self) -> bool {
+        self.inner.valid()
+    }
+
+    fn timestamp(&self) -> Option<u64> {
+        self.inner.timestamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_time_suffix() {
+        let stored = append_write_time(&Slice::from("value"), 1000, 0);
+        assert_eq!(stored.size(), 5 + TTL_SUFFIX_LEN);
+        assert_eq!(strip_write_time(&stored).unwrap(), Slice::from("value"));
+
+        // Database TTL of 60 seconds
+        assert!(!is_expired(stored.data(), 60, 1059));
+        assert!(is_expired(stored.data(), 60, 1060));
+
+        // A per-write TTL overrides it
+        let stored = append_write_time(&Slice::from("value"), 1000, 5);
+        assert!(!is_expired(stored.data(), 60, 1004));
+        assert!(is_expired(stored.data(), 60, 1005));
+
+        // Values without a suffix never expire
+        assert!(!is_expired(b"short", 60, u64::MAX));
+        assert!(strip_write_time(&Slice::from("short")).is_err());
+    }
+
+    #[test]
+    fn test_ttl_compaction_filter() {
+        let key = Slice::from("k");
+        let fresh = append_write_time(&Slice::from("v"), now_secs(), 0);
+        let stale = append_write_time(&Slice::from("v"), 1, 0);
+
+        let filter = TtlCompactionFilter::new(Duration::from_secs(60));
+        assert_eq!(filter.filter(1, &key, &fresh), CompactionDecision::Keep);
+        assert_eq!(filter.filter(1, &key, &stale), CompactionDecision::Remove);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+/// Defines the order of user keys
+///
+/// Every column family sorts its keys with one comparator: MemTables,
+/// SSTables, iterators and compaction all use it. The name is recorded in
+/// the MANIFEST when the column family is created, and opening it with a
+/// comparator of a different name is refused, since the data on disk would
+/// be read in the wrong order.
+///
+/// Two keys must compare `Equal` only if their bytes are equal.
+pub trait Comparator: Send + Sync {
+    /// Returns the name of this comparator
+    fn name(&self) -> &str;
+
+    /// Three-way comparison of two user keys
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering;
+
+    /// Size of the user-defined timestamp every key carries, 0 for none
+    ///
+    /// Timestamps are u64s kept apart from the user key; only 0 and 8 are
+    /// supported. Versions of a key sort newest timestamp first.
+    fn timestamp_size(&self) -> usize {
+        0
+    }
+}
+
+impl fmt::Debug for dyn Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Lexicographic order of the key bytes (the default)
+pub struct BytewiseComparator;
+
+impl BytewiseComparator {
+    /// Name recorded for this comparator
+    pub const NAME: &'static str = "leveldb.BytewiseComparator";
+}
+
+impl Comparator for BytewiseComparator {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    #[inline]
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Lexicographic order of the key bytes, with a u64 timestamp on every key
+///
+/// Column families using it are written with `DB::put_cf_with_ts` and
+/// read as of a timestamp through `ReadOptions::timestamp`.
+pub struct BytewiseComparatorWithU64Ts;
+
+impl BytewiseComparatorWithU64Ts {
+    /// Name recorded for this comparator
+    pub const NAME: &'static str = "leveldb.BytewiseComparator.u64ts";
+}
+
+impl Comparator for BytewiseComparatorWithU64Ts {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    #[inline]
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+
+    fn timestamp_size(&self) -> usize {
+        8
+    }
+}
+
+/// Reverse lexicographic order of the key bytes
+pub struct ReverseBytewiseComparator;
+
+impl ReverseBytewiseComparator {
+    /// Name recorded for this comparator
+    pub const NAME: &'static str = "rocksdb.ReverseBytewiseComparator";
+}
+
+impl Comparator for ReverseBytewiseComparator {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    #[inline]
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+/// Numeric order of keys holding big-endian unsigned integers
+///
+/// Meant for 8-byte `u64::to_be_bytes` keys. Keys of other lengths are
+/// ordered by numeric value too, so `[5]` sorts next to
+/// `5u64.to_be_bytes()`; keys with the same value but different lengths
+/// sort shorter first.
+pub struct U64BigEndianComparator;
+
+impl U64BigEndianComparator {
+    /// Name recorded for this comparator
+    pub const NAME: &'static str = "rucksdb.U64BigEndianComparator";
+}
+
+impl Comparator for U64BigEndianComparator {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        fn significant(key: &[u8]) -> &[u8] {
+            let leading_zeros = key.iter().take_while(|&&b| b == 0).count();
+            &key[leading_zeros..]
+        }
+
+        let (sig_a, sig_b) = (significant(a), significant(b));
+        sig_a
+            .len()
+            .cmp(&sig_b.len())
+            .then_with(|| sig_a.cmp(sig_b))
+            .then_with(|| a.len().cmp(&b.len()))
+    }
+}
+
+/// The default comparator
+pub fn bytewise_comparator() -> Arc<dyn Comparator> {
+    Arc::new(BytewiseComparator)
+}
+
+/// Builtin comparator with this name
+///
+/// Also accepts "bytewise", the name MANIFESTs of older releases record.
+pub fn builtin_comparator(name: &str) -> Option<Arc<dyn Comparator>> {
+    match name {
+        BytewiseComparator::NAME | "bytewise" => Some(Arc::new(BytewiseComparator)),
+        BytewiseComparatorWithU64Ts::NAME => Some(Arc::new(BytewiseComparatorWithU64Ts)),
+        ReverseBytewiseComparator::NAME => Some(Arc::new(ReverseBytewiseComparator)),
+        U64BigEndianComparator::NAME => Some(Arc::new(U64BigEndianComparator)),
+        _ => None,
+    }
+}
+
+/// Check whether a recorded comparator name denotes `comparator`
+pub(crate) fn comparator_name_matches(recorded: &str, comparator: &dyn Comparator) -> bool {
+    recorded == comparator.name()
+        || (recorded == "bytewise" && comparator.name() == BytewiseComparator::NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytewise_comparators() {
+        assert_eq!(BytewiseComparator.compare(b"a", b"b"), Ordering::Less);
+        assert_eq!(BytewiseComparator.compare(b"ab", b"a"), Ordering::Greater);
+        assert_eq!(
+            ReverseBytewiseComparator.compare(b"a", b"b"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            ReverseBytewiseComparator.compare(b"a", b"a"),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_u64_big_endian_comparator() {
+        let cmp = U64BigEndianComparator;
+        let key = |n: u64| n.to_be_bytes();
+        assert_eq!(cmp.compare(&key(2), &key(10)), Ordering::Less);
+        assert_eq!(cmp.compare(&key(256), &key(255)), Ordering::Greater);
+        assert_eq!(cmp.compare(&key(7), &key(7)), Ordering::Equal);
+
+        // Short keys sort by value, ties by length
+        assert_eq!(cmp.compare(&[5], &key(6)), Ordering::Less);
+        assert_eq!(cmp.compare(&[1, 0], &key(255)), Ordering::Greater);
+        assert_eq!(cmp.compare(&[5], &key(5)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_builtin_comparator_names() {
+        for name in [
+            BytewiseComparator::NAME,
+            BytewiseComparatorWithU64Ts::NAME,
+            ReverseBytewiseComparator::NAME,
+            U64BigEndianComparator::NAME,
+        ] {
+            assert_eq!(builtin_comparator(name).unwrap().name(), name);
+        }
+        assert_eq!(
+            builtin_comparator("bytewise").unwrap().name(),
+            BytewiseComparator::NAME
+        );
+        assert!(builtin_comparator("custom").is_none());
+
+        assert!(comparator_name_matches("bytewise", &BytewiseComparator));
+        assert!(!comparator_name_matches(
+            "bytewise",
+            &ReverseBytewiseComparator
+        ));
+    }
+}
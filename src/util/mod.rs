@@ -1,5 +1,21 @@
+pub mod comparator;
 pub mod slice;
+pub mod slice_transform;
 pub mod status;
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub use comparator::{
+    BytewiseComparator, BytewiseComparatorWithU64Ts, Comparator, ReverseBytewiseComparator,
+    U64BigEndianComparator,
+};
 pub use slice::Slice;
+pub use slice_transform::{CappedPrefixTransform, FixedPrefixTransform, SliceTransform};
 pub use status::{Code, Result, Status};
+
+/// Current time in seconds since the epoch
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
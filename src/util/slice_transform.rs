@@ -0,0 +1,119 @@
+use std::fmt;
+
+/// Extracts a prefix from a user key
+///
+/// A prefix extractor lets filters answer "may any key with this prefix
+/// exist?" in addition to whole-key lookups. SSTable filters and the
+/// MemTable prefix Bloom filter both store the prefixes produced by the
+/// column family's extractor, so prefix seeks can skip sources that
+/// cannot contain a matching key.
+///
+/// Keys outside the extractor's domain have no prefix and are only added
+/// to filters as whole keys.
+pub trait SliceTransform: Send + Sync {
+    /// Returns the name of this transform
+    fn name(&self) -> &str;
+
+    /// Extract the prefix of a key
+    ///
+    /// Prerequisite: in_domain(key) == true
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8];
+
+    /// Check whether a key has a prefix under this transform
+    fn in_domain(&self, key: &[u8]) -> bool;
+}
+
+impl fmt::Debug for dyn SliceTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Prefix of a fixed number of bytes
+///
+/// Keys shorter than the prefix length are outside the domain.
+pub struct FixedPrefixTransform {
+    prefix_len: usize,
+    name: String,
+}
+
+impl FixedPrefixTransform {
+    pub fn new(prefix_len: usize) -> Self {
+        FixedPrefixTransform {
+            prefix_len,
+            name: format!("rocksdb.FixedPrefix.{prefix_len}"),
+        }
+    }
+}
+
+impl SliceTransform for FixedPrefixTransform {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+        &key[..self.prefix_len]
+    }
+
+    fn in_domain(&self, key: &[u8]) -> bool {
+        key.len() >= self.prefix_len
+    }
+}
+
+/// Prefix of at most `cap_len` bytes
+///
+/// Every key is in the domain; keys shorter than the cap are their own
+/// prefix.
+pub struct CappedPrefixTransform {
+    cap_len: usize,
+    name: String,
+}
+
+impl CappedPrefixTransform {
+    pub fn new(cap_len: usize) -> Self {
+        CappedPrefixTransform {
+            cap_len,
+            name: format!("rocksdb.CappedPrefix.{cap_len}"),
+        }
+    }
+}
+
+impl SliceTransform for CappedPrefixTransform {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+        &key[..key.len().min(self.cap_len)]
+    }
+
+    fn in_domain(&self, _key: &[u8]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_prefix_transform() {
+        let transform = FixedPrefixTransform::new(3);
+        assert_eq!(transform.name(), "rocksdb.FixedPrefix.3");
+
+        assert!(transform.in_domain(b"abcdef"));
+        assert!(transform.in_domain(b"abc"));
+        assert!(!transform.in_domain(b"ab"));
+        assert_eq!(transform.transform(b"abcdef"), b"abc");
+    }
+
+    #[test]
+    fn test_capped_prefix_transform() {
+        let transform = CappedPrefixTransform::new(3);
+        assert_eq!(transform.name(), "rocksdb.CappedPrefix.3");
+
+        assert!(transform.in_domain(b"ab"));
+        assert_eq!(transform.transform(b"abcdef"), b"abc");
+        assert_eq!(transform.transform(b"ab"), b"ab");
+    }
+}
@@ -74,6 +74,13 @@ impl Status {
         }
     }
 
+    pub fn incomplete(msg: impl Into<String>) -> Self {
+        Status {
+            code: Code::Incomplete,
+            message: Some(msg.into()),
+        }
+    }
+
     pub fn is_ok(&self) -> bool {
         self.code == Code::Ok
     }
@@ -90,6 +97,10 @@ impl Status {
         self.code == Code::IOError
     }
 
+    pub fn is_incomplete(&self) -> bool {
+        self.code == Code::Incomplete
+    }
+
     pub fn code(&self) -> &Code {
         &self.code
     }
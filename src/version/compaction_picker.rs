@@ -1,4 +1,18 @@
-use crate::version::version::Version;
+use std::{
+    cmp::Ordering,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+use parking_lot::{Condvar, Mutex};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    util::{Comparator, Slice},
+    version::{
+        version::Version,
+        version_edit::{FileMetaData, NUM_LEVELS},
+    },
+};
 
 /// Compaction priority score for a level
 #[derive(Debug, Clone)]
@@ -7,7 +21,236 @@ pub struct CompactionScore {
     pub score: f64,
 }
 
+/// Order in which the files of a level are compacted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompactionPri {
+    /// The file overlapping the fewest bytes of the next level relative to
+    /// its own size, keeping write amplification low
+    #[default]
+    MinOverlappingRatio,
+    /// Files in key order, continuing after the last compacted key; the
+    /// position is recorded in the MANIFEST
+    RoundRobin,
+}
+
+/// How the SSTables of a column family are organized and compacted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompactionStyle {
+    /// Every level is a sorted run of bounded size, compacted a few files
+    /// at a time into the next level
+    #[default]
+    Level,
+    /// Level 0 files and whole levels are sorted runs, merged with each
+    /// other by size (see `CompactionOptionsUniversal`); lower write
+    /// amplification at the cost of space and read amplification
+    Universal,
+    /// All files stay in level 0 and the oldest are dropped once the CF
+    /// outgrows its size budget or they outlive the TTL (see
+    /// `CompactionOptionsFIFO`); for data that expires, such as time series
+    Fifo,
+}
+
+/// Input files of one compaction from `level` into `output_level`
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    /// Level the inputs are taken from
+    pub level: usize,
+    /// Level the outputs are written to: `level + 1`, the base level for
+    /// L0 with dynamic level bytes, or `level` itself when merging L0 files
+    /// into a larger L0 file
+    pub output_level: usize,
+    /// Files of `level`
+    pub inputs: Vec<FileMetaData>,
+    /// Files of `output_level` overlapping the inputs
+    pub next_level_inputs: Vec<FileMetaData>,
+    /// Whole levels between `level` and `output_level` merged along
+    /// (universal compaction)
+    pub intermediate_inputs: Vec<(usize, Vec<FileMetaData>)>,
+    /// Round-robin cursor of `level` to record with the result
+    pub compact_cursor: Option<Slice>,
+    /// The inputs are dropped without being read or rewritten (FIFO
+    /// compaction)
+    pub deletion_compaction: bool,
+}
+
+impl Compaction {
+    /// Compaction of `level` into the next level
+    pub fn new(
+        level: usize,
+        inputs: Vec<FileMetaData>,
+        next_level_inputs: Vec<FileMetaData>,
+    ) -> Self {
+        Compaction {
+            level,
+            output_level: level + 1,
+            inputs,
+            next_level_inputs,
+            intermediate_inputs: Vec::new(),
+            compact_cursor: None,
+            deletion_compaction: false,
+        }
+    }
+
+    /// Compaction dropping the files of `level`
+    pub fn deletion(level: usize, inputs: Vec<FileMetaData>) -> Self {
+        Compaction {
+            deletion_compaction: true,
+            ..Self::new(level, inputs, Vec::new()).with_output_level(level)
+        }
+    }
+
+    /// Write the outputs to `output_level` instead of the next level
+    pub fn with_output_level(mut self, output_level: usize) -> Self {
+        self.output_level = output_level;
+        self
+    }
+
+    /// Files of all input levels
+    pub fn input_files(&self) -> impl Iterator<Item = &FileMetaData> + Clone {
+        self.leveled_input_files().map(|(_, file)| file)
+    }
+
+    /// Files of all input levels with their levels
+    pub fn leveled_input_files(&self) -> impl Iterator<Item = (usize, &FileMetaData)> + Clone {
+        let intermediate = self
+            .intermediate_inputs
+            .iter()
+            .flat_map(|(level, files)| files.iter().map(move |file| (*level, file)));
+        self.inputs
+            .iter()
+            .map(|file| (self.level, file))
+            .chain(intermediate)
+            .chain(
+                self.next_level_inputs
+                    .iter()
+                    .map(|file| (self.output_level, file)),
+            )
+    }
+
+    /// Total size of the input files
+    pub fn input_bytes(&self) -> u64 {
+        self.input_files().map(|f| f.file_size).sum()
+    }
+
+    /// Whether the inputs can be moved to the output level as they are:
+    /// they come from one other level and overlap no file of the output
+    /// level, nor each other
+    ///
+    /// Files marked for compaction are rewritten into the last level, which
+    /// drops their deletion markers.
+    pub fn is_trivial_move(&self, comparator: &dyn Comparator) -> bool {
+        if self.output_level == self.level
+            || !self.intermediate_inputs.is_empty()
+            || !self.next_level_inputs.is_empty()
+            || (self.output_level == NUM_LEVELS - 1
+                && self.inputs.iter().any(|f| f.marked_for_compaction))
+        {
+            return false;
+        }
+        let mut inputs: Vec<&FileMetaData> = self.inputs.iter().collect();
+        inputs.sort_by(|a, b| comparator.compare(a.smallest_user_key(), b.smallest_user_key()));
+        inputs.windows(2).all(|pair| {
+            comparator.compare(pair[0].largest_user_key(), pair[1].smallest_user_key())
+                == Ordering::Less
+        })
+    }
+
+    /// Whether two compactions can't run at the same time: they share a
+    /// file, or write or read overlapping key ranges of a common level
+    pub(crate) fn conflicts_with(&self, other: &Compaction, comparator: &dyn Comparator) -> bool {
+        if self
+            .input_files()
+            .any(|f| other.input_files().any(|g| g.number == f.number))
+        {
+            return true;
+        }
+        if self.level > other.output_level || other.level > self.output_level {
+            return false;
+        }
+        match (
+            key_range(self.input_files(), comparator),
+            key_range(other.input_files(), comparator),
+        ) {
+            (Some((smallest, largest)), Some((other_smallest, other_largest))) => {
+                comparator.compare(smallest, other_largest) != Ordering::Greater
+                    && comparator.compare(other_smallest, largest) != Ordering::Greater
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Smallest and largest user key of a set of files
+fn key_range<'a>(
+    files: impl IntoIterator<Item = &'a FileMetaData, IntoIter: Clone>,
+    comparator: &dyn Comparator,
+) -> Option<(&'a [u8], &'a [u8])> {
+    let files = files.into_iter();
+    let smallest = files
+        .clone()
+        .map(|f| f.smallest_user_key())
+        .min_by(|a, b| comparator.compare(a, b))?;
+    let largest = files
+        .map(|f| f.largest_user_key())
+        .max_by(|a, b| comparator.compare(a, b))?;
+    Some((smallest, largest))
+}
+
+/// Fewest level 0 files an intra-L0 compaction of leveled compaction merges
+const MIN_FILES_FOR_INTRA_L0_COMPACTION: usize = 4;
+
+/// The newest level 0 files worth merging into one level 0 file
+///
+/// Takes files from the newest one back while the bytes written per file
+/// removed keep falling, stopping at a file `compacting` reports or once
+/// the total would exceed `max_bytes`. The span is returned, oldest first,
+/// if it has at least `min_files` files and writes less than
+/// `max_bytes_per_deleted_file` per file removed.
+pub(crate) fn find_intra_l0_compaction(
+    level0: &[FileMetaData],
+    compacting: impl Fn(&FileMetaData) -> bool,
+    min_files: usize,
+    max_bytes_per_deleted_file: u64,
+    max_bytes: u64,
+) -> Option<Vec<FileMetaData>> {
+    let mut newest_first = level0.iter().rev();
+    let newest = newest_first.next()?;
+    if compacting(newest) {
+        return None;
+    }
+    let mut bytes = newest.file_size;
+    let mut bytes_per_deleted_file = u64::MAX;
+    let mut span = 1;
+    for file in newest_first {
+        let new_bytes = bytes.saturating_add(file.file_size);
+        let new_bytes_per_deleted_file = new_bytes / span as u64;
+        if compacting(file)
+            || new_bytes_per_deleted_file > bytes_per_deleted_file
+            || new_bytes > max_bytes
+        {
+            break;
+        }
+        bytes = new_bytes;
+        bytes_per_deleted_file = new_bytes_per_deleted_file;
+        span += 1;
+    }
+    (span >= min_files.max(2) && bytes_per_deleted_file < max_bytes_per_deleted_file)
+        .then(|| level0[level0.len() - span..].to_vec())
+}
+
 /// CompactionPicker selects which level to compact based on priority scores
+/// and which files of it to compact
+///
+/// Level 0 is compacted as a whole since its files overlap. From the other
+/// levels one file is picked at a time, in the order given by
+/// `CompactionPri`, together with the files of the next level it overlaps.
+/// Files sharing a boundary key with their neighbours are taken along with
+/// them, and the inputs grow to more files of the level when that doesn't
+/// pull in more files of the next level.
+///
+/// With dynamic level bytes, level targets are derived backward from the
+/// largest level and L0 is compacted into the base level, the highest
+/// level with a target; the levels above it stay empty.
 pub struct CompactionPicker {
     /// Base level size (Level 1 target size)
     base_level_size: u64,
@@ -15,6 +258,14 @@ pub struct CompactionPicker {
     level_multiplier: u64,
     /// Level 0 file count trigger
     level0_file_trigger: usize,
+    /// Order files are picked in
+    compaction_pri: CompactionPri,
+    /// Derive level targets from the size of the largest level
+    dynamic_level_bytes: bool,
+    /// Age of the oldest data at which a file is compacted down (0 = never)
+    ttl: u64,
+    /// Age at which a file is rewritten (0 = never)
+    periodic_compaction_seconds: u64,
 }
 
 impl CompactionPicker {
@@ -24,6 +275,10 @@ impl CompactionPicker {
             base_level_size: 10 * 1024 * 1024, // 10 MB
             level_multiplier: 10,
             level0_file_trigger: 4,
+            compaction_pri: CompactionPri::default(),
+            dynamic_level_bytes: false,
+            ttl: 0,
+            periodic_compaction_seconds: 0,
         }
     }
 
@@ -33,10 +288,40 @@ impl CompactionPicker {
             base_level_size,
             level_multiplier,
             level0_file_trigger: level0_trigger,
+            compaction_pri: CompactionPri::default(),
+            dynamic_level_bytes: false,
+            ttl: 0,
+            periodic_compaction_seconds: 0,
         }
     }
 
-    /// Calculate target size for a level
+    /// Pick files in the order of `compaction_pri`
+    pub fn with_compaction_pri(mut self, compaction_pri: CompactionPri) -> Self {
+        self.compaction_pri = compaction_pri;
+        self
+    }
+
+    /// Size level targets backward from the largest level
+    pub fn with_dynamic_level_bytes(mut self, dynamic_level_bytes: bool) -> Self {
+        self.dynamic_level_bytes = dynamic_level_bytes;
+        self
+    }
+
+    /// Compact files down once their oldest data is `ttl` seconds old, and
+    /// rewrite files `periodic_compaction_seconds` after they were written
+    /// (see `pick_old_file`)
+    pub fn with_time_based_compaction(
+        mut self,
+        ttl: u64,
+        periodic_compaction_seconds: u64,
+    ) -> Self {
+        self.ttl = ttl;
+        self.periodic_compaction_seconds = periodic_compaction_seconds;
+        self
+    }
+
+    /// Calculate target size for a level, growing from the base size at
+    /// level 1
     pub fn target_size_for_level(&self, level: usize) -> u64 {
         if level == 0 {
             return 0; // Level 0 uses file count, not size
@@ -49,23 +334,101 @@ impl CompactionPicker {
         self.base_level_size.saturating_mul(multiplier_pow)
     }
 
+    /// Level L0 is compacted into
+    pub fn base_level(&self, version: &Version) -> usize {
+        self.level_targets(version).0
+    }
+
+    /// Level the files of `level` are compacted into
+    pub fn output_level(&self, version: &Version, level: usize) -> usize {
+        if level == 0 {
+            self.base_level(version)
+        } else {
+            level + 1
+        }
+    }
+
+    /// Base level and target size of every level (0 = no target)
+    ///
+    /// Dynamic targets start from the size of the largest level and are
+    /// divided by the multiplier level by level upwards. The base level is
+    /// the highest one whose target stays above `base_level_size /
+    /// multiplier`, and no target is below `base_level_size`. An empty
+    /// database has its base level at the last level.
+    pub(crate) fn level_targets(&self, version: &Version) -> (usize, [u64; NUM_LEVELS]) {
+        let mut targets = [0; NUM_LEVELS];
+        if !self.dynamic_level_bytes {
+            for (level, target) in targets.iter_mut().enumerate().skip(1) {
+                *target = self.target_size_for_level(level);
+            }
+            return (1, targets);
+        }
+
+        let level_size = |level: usize| -> u64 {
+            version
+                .get_level_files(level)
+                .iter()
+                .map(|f| f.file_size)
+                .sum()
+        };
+        let Some(first_non_empty) = (1..NUM_LEVELS).find(|&level| level_size(level) > 0) else {
+            return (NUM_LEVELS - 1, targets);
+        };
+        let max_level_size = (first_non_empty..NUM_LEVELS)
+            .map(level_size)
+            .max()
+            .unwrap_or(0);
+
+        let multiplier = self.level_multiplier;
+        let base_bytes_max = self.base_level_size;
+        let base_bytes_min = base_bytes_max / multiplier;
+
+        // Target of the first non-empty level if the last level's target
+        // were the largest level's size
+        let mut size = max_level_size;
+        for _ in first_non_empty..NUM_LEVELS - 1 {
+            size /= multiplier;
+        }
+
+        let mut base_level = first_non_empty;
+        let base_size = if size <= base_bytes_min {
+            base_bytes_min + 1
+        } else {
+            while base_level > 1 && size > base_bytes_max {
+                base_level -= 1;
+                size /= multiplier;
+            }
+            size.min(base_bytes_max)
+        };
+
+        let mut target = base_size;
+        for (level, level_target) in targets.iter_mut().enumerate().skip(base_level) {
+            if level > base_level {
+                target = target.saturating_mul(multiplier);
+            }
+            *level_target = target.max(base_bytes_max);
+        }
+        (base_level, targets)
+    }
+
     /// Calculate compaction score for a level
     /// Score > 1.0 means level needs compaction
     /// Higher score = higher priority
-    fn calculate_level_score(&self, version: &Version, level: usize) -> f64 {
+    fn calculate_level_score(&self, version: &Version, level: usize, target_size: u64) -> f64 {
         if level == 0 {
             // Level 0: score based on file count
             let file_count = version.num_level_files(level);
             file_count as f64 / self.level0_file_trigger as f64
         } else {
-            // Other levels: score based on size ratio
+            // Other levels: score based on size ratio, deletion markers
+            // weighted up so they reach the bottom level sooner
+            let average_entry_size = average_entry_size(version);
             let level_size: u64 = version
                 .get_level_files(level)
                 .iter()
-                .map(|f| f.file_size)
+                .map(|f| f.compensated_file_size(average_entry_size))
                 .sum();
 
-            let target_size = self.target_size_for_level(level);
             if target_size == 0 {
                 return 0.0;
             }
@@ -79,10 +442,11 @@ impl CompactionPicker {
     pub fn pick_compaction(&self, version: &Version) -> Option<usize> {
         let mut best_score = 1.0; // Only compact if score > 1.0
         let mut best_level = None;
+        let (_, targets) = self.level_targets(version);
 
         // Score all levels (0 through 6, skip last level)
-        for level in 0..6 {
-            let score = self.calculate_level_score(version, level);
+        for (level, &target_size) in targets.iter().enumerate().take(6) {
+            let score = self.calculate_level_score(version, level, target_size);
 
             if score > best_score {
                 best_score = score;
@@ -93,12 +457,247 @@ impl CompactionPicker {
         best_level
     }
 
+    /// Pick the files to compact next
+    ///
+    /// Levels scoring above 1.0 are tried from the highest score down;
+    /// files a `running` compaction conflicts with are passed over. Without
+    /// such a level, files marked for compaction are pushed a level down.
+    pub fn pick(&self, version: &Version, running: &[Compaction]) -> Option<Compaction> {
+        let mut scores: Vec<CompactionScore> = self
+            .get_all_scores(version)
+            .into_iter()
+            .filter(|s| s.level + 1 < NUM_LEVELS && s.score > 1.0)
+            .collect();
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores
+            .iter()
+            .find_map(|s| self.pick_level(version, s.level, running))
+            .or_else(|| self.pick_marked_file(version, running))
+    }
+
+    /// Compact the first file marked for compaction into the next level
+    ///
+    /// Deletion markers are dropped only in the last level, so marked files
+    /// there are left alone.
+    fn pick_marked_file(&self, version: &Version, running: &[Compaction]) -> Option<Compaction> {
+        self.pick_file_where(version, running, NUM_LEVELS - 1, |file| {
+            file.marked_for_compaction
+        })
+    }
+
+    /// Pick a file that has grown old, once no level needs compaction
+    ///
+    /// With a `ttl`, a file whose oldest data is at least `ttl` seconds old
+    /// is compacted into the next level, until it reaches the last level.
+    /// With `periodic_compaction_seconds`, a file written at least that long
+    /// ago is compacted into the next level, or rewritten in place in the
+    /// last level, so compaction filters see every key now and then.
+    ///
+    /// `now` is in seconds since the epoch and `creation_time` gives the time
+    /// a file's oldest data was written at (0 = unknown). Files of unknown
+    /// age never grow old.
+    pub fn pick_old_file(
+        &self,
+        version: &Version,
+        running: &[Compaction],
+        now: u64,
+        creation_time: impl Fn(&FileMetaData) -> u64,
+    ) -> Option<Compaction> {
+        let older_than =
+            |time: u64, age: u64| age > 0 && time > 0 && time.saturating_add(age) <= now;
+        let expired = self.pick_file_where(version, running, NUM_LEVELS - 1, |file| {
+            older_than(creation_time(file), self.ttl)
+        });
+        expired.or_else(|| {
+            self.pick_file_where(version, running, NUM_LEVELS, |file| {
+                let written = match file.file_creation_time {
+                    0 => creation_time(file),
+                    time => time,
+                };
+                older_than(written, self.periodic_compaction_seconds)
+            })
+        })
+    }
+
+    /// Compact the first file of the levels above `end_level` matching
+    /// `pick` into the next level, or within the last level
+    fn pick_file_where(
+        &self,
+        version: &Version,
+        running: &[Compaction],
+        end_level: usize,
+        pick: impl Fn(&FileMetaData) -> bool,
+    ) -> Option<Compaction> {
+        let comparator = version.comparator().as_ref();
+        (0..end_level).find_map(|level| {
+            let files = version.get_level_files(level);
+            files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| pick(file))
+                .find_map(|(index, _)| {
+                    if level == 0 {
+                        return self.pick_level(version, 0, running);
+                    }
+                    let inputs = clean_cut(files, index..index + 1, comparator);
+                    if level + 1 < NUM_LEVELS {
+                        return self.setup_inputs(version, level, level + 1, inputs, running);
+                    }
+                    let compaction =
+                        Compaction::new(level, inputs, Vec::new()).with_output_level(level);
+                    running
+                        .iter()
+                        .all(|other| !compaction.conflicts_with(other, comparator))
+                        .then_some(compaction)
+                })
+        })
+    }
+
+    /// Pick the files to compact from `level`, or None if the level is
+    /// empty or every candidate conflicts with a `running` compaction
+    pub fn pick_level(
+        &self,
+        version: &Version,
+        level: usize,
+        running: &[Compaction],
+    ) -> Option<Compaction> {
+        if level + 1 >= NUM_LEVELS {
+            return None;
+        }
+        let files = version.get_level_files(level);
+        if files.is_empty() {
+            return None;
+        }
+        if level == 0 {
+            let output_level = self.base_level(version);
+            return self
+                .setup_inputs(version, 0, output_level, files.to_vec(), running)
+                .or_else(|| self.pick_intra_level0(version, running));
+        }
+
+        let comparator = version.comparator().as_ref();
+        let candidates: Vec<usize> = match self.compaction_pri {
+            CompactionPri::MinOverlappingRatio => {
+                let average_entry_size = average_entry_size(version);
+                let ratios: Vec<f64> = files
+                    .iter()
+                    .map(|f| {
+                        let overlap: u64 = version
+                            .get_overlapping_files(level + 1, &f.smallest, &f.largest)
+                            .iter()
+                            .map(|g| g.file_size)
+                            .sum();
+                        let size = f.compensated_file_size(average_entry_size);
+                        overlap as f64 / size.max(1) as f64
+                    })
+                    .collect();
+                let mut order: Vec<usize> = (0..files.len()).collect();
+                order.sort_by(|&a, &b| ratios[a].total_cmp(&ratios[b]));
+                order
+            },
+            CompactionPri::RoundRobin => {
+                let start = version.compact_cursor(level).map_or(0, |cursor| {
+                    files.partition_point(|f| {
+                        comparator.compare(f.smallest_user_key(), cursor.data())
+                            != Ordering::Greater
+                    })
+                });
+                (start..files.len()).chain(0..start).collect()
+            },
+        };
+
+        candidates.into_iter().find_map(|index| {
+            let inputs = clean_cut(files, index..index + 1, comparator);
+            let mut compaction = self.setup_inputs(version, level, level + 1, inputs, running)?;
+            if self.compaction_pri == CompactionPri::RoundRobin {
+                let (_, largest) = key_range(&compaction.inputs, comparator)?;
+                compaction.compact_cursor = Some(Slice::from(largest));
+            }
+            Some(compaction)
+        })
+    }
+
+    /// Merge the newest level 0 files into one level 0 file while level 0
+    /// can't be compacted into the base level
+    ///
+    /// Only done once L0 holds 2 files more than the trigger, so reads
+    /// probe fewer L0 files until the running compactions finish. The output
+    /// takes the place of its inputs in L0, keeping L0 ordered by age.
+    fn pick_intra_level0(&self, version: &Version, running: &[Compaction]) -> Option<Compaction> {
+        let files = version.get_level_files(0);
+        if files.len() < self.level0_file_trigger + 2 {
+            return None;
+        }
+        let compacting = |file: &FileMetaData| {
+            running
+                .iter()
+                .any(|c| c.input_files().any(|f| f.number == file.number))
+        };
+        let inputs = find_intra_l0_compaction(
+            files,
+            compacting,
+            MIN_FILES_FOR_INTRA_L0_COMPACTION,
+            u64::MAX,
+            self.base_level_size,
+        )?;
+        Some(Compaction::new(0, inputs, Vec::new()).with_output_level(0))
+    }
+
+    /// Add the overlapping files of `output_level` to `inputs` and grow
+    /// `inputs` over their key range if that adds no output-level file
+    fn setup_inputs(
+        &self,
+        version: &Version,
+        level: usize,
+        output_level: usize,
+        inputs: Vec<FileMetaData>,
+        running: &[Compaction],
+    ) -> Option<Compaction> {
+        let comparator = version.comparator().as_ref();
+        let overlapping = |level: usize, files: &[FileMetaData]| {
+            let (smallest, largest) = key_range(files, comparator)?;
+            Some(version.get_overlapping_files(
+                level,
+                &Slice::from(smallest),
+                &Slice::from(largest),
+            ))
+        };
+
+        let mut inputs = inputs;
+        let mut next_level_inputs = overlapping(output_level, &inputs)?;
+        if level > 0 && !next_level_inputs.is_empty() {
+            let all: Vec<FileMetaData> = inputs.iter().chain(&next_level_inputs).cloned().collect();
+            let expanded = overlapping(level, &all)?;
+            let files = version.get_level_files(level);
+            let first = files.iter().position(|f| f.number == expanded[0].number)?;
+            let expanded = clean_cut(files, first..first + expanded.len(), comparator);
+            if expanded.len() > inputs.len() {
+                let expanded_next = overlapping(output_level, &expanded)?;
+                if expanded_next.len() == next_level_inputs.len() {
+                    inputs = expanded;
+                    next_level_inputs = expanded_next;
+                }
+            }
+        }
+
+        let compaction =
+            Compaction::new(level, inputs, next_level_inputs).with_output_level(output_level);
+        if running
+            .iter()
+            .any(|other| compaction.conflicts_with(other, comparator))
+        {
+            return None;
+        }
+        Some(compaction)
+    }
+
     /// Get compaction scores for all levels (for monitoring/debugging)
     pub fn get_all_scores(&self, version: &Version) -> Vec<CompactionScore> {
+        let (_, targets) = self.level_targets(version);
         (0..7)
             .map(|level| CompactionScore {
                 level,
-                score: self.calculate_level_score(version, level),
+                score: self.calculate_level_score(version, level, targets[level]),
             })
             .collect()
     }
@@ -110,6 +709,193 @@ impl Default for CompactionPicker {
     }
 }
 
+/// Average size of an entry over the files whose entry count is known
+fn average_entry_size(version: &Version) -> u64 {
+    let (bytes, entries) = (0..NUM_LEVELS)
+        .flat_map(|level| version.get_level_files(level))
+        .filter(|f| f.num_entries > 0)
+        .fold((0u64, 0u64), |(bytes, entries), f| {
+            (bytes + f.file_size, entries + f.num_entries)
+        });
+    bytes.checked_div(entries).unwrap_or(0)
+}
+
+/// Grow a range of the files of a sorted level until it neither starts
+/// nor ends in the middle of a user key's versions
+pub(crate) fn clean_cut(
+    files: &[FileMetaData],
+    range: std::ops::Range<usize>,
+    comparator: &dyn Comparator,
+) -> Vec<FileMetaData> {
+    let (mut start, mut end) = (range.start, range.end);
+    while start > 0
+        && comparator.compare(
+            files[start - 1].largest_user_key(),
+            files[start].smallest_user_key(),
+        ) == Ordering::Equal
+    {
+        start -= 1;
+    }
+    while end < files.len()
+        && comparator.compare(
+            files[end - 1].largest_user_key(),
+            files[end].smallest_user_key(),
+        ) == Ordering::Equal
+    {
+        end += 1;
+    }
+    files[start..end].to_vec()
+}
+
+/// Files of `level` holding user keys in `[start, end]` (None =
+/// unbounded) that `include` accepts, along with the files sharing their
+/// boundary user keys
+///
+/// Level 0 files overlap each other, so level 0 is taken whole if any of
+/// its files qualifies.
+pub(crate) fn files_in_range(
+    version: &Version,
+    level: usize,
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    include: impl Fn(&FileMetaData) -> bool,
+) -> Vec<FileMetaData> {
+    let comparator = version.comparator().as_ref();
+    let files = version.get_level_files(level);
+    let selected = |file: &FileMetaData| {
+        start.is_none_or(|start| {
+            comparator.compare(file.largest_user_key(), start) != Ordering::Less
+        }) && end.is_none_or(|end| {
+            comparator.compare(file.smallest_user_key(), end) != Ordering::Greater
+        }) && include(file)
+    };
+    if level == 0 {
+        return match files.iter().any(selected) {
+            true => files.to_vec(),
+            false => Vec::new(),
+        };
+    }
+    match (
+        files.iter().position(selected),
+        files.iter().rposition(selected),
+    ) {
+        (Some(first), Some(last)) => clean_cut(files, first..last + 1, comparator),
+        _ => Vec::new(),
+    }
+}
+
+/// Compactions in progress in a column family
+///
+/// Compactions register here while they run, so concurrent ones are only
+/// picked from files and key ranges no other compaction touches.
+#[derive(Default)]
+pub(crate) struct RunningCompactions {
+    compactions: Mutex<Vec<Compaction>>,
+    finished: Condvar,
+    /// Exclusive manual compactions in progress
+    exclusive: AtomicUsize,
+}
+
+impl RunningCompactions {
+    /// Register the compaction `pick` chooses given the running ones
+    ///
+    /// Nothing is picked during an exclusive manual compaction.
+    pub(crate) fn start(
+        &self,
+        pick: impl FnOnce(&[Compaction]) -> Option<Compaction>,
+    ) -> Option<RunningCompaction<'_>> {
+        let mut compactions = self.compactions.lock();
+        if self.exclusive.load(AtomicOrdering::SeqCst) > 0 {
+            return None;
+        }
+        let compaction = pick(&compactions)?;
+        compactions.push(compaction.clone());
+        Some(RunningCompaction {
+            running: self,
+            compaction,
+        })
+    }
+
+    /// Register the compaction `build` returns once no running compaction
+    /// conflicts with it
+    ///
+    /// `build` is called again whenever a compaction finishes, since the
+    /// files it chooses may have changed meanwhile.
+    pub(crate) fn start_when_clear(
+        &self,
+        mut build: impl FnMut() -> Option<Compaction>,
+        comparator: &dyn Comparator,
+    ) -> Option<RunningCompaction<'_>> {
+        let mut compactions = self.compactions.lock();
+        loop {
+            let compaction = build()?;
+            if !compactions
+                .iter()
+                .any(|other| compaction.conflicts_with(other, comparator))
+            {
+                compactions.push(compaction.clone());
+                return Some(RunningCompaction {
+                    running: self,
+                    compaction,
+                });
+            }
+            self.finished.wait(&mut compactions);
+        }
+    }
+}
+
+impl RunningCompactions {
+    /// Stop `start` from picking compactions, and wait for the running ones
+    /// to finish
+    ///
+    /// Compactions registered with `start_when_clear` can still run; picking
+    /// resumes once the returned guard is dropped.
+    pub(crate) fn exclusive(&self) -> ExclusiveCompaction<'_> {
+        self.exclusive.fetch_add(1, AtomicOrdering::SeqCst);
+        let mut compactions = self.compactions.lock();
+        while !compactions.is_empty() {
+            self.finished.wait(&mut compactions);
+        }
+        ExclusiveCompaction { running: self }
+    }
+}
+
+/// An exclusive manual compaction, ended when dropped
+pub(crate) struct ExclusiveCompaction<'a> {
+    running: &'a RunningCompactions,
+}
+
+impl Drop for ExclusiveCompaction<'_> {
+    fn drop(&mut self) {
+        self.running.exclusive.fetch_sub(1, AtomicOrdering::SeqCst);
+    }
+}
+
+/// A registered compaction, unregistered when dropped
+pub(crate) struct RunningCompaction<'a> {
+    running: &'a RunningCompactions,
+    compaction: Compaction,
+}
+
+impl RunningCompaction<'_> {
+    pub(crate) fn compaction(&self) -> &Compaction {
+        &self.compaction
+    }
+}
+
+impl Drop for RunningCompaction<'_> {
+    fn drop(&mut self) {
+        // Running compactions never share a file, so the first input
+        // identifies this one
+        let number = self.compaction.inputs[0].number;
+        self.running
+            .compactions
+            .lock()
+            .retain(|c| c.inputs[0].number != number);
+        self.running.finished.notify_all();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,4 +1012,415 @@ mod tests {
         let level = picker.pick_compaction(&version);
         assert_eq!(level, None);
     }
+
+    fn file(number: u64, size: u64, smallest: &str, largest: &str) -> FileMetaData {
+        FileMetaData::new(number, size, Slice::from(smallest), Slice::from(largest))
+    }
+
+    fn numbers(files: &[FileMetaData]) -> Vec<u64> {
+        files.iter().map(|f| f.number).collect()
+    }
+
+    /// L1: 1 [a-b], 2 [c-d], 3 [e-f]; L2: 10 [a-b] (8MB), 11 [e-f] (1MB)
+    fn leveled_version() -> Version {
+        let mut version = Version::new();
+        version.add_file(1, file(1, 1024 * 1024, "a", "b"));
+        version.add_file(1, file(2, 1024 * 1024, "c", "d"));
+        version.add_file(1, file(3, 1024 * 1024, "e", "f"));
+        version.add_file(2, file(10, 8 * 1024 * 1024, "a", "b"));
+        version.add_file(2, file(11, 1024 * 1024, "e", "f"));
+        version
+    }
+
+    #[test]
+    fn test_pick_min_overlapping_ratio() {
+        let picker = CompactionPicker::new();
+        let version = leveled_version();
+
+        // File 2 overlaps nothing in L2
+        let compaction = picker.pick_level(&version, 1, &[]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![2]);
+        assert!(compaction.next_level_inputs.is_empty());
+        assert_eq!(compaction.compact_cursor, None);
+
+        // With file 2 taken, file 3 overlaps less than file 1
+        let compaction = picker.pick_level(&version, 1, &[compaction]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![3]);
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![11]);
+    }
+
+    #[test]
+    fn test_pick_round_robin() {
+        let picker = CompactionPicker::new().with_compaction_pri(CompactionPri::RoundRobin);
+        let mut version = leveled_version();
+
+        let compaction = picker.pick_level(&version, 1, &[]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![1]);
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![10]);
+        assert_eq!(compaction.compact_cursor, Some(Slice::from("b")));
+
+        version.set_compact_cursor(1, Slice::from("b"));
+        let compaction = picker.pick_level(&version, 1, &[]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![2]);
+
+        // Wraps around after the last file
+        version.set_compact_cursor(1, Slice::from("f"));
+        let compaction = picker.pick_level(&version, 1, &[]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![1]);
+    }
+
+    #[test]
+    fn test_pick_expands_inputs() {
+        let picker = CompactionPicker::new();
+        let mut version = Version::new();
+        // Files 1 and 2 share the user key "c"
+        version.add_file(1, file(1, 1024, "a", "c"));
+        version.add_file(1, file(2, 1024, "c", "d"));
+        version.add_file(1, file(3, 1024, "e", "f"));
+        version.add_file(1, file(4, 1024, "g", "h"));
+        // File 3 lies within the range of L2 file 11 the others need
+        version.add_file(2, file(10, 1024, "a", "b"));
+        version.add_file(2, file(11, 4096, "d", "f"));
+
+        let compaction = picker.pick_level(&version, 1, &[]).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![4]);
+
+        let running = [compaction];
+        let compaction = picker.pick_level(&version, 1, &running).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![1, 2, 3]);
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![10, 11]);
+    }
+
+    #[test]
+    fn test_pick_avoids_running_compactions() {
+        let picker = CompactionPicker::new();
+        let mut version = Version::new();
+        for i in 0..5 {
+            version.add_file(0, file(i, 1024, "a", "z"));
+        }
+        version.add_file(1, file(10, 1024, "c", "d"));
+
+        // Level 0 goes as a whole, with the L1 files it overlaps
+        let compaction = picker.pick(&version, &[]).unwrap();
+        assert_eq!(compaction.level, 0);
+        assert_eq!(numbers(&compaction.inputs), vec![0, 1, 2, 3, 4]);
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![10]);
+
+        // A new L0 file waits for the running L0 compaction
+        version.add_file(0, file(5, 1024, "x", "y"));
+        let running = [compaction];
+        assert!(picker.pick_level(&version, 0, &running).is_none());
+
+        // L1 files it reads or writes over are left alone too
+        let comparator = version.comparator().as_ref();
+        let mut other = Compaction::new(1, vec![file(20, 1024, "m", "n")], Vec::new());
+        assert!(other.conflicts_with(&running[0], comparator));
+        // Levels below are free
+        other.level = 2;
+        assert!(!other.conflicts_with(&running[0], comparator));
+    }
+
+    #[test]
+    fn test_compensated_level_score() {
+        let picker = CompactionPicker::new();
+        let mut version = Version::new();
+        // 8MB of 1KB entries in L1, under its 10MB target
+        for i in 0..8 {
+            let mut f = file(i, 1024 * 1024, "a", "z");
+            f.num_entries = 1024;
+            version.add_file(1, f);
+        }
+        assert_eq!(picker.pick_compaction(&version), None);
+
+        // A small file of deletions counts twice the average entry size
+        // per marker, pushing L1 over its target
+        let mut deletions = file(8, 64 * 1024, "a", "z");
+        deletions.num_entries = 2048;
+        deletions.num_deletions = 2048;
+        version.add_file(1, deletions);
+        assert_eq!(picker.pick_compaction(&version), Some(1));
+    }
+
+    #[test]
+    fn test_pick_marked_file() {
+        let picker = CompactionPicker::new();
+        let mut version = leveled_version();
+        assert!(picker.pick(&version, &[]).is_none());
+
+        let mut marked = file(4, 1024, "g", "h");
+        marked.marked_for_compaction = true;
+        version.add_file(1, marked.clone());
+        let compaction = picker.pick(&version, &[]).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (1, 2));
+        assert_eq!(numbers(&compaction.inputs), vec![4]);
+
+        // Not while it is being compacted, nor in the last level
+        assert!(picker.pick(&version, &[compaction]).is_none());
+        let comparator = version.comparator().as_ref();
+        let into_last_level =
+            Compaction::new(5, vec![marked.clone()], Vec::new()).with_output_level(6);
+        assert!(!into_last_level.is_trivial_move(comparator));
+        version.remove_file(1, 4);
+        version.add_file(6, marked);
+        assert!(picker.pick(&version, &[]).is_none());
+    }
+
+    #[test]
+    fn test_pick_old_file() {
+        let mut version = Version::new();
+        version.add_file(1, file(1, 1024, "a", "b"));
+        version.add_file(1, file(2, 1024, "c", "d"));
+        let mut bottom = file(3, 1024, "a", "z");
+        bottom.file_creation_time = 150;
+        version.add_file(6, bottom);
+        // File 2 holds data written at 100, the others data of unknown age
+        let created = |f: &FileMetaData| if f.number == 2 { 100 } else { 0 };
+
+        // Data older than the TTL is compacted down
+        let picker = CompactionPicker::new().with_time_based_compaction(50, 0);
+        let compaction = picker.pick_old_file(&version, &[], 200, created).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (1, 2));
+        assert_eq!(numbers(&compaction.inputs), vec![2]);
+        assert!(picker.pick_old_file(&version, &[], 140, created).is_none());
+        assert!(picker.pick_old_file(&version, &[], 200, |_| 0).is_none());
+
+        // Files written long ago are rewritten, in place in the last level
+        let picker = CompactionPicker::new().with_time_based_compaction(0, 100);
+        let compaction = picker.pick_old_file(&version, &[], 260, created).unwrap();
+        assert_eq!(numbers(&compaction.inputs), vec![2]);
+        let compaction = picker.pick_old_file(&version, &[], 260, |_| 0).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (6, 6));
+        assert_eq!(numbers(&compaction.inputs), vec![3]);
+        assert!(compaction.next_level_inputs.is_empty());
+        assert!(!compaction.is_trivial_move(version.comparator().as_ref()));
+        assert!(
+            picker
+                .pick_old_file(&version, &[compaction], 260, |_| 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_trivial_move() {
+        let comparator = crate::util::comparator::bytewise_comparator();
+        let comparator = comparator.as_ref();
+
+        let compaction = Compaction::new(1, vec![file(1, 1024, "a", "b")], Vec::new());
+        assert!(compaction.is_trivial_move(comparator));
+
+        // Overlapping the next level
+        let compaction = Compaction::new(
+            1,
+            vec![file(1, 1024, "a", "b")],
+            vec![file(2, 1024, "b", "c")],
+        );
+        assert!(!compaction.is_trivial_move(comparator));
+
+        // L0 files only move together if they don't overlap
+        let compaction = Compaction::new(
+            0,
+            vec![file(2, 1024, "d", "e"), file(1, 1024, "a", "c")],
+            Vec::new(),
+        );
+        assert!(compaction.is_trivial_move(comparator));
+        let compaction = Compaction::new(
+            0,
+            vec![file(2, 1024, "c", "e"), file(1, 1024, "a", "c")],
+            Vec::new(),
+        );
+        assert!(!compaction.is_trivial_move(comparator));
+    }
+
+    #[test]
+    fn test_dynamic_level_targets() {
+        let picker = CompactionPicker::with_config(1000, 10, 4).with_dynamic_level_bytes(true);
+
+        // An empty database compacts L0 into the last level
+        let mut version = Version::new();
+        assert_eq!(picker.base_level(&version), 6);
+
+        // Too small for L5 to reach a tenth of the base size
+        version.add_file(6, file(1, 50, "a", "z"));
+        let (base_level, targets) = picker.level_targets(&version);
+        assert_eq!(base_level, 6);
+        assert_eq!(targets, [0, 0, 0, 0, 0, 0, 1000]);
+
+        version.add_file(6, file(2, 4950, "a", "z"));
+        let (base_level, targets) = picker.level_targets(&version);
+        assert_eq!(base_level, 5);
+        assert_eq!(targets, [0, 0, 0, 0, 0, 1000, 5000]);
+
+        version.add_file(6, file(3, 995_000, "a", "z"));
+        let (base_level, targets) = picker.level_targets(&version);
+        assert_eq!(base_level, 3);
+        assert_eq!(targets, [0, 0, 0, 1000, 10_000, 100_000, 1_000_000]);
+
+        // Without dynamic level bytes targets grow from level 1
+        let picker = CompactionPicker::with_config(1000, 10, 4);
+        let (base_level, targets) = picker.level_targets(&version);
+        assert_eq!(base_level, 1);
+        assert_eq!(targets[1..4], [1000, 10_000, 100_000]);
+    }
+
+    #[test]
+    fn test_dynamic_level_l0_compacts_into_base_level() {
+        let picker = CompactionPicker::with_config(1000, 10, 2).with_dynamic_level_bytes(true);
+        let mut version = Version::new();
+        version.add_file(0, file(1, 100, "a", "m"));
+        version.add_file(0, file(2, 100, "k", "z"));
+        version.add_file(0, file(6, 100, "d", "e"));
+        version.add_file(6, file(3, 50_000, "a", "c"));
+        version.add_file(6, file(4, 50_000, "x", "z"));
+
+        // Levels above the base level aren't scored
+        let scores = picker.get_all_scores(&version);
+        assert!(scores[1..4].iter().all(|s| s.score == 0.0));
+
+        let compaction = picker.pick(&version, &[]).unwrap();
+        assert_eq!(compaction.level, 0);
+        assert_eq!(compaction.output_level, 4);
+        assert_eq!(picker.output_level(&version, 0), 4);
+        assert!(compaction.next_level_inputs.is_empty());
+
+        // The base level moves down once it holds data
+        version.add_file(4, file(5, 1000, "a", "z"));
+        assert_eq!(picker.base_level(&version), 4);
+        let compaction = picker.pick_level(&version, 0, &[]).unwrap();
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![5]);
+    }
+
+    #[test]
+    fn test_pick_intra_level0() {
+        let picker = CompactionPicker::with_config(100_000, 10, 2);
+        let mut version = Version::new();
+        version.add_file(1, file(1, 1000, "a", "z"));
+        for number in 2..=4 {
+            version.add_file(0, file(number, 100, "a", "z"));
+        }
+
+        // L1 is busy with the L0 files flushed so far
+        let busy = picker.pick(&version, &[]).unwrap();
+        assert_eq!(busy.output_level, 1);
+        assert!(picker.pick(&version, std::slice::from_ref(&busy)).is_none());
+
+        // Once L0 holds 2 files over the trigger, the newest ones that aren't
+        // being compacted are merged within L0
+        for number in 5..=8 {
+            version.add_file(0, file(number, 100, "a", "z"));
+        }
+        let compaction = picker.pick(&version, std::slice::from_ref(&busy)).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (0, 0));
+        assert_eq!(numbers(&compaction.inputs), vec![5, 6, 7, 8]);
+        assert!(!compaction.is_trivial_move(version.comparator().as_ref()));
+
+        // Fewer than 4 idle files aren't merged
+        let running = [
+            busy,
+            Compaction::new(0, vec![file(5, 100, "a", "z")], Vec::new()),
+        ];
+        assert!(picker.pick(&version, &running).is_none());
+    }
+
+    #[test]
+    fn test_find_intra_l0_compaction() {
+        let level0: Vec<FileMetaData> = [400, 100, 100, 100]
+            .into_iter()
+            .enumerate()
+            .map(|(index, size)| file(index as u64 + 1, size, "a", "z"))
+            .collect();
+
+        // The large oldest file would raise the bytes per file removed
+        let inputs = find_intra_l0_compaction(&level0, |_| false, 2, u64::MAX, u64::MAX).unwrap();
+        assert_eq!(numbers(&inputs), vec![2, 3, 4]);
+
+        // Bounded by total size, bytes per file removed and idle files
+        let inputs = find_intra_l0_compaction(&level0, |_| false, 2, u64::MAX, 250).unwrap();
+        assert_eq!(numbers(&inputs), vec![3, 4]);
+        assert!(find_intra_l0_compaction(&level0, |_| false, 2, 150, u64::MAX).is_none());
+        assert!(
+            find_intra_l0_compaction(&level0, |f| f.number == 3, 2, u64::MAX, u64::MAX).is_none()
+        );
+        assert!(find_intra_l0_compaction(&level0, |_| false, 4, u64::MAX, u64::MAX).is_none());
+    }
+
+    #[test]
+    fn test_files_in_range() {
+        let mut version = leveled_version();
+        // Files 1 and 4 share the user key "b"
+        version.add_file(1, file(4, 1024, "b", "b"));
+        version.add_file(0, file(20, 1024, "x", "y"));
+        version.add_file(0, file(21, 1024, "a", "c"));
+        let all = |_: &FileMetaData| true;
+
+        let files = files_in_range(&version, 1, Some(b"c"), Some(b"e"), all);
+        assert_eq!(numbers(&files), vec![2, 3]);
+        let files = files_in_range(&version, 1, None, Some(b"a"), all);
+        assert_eq!(numbers(&files), vec![1, 4]);
+        let files = files_in_range(&version, 1, None, None, |f| f.number == 3);
+        assert_eq!(numbers(&files), vec![3]);
+        assert!(files_in_range(&version, 2, Some(b"c"), Some(b"d"), all).is_empty());
+
+        // Level 0 goes whole
+        let files = files_in_range(&version, 0, Some(b"y"), None, all);
+        assert_eq!(numbers(&files), vec![20, 21]);
+        assert!(files_in_range(&version, 0, Some(b"z"), None, all).is_empty());
+    }
+
+    #[test]
+    fn test_running_compactions() {
+        let running = RunningCompactions::default();
+        let comparator = crate::util::comparator::bytewise_comparator();
+        let compaction = Compaction::new(1, vec![file(1, 1024, "a", "b")], Vec::new());
+
+        let first = running.start(|_| Some(compaction.clone())).unwrap();
+        // A conflicting compaction isn't registered while the first runs
+        assert!(
+            running
+                .start(|running| {
+                    assert_eq!(running.len(), 1);
+                    None
+                })
+                .is_none()
+        );
+
+        let waiter = std::thread::scope(|scope| {
+            let handle = scope.spawn(|| {
+                running
+                    .start_when_clear(|| Some(compaction.clone()), comparator.as_ref())
+                    .map(|r| r.compaction().level)
+            });
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            assert!(!handle.is_finished());
+            drop(first);
+            handle.join().unwrap()
+        });
+        assert_eq!(waiter, Some(1));
+        assert!(running.start(|running| running.first().cloned()).is_none());
+    }
+
+    #[test]
+    fn test_exclusive_compaction() {
+        let running = RunningCompactions::default();
+        let comparator = crate::util::comparator::bytewise_comparator();
+        let compaction = Compaction::new(1, vec![file(1, 1024, "a", "b")], Vec::new());
+        let other = Compaction::new(1, vec![file(2, 1024, "x", "y")], Vec::new());
+
+        let first = running.start(|_| Some(compaction.clone())).unwrap();
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| running.exclusive());
+            // Waits for the running compaction
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            assert!(!handle.is_finished());
+            drop(first);
+            let exclusive = handle.join().unwrap();
+
+            // Nothing is picked until it ends, but manual compactions run
+            assert!(running.start(|_| Some(other.clone())).is_none());
+            let manual = running.start_when_clear(|| Some(other.clone()), comparator.as_ref());
+            assert!(manual.is_some());
+            drop(manual);
+            drop(exclusive);
+        });
+        assert!(running.start(|_| Some(other.clone())).is_some());
+    }
 }
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+use crate::version::{
+    compaction_picker::{Compaction, find_intra_l0_compaction},
+    version::Version,
+    version_edit::FileMetaData,
+};
+
+/// Options of `CompactionStyle::Fifo`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactionOptionsFIFO {
+    /// Total size of the level 0 files above which the oldest are dropped
+    /// Default: 1GB
+    pub max_table_files_size: u64,
+
+    /// Merge the newest small level 0 files into one once there are
+    /// `l0_compaction_trigger` of them, keeping the file count down
+    /// Default: false
+    pub allow_compaction: bool,
+}
+
+impl Default for CompactionOptionsFIFO {
+    fn default() -> Self {
+        CompactionOptionsFIFO {
+            max_table_files_size: 1024 * 1024 * 1024, // 1GB
+            allow_compaction: false,
+        }
+    }
+}
+
+/// Picks compactions for `CompactionStyle::Fifo`
+///
+/// All files are kept in level 0, in the order they were flushed. In
+/// order:
+///
+/// 1. With a `ttl`, the oldest files whose creation time is at least `ttl`
+///    seconds ago are dropped. Files of unknown creation time never expire.
+/// 2. The oldest files are dropped until the rest fit in
+///    `max_table_files_size`.
+/// 3. With `allow_compaction`, the newest files are merged into one once there
+///    are `level0_file_trigger` of them, if less than `write_buffer_size` plus
+///    10% is rewritten per file removed. Merged files are thus rarely merged
+///    again, and don't grow so large they never expire.
+///
+/// Nothing is picked while another compaction runs.
+pub struct FifoCompactionPicker {
+    options: CompactionOptionsFIFO,
+    ttl: u64,
+    level0_file_trigger: usize,
+    write_buffer_size: u64,
+}
+
+impl FifoCompactionPicker {
+    pub fn new(
+        options: CompactionOptionsFIFO,
+        ttl: u64,
+        level0_file_trigger: usize,
+        write_buffer_size: u64,
+    ) -> Self {
+        FifoCompactionPicker {
+            options,
+            ttl,
+            level0_file_trigger,
+            write_buffer_size,
+        }
+    }
+
+    /// Pick the files to drop or merge next
+    ///
+    /// `now` is in seconds since the epoch and `creation_time` gives the
+    /// creation time recorded in a file's table properties (0 = unknown).
+    pub fn pick(
+        &self,
+        version: &Version,
+        running: &[Compaction],
+        now: u64,
+        creation_time: impl Fn(&FileMetaData) -> u64,
+    ) -> Option<Compaction> {
+        if !running.is_empty() {
+            return None;
+        }
+        let level0 = version.get_level_files(0);
+
+        if self.ttl > 0 {
+            let expired: Vec<FileMetaData> = level0
+                .iter()
+                .take_while(|file| {
+                    let created = creation_time(file);
+                    created > 0 && created.saturating_add(self.ttl) <= now
+                })
+                .cloned()
+                .collect();
+            if !expired.is_empty() {
+                return Some(Compaction::deletion(0, expired));
+            }
+        }
+
+        let mut total: u64 = level0.iter().map(|file| file.file_size).sum();
+        if total > self.options.max_table_files_size {
+            let mut oldest = Vec::new();
+            for file in level0 {
+                if total <= self.options.max_table_files_size {
+                    break;
+                }
+                total -= file.file_size;
+                oldest.push(file.clone());
+            }
+            return Some(Compaction::deletion(0, oldest));
+        }
+
+        if self.options.allow_compaction && level0.len() >= self.level0_file_trigger {
+            // Flushed files may be slightly larger than the memtable
+            let max_bytes_per_deleted_file = self.write_buffer_size.saturating_mul(11) / 10;
+            let inputs = find_intra_l0_compaction(
+                level0,
+                |_| false,
+                self.level0_file_trigger,
+                max_bytes_per_deleted_file,
+                self.options.max_table_files_size,
+            )?;
+            return Some(Compaction::new(0, inputs, Vec::new()).with_output_level(0));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Slice;
+
+    fn file(number: u64, size: u64) -> FileMetaData {
+        FileMetaData::new(number, size, Slice::from("a"), Slice::from("z"))
+    }
+
+    fn numbers(files: &[FileMetaData]) -> Vec<u64> {
+        files.iter().map(|f| f.number).collect()
+    }
+
+    fn level0(sizes: &[u64]) -> Version {
+        let mut version = Version::new();
+        for (number, &size) in sizes.iter().enumerate() {
+            version.add_file(0, file(number as u64 + 1, size));
+        }
+        version
+    }
+
+    fn picker(max_table_files_size: u64, ttl: u64, allow_compaction: bool) -> FifoCompactionPicker {
+        let options = CompactionOptionsFIFO {
+            max_table_files_size,
+            allow_compaction,
+        };
+        FifoCompactionPicker::new(options, ttl, 4, 100)
+    }
+
+    #[test]
+    fn test_pick_within_budget() {
+        let version = level0(&[100, 100, 100]);
+        assert!(
+            picker(300, 0, false)
+                .pick(&version, &[], 0, |_| 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_pick_drops_oldest_over_budget() {
+        let version = level0(&[100, 50, 100, 100]);
+        let compaction = picker(220, 0, false).pick(&version, &[], 0, |_| 0).unwrap();
+        assert!(compaction.deletion_compaction);
+        assert_eq!((compaction.level, compaction.output_level), (0, 0));
+        assert_eq!(numbers(&compaction.inputs), vec![1, 2]);
+
+        // Nothing else is picked while files are being dropped
+        assert!(
+            picker(220, 0, false)
+                .pick(&version, &[compaction], 0, |_| 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_pick_ttl() {
+        // Created at 100, 200, 300 and unknown; TTL 150 at time 360
+        let version = level0(&[10, 10, 10, 10]);
+        let created = |file: &FileMetaData| [0, 100, 200, 300, 0][file.number as usize];
+        let compaction = picker(1000, 150, false)
+            .pick(&version, &[], 360, created)
+            .unwrap();
+        assert!(compaction.deletion_compaction);
+        assert_eq!(numbers(&compaction.inputs), vec![1, 2]);
+
+        // Files of unknown creation time don't expire
+        assert!(
+            picker(1000, 150, false)
+                .pick(&version, &[], 360, |_| 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_pick_intra_l0() {
+        // An older large file stops the merge of the newer small ones
+        let version = level0(&[500, 10, 10, 10, 10]);
+        let compaction = picker(10_000, 0, true)
+            .pick(&version, &[], 0, |_| 0)
+            .unwrap();
+        assert!(!compaction.deletion_compaction);
+        assert_eq!((compaction.level, compaction.output_level), (0, 0));
+        assert_eq!(numbers(&compaction.inputs), vec![2, 3, 4, 5]);
+
+        // Below the trigger, or without allow_compaction, nothing is merged
+        assert!(
+            picker(10_000, 0, true)
+                .pick(&level0(&[10, 10, 10]), &[], 0, |_| 0)
+                .is_none()
+        );
+        assert!(
+            picker(10_000, 0, false)
+                .pick(&version, &[], 0, |_| 0)
+                .is_none()
+        );
+    }
+}
@@ -17,6 +17,10 @@ pub struct LevelStats {
     pub bytes_written: AtomicU64,
     /// Number of compactions involving this level
     pub compactions: AtomicU64,
+    /// Number of trivial moves into this level
+    pub trivial_moves: AtomicU64,
+    /// Bytes moved into this level without being rewritten
+    pub bytes_moved: AtomicU64,
 }
 
 impl LevelStats {
@@ -51,6 +55,13 @@ impl LevelStats {
         self.compactions.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record files moved into this level without being rewritten
+    #[inline]
+    pub fn record_trivial_move(&self, bytes: u64) {
+        self.trivial_moves.fetch_add(1, Ordering::Relaxed);
+        self.bytes_moved.fetch_add(bytes, Ordering::Relaxed);
+    }
+
     /// Get current file count
     #[inline]
     pub fn num_files(&self) -> u64 {
@@ -93,6 +104,18 @@ impl LevelStats {
         self.compactions.load(Ordering::Relaxed)
     }
 
+    /// Get trivial move count
+    #[inline]
+    pub fn trivial_moves(&self) -> u64 {
+        self.trivial_moves.load(Ordering::Relaxed)
+    }
+
+    /// Get total bytes moved
+    #[inline]
+    pub fn bytes_moved(&self) -> u64 {
+        self.bytes_moved.load(Ordering::Relaxed)
+    }
+
     /// Calculate read amplification (bytes read / bytes written)
     /// Returns 0.0 if no writes yet
     pub fn read_amplification(&self) -> f64 {
@@ -123,6 +146,8 @@ impl LevelStats {
         self.writes.store(0, Ordering::Relaxed);
         self.bytes_written.store(0, Ordering::Relaxed);
         self.compactions.store(0, Ordering::Relaxed);
+        self.trivial_moves.store(0, Ordering::Relaxed);
+        self.bytes_moved.store(0, Ordering::Relaxed);
     }
 }
 
@@ -281,6 +306,9 @@ mod tests {
         stats.record_read(1000);
         stats.record_write(500);
         stats.record_compaction();
+        stats.record_trivial_move(4096);
+        assert_eq!(stats.trivial_moves(), 1);
+        assert_eq!(stats.bytes_moved(), 4096);
 
         stats.reset();
 
@@ -291,5 +319,7 @@ mod tests {
         assert_eq!(stats.writes(), 0);
         assert_eq!(stats.bytes_written(), 0);
         assert_eq!(stats.compactions(), 0);
+        assert_eq!(stats.trivial_moves(), 0);
+        assert_eq!(stats.bytes_moved(), 0);
     }
 }
@@ -1,6 +1,8 @@
 pub mod compaction_picker;
+pub mod fifo_compaction_picker;
 pub mod level_stats;
 pub mod subcompaction;
+pub mod universal_compaction_picker;
 #[allow(clippy::module_inception)]
 pub mod version;
 pub mod version_edit;
@@ -1,4 +1,9 @@
-use crate::{util::Slice, version::version_edit::FileMetaData};
+use std::{cmp::Ordering, sync::Arc};
+
+use crate::{
+    util::{Comparator, Slice, comparator::bytewise_comparator},
+    version::version_edit::FileMetaData,
+};
 
 /// A key range for subcompaction
 #[derive(Debug, Clone)]
@@ -13,14 +18,15 @@ impl KeyRange {
     }
 
     /// Check if a key falls within this range
-    pub fn contains(&self, key: &[u8]) -> bool {
-        key >= self.smallest.data() && key <= self.largest.data()
+    pub fn contains(&self, key: &[u8], comparator: &dyn Comparator) -> bool {
+        comparator.compare(key, self.smallest.data()) != Ordering::Less
+            && comparator.compare(key, self.largest.data()) != Ordering::Greater
     }
 
     /// Check if this range overlaps with another range
-    pub fn overlaps(&self, other: &KeyRange) -> bool {
-        !(self.largest.data() < other.smallest.data()
-            || other.largest.data() < self.smallest.data())
+    pub fn overlaps(&self, other: &KeyRange, comparator: &dyn Comparator) -> bool {
+        comparator.compare(self.largest.data(), other.smallest.data()) != Ordering::Less
+            && comparator.compare(other.largest.data(), self.smallest.data()) != Ordering::Less
     }
 }
 
@@ -94,11 +100,17 @@ impl Subcompaction {
 /// SubcompactionPlanner splits a compaction into parallel subcompactions
 pub struct SubcompactionPlanner {
     config: SubcompactionConfig,
+    comparator: Arc<dyn Comparator>,
 }
 
 impl SubcompactionPlanner {
     pub fn new(config: SubcompactionConfig) -> Self {
-        SubcompactionPlanner { config }
+        Self::with_comparator(config, bytewise_comparator())
+    }
+
+    /// Create a planner splitting the key space in the comparator's order
+    pub fn with_comparator(config: SubcompactionConfig, comparator: Arc<dyn Comparator>) -> Self {
+        SubcompactionPlanner { config, comparator }
     }
 
     /// Plan subcompactions for a given compaction
@@ -151,16 +163,16 @@ impl SubcompactionPlanner {
         let mut boundaries: Vec<Vec<u8>> = Vec::new();
 
         for file in level_files {
-            boundaries.push(file.smallest.data().to_vec());
-            boundaries.push(file.largest.data().to_vec());
+            boundaries.push(file.smallest_user_key().to_vec());
+            boundaries.push(file.largest_user_key().to_vec());
         }
         for file in next_level_files {
-            boundaries.push(file.smallest.data().to_vec());
-            boundaries.push(file.largest.data().to_vec());
+            boundaries.push(file.smallest_user_key().to_vec());
+            boundaries.push(file.largest_user_key().to_vec());
         }
 
         // Sort and deduplicate
-        boundaries.sort();
+        boundaries.sort_by(|a, b| self.comparator.compare(a, b));
         boundaries.dedup();
 
         if boundaries.len() < 2 {
@@ -192,8 +204,13 @@ impl SubcompactionPlanner {
         files
             .iter()
             .filter(|f| {
-                !(f.largest.data() < range.smallest.data()
-                    || f.smallest.data() > range.largest.data())
+                range.overlaps(
+                    &KeyRange::new(
+                        Slice::from(f.smallest_user_key()),
+                        Slice::from(f.largest_user_key()),
+                    ),
+                    self.comparator.as_ref(),
+                )
             })
             .cloned()
             .collect()
@@ -209,16 +226,23 @@ impl Default for SubcompactionPlanner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::{BytewiseComparator, ReverseBytewiseComparator};
 
     #[test]
     fn test_key_range_contains() {
         let range = KeyRange::new(Slice::from("a"), Slice::from("z"));
-
-        assert!(range.contains(b"a"));
-        assert!(range.contains(b"m"));
-        assert!(range.contains(b"z"));
-        assert!(!range.contains(b"0")); // Before range
-        assert!(!range.contains(b"zz")); // After range (but close)
+        let cmp = BytewiseComparator;
+
+        assert!(range.contains(b"a", &cmp));
+        assert!(range.contains(b"m", &cmp));
+        assert!(range.contains(b"z", &cmp));
+        assert!(!range.contains(b"0", &cmp)); // Before range
+        assert!(!range.contains(b"zz", &cmp)); // After range (but close)
+
+        // Under a reverse order the range runs from "z" down to "a"
+        let reverse = KeyRange::new(Slice::from("z"), Slice::from("a"));
+        assert!(reverse.contains(b"m", &ReverseBytewiseComparator));
+        assert!(!reverse.contains(b"zz", &ReverseBytewiseComparator));
     }
 
     #[test]
@@ -227,9 +251,11 @@ mod tests {
         let range2 = KeyRange::new(Slice::from("k"), Slice::from("z"));
         let range3 = KeyRange::new(Slice::from("n"), Slice::from("z"));
 
-        assert!(range1.overlaps(&range2)); // k-m overlaps
-        assert!(range2.overlaps(&range1)); // Symmetric
-        assert!(!range1.overlaps(&range3)); // No overlap
+        let cmp = BytewiseComparator;
+
+        assert!(range1.overlaps(&range2, &cmp)); // k-m overlaps
+        assert!(range2.overlaps(&range1, &cmp)); // Symmetric
+        assert!(!range1.overlaps(&range3, &cmp)); // No overlap
     }
 
     #[test]
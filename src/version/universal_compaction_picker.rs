@@ -0,0 +1,380 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    util::{Result, Status},
+    version::{
+        compaction_picker::Compaction,
+        version::Version,
+        version_edit::{FileMetaData, NUM_LEVELS},
+    },
+};
+
+/// Options of `CompactionStyle::Universal`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactionOptionsUniversal {
+    /// Percentage by which a sorted run may be larger than the newer runs
+    /// picked before it and still be merged with them
+    /// Default: 1
+    pub size_ratio: u64,
+
+    /// Fewest sorted runs a size-ratio compaction merges
+    /// Default: 2
+    pub min_merge_width: usize,
+
+    /// Most sorted runs a size-ratio compaction merges
+    /// Default: unlimited
+    pub max_merge_width: usize,
+
+    /// Size of all sorted runs but the oldest, as a percentage of the
+    /// oldest, at which all runs are merged into one
+    /// Default: 200
+    pub max_size_amplification_percent: u64,
+}
+
+impl Default for CompactionOptionsUniversal {
+    fn default() -> Self {
+        CompactionOptionsUniversal {
+            size_ratio: 1,
+            min_merge_width: 2,
+            max_merge_width: usize::MAX,
+            max_size_amplification_percent: 200,
+        }
+    }
+}
+
+impl CompactionOptionsUniversal {
+    /// Check the options for values compaction can't run with
+    pub fn validate(&self) -> Result<()> {
+        if self.min_merge_width < 2 || self.max_merge_width < self.min_merge_width {
+            return Err(Status::invalid_argument(
+                "Universal compaction merge widths must satisfy 2 <= min <= max",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Files read as one sorted run: a level 0 file or a whole level
+#[derive(Debug)]
+struct SortedRun {
+    level: usize,
+    files: Vec<FileMetaData>,
+    size: u64,
+}
+
+/// Sorted runs of a version, newest first
+fn sorted_runs(version: &Version) -> Vec<SortedRun> {
+    let level0 = version
+        .get_level_files(0)
+        .iter()
+        .rev()
+        .map(|file| SortedRun {
+            level: 0,
+            files: vec![file.clone()],
+            size: file.file_size,
+        });
+    let levels = (1..NUM_LEVELS).filter_map(|level| {
+        let files = version.get_level_files(level);
+        (!files.is_empty()).then(|| SortedRun {
+            level,
+            files: files.to_vec(),
+            size: files.iter().map(|f| f.file_size).sum(),
+        })
+    });
+    level0.chain(levels).collect()
+}
+
+/// Picks compactions for `CompactionStyle::Universal`
+///
+/// Every level 0 file and every non-empty lower level is a sorted run.
+/// Once there are at least `level0_file_trigger` runs, in order:
+///
+/// 1. All runs are merged into the last level when the newer runs take up more
+///    than `max_size_amplification_percent` of the oldest.
+/// 2. Starting from the newest run, consecutive runs are merged while each is
+///    at most `size_ratio` percent larger than the runs picked before it
+///    together, if at least `min_merge_width` qualify.
+/// 3. With more runs than the trigger, the newest ones are merged regardless of
+///    size, down to the trigger.
+///
+/// The output goes to the last level if the oldest run is merged, else to
+/// the level just above the next older run, so runs stay in age order.
+pub struct UniversalCompactionPicker {
+    options: CompactionOptionsUniversal,
+    level0_file_trigger: usize,
+}
+
+impl UniversalCompactionPicker {
+    pub fn new(options: CompactionOptionsUniversal, level0_file_trigger: usize) -> Self {
+        UniversalCompactionPicker {
+            options,
+            level0_file_trigger,
+        }
+    }
+
+    /// Pick the sorted runs to merge next
+    ///
+    /// Runs a `running` compaction reads are left alone.
+    pub fn pick(&self, version: &Version, running: &[Compaction]) -> Option<Compaction> {
+        let runs = sorted_runs(version);
+        if runs.len() < self.level0_file_trigger.max(2) {
+            return None;
+        }
+        let compacting: Vec<bool> = runs
+            .iter()
+            .map(|run| {
+                run.files.iter().any(|file| {
+                    running
+                        .iter()
+                        .any(|c| c.input_files().any(|f| f.number == file.number))
+                })
+            })
+            .collect();
+
+        let range = self
+            .pick_size_amplification(&runs, &compacting)
+            .or_else(|| {
+                self.pick_size_ratio(
+                    &runs,
+                    &compacting,
+                    self.options.size_ratio,
+                    self.options.max_merge_width,
+                )
+            })
+            .or_else(|| {
+                let idle = compacting.iter().filter(|&&c| !c).count();
+                if idle <= self.level0_file_trigger {
+                    return None;
+                }
+                self.pick_size_ratio(
+                    &runs,
+                    &compacting,
+                    u64::MAX,
+                    idle - self.level0_file_trigger + 1,
+                )
+            })?;
+
+        let compaction = compaction_of(&runs, range);
+        let comparator = version.comparator().as_ref();
+        if running
+            .iter()
+            .any(|other| compaction.conflicts_with(other, comparator))
+        {
+            return None;
+        }
+        Some(compaction)
+    }
+
+    /// All runs, if none is being compacted and the newer ones are too
+    /// large relative to the oldest
+    fn pick_size_amplification(
+        &self,
+        runs: &[SortedRun],
+        compacting: &[bool],
+    ) -> Option<std::ops::Range<usize>> {
+        if compacting.iter().any(|&c| c) {
+            return None;
+        }
+        let (oldest, newer) = runs.split_last()?;
+        let newer_size: u64 = newer.iter().map(|run| run.size).sum();
+        let limit =
+            u128::from(self.options.max_size_amplification_percent) * u128::from(oldest.size);
+        (u128::from(newer_size) * 100 >= limit).then_some(0..runs.len())
+    }
+
+    /// The newest consecutive runs no larger than `size_ratio` percent
+    /// above the runs before them, at most `max_width` of them
+    fn pick_size_ratio(
+        &self,
+        runs: &[SortedRun],
+        compacting: &[bool],
+        size_ratio: u64,
+        max_width: usize,
+    ) -> Option<std::ops::Range<usize>> {
+        let max_width = max_width.min(self.options.max_merge_width);
+        (0..runs.len()).find_map(|start| {
+            if compacting[start] {
+                return None;
+            }
+            let mut size = u128::from(runs[start].size);
+            let mut end = start + 1;
+            while end < runs.len()
+                && end - start < max_width
+                && !compacting[end]
+                && u128::from(runs[end].size) * 100 <= size * (100 + u128::from(size_ratio))
+            {
+                size += u128::from(runs[end].size);
+                end += 1;
+            }
+            (end - start >= self.options.min_merge_width).then_some(start..end)
+        })
+    }
+}
+
+/// Compaction merging a range of sorted runs
+fn compaction_of(runs: &[SortedRun], range: std::ops::Range<usize>) -> Compaction {
+    let output_level = match runs.get(range.end) {
+        None => NUM_LEVELS - 1,
+        Some(next) if next.level == 0 => 0,
+        Some(next) => next.level - 1,
+    };
+    let level = runs[range.start].level;
+
+    let mut compaction =
+        Compaction::new(level, Vec::new(), Vec::new()).with_output_level(output_level);
+    for run in &runs[range] {
+        if run.level == level {
+            compaction.inputs.extend(run.files.iter().cloned());
+        } else if run.level == output_level {
+            compaction.next_level_inputs = run.files.clone();
+        } else {
+            compaction
+                .intermediate_inputs
+                .push((run.level, run.files.clone()));
+        }
+    }
+    compaction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Slice;
+
+    fn file(number: u64, size: u64) -> FileMetaData {
+        FileMetaData::new(number, size, Slice::from("a"), Slice::from("z"))
+    }
+
+    fn numbers(files: &[FileMetaData]) -> Vec<u64> {
+        files.iter().map(|f| f.number).collect()
+    }
+
+    fn picker() -> UniversalCompactionPicker {
+        UniversalCompactionPicker::new(CompactionOptionsUniversal::default(), 4)
+    }
+
+    #[test]
+    fn test_sorted_runs() {
+        let mut version = Version::new();
+        version.add_file(0, file(1, 10));
+        version.add_file(0, file(2, 10));
+        version.add_file(4, file(3, 100));
+        version.add_file(4, file(4, 100));
+
+        let runs = sorted_runs(&version);
+        let levels: Vec<usize> = runs.iter().map(|run| run.level).collect();
+        assert_eq!(levels, vec![0, 0, 4]);
+        assert_eq!(numbers(&runs[0].files), vec![2]);
+        assert_eq!(runs[2].size, 200);
+    }
+
+    #[test]
+    fn test_pick_below_trigger() {
+        let mut version = Version::new();
+        for number in 1..=3 {
+            version.add_file(0, file(number, 10));
+        }
+        assert!(picker().pick(&version, &[]).is_none());
+    }
+
+    #[test]
+    fn test_pick_size_amplification() {
+        // 3 x 100 newer bytes over 100 in the last level: 300%
+        let mut version = Version::new();
+        version.add_file(6, file(1, 100));
+        for number in 2..=4 {
+            version.add_file(0, file(number, 100));
+        }
+
+        let compaction = picker().pick(&version, &[]).unwrap();
+        assert_eq!(compaction.level, 0);
+        assert_eq!(compaction.output_level, 6);
+        assert_eq!(numbers(&compaction.inputs), vec![4, 3, 2]);
+        assert_eq!(numbers(&compaction.next_level_inputs), vec![1]);
+    }
+
+    #[test]
+    fn test_pick_size_ratio() {
+        // Runs of 10, 10, 15 and 1000 bytes: the first three merge into
+        // the level above the last run
+        let mut version = Version::new();
+        version.add_file(5, file(1, 1000));
+        version.add_file(0, file(2, 15));
+        version.add_file(0, file(3, 10));
+        version.add_file(0, file(4, 10));
+
+        let compaction = picker().pick(&version, &[]).unwrap();
+        assert_eq!(compaction.output_level, 4);
+        assert_eq!(numbers(&compaction.inputs), vec![4, 3, 2]);
+        assert!(compaction.next_level_inputs.is_empty());
+    }
+
+    #[test]
+    fn test_pick_into_level0_and_across_levels() {
+        // 10, 10, 1000 in L0: the newest two merge into L0
+        let mut version = Version::new();
+        version.add_file(3, file(1, 10_000));
+        version.add_file(5, file(2, 100_000));
+        version.add_file(0, file(3, 1000));
+        version.add_file(0, file(4, 10));
+        version.add_file(0, file(5, 10));
+
+        let compaction = picker().pick(&version, &[]).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (0, 0));
+        assert_eq!(numbers(&compaction.inputs), vec![5, 4]);
+
+        // While those are compacted, nothing else qualifies by size ratio
+        // and 3 runs are left, below the trigger
+        assert!(picker().pick(&version, &[compaction]).is_none());
+
+        // Six runs of similar sizes with the oldest ones in levels
+        let mut version = Version::new();
+        version.add_file(6, file(1, 1_000_000));
+        version.add_file(3, file(2, 100));
+        version.add_file(4, file(3, 100));
+        for number in 4..=6 {
+            version.add_file(0, file(number, 100));
+        }
+        let compaction = picker().pick(&version, &[]).unwrap();
+        assert_eq!((compaction.level, compaction.output_level), (0, 5));
+        assert_eq!(numbers(&compaction.inputs), vec![6, 5, 4]);
+        let intermediate: Vec<usize> = compaction
+            .intermediate_inputs
+            .iter()
+            .map(|(level, _)| *level)
+            .collect();
+        assert_eq!(intermediate, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_pick_reduces_sorted_runs() {
+        // Sizes growing too fast for size ratio: the newest runs are merged
+        // down to the trigger
+        let mut version = Version::new();
+        version.add_file(6, file(1, 1_000_000));
+        for (number, size) in [(2, 100_000), (3, 10_000), (4, 1000), (5, 100), (6, 10)] {
+            version.add_file(0, file(number, size));
+        }
+
+        let compaction = picker().pick(&version, &[]).unwrap();
+        assert_eq!(compaction.output_level, 0);
+        assert_eq!(numbers(&compaction.inputs), vec![6, 5, 4]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(CompactionOptionsUniversal::default().validate().is_ok());
+        let options = CompactionOptionsUniversal {
+            min_merge_width: 1,
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+        let options = CompactionOptionsUniversal {
+            min_merge_width: 4,
+            max_merge_width: 3,
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
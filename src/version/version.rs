@@ -1,11 +1,10 @@
-use std::sync::Arc;
+use std::{cmp::Ordering, sync::Arc};
 
 use crate::{
-    memtable::memtable::InternalKey,
-    util::Slice,
+    util::{Comparator, Slice, comparator::bytewise_comparator},
     version::{
         level_stats::AllLevelStats,
-        version_edit::{FileMetaData, NUM_LEVELS},
+        version_edit::{FileMetaData, NUM_LEVELS, VersionEdit},
     },
 };
 
@@ -13,21 +12,39 @@ use crate::{
 ///
 /// Level 0: SSTables may have overlapping keys (from MemTable flush)
 /// Level 1+: SSTables have non-overlapping keys
+///
+/// File key ranges are ordered by the column family's comparator.
 pub struct Version {
     /// Files at each level
     pub files: Vec<Vec<FileMetaData>>,
     /// Per-level statistics
     pub level_stats: Arc<AllLevelStats>,
+    /// Largest user key of the last round-robin compaction of each level
+    compact_cursors: Vec<Option<Slice>>,
+    /// Comparator of user keys
+    comparator: Arc<dyn Comparator>,
 }
 
 impl Version {
     pub fn new() -> Self {
+        Self::with_comparator(bytewise_comparator())
+    }
+
+    /// Create an empty Version ordering keys with `comparator`
+    pub fn with_comparator(comparator: Arc<dyn Comparator>) -> Self {
         Version {
             files: vec![Vec::new(); NUM_LEVELS],
             level_stats: Arc::new(AllLevelStats::new(NUM_LEVELS)),
+            compact_cursors: vec![None; NUM_LEVELS],
+            comparator,
         }
     }
 
+    /// Get the comparator of user keys
+    pub fn comparator(&self) -> &Arc<dyn Comparator> {
+        &self.comparator
+    }
+
     /// Update level statistics based on current files
     fn update_level_stats(&self, level: usize) {
         if let Some(stats) = self.level_stats.level(level) {
@@ -67,32 +84,9 @@ impl Version {
             self.update_level_stats(level);
             // Sort files at level 1+ by smallest key
             if level > 0 {
+                let comparator = Arc::clone(&self.comparator);
                 self.files[level].sort_by(|a, b| {
-                    // Decode InternalKeys for proper comparison
-                    let key_a = match InternalKey::decode(&a.smallest) {
-                        Ok(k) => k,
-                        Err(_) => {
-                            // If decode fails, fall back to raw byte comparison
-                            return a.smallest.data().cmp(b.smallest.data());
-                        },
-                    };
-                    let key_b = match InternalKey::decode(&b.smallest) {
-                        Ok(k) => k,
-                        Err(_) => {
-                            // If decode fails, fall back to raw byte comparison
-                            return a.smallest.data().cmp(b.smallest.data());
-                        },
-                    };
-
-                    // Compare user keys first
-                    match key_a.user_key().data().cmp(key_b.user_key().data()) {
-                        std::cmp::Ordering::Equal => {
-                            // For same user key, sort by sequence (descending - higher sequence
-                            // first)
-                            key_b.sequence().cmp(&key_a.sequence())
-                        },
-                        other => other,
-                    }
+                    comparator.compare(a.smallest_user_key(), b.smallest_user_key())
                 });
             }
         }
@@ -106,6 +100,49 @@ impl Version {
         }
     }
 
+    /// Apply the file changes and compact cursors of an edit
+    ///
+    /// Level 0 is ordered from oldest to newest data. Level 0 files added
+    /// by an edit that also deletes level 0 files (a universal compaction
+    /// into level 0) take the place of the oldest deleted file, rather than
+    /// that of the newest file.
+    pub fn apply(&mut self, edit: &VersionEdit) {
+        let mut l0_position = self.files[0].iter().position(|f| {
+            edit.deleted_files
+                .iter()
+                .any(|&(level, number)| level == 0 && number == f.number)
+        });
+
+        for &(level, file_number) in &edit.deleted_files {
+            self.remove_file(level, file_number);
+        }
+        for (level, file) in &edit.new_files {
+            match l0_position {
+                Some(ref mut position) if *level == 0 => {
+                    self.files[0].insert(*position, file.clone());
+                    self.update_level_stats(0);
+                    *position += 1;
+                },
+                _ => self.add_file(*level, file.clone()),
+            }
+        }
+        for (level, key) in &edit.compact_cursors {
+            self.set_compact_cursor(*level, key.clone());
+        }
+    }
+
+    /// Key round-robin compaction of `level` continues after
+    pub fn compact_cursor(&self, level: usize) -> Option<&Slice> {
+        self.compact_cursors.get(level)?.as_ref()
+    }
+
+    /// Set the key round-robin compaction of `level` continues after
+    pub fn set_compact_cursor(&mut self, level: usize, key: Slice) {
+        if level < NUM_LEVELS {
+            self.compact_cursors[level] = Some(key);
+        }
+    }
+
     /// Get overlapping files at level 0 for a key range
     /// Level 0 files can overlap, so we need to check all files
     pub fn get_overlapping_level0_files(
@@ -116,7 +153,7 @@ impl Version {
         let mut result = Vec::new();
 
         for file in &self.files[0] {
-            if Self::key_range_overlaps(smallest, largest, &file.smallest, &file.largest) {
+            if self.key_range_overlaps(smallest.data(), largest.data(), file) {
                 result.push(file.clone());
             }
         }
@@ -144,14 +181,19 @@ impl Version {
 
         // Binary search for the first file that might overlap
         let files = &self.files[level];
-        let start_idx = files
-            .iter()
-            .position(|f| f.largest.data() >= smallest.data())
-            .unwrap_or(files.len());
+        let start_idx = files.partition_point(|f| {
+            self.comparator
+                .compare(f.largest_user_key(), smallest.data())
+                == Ordering::Less
+        });
 
         // Add all files that overlap
         for file in files.iter().skip(start_idx) {
-            if file.smallest.data() > largest.data() {
+            if self
+                .comparator
+                .compare(file.smallest_user_key(), largest.data())
+                == Ordering::Greater
+            {
                 break;
             }
             result.push(file.clone());
@@ -160,16 +202,12 @@ impl Version {
         result
     }
 
-    /// Check if two key ranges overlap
-    fn key_range_overlaps(
-        a_smallest: &Slice,
-        a_largest: &Slice,
-        b_smallest: &Slice,
-        b_largest: &Slice,
-    ) -> bool {
+    /// Check if a key range overlaps the key range of a file
+    fn key_range_overlaps(&self, smallest: &[u8], largest: &[u8], file: &FileMetaData) -> bool {
         // Ranges overlap if they're not disjoint
         // Disjoint means: a is entirely before b OR b is entirely before a
-        !(a_largest.data() < b_smallest.data() || b_largest.data() < a_smallest.data())
+        !(self.comparator.compare(largest, file.smallest_user_key()) == Ordering::Less
+            || self.comparator.compare(file.largest_user_key(), smallest) == Ordering::Less)
     }
 
     /// Pick level for compaction using priority-based selection
@@ -235,6 +273,44 @@ mod tests {
         assert_eq!(version.num_files(), 0);
     }
 
+    #[test]
+    fn test_apply_keeps_level0_order() {
+        let mut version = Version::new();
+        for number in 1..=4 {
+            version.add_file(
+                0,
+                FileMetaData::new(number, 1024, Slice::from("a"), Slice::from("z")),
+            );
+        }
+
+        // Files 2 and 3 are merged into file 9, which holds older data
+        // than file 4
+        let mut edit = VersionEdit::new();
+        edit.delete_file(0, 2);
+        edit.delete_file(0, 3);
+        edit.add_file(
+            0,
+            FileMetaData::new(9, 2048, Slice::from("a"), Slice::from("z")),
+        );
+        version.apply(&edit);
+        let numbers: Vec<u64> = version
+            .get_level_files(0)
+            .iter()
+            .map(|f| f.number)
+            .collect();
+        assert_eq!(numbers, vec![1, 9, 4]);
+
+        // New files without L0 deletions are the newest
+        let mut edit = VersionEdit::new();
+        edit.add_file(
+            0,
+            FileMetaData::new(10, 1024, Slice::from("a"), Slice::from("z")),
+        );
+        edit.delete_file(1, 5);
+        version.apply(&edit);
+        assert_eq!(version.get_level_files(0).last().unwrap().number, 10);
+    }
+
     #[test]
     fn test_overlapping_level0_files() {
         let mut version = Version::new();
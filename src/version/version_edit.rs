@@ -10,10 +10,22 @@ pub struct FileMetaData {
     pub number: u64,
     /// File size in bytes
     pub file_size: u64,
-    /// Smallest key in this file
+    /// Smallest user key in this file
     pub smallest: Slice,
-    /// Largest key in this file
+    /// Largest user key in this file
     pub largest: Slice,
+    /// Number of entries (0 if unknown, e.g. recorded by an older release)
+    pub num_entries: u64,
+    /// Number of deletion markers among the entries
+    pub num_deletions: u64,
+    /// The file should be compacted even if its level doesn't need it
+    /// (see `ColumnFamilyOptions::compact_on_deletion`)
+    pub marked_for_compaction: bool,
+    /// Seconds since the epoch the file's oldest data was written at (0 if
+    /// unknown), as in its table properties
+    pub creation_time: u64,
+    /// Seconds since the epoch the file itself was written at (0 if unknown)
+    pub file_creation_time: u64,
 }
 
 impl FileMetaData {
@@ -23,8 +35,52 @@ impl FileMetaData {
             file_size,
             smallest,
             largest,
+            num_entries: 0,
+            num_deletions: 0,
+            marked_for_compaction: false,
+            creation_time: 0,
+            file_creation_time: 0,
         }
     }
+
+    /// File size weighted up by the deletion markers in the file
+    ///
+    /// Deletions free space only once compacted down, so in a file where
+    /// they make up at least half of the entries, each marker beyond the
+    /// other entries counts as twice an average entry.
+    pub fn compensated_file_size(&self, average_entry_size: u64) -> u64 {
+        if self.num_deletions * 2 < self.num_entries {
+            return self.file_size;
+        }
+        (self.num_deletions * 2 - self.num_entries)
+            .saturating_mul(average_entry_size)
+            .saturating_mul(2)
+            .saturating_add(self.file_size)
+    }
+
+    /// User key of `smallest`
+    ///
+    /// Older releases recorded the InternalKeys of flushed and ingested
+    /// tables as bounds; their user keys are returned.
+    pub fn smallest_user_key(&self) -> &[u8] {
+        bound_user_key(self.smallest.data())
+    }
+
+    /// User key of `largest` (see `smallest_user_key`)
+    pub fn largest_user_key(&self) -> &[u8] {
+        bound_user_key(self.largest.data())
+    }
+}
+
+/// User key of a file bound that may be an encoded InternalKey
+fn bound_user_key(key: &[u8]) -> &[u8] {
+    if key.len() >= 11 {
+        let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+        if key.len() == len + 11 {
+            return &key[2..2 + len];
+        }
+    }
+    key
 }
 
 /// A VersionEdit represents the changes between two versions
@@ -32,7 +88,7 @@ impl FileMetaData {
 /// and Column Family create/drop operations
 #[derive(Debug, Default)]
 pub struct VersionEdit {
-    /// Comparator name
+    /// Comparator name of the default Column Family
     pub comparator: Option<String>,
     /// Log file number
     pub log_number: Option<u64>,
@@ -50,6 +106,15 @@ pub struct VersionEdit {
     pub created_column_families: Vec<(u32, String)>,
     /// Column Families to drop: cf_id
     pub dropped_column_families: Vec<u32>,
+    /// Options of Column Families: (cf_id, encoded options)
+    pub column_family_options: Vec<(u32, String)>,
+    /// Comparators of non-default Column Families: (cf_id, comparator name)
+    pub column_family_comparators: Vec<(u32, String)>,
+    /// Timestamps below which Column Families may drop old versions:
+    /// (cf_id, full_history_ts_low)
+    pub full_history_ts_low: Vec<(u32, u64)>,
+    /// Keys round-robin compaction continues after: (level, user key)
+    pub compact_cursors: Vec<(usize, Slice)>,
 }
 
 impl VersionEdit {
@@ -93,6 +158,22 @@ impl VersionEdit {
         self.dropped_column_families.push(cf_id);
     }
 
+    pub fn set_column_family_options(&mut self, cf_id: u32, options: String) {
+        self.column_family_options.push((cf_id, options));
+    }
+
+    pub fn set_column_family_comparator(&mut self, cf_id: u32, name: String) {
+        self.column_family_comparators.push((cf_id, name));
+    }
+
+    pub fn set_full_history_ts_low(&mut self, cf_id: u32, timestamp: u64) {
+        self.full_history_ts_low.push((cf_id, timestamp));
+    }
+
+    pub fn set_compact_cursor(&mut self, level: usize, key: Slice) {
+        self.compact_cursors.push((level, key));
+    }
+
     /// Encode VersionEdit to bytes for MANIFEST file
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::new();
@@ -146,6 +227,29 @@ impl VersionEdit {
             buf.extend_from_slice(largest_data);
         }
 
+        // Tag: 13=file_stats (of a new file above)
+        for (_, file) in &self.new_files {
+            if file.num_entries == 0 && !file.marked_for_compaction {
+                continue;
+            }
+            buf.push(13);
+            buf.extend_from_slice(&file.number.to_le_bytes());
+            buf.extend_from_slice(&file.num_entries.to_le_bytes());
+            buf.extend_from_slice(&file.num_deletions.to_le_bytes());
+            buf.push(file.marked_for_compaction as u8);
+        }
+
+        // Tag: 14=file_times (of a new file above)
+        for (_, file) in &self.new_files {
+            if file.creation_time == 0 && file.file_creation_time == 0 {
+                continue;
+            }
+            buf.push(14);
+            buf.extend_from_slice(&file.number.to_le_bytes());
+            buf.extend_from_slice(&file.creation_time.to_le_bytes());
+            buf.extend_from_slice(&file.file_creation_time.to_le_bytes());
+        }
+
         // Tag: 7=create_column_family
         for (cf_id, cf_name) in &self.created_column_families {
             buf.push(7);
@@ -161,6 +265,39 @@ impl VersionEdit {
             buf.extend_from_slice(&cf_id.to_le_bytes());
         }
 
+        // Tag: 9=column_family_options
+        for (cf_id, options) in &self.column_family_options {
+            buf.push(9);
+            buf.extend_from_slice(&cf_id.to_le_bytes());
+            let options_bytes = options.as_bytes();
+            buf.extend_from_slice(&(options_bytes.len() as u32).to_le_bytes());
+            buf.extend_from_slice(options_bytes);
+        }
+
+        // Tag: 10=column_family_comparator
+        for (cf_id, name) in &self.column_family_comparators {
+            buf.push(10);
+            buf.extend_from_slice(&cf_id.to_le_bytes());
+            let name_bytes = name.as_bytes();
+            buf.extend_from_slice(&(name_bytes.len() as u32).to_le_bytes());
+            buf.extend_from_slice(name_bytes);
+        }
+
+        // Tag: 11=full_history_ts_low
+        for (cf_id, timestamp) in &self.full_history_ts_low {
+            buf.push(11);
+            buf.extend_from_slice(&cf_id.to_le_bytes());
+            buf.extend_from_slice(&timestamp.to_le_bytes());
+        }
+
+        // Tag: 12=compact_cursor
+        for (level, key) in &self.compact_cursors {
+            buf.push(12);
+            buf.push(*level as u8);
+            buf.extend_from_slice(&(key.size() as u32).to_le_bytes());
+            buf.extend_from_slice(key.data());
+        }
+
         buf
     }
 
@@ -326,6 +463,112 @@ impl VersionEdit {
                     pos += 4;
                     edit.drop_column_family(cf_id);
                 },
+                9 => {
+                    // Column family options
+                    if pos + 8 > data.len() {
+                        return Err(Status::corruption("Invalid CF options entry"));
+                    }
+                    let cf_id = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                    pos += 4;
+                    let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+                    pos += 4;
+
+                    if pos + len > data.len() {
+                        return Err(Status::corruption("CF options data truncated"));
+                    }
+                    let options = String::from_utf8(data[pos..pos + len].to_vec())
+                        .map_err(|_| Status::corruption("Invalid UTF-8 in CF options"))?;
+                    pos += len;
+
+                    edit.set_column_family_options(cf_id, options);
+                },
+                10 => {
+                    // Column family comparator
+                    if pos + 8 > data.len() {
+                        return Err(Status::corruption("Invalid CF comparator entry"));
+                    }
+                    let cf_id = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                    pos += 4;
+                    let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+                    pos += 4;
+
+                    if pos + len > data.len() {
+                        return Err(Status::corruption("CF comparator data truncated"));
+                    }
+                    let name = String::from_utf8(data[pos..pos + len].to_vec())
+                        .map_err(|_| Status::corruption("Invalid UTF-8 in CF comparator"))?;
+                    pos += len;
+
+                    edit.set_column_family_comparator(cf_id, name);
+                },
+                11 => {
+                    // Full history timestamp low watermark
+                    if pos + 12 > data.len() {
+                        return Err(Status::corruption("Invalid full_history_ts_low entry"));
+                    }
+                    let cf_id = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+                    let timestamp = u64::from_le_bytes(data[pos + 4..pos + 12].try_into().unwrap());
+                    pos += 12;
+                    edit.set_full_history_ts_low(cf_id, timestamp);
+                },
+                12 => {
+                    // Compact cursor
+                    if pos + 5 > data.len() {
+                        return Err(Status::corruption("Invalid compact cursor entry"));
+                    }
+                    let level = data[pos] as usize;
+                    let len =
+                        u32::from_le_bytes(data[pos + 1..pos + 5].try_into().unwrap()) as usize;
+                    pos += 5;
+
+                    if pos + len > data.len() {
+                        return Err(Status::corruption("Compact cursor data truncated"));
+                    }
+                    edit.set_compact_cursor(level, Slice::from(&data[pos..pos + len]));
+                    pos += len;
+                },
+                13 => {
+                    // File stats
+                    if pos + 25 > data.len() {
+                        return Err(Status::corruption("Invalid file stats entry"));
+                    }
+                    let number = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+                    let num_entries =
+                        u64::from_le_bytes(data[pos + 8..pos + 16].try_into().unwrap());
+                    let num_deletions =
+                        u64::from_le_bytes(data[pos + 16..pos + 24].try_into().unwrap());
+                    let marked_for_compaction = data[pos + 24] != 0;
+                    pos += 25;
+
+                    let (_, file) = edit
+                        .new_files
+                        .iter_mut()
+                        .find(|(_, file)| file.number == number)
+                        .ok_or_else(|| Status::corruption("File stats of a file not added"))?;
+                    file.num_entries = num_entries;
+                    file.num_deletions = num_deletions;
+                    file.marked_for_compaction = marked_for_compaction;
+                },
+                14 => {
+                    // File times
+                    if pos + 24 > data.len() {
+                        return Err(Status::corruption("Invalid file times entry"));
+                    }
+                    let number = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+                    let creation_time =
+                        u64::from_le_bytes(data[pos + 8..pos + 16].try_into().unwrap());
+                    let file_creation_time =
+                        u64::from_le_bytes(data[pos + 16..pos + 24].try_into().unwrap());
+                    pos += 24;
+
+                    let (_, file) = edit
+                        .new_files
+                        .iter_mut()
+                        .find(|(_, file)| file.number == number)
+                        .ok_or_else(|| Status::corruption("File times of a file not added"))?;
+                    file.creation_time = creation_time;
+                    file.file_creation_time = file_creation_time;
+                },
                 _ => {
                     return Err(Status::corruption(format!(
                         "Unknown tag in VersionEdit: {tag}"
@@ -369,6 +612,59 @@ mod tests {
         assert_eq!(decoded.deleted_files[0], (1, 5));
     }
 
+    #[test]
+    fn test_file_stats_encode_decode() {
+        let mut file = FileMetaData::new(7, 4096, Slice::from("a"), Slice::from("z"));
+        file.num_entries = 100;
+        file.num_deletions = 80;
+        file.marked_for_compaction = true;
+        let mut edit = VersionEdit::new();
+        edit.add_file(1, file);
+        edit.add_file(
+            1,
+            FileMetaData::new(8, 4096, Slice::from("a"), Slice::from("z")),
+        );
+
+        let decoded = VersionEdit::decode(&edit.encode()).unwrap();
+        let (_, file) = &decoded.new_files[0];
+        assert_eq!((file.num_entries, file.num_deletions), (100, 80));
+        assert!(file.marked_for_compaction);
+        let (_, file) = &decoded.new_files[1];
+        assert_eq!((file.num_entries, file.num_deletions), (0, 0));
+        assert!(!file.marked_for_compaction);
+    }
+
+    #[test]
+    fn test_file_times_encode_decode() {
+        let mut file = FileMetaData::new(7, 4096, Slice::from("a"), Slice::from("z"));
+        file.creation_time = 1_700_000_000;
+        file.file_creation_time = 1_700_000_500;
+        let mut edit = VersionEdit::new();
+        edit.add_file(1, file);
+
+        let decoded = VersionEdit::decode(&edit.encode()).unwrap();
+        let (_, file) = &decoded.new_files[0];
+        assert_eq!(file.creation_time, 1_700_000_000);
+        assert_eq!(file.file_creation_time, 1_700_000_500);
+
+        // Times of a file the edit doesn't add are corrupt
+        let mut data = vec![14];
+        data.extend_from_slice(&[0; 24]);
+        assert!(VersionEdit::decode(&data).is_err());
+    }
+
+    #[test]
+    fn test_compensated_file_size() {
+        let mut file = FileMetaData::new(1, 1000, Slice::from("a"), Slice::from("z"));
+        assert_eq!(file.compensated_file_size(50), 1000);
+        file.num_entries = 100;
+        file.num_deletions = 40;
+        assert_eq!(file.compensated_file_size(50), 1000);
+        // 60 markers beyond the other entries, each counted as 2 x 50 bytes
+        file.num_deletions = 80;
+        assert_eq!(file.compensated_file_size(50), 7000);
+    }
+
     #[test]
     fn test_file_metadata() {
         let file = FileMetaData::new(42, 8192, Slice::from("aaa"), Slice::from("zzz"));
@@ -427,4 +723,44 @@ mod tests {
         assert_eq!(decoded.dropped_column_families, vec![2]);
         assert_eq!(decoded.new_files.len(), 1);
     }
+
+    #[test]
+    fn test_column_family_options_encode_decode() {
+        let mut edit = VersionEdit::new();
+        edit.create_column_family(1, "users".to_string());
+        edit.set_column_family_options(1, r#"{"write_buffer_size":1024}"#.to_string());
+        edit.set_column_family_comparator(1, "rocksdb.ReverseBytewiseComparator".to_string());
+        edit.set_full_history_ts_low(1, 42);
+
+        let decoded = VersionEdit::decode(&edit.encode()).unwrap();
+        assert_eq!(
+            decoded.column_family_options,
+            vec![(1, r#"{"write_buffer_size":1024}"#.to_string())]
+        );
+        assert_eq!(
+            decoded.column_family_comparators,
+            vec![(1, "rocksdb.ReverseBytewiseComparator".to_string())]
+        );
+        assert_eq!(decoded.full_history_ts_low, vec![(1, 42)]);
+
+        // Truncated options are corruption
+        let encoded = edit.encode();
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_compact_cursor_encode_decode() {
+        let mut edit = VersionEdit::new();
+        edit.set_compact_cursor(2, Slice::from("key042"));
+        edit.delete_file(2, 7);
+
+        let decoded = VersionEdit::decode(&edit.encode()).unwrap();
+        assert_eq!(decoded.compact_cursors, vec![(2, Slice::from("key042"))]);
+        assert_eq!(decoded.deleted_files, vec![(2, 7)]);
+
+        let mut edit = VersionEdit::new();
+        edit.set_compact_cursor(1, Slice::from("key"));
+        let encoded = edit.encode();
+        assert!(VersionEdit::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
 }
@@ -9,7 +9,10 @@ use std::{
 use parking_lot::RwLock;
 
 use crate::{
-    util::Result,
+    util::{
+        Comparator, Result, Status,
+        comparator::{bytewise_comparator, comparator_name_matches},
+    },
     version::{version::Version, version_edit::VersionEdit},
     wal,
 };
@@ -20,6 +23,9 @@ use crate::{
 /// - Current version (snapshot of all SSTables)
 /// - MANIFEST file (persistent log of version edits)
 /// - Next file number allocation
+///
+/// The MANIFEST records the name of the comparator; recovering with a
+/// comparator of another name fails.
 pub struct VersionSet {
     /// Database directory
     db_path: PathBuf,
@@ -33,18 +39,28 @@ pub struct VersionSet {
     manifest_writer: Arc<RwLock<Option<wal::Writer>>>,
     /// MANIFEST file number
     _manifest_file_number: u64,
+    /// Comparator of user keys
+    comparator: Arc<dyn Comparator>,
 }
 
 impl VersionSet {
     /// Create a new VersionSet
     pub fn new(db_path: &Path) -> Self {
+        Self::with_comparator(db_path, bytewise_comparator())
+    }
+
+    /// Create a VersionSet whose versions order keys with `comparator`
+    pub fn with_comparator(db_path: &Path, comparator: Arc<dyn Comparator>) -> Self {
         VersionSet {
             db_path: db_path.to_path_buf(),
-            current: Arc::new(RwLock::new(Version::new())),
+            current: Arc::new(RwLock::new(Version::with_comparator(Arc::clone(
+                &comparator,
+            )))),
             next_file_number: Arc::new(AtomicU64::new(1)),
             last_sequence: Arc::new(AtomicU64::new(0)),
             manifest_writer: Arc::new(RwLock::new(None)),
             _manifest_file_number: 0,
+            comparator,
         }
     }
 
@@ -68,7 +84,7 @@ impl VersionSet {
         let manifest_path = self.db_path.join("MANIFEST");
         let mut reader = wal::Reader::new(&manifest_path)?;
 
-        let mut version = Version::new();
+        let mut version = Version::with_comparator(Arc::clone(&self.comparator));
         let mut next_file_num = 1u64;
         let mut last_seq = 0u64;
 
@@ -79,14 +95,17 @@ impl VersionSet {
 
             let edit = VersionEdit::decode(&record)?;
 
-            // Apply edit to current version
-            for (level, file) in &edit.new_files {
-                version.add_file(*level, file.clone());
+            if let Some(ref name) = edit.comparator
+                && !comparator_name_matches(name, self.comparator.as_ref())
+            {
+                return Err(Status::invalid_argument(format!(
+                    "Comparator '{}' does not match '{name}' the database was created with",
+                    self.comparator.name()
+                )));
             }
 
-            for (level, file_number) in &edit.deleted_files {
-                version.remove_file(*level, *file_number);
-            }
+            // Apply edit to current version
+            version.apply(&edit);
 
             // Update metadata
             if let Some(num) = edit.next_file_number {
@@ -116,7 +135,7 @@ impl VersionSet {
 
         // Write initial VersionEdit
         let mut edit = VersionEdit::new();
-        edit.set_comparator("bytewise".to_string());
+        edit.set_comparator(self.comparator.name().to_string());
         edit.set_next_file_number(1);
         edit.set_last_sequence(0);
 
@@ -139,47 +158,47 @@ impl VersionSet {
             edit.set_last_sequence(self.last_sequence.load(Ordering::SeqCst));
         }
 
+        // Edits are applied one at a time: each new version starts from the
+        // one the previous edit installed
+        let mut writer_guard = self.manifest_writer.write();
+
         // Apply edit to create new version
         let new_version = {
             let current = self.current.read();
-            let mut new_version = Version::new();
+            let mut new_version = Version::with_comparator(Arc::clone(&self.comparator));
+            // Level statistics accumulate across versions
+            new_version.level_stats = Arc::clone(&current.level_stats);
 
             // Copy all files from current version
             for level in 0..current.files.len() {
                 for file in &current.files[level] {
                     new_version.add_file(level, file.clone());
                 }
+                if let Some(key) = current.compact_cursor(level) {
+                    new_version.set_compact_cursor(level, key.clone());
+                }
             }
 
-            // Apply deletions
-            for (level, file_number) in &edit.deleted_files {
-                new_version.remove_file(*level, *file_number);
-            }
-
-            // Apply additions
-            for (level, file) in &edit.new_files {
-                new_version.add_file(*level, file.clone());
-            }
-
+            new_version.apply(&edit);
             new_version
         };
 
         // Write edit to MANIFEST
         let encoded = edit.encode();
-        {
-            let mut writer_guard = self.manifest_writer.write();
-            if let Some(writer) = writer_guard.as_mut() {
-                writer.add_record(&encoded)?;
-                writer.sync()?;
-            }
+        if let Some(writer) = writer_guard.as_mut() {
+            writer.add_record(&encoded)?;
+            writer.sync()?;
         }
 
         // Update current version
         *self.current.write() = new_version;
+        drop(writer_guard);
 
         // Update metadata
         if let Some(num) = edit.next_file_number {
-            self.next_file_number.store(num, Ordering::SeqCst);
+            // Never move backwards: the counter may be shared with other
+            // column families allocating concurrently
+            self.next_file_number.fetch_max(num, Ordering::SeqCst);
         }
 
         if let Some(seq) = edit.last_sequence {
@@ -194,11 +213,33 @@ impl VersionSet {
         Arc::clone(&self.current)
     }
 
+    /// Counter file numbers are allocated from
+    pub(crate) fn file_number_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.next_file_number)
+    }
+
+    /// Allocate file numbers from a counter shared with other VersionSets
+    ///
+    /// Column families write their tables to the same directory, so their
+    /// file numbers must come from one sequence.
+    pub(crate) fn share_file_numbers(&mut self, counter: Arc<AtomicU64>) {
+        counter.fetch_max(
+            self.next_file_number.load(Ordering::SeqCst),
+            Ordering::SeqCst,
+        );
+        self.next_file_number = counter;
+    }
+
     /// Allocate a new file number
     pub fn new_file_number(&self) -> u64 {
         self.next_file_number.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// Number the next new file will get, without allocating it
+    pub fn next_file_number(&self) -> u64 {
+        self.next_file_number.load(Ordering::SeqCst)
+    }
+
     /// Get the last sequence number
     pub fn last_sequence(&self) -> u64 {
         self.last_sequence.load(Ordering::SeqCst)
@@ -231,6 +272,22 @@ mod tests {
         assert_eq!(version.num_files(), 0);
     }
 
+    #[test]
+    fn test_version_set_comparator_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_db");
+        std::fs::create_dir_all(&db_path).unwrap();
+
+        let mut vset =
+            VersionSet::with_comparator(&db_path, Arc::new(crate::util::ReverseBytewiseComparator));
+        vset.open_or_create().unwrap();
+        drop(vset);
+
+        let mut vset = VersionSet::new(&db_path);
+        let err = vset.open_or_create().unwrap_err();
+        assert!(err.message().unwrap().contains("Comparator"));
+    }
+
     #[test]
     fn test_version_set_log_and_apply() {
         let temp_dir = TempDir::new().unwrap();
@@ -291,6 +348,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_version_set_recovers_compact_cursors() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_db");
+        std::fs::create_dir_all(&db_path).unwrap();
+
+        {
+            let mut vset = VersionSet::new(&db_path);
+            vset.open_or_create().unwrap();
+
+            let mut edit = VersionEdit::new();
+            edit.set_compact_cursor(1, Slice::from("m"));
+            vset.log_and_apply(edit).unwrap();
+
+            // Later edits keep the cursor
+            let mut edit = VersionEdit::new();
+            edit.add_file(
+                1,
+                FileMetaData::new(1, 4096, Slice::from("a"), Slice::from("z")),
+            );
+            vset.log_and_apply(edit).unwrap();
+            let current = vset.current();
+            assert_eq!(current.read().compact_cursor(1), Some(&Slice::from("m")));
+        }
+
+        let mut vset = VersionSet::new(&db_path);
+        vset.open_or_create().unwrap();
+        let current = vset.current();
+        let version = current.read();
+        assert_eq!(version.compact_cursor(1), Some(&Slice::from("m")));
+        assert_eq!(version.compact_cursor(2), None);
+    }
+
     #[test]
     fn test_file_number_allocation() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,165 @@
+use crate::{Result, Slice, Status};
+
+/// Name of the anonymous column plain values are read from
+pub const DEFAULT_COLUMN_NAME: &[u8] = b"";
+
+/// The columns of a wide-column entity, sorted by name
+///
+/// Entities are stored under a single key with their own value type. A plain
+/// value reads as an entity with only the default (anonymous) column, and
+/// `get` on an entity returns its default column.
+///
+/// # Serialized Form
+///
+/// ```text
+/// column_count (u32) | [name_len (u16) | name | value_len (u32) | value]*
+/// ```
+///
+/// All integers are little-endian.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WideColumns {
+    columns: Vec<(Slice, Slice)>,
+}
+
+impl WideColumns {
+    /// Build an entity from (name, value) pairs in any order
+    ///
+    /// Returns InvalidArgument for duplicate or over-long column names.
+    pub fn new(mut columns: Vec<(Slice, Slice)>) -> Result<Self> {
+        columns.sort_by(|a, b| a.0.data().cmp(b.0.data()));
+        if let Some(pair) = columns.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(Status::invalid_argument(format!(
+                "Duplicate column name '{}'",
+                pair[0].0
+            )));
+        }
+        if columns
+            .iter()
+            .any(|(name, _)| name.size() > u16::MAX as usize)
+        {
+            return Err(Status::invalid_argument("Column name too long"));
+        }
+        Ok(WideColumns { columns })
+    }
+
+    /// An entity holding a plain value as its default column
+    pub fn from_value(value: Slice) -> Self {
+        WideColumns {
+            columns: vec![(Slice::from(DEFAULT_COLUMN_NAME), value)],
+        }
+    }
+
+    /// Value of a column by name
+    pub fn get(&self, name: &[u8]) -> Option<&Slice> {
+        self.columns
+            .binary_search_by(|(column, _)| column.data().cmp(name))
+            .ok()
+            .map(|index| &self.columns[index].1)
+    }
+
+    /// Value of the default (anonymous) column
+    pub fn default_value(&self) -> Option<&Slice> {
+        self.get(DEFAULT_COLUMN_NAME)
+    }
+
+    /// (name, value) pairs in name order
+    pub fn iter(&self) -> impl std::iter::Iterator<Item = &(Slice, Slice)> {
+        self.columns.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    pub fn encode(&self) -> Slice {
+        let size = 4 + self
+            .columns
+            .iter()
+            .map(|(name, value)| 6 + name.size() + value.size())
+            .sum::<usize>();
+        let mut buf = Vec::with_capacity(size);
+        buf.extend_from_slice(&(self.columns.len() as u32).to_le_bytes());
+        for (name, value) in &self.columns {
+            buf.extend_from_slice(&(name.size() as u16).to_le_bytes());
+            buf.extend_from_slice(name.data());
+            buf.extend_from_slice(&(value.size() as u32).to_le_bytes());
+            buf.extend_from_slice(value.data());
+        }
+        Slice::from(buf)
+    }
+
+    pub fn decode(data: &Slice) -> Result<Self> {
+        let data = data.data();
+        let mut pos = 0;
+        let mut take = |len: usize| -> Result<&[u8]> {
+            let bytes = data
+                .get(pos..pos + len)
+                .ok_or_else(|| Status::corruption("Wide-column entity truncated"))?;
+            pos += len;
+            Ok(bytes)
+        };
+
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let mut columns = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let name_len = u16::from_le_bytes(take(2)?.try_into().unwrap()) as usize;
+            let name = Slice::from(take(name_len)?);
+            let value_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let value = Slice::from(take(value_len)?);
+            columns.push((name, value));
+        }
+
+        if pos != data.len() {
+            return Err(Status::corruption(
+                "Trailing bytes after wide-column entity",
+            ));
+        }
+        Ok(WideColumns { columns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_columns_sorted_and_encoded() {
+        let columns = WideColumns::new(vec![
+            (Slice::from("zip"), Slice::from("10115")),
+            (Slice::from(""), Slice::from("Alice")),
+            (Slice::from("city"), Slice::from("Berlin")),
+        ])
+        .unwrap();
+
+        let names: Vec<Slice> = columns.iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(
+            names,
+            vec![Slice::from(""), Slice::from("city"), Slice::from("zip")]
+        );
+        assert_eq!(columns.default_value(), Some(&Slice::from("Alice")));
+        assert_eq!(columns.get(b"city"), Some(&Slice::from("Berlin")));
+        assert_eq!(columns.get(b"country"), None);
+
+        let decoded = WideColumns::decode(&columns.encode()).unwrap();
+        assert_eq!(decoded, columns);
+    }
+
+    #[test]
+    fn test_wide_columns_errors() {
+        assert!(
+            WideColumns::new(vec![
+                (Slice::from("a"), Slice::from("1")),
+                (Slice::from("a"), Slice::from("2")),
+            ])
+            .is_err()
+        );
+
+        let encoded = WideColumns::from_value(Slice::from("v")).encode();
+        let truncated = Slice::from(&encoded.data()[..encoded.size() - 1]);
+        assert!(WideColumns::decode(&truncated).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use rucksdb::{
-    BloomFilterPolicy, Slice,
+    BloomFilterFormat, BloomFilterPolicy, FilterPolicy, Slice,
     memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
     table::{CompressionType, TableBuilder, TableReader},
 };
@@ -159,3 +159,38 @@ fn test_filter_effectiveness() {
         );
     }
 }
+
+#[test]
+fn test_legacy_bloom_sstable_readable() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let legacy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::with_format(
+        10,
+        BloomFilterFormat::Legacy,
+    ));
+
+    // Table written by an older release
+    {
+        let mut builder = TableBuilder::new_with_filter(temp_file.path(), Some(legacy)).unwrap();
+        for i in 0..100 {
+            let key = format!("key{i:04}");
+            let internal_key =
+                InternalKey::new(Slice::from(key), i as u64 + 1, VALUE_TYPE_VALUE).encode();
+            builder
+                .add(&internal_key, &Slice::from(format!("value{i:04}")))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+    }
+
+    // Read with the current (cache-local) policy
+    let current: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+    let mut reader =
+        TableReader::open_with_filter(temp_file.path(), 1, None, Some(current)).unwrap();
+    for i in 0..100 {
+        let key = format!("key{i:04}");
+        let (found, value) = reader.get(&Slice::from(key.as_str())).unwrap();
+        assert!(found, "Key {key} should exist");
+        assert_eq!(value, Some(Slice::from(format!("value{i:04}"))));
+    }
+    assert!(!reader.get(&Slice::from("key9999")).unwrap().0);
+}
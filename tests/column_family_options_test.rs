@@ -0,0 +1,136 @@
+use std::{collections::HashSet, path::Path};
+
+use rucksdb::{
+    ColumnFamilyHandle, ColumnFamilyOptions, CompressionType, DB, DBOptions, ReadOptions, Slice,
+    WriteOptions,
+};
+use tempfile::TempDir;
+
+fn sst_files(dir: &Path) -> HashSet<(String, u64)> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "sst"))
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.metadata().unwrap().len(),
+            )
+        })
+        .collect()
+}
+
+fn fill(db: &DB, cf: &ColumnFamilyHandle, count: usize) {
+    for i in 0..count {
+        db.put_cf(
+            &WriteOptions::default(),
+            cf,
+            Slice::from(format!("key{i:05}")),
+            Slice::from("x".repeat(200)),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_compression_per_column_family() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    let hot = db
+        .create_column_family(
+            "hot",
+            ColumnFamilyOptions {
+                write_buffer_size: 16 * 1024,
+                compression_type: CompressionType::None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let archive = db
+        .create_column_family(
+            "archive",
+            ColumnFamilyOptions {
+                write_buffer_size: 16 * 1024,
+                compression_type: CompressionType::Lz4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    fill(&db, &hot, 500);
+    let hot_files = sst_files(temp_dir.path());
+    fill(&db, &archive, 500);
+    let archive_files: HashSet<_> = sst_files(temp_dir.path())
+        .difference(&hot_files)
+        .cloned()
+        .collect();
+
+    let hot_size: u64 = hot_files.iter().map(|(_, size)| size).sum();
+    let archive_size: u64 = archive_files.iter().map(|(_, size)| size).sum();
+    assert!(!hot_files.is_empty() && !archive_files.is_empty());
+    assert!(
+        archive_size * 2 < hot_size,
+        "archive {archive_size} bytes, hot {hot_size} bytes"
+    );
+
+    for cf in [&hot, &archive] {
+        let value = db
+            .get_cf(&ReadOptions::default(), cf, &Slice::from("key00042"))
+            .unwrap();
+        assert_eq!(value, Some(Slice::from("x".repeat(200))));
+    }
+}
+
+#[test]
+fn test_compaction_trigger_per_column_family() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), DBOptions::default()).unwrap();
+
+    let cf_options = |l0_compaction_trigger| ColumnFamilyOptions {
+        write_buffer_size: 16 * 1024,
+        compression_type: CompressionType::None,
+        l0_compaction_trigger,
+        ..Default::default()
+    };
+    // Keys are written twice so compactions merge files rather than move
+    // them to L1 as they are
+    let lazy = db.create_column_family("lazy", cf_options(100)).unwrap();
+    fill(&db, &lazy, 1000);
+    fill(&db, &lazy, 1000);
+    let lazy_files = sst_files(temp_dir.path());
+
+    let eager = db.create_column_family("eager", cf_options(1)).unwrap();
+    fill(&db, &eager, 1000);
+    fill(&db, &eager, 1000);
+    let eager_files = sst_files(temp_dir.path()).difference(&lazy_files).count();
+
+    // The lazy CF never reaches its trigger; the eager one compacts L0
+    // files away as they are flushed
+    assert!(
+        lazy_files.len() > 4,
+        "lazy CF has {} files",
+        lazy_files.len()
+    );
+    assert!(
+        eager_files < lazy_files.len(),
+        "eager CF has {eager_files} files"
+    );
+
+    for cf in [&lazy, &eager] {
+        for i in (0..1000).step_by(97) {
+            let value = db
+                .get_cf(
+                    &ReadOptions::default(),
+                    cf,
+                    &Slice::from(format!("key{i:05}")),
+                )
+                .unwrap();
+            assert_eq!(value, Some(Slice::from("x".repeat(200))));
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rucksdb::{
+    BytewiseComparatorWithU64Ts, ColumnFamilyOptions, CompactionDecision, CompactionFilter, DB,
+    DBOptions, DBWithTTL, OptionsFile, ReadOptions, Slice, WriteOptions,
+};
+use tempfile::TempDir;
+
+/// Removes "drop:" keys, uppercases lowercase "upper:" values and skips the
+/// "range:" keys from "range:start" on
+struct TestFilter;
+
+impl CompactionFilter for TestFilter {
+    fn name(&self) -> &str {
+        "TestFilter"
+    }
+
+    fn filter(&self, _level: usize, key: &Slice, existing_value: &Slice) -> CompactionDecision {
+        let key = key.data();
+        if key.starts_with(b"drop:") {
+            CompactionDecision::Remove
+        } else if key.starts_with(b"upper:")
+            && existing_value.data().iter().any(u8::is_ascii_lowercase)
+        {
+            CompactionDecision::ChangeValue(Slice::from(existing_value.to_string().to_uppercase()))
+        } else if key == b"range:start" {
+            CompactionDecision::RemoveAndSkipUntil(Slice::from("range:~"))
+        } else {
+            CompactionDecision::Keep
+        }
+    }
+}
+
+fn put(db: &DB, cf: &rucksdb::ColumnFamilyHandle, key: &str, value: &str) {
+    db.put_cf(
+        &WriteOptions::default(),
+        cf,
+        Slice::from(key),
+        Slice::from(value),
+    )
+    .unwrap();
+}
+
+fn get(db: &DB, cf: &rucksdb::ColumnFamilyHandle, key: &str) -> Option<Slice> {
+    db.get_cf(&ReadOptions::default(), cf, &Slice::from(key))
+        .unwrap()
+}
+
+fn check_filter_applied(parallel_compaction_threads: usize) {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        parallel_compaction_threads,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    let cf = db
+        .create_column_family(
+            "filtered",
+            ColumnFamilyOptions {
+                write_buffer_size: 4096,
+                compaction_filter: Some(Arc::new(TestFilter)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    put(&db, &cf, "drop:1", "gone");
+    put(&db, &cf, "keep:1", "kept");
+    put(&db, &cf, "range:start", "gone");
+    put(&db, &cf, "range:x", "gone");
+    put(&db, &cf, "range:y", "gone");
+    put(&db, &cf, "upper:1", "old");
+    put(&db, &cf, "upper:1", "shout");
+    // Push the entries above out to SSTables
+    for i in 0..200 {
+        put(&db, &cf, &format!("zfiller{i:03}"), "x");
+    }
+
+    // Nothing is filtered before compaction
+    assert_eq!(get(&db, &cf, "drop:1"), Some(Slice::from("gone")));
+
+    db.compact_range_cf(&cf, None, None).unwrap();
+
+    assert_eq!(get(&db, &cf, "drop:1"), None);
+    assert_eq!(get(&db, &cf, "keep:1"), Some(Slice::from("kept")));
+    assert_eq!(get(&db, &cf, "range:start"), None);
+    assert_eq!(get(&db, &cf, "range:x"), None);
+    assert_eq!(get(&db, &cf, "range:y"), None);
+    assert_eq!(get(&db, &cf, "upper:1"), Some(Slice::from("SHOUT")));
+    assert_eq!(get(&db, &cf, "zfiller000"), Some(Slice::from("x")));
+
+    let stats = db.statistics();
+    assert_eq!(stats.compaction_filter_removed(), 4);
+    assert_eq!(stats.compaction_filter_changed(), 1);
+}
+
+#[test]
+fn test_compaction_filter_sequential() {
+    check_filter_applied(0);
+}
+
+#[test]
+fn test_compaction_filter_parallel_executor() {
+    check_filter_applied(4);
+}
+
+/// Removes "k" when compacting into the last level
+struct BottommostFilter;
+
+impl CompactionFilter for BottommostFilter {
+    fn name(&self) -> &str {
+        "BottommostFilter"
+    }
+
+    fn filter(&self, level: usize, key: &Slice, _existing_value: &Slice) -> CompactionDecision {
+        if level == 5 && key.data() == b"k" {
+            CompactionDecision::Remove
+        } else {
+            CompactionDecision::Keep
+        }
+    }
+}
+
+/// A value as `DBWithTTL` stores it: write time and the TTL override
+/// (0 = the database TTL) appended
+fn ttl_value(value: &str, write_time: u64) -> Slice {
+    let mut buf = value.as_bytes().to_vec();
+    buf.extend_from_slice(&write_time.to_le_bytes());
+    buf.extend_from_slice(&0u32.to_le_bytes());
+    Slice::from(buf)
+}
+
+fn put_at(db: &DB, cf: &rucksdb::ColumnFamilyHandle, key: &str, timestamp: u64, value: Slice) {
+    db.put_cf_with_ts(
+        &WriteOptions::default(),
+        cf,
+        Slice::from(key),
+        timestamp,
+        value,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_bottommost_removal_hides_older_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    let cf = db
+        .create_column_family(
+            "versioned",
+            ColumnFamilyOptions {
+                write_buffer_size: 4096,
+                comparator: Arc::new(BytewiseComparatorWithU64Ts),
+                compaction_filter: Some(Arc::new(BottommostFilter)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    put_at(&db, &cf, "k", 10, Slice::from("old"));
+    put_at(&db, &cf, "k", 20, Slice::from("new"));
+    for i in 0..400 {
+        put_at(&db, &cf, &format!("pad{i:03}"), 1, Slice::from("x"));
+    }
+    db.compact_range_cf(&cf, None, None).unwrap();
+
+    // The removed version leaves a deletion marker above the one it shadowed
+    assert_eq!(get(&db, &cf, "k"), None);
+    let read_at_15 = ReadOptions {
+        timestamp: Some(15),
+        ..Default::default()
+    };
+    assert_eq!(
+        db.get_cf(&read_at_15, &cf, &Slice::from("k")).unwrap(),
+        Some(Slice::from("old"))
+    );
+    assert_eq!(get(&db, &cf, "pad000"), Some(Slice::from("x")));
+}
+
+#[test]
+fn test_bottommost_ttl_expiry_hides_older_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DBWithTTL::open(
+        Arc::new(DB::open(temp_dir.path().to_str().unwrap(), options).unwrap()),
+        Duration::from_secs(3600),
+    );
+    // With dynamic level sizing a small CF compacts L0 straight into the
+    // last level
+    let cf = db
+        .create_column_family(
+            "versioned",
+            ColumnFamilyOptions {
+                write_buffer_size: 4096,
+                comparator: Arc::new(BytewiseComparatorWithU64Ts),
+                level_compaction_dynamic_level_bytes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    put_at(db.db(), &cf, "k", 10, ttl_value("old", now));
+    // Written long ago, so it has expired
+    put_at(db.db(), &cf, "k", 20, ttl_value("new", 1));
+    for i in 0..400 {
+        put_at(db.db(), &cf, &format!("pad{i:03}"), 1, ttl_value("x", now));
+    }
+    db.db().compact_range_cf(&cf, None, None).unwrap();
+
+    assert_eq!(get(db.db(), &cf, "k"), None);
+    assert_eq!(
+        db.get_cf(&ReadOptions::default(), &cf, &Slice::from("pad000"))
+            .unwrap(),
+        Some(Slice::from("x"))
+    );
+}
+
+#[test]
+fn test_compaction_filter_recorded_in_options_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    let options = DBOptions {
+        compaction_filter: Some(Arc::new(TestFilter)),
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(path, options).unwrap();
+    let default_cf = db.cf_handle("default").unwrap();
+    assert_eq!(
+        db.get_options_cf(&default_cf)
+            .unwrap()
+            .compaction_filter
+            .unwrap()
+            .name(),
+        "TestFilter"
+    );
+    drop(db);
+
+    let (_, file_path) = rucksdb::options::latest_options_file(path)
+        .unwrap()
+        .unwrap();
+    let options_file = OptionsFile::load(file_path).unwrap();
+    assert_eq!(
+        options_file
+            .column_family("default")
+            .unwrap()
+            .compaction_filter
+            .as_deref(),
+        Some("TestFilter")
+    );
+}
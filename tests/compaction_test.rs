@@ -1,4 +1,16 @@
-use rucksdb::{DB, DBOptions, ReadOptions, Slice, WriteOptions};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use rucksdb::{
+    BottommostLevelCompaction, CompactOnDeletionOptions, CompactRangeOptions, CompactionDecision,
+    CompactionFilter, CompactionOptionsFIFO, CompactionPri, CompactionStyle, CompressionType, DB,
+    DBOptions, ReadOptions, Slice, WriteOptions,
+};
 use tempfile::TempDir;
 
 #[test]
@@ -181,3 +193,739 @@ fn test_compaction_with_deletes() {
         );
     }
 }
+
+#[test]
+fn test_compaction_splits_output_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test_db");
+
+    for parallel_compaction_threads in [0, 4] {
+        let path = db_path.join(format!("threads{parallel_compaction_threads}"));
+        let options = DBOptions {
+            write_buffer_size: 16 * 1024,
+            target_file_size_base: 8 * 1024,
+            compression_type: CompressionType::None,
+            parallel_compaction_threads,
+            enable_background_compaction: false,
+            ..Default::default()
+        };
+        let db = DB::open(path.to_str().unwrap(), options).unwrap();
+
+        // Even keys, then odd ones: the L0 files overlap and are merged
+        for i in (0..2000).step_by(2).chain((1..2000).step_by(2)) {
+            let key = format!("key{i:05}");
+            let value = format!("value{i:05}_with_some_padding_to_fill_blocks");
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(key),
+                Slice::from(value),
+            )
+            .unwrap();
+        }
+        db.compact_level(0).unwrap();
+        assert_eq!(db.statistics().num_trivial_moves(), 0);
+
+        let l1_files: usize = db
+            .get_property("rocksdb.num-files-at-level1")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(l1_files > 3, "{l1_files} files in L1");
+
+        let mut iter = db.iter().unwrap();
+        let mut count = 0;
+        let mut valid = iter.seek_to_first().unwrap();
+        while valid {
+            assert_eq!(iter.key(), Slice::from(format!("key{count:05}")));
+            count += 1;
+            valid = iter.next().unwrap();
+        }
+        assert_eq!(count, 2000);
+    }
+}
+
+fn num_files_at_level(db: &DB, level: usize) -> usize {
+    db.get_property(&format!("rocksdb.num-files-at-level{level}"))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+/// Open a database whose L1 holds many small files, above its target size
+fn open_with_full_level1(path: &std::path::Path, compaction_pri: CompactionPri) -> DB {
+    let options = DBOptions {
+        write_buffer_size: 4 * 1024,
+        max_bytes_for_level_base: 16 * 1024,
+        compression_type: CompressionType::None,
+        compaction_pri,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(path.to_str().unwrap(), options).unwrap();
+
+    for i in 0..2000 {
+        let key = format!("key{i:05}");
+        let value = format!("value{i:05}_with_some_padding_to_fill_blocks");
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(key),
+            Slice::from(value),
+        )
+        .unwrap();
+    }
+    db.compact_level(0).unwrap();
+    db
+}
+
+fn check_all_keys(db: &DB) {
+    for i in 0..2000 {
+        let key = format!("key{i:05}");
+        let value = db
+            .get(&ReadOptions::default(), &Slice::from(key.as_str()))
+            .unwrap();
+        assert_eq!(
+            value,
+            Some(Slice::from(format!(
+                "value{i:05}_with_some_padding_to_fill_blocks"
+            ))),
+            "Failed for key: {key}"
+        );
+    }
+}
+
+#[test]
+fn test_compaction_picks_files_of_a_level() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for compaction_pri in [
+        CompactionPri::MinOverlappingRatio,
+        CompactionPri::RoundRobin,
+    ] {
+        let path = temp_dir.path().join(format!("{compaction_pri:?}"));
+        let db = open_with_full_level1(&path, compaction_pri);
+        let l1_files = num_files_at_level(&db, 1);
+        assert!(l1_files > 8, "{l1_files} files in L1");
+
+        // Only enough files move down to bring L1 under its target
+        db.maybe_compact().unwrap();
+        let remaining = num_files_at_level(&db, 1);
+        assert!(
+            remaining > 0 && remaining < l1_files,
+            "{remaining} files in L1"
+        );
+        assert!(num_files_at_level(&db, 2) > 0);
+        check_all_keys(&db);
+    }
+}
+
+#[test]
+fn test_concurrent_compactions() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test_db");
+    let db = Arc::new(open_with_full_level1(&path, CompactionPri::RoundRobin));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || db.maybe_compact().unwrap())
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    check_all_keys(&db);
+
+    // Every table on disk belongs to exactly one level
+    let tables = std::fs::read_dir(&path)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".sst")
+        })
+        .count();
+    let in_levels: usize = (0..7).map(|level| num_files_at_level(&db, level)).sum();
+    assert_eq!(tables, in_levels);
+}
+
+fn sst_file_names(path: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".sst"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_trivial_move() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("test_db");
+    let options = || DBOptions {
+        write_buffer_size: 4 * 1024,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+
+    {
+        let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+        // Ascending keys: the L0 files overlap neither each other nor L1
+        for i in 0..500 {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("key{i:05}")),
+                Slice::from(format!("value{i:05}_padding")),
+            )
+            .unwrap();
+        }
+        let l0_files = num_files_at_level(&db, 0);
+        assert!(l0_files > 1);
+        let tables = sst_file_names(&path);
+
+        db.compact_level(0).unwrap();
+
+        // The same tables now make up L1
+        assert_eq!(num_files_at_level(&db, 0), 0);
+        assert_eq!(num_files_at_level(&db, 1), l0_files);
+        assert_eq!(sst_file_names(&path), tables);
+        let stats = db.statistics();
+        assert_eq!(stats.num_trivial_moves(), 1);
+        assert!(stats.trivial_move_bytes() > 0);
+        assert_eq!(stats.num_compactions.load(Ordering::Relaxed), 0);
+
+        // Files overlapping L1 are merged into it
+        for i in (0..500).step_by(2) {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("key{i:05}")),
+                Slice::from("updated"),
+            )
+            .unwrap();
+        }
+        db.compact_level(0).unwrap();
+        assert_eq!(stats.num_trivial_moves(), 1);
+        assert_eq!(stats.num_compactions.load(Ordering::Relaxed), 1);
+    }
+
+    // The moves are recovered from the MANIFEST
+    let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+    assert_eq!(num_files_at_level(&db, 0), 0);
+    for i in 0..500 {
+        let expected = if i % 2 == 0 {
+            "updated".to_string()
+        } else {
+            format!("value{i:05}_padding")
+        };
+        let value = db
+            .get(
+                &ReadOptions::default(),
+                &Slice::from(format!("key{i:05}").as_str()),
+            )
+            .unwrap();
+        assert_eq!(value, Some(Slice::from(expected)));
+    }
+}
+
+#[test]
+fn test_dynamic_level_bytes() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path();
+    let options = || DBOptions {
+        write_buffer_size: 4 * 1024,
+        max_bytes_for_level_base: 16 * 1024,
+        compression_type: CompressionType::None,
+        level_compaction_dynamic_level_bytes: true,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+
+    {
+        let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+        let put_range = |keys: std::ops::Range<usize>| {
+            for i in keys {
+                db.put(
+                    &WriteOptions::default(),
+                    Slice::from(format!("key{i:05}")),
+                    Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+                )
+                .unwrap();
+            }
+        };
+        put_range(0..100);
+        // A small database compacts L0 straight into the last level
+        db.compact_level(0).unwrap();
+        assert_eq!(num_files_at_level(&db, 0), 0);
+        assert!(num_files_at_level(&db, 6) > 0);
+        assert!((1..6).all(|level| num_files_at_level(&db, level) == 0));
+
+        put_range(100..2000);
+        db.compact_level(0).unwrap();
+        assert!((1..6).all(|level| num_files_at_level(&db, level) == 0));
+
+        // Once the last level outgrows the base size, L0 compacts into
+        // the level above it
+        put_range(0..300);
+        db.compact_level(0).unwrap();
+        assert!(num_files_at_level(&db, 5) > 0);
+        assert!((1..5).all(|level| num_files_at_level(&db, level) == 0));
+
+        db.compact_range(None, None).unwrap();
+        assert_eq!(num_files_at_level(&db, 5), 0);
+        check_all_keys(&db);
+
+        // The level targets derive from the last level, so no level above
+        // it fits the data
+        let last_level_files = num_files_at_level(&db, 6);
+        let change_level = CompactRangeOptions {
+            change_level: true,
+            ..Default::default()
+        };
+        db.compact_range_opt(&change_level, None, None).unwrap();
+        assert_eq!(num_files_at_level(&db, 6), last_level_files);
+    }
+
+    let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+    assert!(num_files_at_level(&db, 6) > 0);
+    check_all_keys(&db);
+}
+
+fn num_sorted_runs(db: &DB) -> usize {
+    num_files_at_level(db, 0)
+        + (1..7)
+            .filter(|&level| num_files_at_level(db, level) > 0)
+            .count()
+}
+
+#[test]
+fn test_universal_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for parallel_compaction_threads in [0, 4] {
+        let path = temp_dir
+            .path()
+            .join(format!("threads{parallel_compaction_threads}"));
+        let options = || DBOptions {
+            write_buffer_size: 4 * 1024,
+            compression_type: CompressionType::None,
+            compaction_style: CompactionStyle::Universal,
+            parallel_compaction_threads,
+            subcompaction_min_size: 0,
+            ..Default::default()
+        };
+
+        {
+            let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+            // Older values first, so newer sorted runs must win
+            for value in ["old", "new"] {
+                for i in 0..2000 {
+                    let value = match value {
+                        "old" => "old".to_string(),
+                        _ => format!("value{i:05}_with_some_padding_to_fill_blocks"),
+                    };
+                    db.put(
+                        &WriteOptions::default(),
+                        Slice::from(format!("key{i:05}")),
+                        Slice::from(value),
+                    )
+                    .unwrap();
+                }
+                // Compactions after each flush keep the sorted runs at the
+                // trigger
+                assert!(num_sorted_runs(&db) <= 4, "{} runs", num_sorted_runs(&db));
+            }
+            assert!(db.statistics().num_compactions.load(Ordering::Relaxed) > 0);
+            check_all_keys(&db);
+        }
+
+        let db = DB::open(path.to_str().unwrap(), options()).unwrap();
+        check_all_keys(&db);
+        db.compact_range(None, None).unwrap();
+        assert_eq!(num_sorted_runs(&db), 1);
+        assert!(num_files_at_level(&db, 6) > 0);
+        check_all_keys(&db);
+    }
+}
+
+fn total_size(db: &DB) -> u64 {
+    db.get_property("rocksdb.total-size")
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_fifo_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 4 * 1024,
+        compression_type: CompressionType::None,
+        compaction_style: CompactionStyle::Fifo,
+        compaction_options_fifo: CompactionOptionsFIFO {
+            max_table_files_size: 32 * 1024,
+            allow_compaction: false,
+        },
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    // The oldest files are dropped once L0 outgrows the size budget
+    for i in 0..2000 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("key{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+        assert!(total_size(&db) <= 32 * 1024);
+    }
+    assert!((1..7).all(|level| num_files_at_level(&db, level) == 0));
+    assert!(num_files_at_level(&db, 0) > 1);
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("key00000"))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("key01999"))
+            .unwrap(),
+        Some(Slice::from("value01999_with_some_padding_to_fill_blocks"))
+    );
+
+    // Compacting L0 by hand keeps the files in L0, where FIFO compaction
+    // accounts for them
+    let l0_files = num_files_at_level(&db, 0);
+    db.compact_level(0).unwrap();
+    assert_eq!(num_files_at_level(&db, 0), l0_files);
+    assert_eq!(num_files_at_level(&db, 1), 0);
+
+    // Files older than the TTL expire
+    db.set_options(&db.default_cf(), &[("ttl", "1")]).unwrap();
+    thread::sleep(std::time::Duration::from_millis(2100));
+    db.compact_range(None, None).unwrap();
+    assert_eq!(num_files_at_level(&db, 0), 0);
+}
+
+#[test]
+fn test_fifo_compaction_merges_level0() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 16 * 1024,
+        compression_type: CompressionType::Snappy,
+        compaction_style: CompactionStyle::Fifo,
+        compaction_options_fifo: CompactionOptionsFIFO {
+            allow_compaction: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options.clone()).unwrap();
+
+    for i in 0..2000 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("key{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+    }
+    // Every 4 flushed files are merged into one; the merged files are
+    // too large to be merged again
+    assert!(num_files_at_level(&db, 0) <= 5);
+    assert!(db.statistics().num_compactions.load(Ordering::Relaxed) > 0);
+    assert!((1..7).all(|level| num_files_at_level(&db, level) == 0));
+    check_all_keys(&db);
+    drop(db);
+
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    check_all_keys(&db);
+}
+
+/// Write and then delete 1000 keys
+fn write_and_delete(
+    path: &std::path::Path,
+    compact_on_deletion: Option<CompactOnDeletionOptions>,
+) -> DB {
+    let options = DBOptions {
+        write_buffer_size: 4 * 1024,
+        compression_type: CompressionType::None,
+        compact_on_deletion,
+        ..Default::default()
+    };
+    let db = DB::open(path.to_str().unwrap(), options).unwrap();
+    for i in 0..1000 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("key{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+    }
+    for i in 0..1000 {
+        db.delete(&WriteOptions::default(), Slice::from(format!("key{i:05}")))
+            .unwrap();
+    }
+    // Deletions don't fill the memtable; later writes flush them
+    for i in 0..100 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("other{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+    }
+    for i in 0..1000 {
+        let key = format!("key{i:05}");
+        let value = db
+            .get(&ReadOptions::default(), &Slice::from(key.as_str()))
+            .unwrap();
+        assert_eq!(value, None, "Key {key} should be deleted");
+    }
+    db
+}
+
+#[test]
+fn test_compact_on_deletion() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = write_and_delete(&temp_dir.path().join("without"), None);
+    let without = total_size(&db);
+
+    // The flushed deletions are marked and compacted down to the last
+    // level, where they are dropped along with the keys they delete
+    let db = write_and_delete(
+        &temp_dir.path().join("with"),
+        Some(CompactOnDeletionOptions {
+            sliding_window_size: 100,
+            deletion_trigger: 50,
+        }),
+    );
+    assert!((1..6).all(|level| num_files_at_level(&db, level) == 0));
+    assert_eq!(num_files_at_level(&db, 6), 1);
+    assert!(total_size(&db) < without / 2);
+}
+
+#[test]
+fn test_ttl_and_periodic_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 4 * 1024,
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    for i in 0..2000 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("key{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+    }
+    assert!((0..6).any(|level| num_files_at_level(&db, level) > 0));
+
+    // Data older than the TTL is compacted down to the last level
+    db.set_options(&db.default_cf(), &[("ttl", "1")]).unwrap();
+    thread::sleep(std::time::Duration::from_millis(2100));
+    db.maybe_compact().unwrap();
+    assert!((0..6).all(|level| num_files_at_level(&db, level) == 0));
+    let last_level_files = num_files_at_level(&db, 6);
+    assert!(last_level_files > 0);
+    check_all_keys(&db);
+
+    // Files written long ago are rewritten in the last level
+    db.set_options(
+        &db.default_cf(),
+        &[("ttl", "0"), ("periodic_compaction_seconds", "1")],
+    )
+    .unwrap();
+    let compactions = db.statistics().num_compactions.load(Ordering::Relaxed);
+    thread::sleep(std::time::Duration::from_millis(2100));
+    db.maybe_compact().unwrap();
+    assert!(db.statistics().num_compactions.load(Ordering::Relaxed) > compactions);
+    assert!((0..6).all(|level| num_files_at_level(&db, level) == 0));
+    assert_eq!(num_files_at_level(&db, 6), last_level_files);
+    check_all_keys(&db);
+}
+
+#[test]
+fn test_compact_range_options() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 4 * 1024,
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    for i in 0..2000 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("key{i:05}")),
+            Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+        )
+        .unwrap();
+    }
+    let num_compactions = || db.statistics().num_compactions.load(Ordering::Relaxed);
+
+    // A canceled compaction stops before its first step
+    let canceled = Arc::new(AtomicBool::new(true));
+    let status = db
+        .compact_range_opt(
+            &CompactRangeOptions {
+                canceled: Some(Arc::clone(&canceled)),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert!(status.is_incomplete());
+    assert!((0..6).any(|level| num_files_at_level(&db, level) > 0));
+
+    // Everything ends in the last level
+    canceled.store(false, Ordering::SeqCst);
+    let options = CompactRangeOptions {
+        canceled: Some(canceled),
+        ..Default::default()
+    };
+    db.compact_range_opt(&options, None, None).unwrap();
+    assert!((0..6).all(|level| num_files_at_level(&db, level) == 0));
+    check_all_keys(&db);
+
+    // Without compaction filters, the last level is only rewritten when
+    // forced, and only where the range has files
+    let compactions = num_compactions();
+    db.compact_range_opt(&options, None, None).unwrap();
+    assert_eq!(num_compactions(), compactions);
+    let force = CompactRangeOptions {
+        bottommost_level_compaction: BottommostLevelCompaction::Force,
+        ..Default::default()
+    };
+    db.compact_range_opt(&force, Some(b"zzz"), None).unwrap();
+    assert_eq!(num_compactions(), compactions);
+    db.compact_range_opt(&force, Some(b"key01000"), Some(b"key01999"))
+        .unwrap();
+    assert_eq!(num_compactions(), compactions + 1);
+    assert!((0..6).all(|level| num_files_at_level(&db, level) == 0));
+    let last_level_files = num_files_at_level(&db, 6);
+    check_all_keys(&db);
+
+    // The result moves to the target level, or the highest it fits in
+    let change_level = |target_level| CompactRangeOptions {
+        change_level: true,
+        target_level,
+        ..Default::default()
+    };
+    db.compact_range_opt(&change_level(Some(3)), None, None)
+        .unwrap();
+    assert_eq!(num_files_at_level(&db, 3), last_level_files);
+    assert_eq!(num_files_at_level(&db, 6), 0);
+    check_all_keys(&db);
+    db.compact_range_opt(&change_level(None), None, None)
+        .unwrap();
+    assert_eq!(num_files_at_level(&db, 1), last_level_files);
+    check_all_keys(&db);
+
+    // There is no level past the last one
+    assert!(
+        db.compact_range_opt(&change_level(Some(7)), None, None)
+            .is_err()
+    );
+    assert_eq!(num_files_at_level(&db, 1), last_level_files);
+}
+
+/// Holds the compaction at "key01500" until it is canceled, the first time
+/// it gets there
+struct WaitForCancel {
+    reached: Arc<AtomicBool>,
+    canceled: Arc<AtomicBool>,
+}
+
+impl CompactionFilter for WaitForCancel {
+    fn name(&self) -> &str {
+        "WaitForCancel"
+    }
+
+    fn filter(&self, _level: usize, key: &Slice, _existing_value: &Slice) -> CompactionDecision {
+        if key.data() == b"key01500" && !self.reached.swap(true, Ordering::SeqCst) {
+            while !self.canceled.load(Ordering::SeqCst) {
+                thread::sleep(std::time::Duration::from_millis(1));
+            }
+        }
+        CompactionDecision::Keep
+    }
+}
+
+#[test]
+fn test_cancel_compact_range_mid_step() {
+    for parallel_compaction_threads in [0, 4] {
+        let temp_dir = TempDir::new().unwrap();
+        let reached = Arc::new(AtomicBool::new(false));
+        let canceled = Arc::new(AtomicBool::new(false));
+        let options = DBOptions {
+            write_buffer_size: 4 * 1024,
+            compression_type: CompressionType::None,
+            target_file_size_base: 16 * 1024,
+            enable_background_compaction: false,
+            parallel_compaction_threads,
+            subcompaction_min_size: 0,
+            compaction_filter: Some(Arc::new(WaitForCancel {
+                reached: Arc::clone(&reached),
+                canceled: Arc::clone(&canceled),
+            })),
+            ..Default::default()
+        };
+        let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+        for i in 0..2000 {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("key{i:05}")),
+                Slice::from(format!("value{i:05}_with_some_padding_to_fill_blocks")),
+            )
+            .unwrap();
+        }
+        let l0_files = num_files_at_level(&db, 0);
+        assert!(l0_files > 1);
+        let files_before = sst_file_names(temp_dir.path());
+        let options = CompactRangeOptions {
+            canceled: Some(Arc::clone(&canceled)),
+            ..Default::default()
+        };
+
+        // Canceled before it starts, no step runs
+        canceled.store(true, Ordering::SeqCst);
+        let status = db.compact_range_opt(&options, None, None).unwrap_err();
+        assert!(status.is_incomplete());
+        assert!(!reached.load(Ordering::SeqCst));
+        assert_eq!(sst_file_names(temp_dir.path()), files_before);
+        canceled.store(false, Ordering::SeqCst);
+
+        // Cancel while the L0 compaction is held in the middle of its input
+        let canceler = thread::spawn({
+            let reached = Arc::clone(&reached);
+            let canceled = Arc::clone(&canceled);
+            move || {
+                while !reached.load(Ordering::SeqCst) {
+                    thread::sleep(std::time::Duration::from_millis(1));
+                }
+                canceled.store(true, Ordering::SeqCst);
+            }
+        });
+        let status = db.compact_range_opt(&options, None, None).unwrap_err();
+        canceler.join().unwrap();
+        assert!(status.is_incomplete());
+        assert!(reached.load(Ordering::SeqCst));
+
+        // The outputs written so far are gone, and the inputs stay in L0
+        assert_eq!(sst_file_names(temp_dir.path()), files_before);
+        assert_eq!(num_files_at_level(&db, 0), l0_files);
+        check_all_keys(&db);
+
+        canceled.store(false, Ordering::SeqCst);
+        db.compact_range_opt(&options, None, None).unwrap();
+        assert!((0..6).all(|level| num_files_at_level(&db, level) == 0));
+        check_all_keys(&db);
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use rucksdb::{
+    ColumnFamilyDescriptor, ColumnFamilyOptions, DB, DBOptions, ReadOptions,
+    ReverseBytewiseComparator, Slice, U64BigEndianComparator, WriteOptions,
+};
+use tempfile::TempDir;
+
+fn collect_keys(db: &DB, cf: &rucksdb::ColumnFamilyHandle) -> Vec<Vec<u8>> {
+    let mut iter = db.iter_cf(cf).unwrap();
+    let mut keys = Vec::new();
+    let mut valid = iter.seek_to_first().unwrap();
+    while valid {
+        keys.push(iter.key().data().to_vec());
+        valid = iter.next().unwrap();
+    }
+    keys
+}
+
+fn reverse_options() -> ColumnFamilyOptions {
+    ColumnFamilyOptions {
+        write_buffer_size: 4096,
+        comparator: Arc::new(ReverseBytewiseComparator),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_reverse_comparator_across_flush_and_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    let cf = db
+        .create_column_family("reverse", reverse_options())
+        .unwrap();
+
+    for i in 0..500 {
+        db.put_cf(
+            &WriteOptions::default(),
+            &cf,
+            Slice::from(format!("key{i:04}")),
+            Slice::from(format!("value{i:04}")),
+        )
+        .unwrap();
+    }
+    db.compact_level_cf(&cf, 0).unwrap();
+    db.put_cf(
+        &WriteOptions::default(),
+        &cf,
+        Slice::from("key0250"),
+        Slice::from("updated"),
+    )
+    .unwrap();
+
+    let expected: Vec<Vec<u8>> = (0..500)
+        .rev()
+        .map(|i| format!("key{i:04}").into_bytes())
+        .collect();
+    assert_eq!(collect_keys(&db, &cf), expected);
+
+    for i in (0..500).step_by(37) {
+        let value = db
+            .get_cf(
+                &ReadOptions::default(),
+                &cf,
+                &Slice::from(format!("key{i:04}")),
+            )
+            .unwrap();
+        assert_eq!(value, Some(Slice::from(format!("value{i:04}"))));
+    }
+    let value = db
+        .get_cf(&ReadOptions::default(), &cf, &Slice::from("key0250"))
+        .unwrap();
+    assert_eq!(value, Some(Slice::from("updated")));
+}
+
+#[test]
+fn test_variable_length_keys_sorted_bytewise() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 16 * 1024,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    let mut keys: Vec<String> = (0..3000)
+        .map(|i| format!("{}", i * 7919 % 100000))
+        .collect();
+    for key in &keys {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(key.as_str()),
+            Slice::from("v"),
+        )
+        .unwrap();
+    }
+
+    for key in &keys {
+        let value = db
+            .get(&ReadOptions::default(), &Slice::from(key.as_str()))
+            .unwrap();
+        assert_eq!(value, Some(Slice::from("v")), "{key}");
+    }
+
+    keys.sort();
+    keys.dedup();
+    let expected: Vec<Vec<u8>> = keys.into_iter().map(String::into_bytes).collect();
+    let default_cf = db.cf_handle("default").unwrap();
+    assert_eq!(collect_keys(&db, &default_cf), expected);
+}
+
+#[test]
+fn test_u64_comparator_numeric_order() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        comparator: Arc::new(U64BigEndianComparator),
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    for n in [1000u64, 3, 70000, 42, 255, 256] {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(&n.to_be_bytes()[..]),
+            Slice::from(n.to_string()),
+        )
+        .unwrap();
+    }
+
+    let default_cf = db.cf_handle("default").unwrap();
+    let keys: Vec<u64> = collect_keys(&db, &default_cf)
+        .into_iter()
+        .map(|key| u64::from_be_bytes(key.try_into().unwrap()))
+        .collect();
+    assert_eq!(keys, vec![3, 42, 255, 256, 1000, 70000]);
+}
+
+#[test]
+fn test_reopen_with_different_comparator_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        db.create_column_family("reverse", reverse_options())
+            .unwrap();
+    }
+
+    // Default CF opened with another comparator
+    let options = DBOptions {
+        comparator: Arc::new(ReverseBytewiseComparator),
+        ..Default::default()
+    };
+    let err = DB::open(path, options).err().unwrap();
+    assert!(err.message().unwrap().contains("Comparator"));
+
+    // Non-default CF opened with another comparator
+    let err = DB::open_cf(
+        path,
+        DBOptions::default(),
+        vec![
+            ColumnFamilyDescriptor::new("default", ColumnFamilyOptions::default()),
+            ColumnFamilyDescriptor::new("reverse", ColumnFamilyOptions::default()),
+        ],
+    )
+    .err()
+    .unwrap();
+    assert!(err.message().unwrap().contains("comparator"));
+}
+
+#[test]
+fn test_builtin_comparator_restored_on_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        let cf = db
+            .create_column_family("reverse", reverse_options())
+            .unwrap();
+        for key in ["a", "c", "b"] {
+            db.put_cf(
+                &WriteOptions::default(),
+                &cf,
+                Slice::from(key),
+                Slice::from(key),
+            )
+            .unwrap();
+        }
+    }
+
+    // Without descriptors the CF gets its recorded comparator back
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    let cf = db.cf_handle("reverse").unwrap();
+    assert_eq!(
+        db.get_options_cf(&cf).unwrap().comparator.name(),
+        ReverseBytewiseComparator::NAME
+    );
+    assert_eq!(
+        collect_keys(&db, &cf),
+        vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]
+    );
+}
@@ -254,3 +254,74 @@ fn test_delete_after_flush() {
         assert_eq!(value, Some(Slice::from(expected_value)));
     }
 }
+
+#[test]
+fn test_iterate_across_flush() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test_db");
+
+    let options = DBOptions {
+        create_if_missing: true,
+        error_if_exists: false,
+        write_buffer_size: 1024,
+        block_cache_size: 1000,
+        ..Default::default()
+    };
+
+    let db = DB::open(db_path.to_str().unwrap(), options).unwrap();
+
+    // Enough data for several SSTables
+    for i in 0..100 {
+        let key = format!("key{i:04}");
+        let value = format!("value{i:04}_padding_text");
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(key),
+            Slice::from(value),
+        )
+        .unwrap();
+    }
+
+    // Deletions and overwrites shadow flushed entries
+    for i in 0..10 {
+        let key = format!("key{i:04}");
+        db.delete(&WriteOptions::default(), Slice::from(key))
+            .unwrap();
+    }
+    for i in 10..20 {
+        let key = format!("key{i:04}");
+        let value = format!("new_value{i:04}");
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(key),
+            Slice::from(value),
+        )
+        .unwrap();
+    }
+
+    // Iteration yields each user key once, with its latest value
+    let mut iter = db.iter().unwrap();
+    let mut i = 10;
+    let mut valid = iter.seek_to_first().unwrap();
+    while valid {
+        let expected_value = if i < 20 {
+            format!("new_value{i:04}")
+        } else {
+            format!("value{i:04}_padding_text")
+        };
+        assert_eq!(iter.key(), Slice::from(format!("key{i:04}")));
+        assert_eq!(iter.value(), Slice::from(expected_value));
+        i += 1;
+        valid = iter.next().unwrap();
+    }
+    assert_eq!(i, 100);
+
+    // Seeks compare user keys
+    assert!(iter.seek(&Slice::from("key0005")).unwrap());
+    assert_eq!(iter.key(), Slice::from("key0010"));
+    assert!(iter.seek(&Slice::from("key0050")).unwrap());
+    assert_eq!(iter.key(), Slice::from("key0050"));
+    assert!(iter.seek_for_prev(&Slice::from("key0050")).unwrap());
+    assert_eq!(iter.key(), Slice::from("key0050"));
+    assert!(!iter.seek(&Slice::from("key0100")).unwrap());
+}
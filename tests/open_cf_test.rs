@@ -0,0 +1,184 @@
+use rucksdb::{
+    ColumnFamilyDescriptor, ColumnFamilyOptions, CompressionType, DB, DBOptions, ReadOptions,
+    Slice, WriteOptions,
+};
+use tempfile::TempDir;
+
+fn descriptor(name: &str) -> ColumnFamilyDescriptor {
+    ColumnFamilyDescriptor::new(name, ColumnFamilyOptions::default())
+}
+
+fn create_db(path: &str, cf_names: &[&str]) {
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    for name in cf_names {
+        let cf = db
+            .create_column_family(name, ColumnFamilyOptions::default())
+            .unwrap();
+        db.put_cf(
+            &WriteOptions::default(),
+            &cf,
+            Slice::from("key"),
+            Slice::from(format!("value_{name}")),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_list_column_families() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    assert!(DB::list_column_families(path).unwrap_err().is_not_found());
+
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        db.create_column_family("users", ColumnFamilyOptions::default())
+            .unwrap();
+        let posts = db
+            .create_column_family("posts", ColumnFamilyOptions::default())
+            .unwrap();
+        db.create_column_family("tags", ColumnFamilyOptions::default())
+            .unwrap();
+        db.drop_column_family(&posts).unwrap();
+    }
+
+    assert_eq!(
+        DB::list_column_families(path).unwrap(),
+        vec!["default", "users", "tags"]
+    );
+}
+
+#[test]
+fn test_open_cf_requires_existing_column_families() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    create_db(path, &["users", "posts"]);
+
+    // Every existing CF must be listed
+    let result = DB::open_cf(
+        path,
+        DBOptions::default(),
+        vec![descriptor("default"), descriptor("users")],
+    );
+    let err = result.err().unwrap();
+    assert!(err.message().unwrap().contains("posts"), "{err:?}");
+
+    // So must the default CF
+    let result = DB::open_cf(
+        path,
+        DBOptions::default(),
+        vec![descriptor("users"), descriptor("posts")],
+    );
+    assert!(result.is_err());
+
+    let db = DB::open_cf(
+        path,
+        DBOptions::default(),
+        vec![
+            descriptor("posts"),
+            descriptor("default"),
+            descriptor("users"),
+        ],
+    )
+    .unwrap();
+    for name in ["users", "posts"] {
+        let cf = db.cf_handle(name).unwrap();
+        let value = db
+            .get_cf(&ReadOptions::default(), &cf, &Slice::from("key"))
+            .unwrap();
+        assert_eq!(value, Some(Slice::from(format!("value_{name}"))));
+    }
+    assert!(db.cf_handle("tags").is_none());
+}
+
+#[test]
+fn test_open_cf_create_missing_column_families() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    create_db(path, &["users"]);
+
+    let descriptors = vec![
+        descriptor("default"),
+        descriptor("users"),
+        descriptor("tags"),
+    ];
+    assert!(DB::open_cf(path, DBOptions::default(), descriptors.clone()).is_err());
+
+    let options = DBOptions {
+        create_missing_column_families: true,
+        ..Default::default()
+    };
+    {
+        let db = DB::open_cf(path, options, descriptors).unwrap();
+        let tags = db.cf_handle("tags").unwrap();
+        db.put_cf(
+            &WriteOptions::default(),
+            &tags,
+            Slice::from("key"),
+            Slice::from("value_tags"),
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        DB::list_column_families(path).unwrap(),
+        vec!["default", "users", "tags"]
+    );
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    let tags = db.cf_handle("tags").unwrap();
+    assert_eq!(
+        db.get_cf(&ReadOptions::default(), &tags, &Slice::from("key"))
+            .unwrap(),
+        Some(Slice::from("value_tags"))
+    );
+}
+
+#[test]
+fn test_column_family_options_persisted() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        let cf_options = ColumnFamilyOptions {
+            write_buffer_size: 64 * 1024,
+            compression_type: CompressionType::Lz4,
+            l0_compaction_trigger: 8,
+            ..Default::default()
+        };
+        db.create_column_family("archive", cf_options).unwrap();
+    }
+
+    // Reopening without descriptors restores the persisted options
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        let archive = db.cf_handle("archive").unwrap();
+        let options = db.get_options_cf(&archive).unwrap();
+        assert_eq!(options.write_buffer_size, 64 * 1024);
+        assert_eq!(options.compression_type, CompressionType::Lz4);
+        assert_eq!(options.l0_compaction_trigger, 8);
+    }
+
+    // Options given to open_cf replace the persisted ones
+    {
+        let cf_options = ColumnFamilyOptions {
+            compression_type: CompressionType::None,
+            ..Default::default()
+        };
+        DB::open_cf(
+            path,
+            DBOptions::default(),
+            vec![
+                descriptor("default"),
+                ColumnFamilyDescriptor::new("archive", cf_options),
+            ],
+        )
+        .unwrap();
+    }
+
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    let archive = db.cf_handle("archive").unwrap();
+    let options = db.get_options_cf(&archive).unwrap();
+    assert_eq!(options.compression_type, CompressionType::None);
+    assert_eq!(options.write_buffer_size, 4 * 1024 * 1024);
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use rucksdb::{
+    ColumnFamilyOptions, CompressionType, CounterMerge, DB, DBOptions, ReadOptions, Slice,
+    StringAppendMerge, WriteOptions, load_latest_options,
+};
+use tempfile::TempDir;
+
+fn options_files(dir: &std::path::Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("OPTIONS-"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_options_file_written_and_loaded() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    assert!(load_latest_options(path).unwrap_err().is_not_found());
+
+    {
+        let options = DBOptions {
+            write_buffer_size: 128 * 1024,
+            l0_compaction_trigger: 6,
+            ..Default::default()
+        };
+        let db = DB::open(path, options).unwrap();
+        assert_eq!(options_files(temp_dir.path()), vec!["OPTIONS-000001"]);
+
+        let cf_options = ColumnFamilyOptions {
+            compression_type: CompressionType::Lz4,
+            ..Default::default()
+        };
+        db.create_column_family("archive", cf_options).unwrap();
+    }
+
+    // One file per open or CF change, only the latest two are kept
+    assert_eq!(
+        options_files(temp_dir.path()),
+        vec!["OPTIONS-000001", "OPTIONS-000002"]
+    );
+
+    let (db_options, descriptors) = load_latest_options(path).unwrap();
+    assert_eq!(db_options.write_buffer_size, 128 * 1024);
+    assert_eq!(db_options.l0_compaction_trigger, 6);
+    let names: Vec<&str> = descriptors.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["default", "archive"]);
+    assert_eq!(descriptors[0].options.write_buffer_size, 128 * 1024);
+    assert_eq!(
+        descriptors[1].options.compression_type,
+        CompressionType::Lz4
+    );
+
+    // The loaded options open the database again
+    let db = DB::open_cf(path, db_options, descriptors).unwrap();
+    assert!(db.cf_handle("archive").is_some());
+    assert_eq!(
+        options_files(temp_dir.path()),
+        vec!["OPTIONS-000002", "OPTIONS-000003"]
+    );
+}
+
+#[test]
+fn test_incompatible_merge_operator_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    let counter_options = || DBOptions {
+        merge_operator: Some(Arc::new(CounterMerge)),
+        ..Default::default()
+    };
+
+    {
+        let db = DB::open(path, counter_options()).unwrap();
+        db.put(
+            &WriteOptions::default(),
+            Slice::from("hits"),
+            Slice::from("5"),
+        )
+        .unwrap();
+    }
+
+    let err = DB::open(path, DBOptions::default()).err().unwrap();
+    assert!(err.message().unwrap().contains("merge operator"), "{err:?}");
+
+    let append_options = DBOptions {
+        merge_operator: Some(Arc::new(StringAppendMerge::new(","))),
+        ..Default::default()
+    };
+    let err = DB::open(path, append_options).err().unwrap();
+    assert!(err.message().unwrap().contains("CounterMerge"), "{err:?}");
+
+    // A rejected open leaves the data and OPTIONS file untouched
+    let db = DB::open(path, counter_options()).unwrap();
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("hits"))
+            .unwrap(),
+        Some(Slice::from("5"))
+    );
+}
+
+#[test]
+fn test_merge_operator_can_be_added() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    drop(DB::open(path, DBOptions::default()).unwrap());
+
+    let options = DBOptions {
+        merge_operator: Some(Arc::new(CounterMerge)),
+        ..Default::default()
+    };
+    assert!(DB::open(path, options).is_ok());
+}
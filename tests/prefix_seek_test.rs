@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use rucksdb::{DB, DBOptions, FixedPrefixTransform, ReadOptions, Slice, WriteOptions};
+use tempfile::TempDir;
+
+fn open_prefix_db(db_path: &str) -> DB {
+    let options = DBOptions {
+        write_buffer_size: 1024, // Small buffer so data spreads over several SSTables
+        prefix_extractor: Some(Arc::new(FixedPrefixTransform::new(4))),
+        memtable_prefix_bloom_size_ratio: 0.1,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    DB::open(db_path, options).unwrap()
+}
+
+fn collect_from(iter: &mut dyn rucksdb::iterator::Iterator, target: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    iter.seek(&Slice::from(target)).unwrap();
+    while iter.valid() {
+        keys.push(iter.key().to_string());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+#[test]
+fn test_prefix_seek_stays_within_prefix() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_prefix_db(temp_dir.path().to_str().unwrap());
+
+    for prefix in ["aaaa", "bbbb", "cccc"] {
+        for i in 0..50 {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("{prefix}{i:03}")),
+                Slice::from(format!("value_{prefix}_{i:03}_padding")),
+            )
+            .unwrap();
+        }
+    }
+    db.delete(&WriteOptions::default(), Slice::from("bbbb010"))
+        .unwrap();
+
+    let prefix_options = ReadOptions {
+        prefix_same_as_start: true,
+        ..Default::default()
+    };
+
+    let mut iter = db.iter_with_options(&prefix_options).unwrap();
+    let keys = collect_from(iter.as_mut(), "bbbb");
+    assert_eq!(keys.len(), 49);
+    assert!(keys.iter().all(|k| k.starts_with("bbbb")));
+    assert!(!keys.contains(&"bbbb010".to_string()));
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+    // Seeking into the middle of a prefix
+    let keys = collect_from(iter.as_mut(), "cccc045");
+    assert_eq!(
+        keys,
+        vec!["cccc045", "cccc046", "cccc047", "cccc048", "cccc049"]
+    );
+
+    // A prefix with no keys
+    let keys = collect_from(iter.as_mut(), "dddd");
+    assert!(keys.is_empty());
+
+    // Without prefix_same_as_start, iteration continues past the prefix
+    let mut iter = db.iter().unwrap();
+    let keys = collect_from(iter.as_mut(), "bbbb");
+    assert_eq!(keys.len(), 99);
+}
+
+#[test]
+fn test_prefix_seek_skips_excluded_sstables() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_prefix_db(temp_dir.path().to_str().unwrap());
+
+    for i in 0..200 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("aaaa{i:03}")),
+            Slice::from(format!("value{i:03}_padding_padding")),
+        )
+        .unwrap();
+    }
+    assert!(
+        db.get_property("rocksdb.num-files-at-level0")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap()
+            > 0
+    );
+
+    let prefix_options = ReadOptions {
+        prefix_same_as_start: true,
+        ..Default::default()
+    };
+
+    // The prefix filters exclude "zzzz", so no data block is read
+    let cached_blocks = db.cache_stats().entries;
+    let mut iter = db.iter_with_options(&prefix_options).unwrap();
+    assert!(collect_from(iter.as_mut(), "zzzz").is_empty());
+    assert_eq!(db.cache_stats().entries, cached_blocks);
+
+    // Keys with a present prefix are still found
+    assert_eq!(collect_from(iter.as_mut(), "aaaa").len(), 200);
+    assert!(db.cache_stats().entries > cached_blocks);
+}
@@ -0,0 +1,231 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rucksdb::{
+    BloomFilterPolicy, ColumnFamilyOptions, DB, DBOptions, FilterPolicy, ReadOptions,
+    RibbonFilterPolicy, Slice, WriteOptions,
+    memtable::memtable::{InternalKey, VALUE_TYPE_VALUE},
+    table::{CompressionType, TableBuilder, TableReader},
+};
+use tempfile::{NamedTempFile, TempDir};
+
+#[test]
+fn test_ribbon_filter_false_positive_rate() {
+    let policy = RibbonFilterPolicy::new(10);
+
+    let keys: Vec<Vec<u8>> = (0..10_000)
+        .map(|i| format!("key{i:06}").into_bytes())
+        .collect();
+    let filter = policy.create_filter(&keys);
+
+    // No false negatives
+    for key in &keys {
+        assert!(policy.may_contain(&filter, key));
+    }
+
+    // 10 Bloom-equivalent bits per key -> 7-bit fingerprints, ~0.8% FP rate
+    let false_positives = (0..100_000)
+        .filter(|i| policy.may_contain(&filter, format!("absent{i:06}").as_bytes()))
+        .count();
+    assert!(
+        false_positives < 1500,
+        "Too many false positives: {}/100000 ({:.3}%)",
+        false_positives,
+        false_positives as f64 / 1000.0
+    );
+    println!(
+        "Ribbon filter false positive rate: {:.3}% ({}/100000)",
+        false_positives as f64 / 1000.0,
+        false_positives
+    );
+}
+
+#[test]
+fn test_ribbon_filter_space_vs_bloom() {
+    let keys: Vec<Vec<u8>> = (0..50_000)
+        .map(|i| format!("key{i:06}").into_bytes())
+        .collect();
+
+    let ribbon = RibbonFilterPolicy::new(10).create_filter(&keys);
+    let bloom = BloomFilterPolicy::new(10).create_filter(&keys);
+
+    let ribbon_bits_per_key = ribbon.len() as f64 * 8.0 / keys.len() as f64;
+    let bloom_bits_per_key = bloom.len() as f64 * 8.0 / keys.len() as f64;
+    println!("Ribbon: {ribbon_bits_per_key:.2} bits/key, Bloom: {bloom_bits_per_key:.2} bits/key");
+    assert!(ribbon_bits_per_key < bloom_bits_per_key * 0.8);
+}
+
+#[test]
+fn test_ribbon_filter_with_sstable() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let filter_policy: Arc<dyn FilterPolicy> = Arc::new(RibbonFilterPolicy::new(10));
+
+    {
+        let mut builder =
+            TableBuilder::new_with_filter(temp_file.path(), Some(filter_policy.clone())).unwrap();
+        for i in 0..1000 {
+            let key = format!("key{i:04}");
+            let internal_key =
+                InternalKey::new(Slice::from(key), i as u64 + 1, VALUE_TYPE_VALUE).encode();
+            builder
+                .add(&internal_key, &Slice::from(format!("value{i:04}")))
+                .unwrap();
+        }
+        builder.finish(CompressionType::None).unwrap();
+    }
+
+    // Readers configured with a Bloom policy understand Ribbon filters too
+    let reader_policy: Arc<dyn FilterPolicy> = Arc::new(BloomFilterPolicy::new(10));
+    for policy in [filter_policy, reader_policy] {
+        let mut reader =
+            TableReader::open_with_filter(temp_file.path(), 1, None, Some(policy)).unwrap();
+        for i in 0..1000 {
+            let key = format!("key{i:04}");
+            let (found, value) = reader.get(&Slice::from(key.as_str())).unwrap();
+            assert!(found, "Key {key} should exist");
+            assert_eq!(value, Some(Slice::from(format!("value{i:04}"))));
+        }
+        assert!(!reader.get(&Slice::from("key9999")).unwrap().0);
+    }
+}
+
+#[test]
+fn test_ribbon_filter_per_column_family_and_level() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 1024,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    // Bloom for L0, Ribbon for L1 and below
+    let cf_options = ColumnFamilyOptions {
+        write_buffer_size: 1024,
+        filter_policy: Some(Arc::new(RibbonFilterPolicy::with_bloom_before_level(10, 1))),
+        ..Default::default()
+    };
+    let cf = db.create_column_family("ribbon", cf_options).unwrap();
+
+    for i in 0..300 {
+        db.put_cf(
+            &WriteOptions::default(),
+            &cf,
+            Slice::from(format!("key{i:04}")),
+            Slice::from(format!("value{i:04}_padding")),
+        )
+        .unwrap();
+    }
+    db.compact_level_cf(&cf, 0).unwrap();
+
+    for i in 0..300 {
+        let value = db
+            .get_cf(
+                &ReadOptions::default(),
+                &cf,
+                &Slice::from(format!("key{i:04}")),
+            )
+            .unwrap();
+        assert_eq!(value, Some(Slice::from(format!("value{i:04}_padding"))));
+    }
+    assert_eq!(
+        db.get_cf(&ReadOptions::default(), &cf, &Slice::from("key9999"))
+            .unwrap(),
+        None
+    );
+}
+
+/// Delegates to another policy, counting filter queries
+struct CountingPolicy {
+    inner: Arc<dyn FilterPolicy>,
+    queries: AtomicUsize,
+}
+
+impl FilterPolicy for CountingPolicy {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn create_filter(&self, keys: &[Vec<u8>]) -> Vec<u8> {
+        self.inner.create_filter(keys)
+    }
+
+    fn may_contain(&self, filter: &[u8], key: &[u8]) -> bool {
+        self.queries.fetch_add(1, Ordering::SeqCst);
+        self.inner.may_contain(filter, key)
+    }
+}
+
+#[test]
+fn test_filter_policy_per_column_family_on_read() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 1024,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+
+    let ribbon = Arc::new(CountingPolicy {
+        inner: Arc::new(RibbonFilterPolicy::new(10)),
+        queries: AtomicUsize::new(0),
+    });
+    let bloom = Arc::new(CountingPolicy {
+        inner: Arc::new(BloomFilterPolicy::new(10)),
+        queries: AtomicUsize::new(0),
+    });
+    let mut cfs = Vec::new();
+    for (name, policy) in [("ribbon", &ribbon), ("bloom", &bloom)] {
+        let cf_options = ColumnFamilyOptions {
+            write_buffer_size: 1024,
+            filter_policy: Some(Arc::clone(policy) as Arc<dyn FilterPolicy>),
+            ..Default::default()
+        };
+        let cf = db.create_column_family(name, cf_options).unwrap();
+        for i in 0..300 {
+            db.put_cf(
+                &WriteOptions::default(),
+                &cf,
+                Slice::from(format!("key{i:04}")),
+                Slice::from(format!("value{i:04}_padding")),
+            )
+            .unwrap();
+        }
+        db.compact_level_cf(&cf, 0).unwrap();
+        cfs.push(cf);
+    }
+
+    // Each CF's tables are read with that CF's policy
+    let ribbon_queries = ribbon.queries.load(Ordering::SeqCst);
+    let bloom_queries = bloom.queries.load(Ordering::SeqCst);
+    for i in 0..50 {
+        let key = Slice::from(format!("key{i:04}x"));
+        assert_eq!(
+            db.get_cf(&ReadOptions::default(), &cfs[0], &key).unwrap(),
+            None
+        );
+    }
+    assert!(ribbon.queries.load(Ordering::SeqCst) > ribbon_queries);
+    assert_eq!(bloom.queries.load(Ordering::SeqCst), bloom_queries);
+
+    let ribbon_queries = ribbon.queries.load(Ordering::SeqCst);
+    for i in 0..50 {
+        let key = Slice::from(format!("key{i:04}x"));
+        assert_eq!(
+            db.get_cf(&ReadOptions::default(), &cfs[1], &key).unwrap(),
+            None
+        );
+    }
+    assert!(bloom.queries.load(Ordering::SeqCst) > bloom_queries);
+    assert_eq!(ribbon.queries.load(Ordering::SeqCst), ribbon_queries);
+
+    for cf in &cfs {
+        assert_eq!(
+            db.get_cf(&ReadOptions::default(), cf, &Slice::from("key0123"))
+                .unwrap(),
+            Some(Slice::from("value0123_padding"))
+        );
+    }
+}
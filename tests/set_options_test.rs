@@ -0,0 +1,238 @@
+use std::path::Path;
+
+use rucksdb::{
+    ColumnFamilyHandle, ColumnFamilyOptions, CompressionType, DB, DBOptions, ReadOptions, Slice,
+    WriteOptions, load_latest_options,
+};
+use tempfile::TempDir;
+
+fn sst_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "sst")
+        })
+        .count()
+}
+
+fn fill(db: &DB, cf: &ColumnFamilyHandle, range: std::ops::Range<usize>) {
+    for i in range {
+        db.put_cf(
+            &WriteOptions::default(),
+            cf,
+            Slice::from(format!("key{i:05}")),
+            Slice::from("x".repeat(200)),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_set_write_buffer_size() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), DBOptions::default()).unwrap();
+    let cf = db
+        .create_column_family("cf", ColumnFamilyOptions::default())
+        .unwrap();
+
+    // 100KB stays in the 4MB write buffer
+    fill(&db, &cf, 0..500);
+    assert_eq!(sst_count(temp_dir.path()), 0);
+
+    db.set_options(&cf, &[("write_buffer_size", "16384")])
+        .unwrap();
+    assert_eq!(db.get_options_cf(&cf).unwrap().write_buffer_size, 16384);
+    fill(&db, &cf, 500..1000);
+    assert!(sst_count(temp_dir.path()) > 0);
+
+    for i in (0..1000).step_by(111) {
+        let value = db
+            .get_cf(
+                &ReadOptions::default(),
+                &cf,
+                &Slice::from(format!("key{i:05}")),
+            )
+            .unwrap();
+        assert_eq!(value, Some(Slice::from("x".repeat(200))));
+    }
+}
+
+#[test]
+fn test_set_compaction_trigger() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), DBOptions::default()).unwrap();
+    let cf_options = ColumnFamilyOptions {
+        write_buffer_size: 16 * 1024,
+        compression_type: CompressionType::None,
+        l0_compaction_trigger: 10,
+        ..Default::default()
+    };
+    let cf = db.create_column_family("cf", cf_options).unwrap();
+
+    fill(&db, &cf, 0..600);
+    let l0_files = sst_count(temp_dir.path());
+    assert!(l0_files >= 4, "{l0_files} files");
+
+    // The next flush sees the lower trigger and compacts L0; it overwrites
+    // keys, so the files are merged rather than moved to L1
+    db.set_options(&cf, &[("l0_compaction_trigger", "2")])
+        .unwrap();
+    fill(&db, &cf, 0..100);
+    assert!(sst_count(temp_dir.path()) < l0_files);
+}
+
+#[test]
+fn test_set_options_rejects_invalid_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), DBOptions::default()).unwrap();
+    let cf = db
+        .create_column_family("cf", ColumnFamilyOptions::default())
+        .unwrap();
+
+    for changes in [
+        vec![("write_buffer_size", "0")],
+        vec![("write_buffer_size", "lots")],
+        vec![("l0_stop_writes_trigger", "0")],
+        vec![("block_cache_size", "10")],
+        vec![("compression_type", "Lz4"), ("no_such_option", "1")],
+        vec![("compaction_style", "Universal")],
+        vec![("compaction_options_universal", r#"{"min_merge_width": 1}"#)],
+    ] {
+        assert!(db.set_options(&cf, &changes).is_err(), "{changes:?}");
+    }
+    assert!(
+        db.set_db_options(&[("create_if_missing", "false")])
+            .is_err()
+    );
+    assert!(
+        db.set_db_options(&[("compaction_check_interval_ms", "10")])
+            .is_err()
+    );
+
+    // Nothing was applied
+    let options = db.get_options_cf(&cf).unwrap();
+    assert_eq!(options.compression_type, CompressionType::Snappy);
+    assert_eq!(options.write_buffer_size, 4 * 1024 * 1024);
+}
+
+#[test]
+fn test_db_options_checked_at_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+
+    #[allow(deprecated)]
+    let options = DBOptions {
+        compaction_check_interval_ms: 0,
+        ..Default::default()
+    };
+    assert!(DB::open(path, options).is_err());
+
+    // Column family settings are checked as the default CF's seed
+    let options = DBOptions {
+        l0_stop_writes_trigger: 0,
+        ..Default::default()
+    };
+    assert!(DB::open(path, options).is_err());
+
+    // The DB-level stall trigger only seeds the default CF
+    let options = DBOptions {
+        l0_stop_writes_trigger: 20,
+        ..Default::default()
+    };
+    let db = DB::open(path, options).unwrap();
+    let default_cf = db.default_cf();
+    assert_eq!(
+        db.get_options_cf(&default_cf)
+            .unwrap()
+            .l0_stop_writes_trigger,
+        20
+    );
+    let cf = db
+        .create_column_family("cf", ColumnFamilyOptions::default())
+        .unwrap();
+    assert_eq!(db.get_options_cf(&cf).unwrap().l0_stop_writes_trigger, 12);
+}
+
+#[test]
+fn test_changed_options_recorded() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        let cf = db
+            .create_column_family("cf", ColumnFamilyOptions::default())
+            .unwrap();
+        db.set_options(
+            &cf,
+            &[
+                ("compression_type", "Lz4"),
+                ("compaction_options_universal", r#"{"size_ratio": 10}"#),
+                ("ttl", "3600"),
+                ("periodic_compaction_seconds", "86400"),
+            ],
+        )
+        .unwrap();
+        db.set_db_options(&[
+            ("parallel_compaction_threads", "2"),
+            ("enable_subcompaction", "false"),
+        ])
+        .unwrap();
+
+        let db_options = db.get_db_options();
+        assert_eq!(db_options.parallel_compaction_threads, 2);
+        assert!(!db_options.enable_subcompaction);
+
+        let (db_options, descriptors) = load_latest_options(path).unwrap();
+        assert_eq!(db_options.parallel_compaction_threads, 2);
+        assert_eq!(
+            descriptors[1].options.compression_type,
+            CompressionType::Lz4
+        );
+        let universal = &descriptors[1].options.compaction_options_universal;
+        assert_eq!(universal.size_ratio, 10);
+        // Fields left out keep their defaults
+        assert_eq!(universal.max_size_amplification_percent, 200);
+        assert_eq!(descriptors[1].options.ttl, 3600);
+        assert_eq!(descriptors[1].options.periodic_compaction_seconds, 86400);
+    }
+
+    // CF options survive a reopen without descriptors
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    let cf = db.cf_handle("cf").unwrap();
+    assert_eq!(
+        db.get_options_cf(&cf).unwrap().compression_type,
+        CompressionType::Lz4
+    );
+}
+
+#[test]
+fn test_default_cf_options_survive_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+
+    {
+        let db = DB::open(path, DBOptions::default()).unwrap();
+        let default_cf = db.default_cf();
+        db.set_options(
+            &default_cf,
+            &[("compression_type", "Lz4"), ("l0_compaction_trigger", "6")],
+        )
+        .unwrap();
+    }
+
+    // Recorded options win over the DBOptions seed
+    let db = DB::open(path, DBOptions::default()).unwrap();
+    let options = db.get_options_cf(&db.default_cf()).unwrap();
+    assert_eq!(options.compression_type, CompressionType::Lz4);
+    assert_eq!(options.l0_compaction_trigger, 6);
+    assert_eq!(
+        options.write_buffer_size,
+        DBOptions::default().write_buffer_size
+    );
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use rucksdb::{
+    BlockBasedTableOptions, ColumnFamilyOptions, DB, DBOptions, FixedPrefixTransform, ReadOptions,
+    Slice, WriteOptions,
+    table::{CompressionType, TableReader},
+};
+use tempfile::TempDir;
+
+fn open_db(path: &str) -> DB {
+    let options = DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    DB::open(path, options).unwrap()
+}
+
+fn sst_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sst"))
+        .collect()
+}
+
+#[test]
+fn test_table_options_per_column_family() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_db(temp_dir.path().to_str().unwrap());
+
+    let cf_options = ColumnFamilyOptions {
+        write_buffer_size: 4096,
+        prefix_extractor: Some(Arc::new(FixedPrefixTransform::new(4))),
+        table_options: BlockBasedTableOptions {
+            block_size: 256,
+            block_restart_interval: 4,
+            whole_key_filtering: false,
+            format_version: 0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let cf = db.create_column_family("small_blocks", cf_options).unwrap();
+
+    for i in 0..500 {
+        db.put_cf(
+            &WriteOptions::default(),
+            &cf,
+            Slice::from(format!("key{i:04}")),
+            Slice::from(format!("value{i:04}")),
+        )
+        .unwrap();
+    }
+    db.compact_level_cf(&cf, 0).unwrap();
+
+    for i in 0..500 {
+        let value = db
+            .get_cf(
+                &ReadOptions::default(),
+                &cf,
+                &Slice::from(format!("key{i:04}")),
+            )
+            .unwrap();
+        assert_eq!(value, Some(Slice::from(format!("value{i:04}"))));
+    }
+
+    // Only this CF has written tables; every one uses 256-byte blocks
+    let files = sst_files(temp_dir.path());
+    assert!(!files.is_empty());
+    for path in files {
+        let mut reader = TableReader::open(&path, 1, None).unwrap();
+        let size = reader.file_size();
+        let blocks = reader.data_block_handles().unwrap().len() as u64;
+        assert!(
+            size / blocks < 1024,
+            "{path:?}: {blocks} blocks, {size} bytes"
+        );
+    }
+}
+
+#[test]
+fn test_invalid_table_options_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_db(temp_dir.path().to_str().unwrap());
+
+    let cf_options = ColumnFamilyOptions {
+        table_options: BlockBasedTableOptions {
+            format_version: 99,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(db.create_column_family("bad", cf_options).is_err());
+
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        table_options: BlockBasedTableOptions {
+            block_size: 0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(DB::open(temp_dir.path().to_str().unwrap(), options).is_err());
+}
+
+#[test]
+fn test_read_options_verify_checksums() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    let options = DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        compression_type: CompressionType::None,
+        table_options: BlockBasedTableOptions {
+            verify_checksums: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    {
+        let db = DB::open(path, options.clone()).unwrap();
+        for i in 0..200 {
+            db.put(
+                &WriteOptions::default(),
+                Slice::from(format!("key{i:04}")),
+                Slice::from(format!("value{i:04}").repeat(10)),
+            )
+            .unwrap();
+        }
+        db.compact_range(None, None).unwrap();
+    }
+
+    // Reads must come from the tables: drop the WAL and flip a byte inside
+    // the first value of every table
+    std::fs::remove_file(temp_dir.path().join("wal.log")).unwrap();
+    for file in sst_files(temp_dir.path()) {
+        let mut data = std::fs::read(&file).unwrap();
+        data[40] ^= 0xff;
+        std::fs::write(&file, data).unwrap();
+    }
+
+    let db = DB::open(path, options).unwrap();
+    let key = Slice::from("key0000");
+    let value = db.get(&ReadOptions::default(), &key).unwrap();
+    assert_ne!(value, Some(Slice::from("value0000".repeat(10))));
+
+    let verify = ReadOptions {
+        verify_checksums: true,
+        ..Default::default()
+    };
+    assert!(db.get(&verify, &key).is_err());
+
+    let mut iter = db.iter_with_options(&verify).unwrap();
+    assert!(iter.seek_to_first().is_err());
+}
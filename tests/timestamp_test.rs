@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use rucksdb::{
+    BytewiseComparatorWithU64Ts, ColumnFamilyHandle, ColumnFamilyOptions, DB, DBOptions,
+    ReadOptions, Slice, WriteOptions,
+};
+use tempfile::TempDir;
+
+fn ts_options() -> DBOptions {
+    DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        comparator: Arc::new(BytewiseComparatorWithU64Ts),
+        ..Default::default()
+    }
+}
+
+fn read_at(timestamp: u64) -> ReadOptions {
+    ReadOptions {
+        timestamp: Some(timestamp),
+        ..Default::default()
+    }
+}
+
+fn get_at(db: &DB, cf: &ColumnFamilyHandle, key: &str, timestamp: u64) -> Option<Slice> {
+    db.get_cf(&read_at(timestamp), cf, &Slice::from(key))
+        .unwrap()
+}
+
+fn scan_at(db: &DB, cf: &ColumnFamilyHandle, timestamp: u64) -> Vec<(String, String)> {
+    let mut iter = db.iter_cf_with_options(&read_at(timestamp), cf).unwrap();
+    let mut entries = Vec::new();
+    let mut valid = iter.seek_to_first().unwrap();
+    while valid {
+        entries.push((iter.key().to_string(), iter.value().to_string()));
+        valid = iter.next().unwrap();
+    }
+    entries
+}
+
+fn put_at(db: &DB, cf: &ColumnFamilyHandle, key: &str, timestamp: u64, value: &str) {
+    db.put_cf_with_ts(
+        &WriteOptions::default(),
+        cf,
+        Slice::from(key),
+        timestamp,
+        Slice::from(value),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_get_and_iterate_as_of_timestamp() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), ts_options()).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    put_at(&db, &cf, "a", 10, "a10");
+    put_at(&db, &cf, "a", 20, "a20");
+    put_at(&db, &cf, "b", 15, "b15");
+    db.delete_cf_with_ts(&WriteOptions::default(), &cf, Slice::from("b"), 25)
+        .unwrap();
+
+    assert_eq!(get_at(&db, &cf, "a", 5), None);
+    assert_eq!(get_at(&db, &cf, "a", 10), Some(Slice::from("a10")));
+    assert_eq!(get_at(&db, &cf, "a", 19), Some(Slice::from("a10")));
+    assert_eq!(get_at(&db, &cf, "a", 20), Some(Slice::from("a20")));
+    assert_eq!(get_at(&db, &cf, "b", 20), Some(Slice::from("b15")));
+    assert_eq!(get_at(&db, &cf, "b", 25), None);
+
+    // Without a read timestamp the latest versions are visible
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("a")).unwrap(),
+        Some(Slice::from("a20"))
+    );
+
+    assert_eq!(
+        scan_at(&db, &cf, 17),
+        vec![
+            ("a".to_string(), "a10".to_string()),
+            ("b".to_string(), "b15".to_string()),
+        ]
+    );
+    assert_eq!(
+        scan_at(&db, &cf, 30),
+        vec![("a".to_string(), "a20".to_string())]
+    );
+}
+
+#[test]
+fn test_timestamps_survive_flush_and_reopen() {
+    // The small write buffer flushes several times along the way
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    {
+        let db = DB::open(path, ts_options()).unwrap();
+        let cf = db.cf_handle("default").unwrap();
+        for ts in 1..=5 {
+            for i in 0..200 {
+                put_at(
+                    &db,
+                    &cf,
+                    &format!("key{i:03}"),
+                    ts * 10,
+                    &format!("v{ts}-{i}"),
+                );
+            }
+        }
+    }
+
+    let db = DB::open(path, ts_options()).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+    for ts in 1..=5 {
+        for i in (0..200).step_by(17) {
+            assert_eq!(
+                get_at(&db, &cf, &format!("key{i:03}"), ts * 10 + 5),
+                Some(Slice::from(format!("v{ts}-{i}")))
+            );
+        }
+    }
+    assert_eq!(get_at(&db, &cf, "key000", 5), None);
+    assert_eq!(scan_at(&db, &cf, 25).len(), 200);
+}
+
+#[test]
+fn test_full_history_ts_low_collects_old_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    {
+        let db = DB::open(path, ts_options()).unwrap();
+        let cf = db.cf_handle("default").unwrap();
+        put_at(&db, &cf, "k", 10, "v10");
+        put_at(&db, &cf, "k", 20, "v20");
+        put_at(&db, &cf, "k", 30, "v30");
+        // Push the versions out to an SSTable
+        for i in 0..200 {
+            put_at(&db, &cf, &format!("filler{i:03}"), 1, "x");
+        }
+
+        db.increase_full_history_ts_low(&cf, 25).unwrap();
+        db.compact_level(0).unwrap();
+
+        // v20 is what a read at the watermark sees, so it stays
+        assert_eq!(get_at(&db, &cf, "k", 25), Some(Slice::from("v20")));
+        assert_eq!(get_at(&db, &cf, "k", 30), Some(Slice::from("v30")));
+        assert!(db.get_cf(&read_at(15), &cf, &Slice::from("k")).is_err());
+
+        let err = db.increase_full_history_ts_low(&cf, 5).unwrap_err();
+        assert!(err.message().unwrap().contains("decrease"));
+    }
+
+    // The watermark is recovered from the MANIFEST
+    let db = DB::open(path, ts_options()).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+    assert!(db.get_cf(&read_at(15), &cf, &Slice::from("k")).is_err());
+    assert_eq!(get_at(&db, &cf, "k", 25), Some(Slice::from("v20")));
+}
+
+#[test]
+fn test_timestamp_mismatch_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let options = DBOptions {
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    let db = DB::open(temp_dir.path().to_str().unwrap(), options).unwrap();
+    let default_cf = db.cf_handle("default").unwrap();
+    let ts_cf = db
+        .create_column_family(
+            "versioned",
+            ColumnFamilyOptions {
+                comparator: Arc::new(BytewiseComparatorWithU64Ts),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // Timestamps on a CF without them
+    assert!(
+        db.put_cf_with_ts(
+            &WriteOptions::default(),
+            &default_cf,
+            Slice::from("k"),
+            1,
+            Slice::from("v"),
+        )
+        .is_err()
+    );
+    assert!(
+        db.get_cf(&read_at(1), &default_cf, &Slice::from("k"))
+            .is_err()
+    );
+    assert!(db.increase_full_history_ts_low(&default_cf, 1).is_err());
+
+    // Missing timestamps on a CF with them
+    assert!(
+        db.put_cf(
+            &WriteOptions::default(),
+            &ts_cf,
+            Slice::from("k"),
+            Slice::from("v")
+        )
+        .is_err()
+    );
+    assert!(
+        db.delete_cf(&WriteOptions::default(), &ts_cf, Slice::from("k"))
+            .is_err()
+    );
+
+    put_at(&db, &ts_cf, "k", 7, "v7");
+    assert_eq!(get_at(&db, &ts_cf, "k", 7), Some(Slice::from("v7")));
+    assert_eq!(
+        db.get_cf(&ReadOptions::default(), &ts_cf, &Slice::from("k"))
+            .unwrap(),
+        Some(Slice::from("v7"))
+    );
+}
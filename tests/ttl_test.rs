@@ -0,0 +1,117 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use rucksdb::{DB, DBOptions, DBWithTTL, ReadOptions, Slice, WriteOptions};
+use tempfile::TempDir;
+
+fn open_ttl_db(path: &str, ttl: Duration) -> DBWithTTL {
+    let options = DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        ..Default::default()
+    };
+    DBWithTTL::open(Arc::new(DB::open(path, options).unwrap()), ttl)
+}
+
+fn collect(db: &DBWithTTL) -> Vec<(String, String)> {
+    let mut iter = db.iter().unwrap();
+    let mut entries = Vec::new();
+    let mut valid = iter.seek_to_first().unwrap();
+    while valid {
+        entries.push((iter.key().to_string(), iter.value().to_string()));
+        valid = iter.next().unwrap();
+    }
+    entries
+}
+
+#[test]
+fn test_values_before_expiry() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_ttl_db(temp_dir.path().to_str().unwrap(), Duration::from_secs(3600));
+
+    db.put(&WriteOptions::default(), Slice::from("a"), Slice::from("1"))
+        .unwrap();
+    db.put(&WriteOptions::default(), Slice::from("b"), Slice::from("2"))
+        .unwrap();
+
+    // The write time is stripped from values
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("a")).unwrap(),
+        Some(Slice::from("1"))
+    );
+    assert_eq!(
+        collect(&db),
+        vec![
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "2".to_string()),
+        ]
+    );
+
+    // The underlying database holds the suffixed value
+    let raw = db
+        .db()
+        .get(&ReadOptions::default(), &Slice::from("a"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(raw.size(), 1 + 12);
+}
+
+#[test]
+fn test_expired_values_hidden_and_compacted_away() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = open_ttl_db(temp_dir.path().to_str().unwrap(), Duration::from_secs(1));
+    let cf = db
+        .create_column_family("sessions", Default::default())
+        .unwrap();
+
+    // Enough to flush the first keys to SSTables
+    for i in 0..200 {
+        db.put(
+            &WriteOptions::default(),
+            Slice::from(format!("short{i:03}")),
+            Slice::from("v"),
+        )
+        .unwrap();
+    }
+    db.put_with_ttl(
+        &WriteOptions::default(),
+        Slice::from("long"),
+        Slice::from("kept"),
+        Duration::from_secs(3600),
+    )
+    .unwrap();
+    db.put_cf(
+        &WriteOptions::default(),
+        &cf,
+        Slice::from("session"),
+        Slice::from("s"),
+    )
+    .unwrap();
+
+    thread::sleep(Duration::from_millis(2100));
+
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("short000"))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        db.get_cf(&ReadOptions::default(), &cf, &Slice::from("session"))
+            .unwrap(),
+        None
+    );
+    assert_eq!(collect(&db), vec![("long".to_string(), "kept".to_string())]);
+
+    // Compaction drops expired values from the underlying database
+    db.db().compact_range(None, None).unwrap();
+    assert_eq!(
+        db.db()
+            .get(&ReadOptions::default(), &Slice::from("short000"))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("long"))
+            .unwrap(),
+        Some(Slice::from("kept"))
+    );
+}
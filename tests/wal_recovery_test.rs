@@ -490,7 +490,7 @@ fn test_cross_restart_multi_cf_basic() {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
         // Verify CFs exist
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         assert_eq!(cfs.len(), 3);
 
         let cf_names: Vec<&str> = cfs.iter().map(|h| h.name()).collect();
@@ -575,7 +575,7 @@ fn test_cross_restart_multi_cf_with_delete() {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
         // Get CF1 handle
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         let cf1 = cfs.iter().find(|h| h.name() == "cf1").unwrap();
 
         // Verify default CF - key1 should be deleted
@@ -658,7 +658,7 @@ fn test_cross_restart_multi_cf_interleaved() {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
         // Get CF handles
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         let cf1 = cfs.iter().find(|h| h.name() == "cf1").unwrap();
         let cf2 = cfs.iter().find(|h| h.name() == "cf2").unwrap();
 
@@ -733,7 +733,7 @@ fn test_cross_restart_cf_drop_and_create() {
     {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         assert_eq!(cfs.len(), 2); // default + cf2
 
         let cf_names: Vec<&str> = cfs.iter().map(|h| h.name()).collect();
@@ -808,7 +808,7 @@ fn test_cross_restart_multi_cf_many_keys() {
     {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         assert_eq!(cfs.len(), 4); // default + cf1 + cf2 + cf3
 
         let cf1 = cfs.iter().find(|h| h.name() == "cf1").unwrap();
@@ -881,7 +881,7 @@ fn test_cross_restart_cf_id_preservation() {
     {
         let db = DB::open(db_path.to_str().unwrap(), DBOptions::default()).unwrap();
 
-        let cfs = db.list_column_families();
+        let cfs = db.column_family_handles();
         let cf1 = cfs.iter().find(|h| h.name() == "cf1").unwrap();
         let cf2 = cfs.iter().find(|h| h.name() == "cf2").unwrap();
 
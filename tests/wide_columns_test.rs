@@ -0,0 +1,167 @@
+use rucksdb::{DB, DBOptions, ReadOptions, Slice, WideColumns, WriteOptions};
+use tempfile::TempDir;
+
+fn profile(name: &str, city: &str) -> WideColumns {
+    WideColumns::new(vec![
+        (Slice::from("city"), Slice::from(city)),
+        (Slice::from(""), Slice::from(name)),
+        (Slice::from("age"), Slice::from("42")),
+    ])
+    .unwrap()
+}
+
+fn small_buffer_options() -> DBOptions {
+    DBOptions {
+        write_buffer_size: 4096,
+        enable_background_compaction: false,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_put_and_get_entity() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), DBOptions::default()).unwrap();
+    let cf = db.default_cf();
+
+    db.put_entity(
+        &WriteOptions::default(),
+        &cf,
+        Slice::from("user1"),
+        &profile("Alice", "Berlin"),
+    )
+    .unwrap();
+
+    let entity = db
+        .get_entity(&ReadOptions::default(), &cf, &Slice::from("user1"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(entity, profile("Alice", "Berlin"));
+    assert_eq!(entity.get(b"city"), Some(&Slice::from("Berlin")));
+
+    // Plain reads see the default column
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("user1"))
+            .unwrap(),
+        Some(Slice::from("Alice"))
+    );
+
+    // Plain values read as entities with only the default column
+    db.put(
+        &WriteOptions::default(),
+        Slice::from("user2"),
+        Slice::from("Bob"),
+    )
+    .unwrap();
+    assert_eq!(
+        db.get_entity(&ReadOptions::default(), &cf, &Slice::from("user2"))
+            .unwrap(),
+        Some(WideColumns::from_value(Slice::from("Bob")))
+    );
+
+    // Entities without a default column read as an empty value
+    let no_default = WideColumns::new(vec![(Slice::from("a"), Slice::from("1"))]).unwrap();
+    db.put_entity(
+        &WriteOptions::default(),
+        &cf,
+        Slice::from("user3"),
+        &no_default,
+    )
+    .unwrap();
+    assert_eq!(
+        db.get(&ReadOptions::default(), &Slice::from("user3"))
+            .unwrap(),
+        Some(Slice::empty())
+    );
+
+    db.delete(&WriteOptions::default(), Slice::from("user1"))
+        .unwrap();
+    assert_eq!(
+        db.get_entity(&ReadOptions::default(), &cf, &Slice::from("user1"))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_entities_survive_flush_compaction_and_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    {
+        let db = DB::open(path, small_buffer_options()).unwrap();
+        let cf = db.default_cf();
+        for i in 0..300 {
+            db.put_entity(
+                &WriteOptions::default(),
+                &cf,
+                Slice::from(format!("user{i:03}")),
+                &profile(&format!("name{i}"), &format!("city{i}")),
+            )
+            .unwrap();
+        }
+        db.compact_level(0).unwrap();
+    }
+
+    let db = DB::open(path, small_buffer_options()).unwrap();
+    let cf = db.default_cf();
+    for i in (0..300).step_by(13) {
+        let entity = db
+            .get_entity(
+                &ReadOptions::default(),
+                &cf,
+                &Slice::from(format!("user{i:03}")),
+            )
+            .unwrap();
+        assert_eq!(
+            entity,
+            Some(profile(&format!("name{i}"), &format!("city{i}")))
+        );
+    }
+}
+
+#[test]
+fn test_iterator_columns() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DB::open(temp_dir.path().to_str().unwrap(), small_buffer_options()).unwrap();
+    let cf = db.default_cf();
+
+    for i in 0..100 {
+        let key = Slice::from(format!("key{i:03}"));
+        if i % 2 == 0 {
+            db.put_entity(
+                &WriteOptions::default(),
+                &cf,
+                key,
+                &profile(&format!("name{i}"), "Paris"),
+            )
+            .unwrap();
+        } else {
+            db.put(
+                &WriteOptions::default(),
+                key,
+                Slice::from(format!("plain{i}")),
+            )
+            .unwrap();
+        }
+    }
+
+    let mut iter = db.iter().unwrap();
+    let mut valid = iter.seek_to_first().unwrap();
+    let mut count = 0;
+    while valid {
+        let i = count;
+        if i % 2 == 0 {
+            assert_eq!(iter.value(), Slice::from(format!("name{i}")));
+            assert_eq!(iter.columns(), profile(&format!("name{i}"), "Paris"));
+        } else {
+            assert_eq!(iter.value(), Slice::from(format!("plain{i}")));
+            assert_eq!(
+                iter.columns(),
+                WideColumns::from_value(Slice::from(format!("plain{i}")))
+            );
+        }
+        count += 1;
+        valid = iter.next().unwrap();
+    }
+    assert_eq!(count, 100);
+}